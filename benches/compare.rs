@@ -25,6 +25,21 @@ pub fn interned_length<'db>(db: &'db dyn salsa::Database, input: InternedInput<'
     input.text(db).len()
 }
 
+#[salsa::tracked]
+pub struct TrackedText<'db> {
+    pub text: String,
+}
+
+#[salsa::tracked]
+pub fn tracked_text(db: &dyn salsa::Database, input: Input) -> TrackedText<'_> {
+    TrackedText::new(db, input.text(db))
+}
+
+#[salsa::tracked]
+pub fn tracked_length<'db>(db: &'db dyn salsa::Database, tracked: TrackedText<'db>) -> usize {
+    tracked.text(db).len()
+}
+
 fn mutating_inputs(c: &mut Criterion) {
     let mut group: codspeed_criterion_compat::BenchmarkGroup<
         codspeed_criterion_compat::measurement::WallTime,
@@ -119,6 +134,22 @@ fn inputs(c: &mut Criterion) {
         )
     });
 
+    // A function keyed by a tracked struct: its memo is found directly from the struct's id.
+    group.bench_function(BenchmarkId::new("amortized", "TrackedStruct"), |b| {
+        b.iter_batched_ref(
+            || {
+                let db = salsa::DatabaseImpl::default();
+                let input = Input::new(&db, "hello, world!".to_owned());
+                let _ = tracked_length(&db, tracked_text(&db, input));
+                (db, input)
+            },
+            |&mut (ref db, input)| {
+                tracked_length(db, tracked_text(db, input));
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
     group.finish();
 }
 
@@ -0,0 +1,23 @@
+use codspeed_criterion_compat::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use salsa::bench::{fetch_hot_loop, input_read_loop, interned_lookup_loop, Fixture};
+
+/// The code paths salsa treats as part of its performance contract, see `salsa::bench`.
+fn contract(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Contract");
+    let fixture = Fixture::new();
+
+    group.bench_function(BenchmarkId::new("fetch_hot", 100), |b| {
+        b.iter(|| fetch_hot_loop(&fixture, 100))
+    });
+    group.bench_function(BenchmarkId::new("interned_lookup", 100), |b| {
+        b.iter(|| interned_lookup_loop(&fixture, 100))
+    });
+    group.bench_function(BenchmarkId::new("input_read", 100), |b| {
+        b.iter(|| input_read_loop(&fixture, 100))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, contract);
+criterion_main!(benches);
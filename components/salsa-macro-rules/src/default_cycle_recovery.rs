@@ -0,0 +1,14 @@
+// Macro that generates the body of the cycle recovery function
+// for `recovery_fn = default`, which recovers with the default value
+// of the output. This has to be a macro because it can take a variadic
+// number of arguments.
+#[macro_export]
+macro_rules! default_cycle_recovery {
+    ($db:ident, $cycle:ident, $($other_inputs:ident),*) => {
+        {
+            std::mem::drop(($db, $cycle));
+            std::mem::drop(($($other_inputs),*));
+            ::core::default::Default::default()
+        }
+    }
+}
@@ -12,6 +12,7 @@
 //! from a submodule is to use multiple crates, hence the existence
 //! of this crate.
 
+mod default_cycle_recovery;
 mod macro_if;
 mod maybe_backdate;
 mod maybe_clone;
@@ -23,3 +24,4 @@ mod setup_method_body;
 mod setup_tracked_fn;
 mod setup_tracked_struct;
 mod unexpected_cycle_recovery;
+mod unexpected_timeout_result;
@@ -7,6 +7,7 @@ macro_rules! maybe_backdate {
         $old_field_place:expr,
         $new_field_place:expr,
         $revision_place:expr,
+        $element_revisions_place:expr,
         $current_revision:expr,
         $zalsa:ident,
 
@@ -25,6 +26,7 @@ macro_rules! maybe_backdate {
         $old_field_place:expr,
         $new_field_place:expr,
         $revision_place:expr,
+        $element_revisions_place:expr,
         $current_revision:expr,
         $zalsa:ident,
      ) => {
@@ -35,4 +37,74 @@ macro_rules! maybe_backdate {
             $revision_place = $current_revision;
         }
     };
+
+    (
+        ($maybe_clone:ident, elements, $maybe_default:ident),
+        $field_ty:ty,
+        $old_field_place:expr,
+        $new_field_place:expr,
+        $revision_place:expr,
+        $element_revisions_place:expr,
+        $current_revision:expr,
+        $zalsa:ident,
+     ) => {
+        $zalsa::tracked_struct::update_elements(
+            &mut $revision_place,
+            &mut $element_revisions_place,
+            $current_revision,
+            std::ptr::addr_of_mut!($old_field_place),
+            $new_field_place,
+            |old_pointer, new_value| {
+                $zalsa::UpdateDispatch::<<$field_ty as $zalsa::tracked_struct::Elements>::Element>::maybe_update(
+                    old_pointer,
+                    new_value,
+                )
+            },
+        );
+    };
+}
+
+/// The initial per-element revisions of a tracked struct field:
+/// one per element for `#[elements]` fields, none otherwise.
+#[macro_export]
+macro_rules! maybe_element_revisions {
+    (
+        ($maybe_clone:ident, elements, $maybe_default:ident),
+        $field_place:expr,
+        $current_revision:expr,
+    ) => {
+        vec![$current_revision; $field_place.len()]
+    };
+
+    (
+        ($maybe_clone:ident, $maybe_backdate:ident, $maybe_default:ident),
+        $field_place:expr,
+        $current_revision:expr,
+    ) => {
+        Vec::new()
+    };
+}
+
+/// Whether a field of a `lazy_update` tracked struct differs from its old value.
+#[macro_export]
+macro_rules! maybe_changed {
+    (
+        ($maybe_clone:ident, no_backdate, $maybe_default:ident),
+        $field_ty:ty,
+        $old_field_place:expr,
+        $new_field_place:expr,
+        $zalsa:ident,
+    ) => {
+        true
+    };
+
+    (
+        ($maybe_clone:ident, $maybe_backdate:ident, $maybe_default:ident),
+        $field_ty:ty,
+        $old_field_place:expr,
+        $new_field_place:expr,
+        $zalsa:ident,
+    ) => {
+        $zalsa::LazyEqDispatch::<$field_ty>::changed(&$old_field_place, &$new_field_place)
+    };
 }
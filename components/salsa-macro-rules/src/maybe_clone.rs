@@ -18,6 +18,14 @@ macro_rules! maybe_clone {
     ) => {
         std::clone::Clone::clone($field_ref_expr)
     };
+
+    (
+        (borrowed, $maybe_backdate:ident, $maybe_default:ident),
+        $field_ty:ty,
+        $field_ref_expr:expr,
+    ) => {
+        salsa::plumbing::interned::ArenaRef::get(*$field_ref_expr)
+    };
 }
 
 #[macro_export]
@@ -37,4 +45,12 @@ macro_rules! maybe_cloned_ty {
     ) => {
         $field_ty
     };
+
+    (
+        (borrowed, $maybe_backdate:ident, $maybe_default:ident),
+        $db_lt:lifetime,
+        $field_ty:ty
+    ) => {
+        & $db_lt <$field_ty as std::ops::Deref>::Target
+    };
 }
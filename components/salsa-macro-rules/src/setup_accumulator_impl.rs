@@ -38,6 +38,14 @@ macro_rules! setup_accumulator_impl {
                     let db = db.as_dyn_database();
                     $ingredient(db).push(db, self);
                 }
+
+                fn accumulated_so_far<Db>(db: &Db) -> Vec<Self>
+                where
+                    Db: ?Sized + $zalsa::Database,
+                {
+                    let db = db.as_dyn_database();
+                    $ingredient(db).accumulated_so_far(db)
+                }
             }
         };
     };
@@ -20,11 +20,15 @@ macro_rules! setup_input_struct {
         // Field names
         field_ids: [$($field_id:ident),*],
 
-        // Names for field getter methods (typically `foo`)
-        field_getters: [$($field_getter_vis:vis $field_getter_id:ident),*],
+        // Names for field getter methods (typically `foo`), preceded by their doc attributes
+        field_getters: [$($(#[$field_getter_attr:meta])* $field_getter_vis:vis $field_getter_id:ident),*],
 
-        // Names for field setter methods (typically `set_foo`)
-        field_setters: [$($field_setter_vis:vis $field_setter_id:ident),*],
+        // Names for field setter methods (typically `set_foo`), preceded by their doc attributes
+        field_setters: [$($(#[$field_setter_attr:meta])* $field_setter_vis:vis $field_setter_id:ident),*],
+
+        // Names for field setter methods used within a `WriteScope` (typically `set_foo_in`),
+        // preceded by their doc attributes
+        field_scoped_setters: [$($(#[$field_scoped_setter_attr:meta])* $field_scoped_setter_vis:vis $field_scoped_setter_id:ident),*],
 
         // Field types
         field_tys: [$($field_ty:ty),*],
@@ -38,12 +42,23 @@ macro_rules! setup_input_struct {
         // Names for the field durability methods on the builder (typically `foo_durability`)
         field_durability_ids: [$($field_durability_id:ident),*],
 
+        // Fields annotated with `#[delta]`. Each item is the field's index, the visibility and
+        // name of its edit setter (typically `set_foo_edit`), the name of its range getter
+        // (typically `foo_range`), and its type.
+        delta_fields: [$(($delta_field_index:tt, $delta_field_vis:vis $delta_field_setter_id:ident, $delta_field_range_id:ident, $delta_field_ty:ty)),*],
+
         // Number of fields
         num_fields: $N:literal,
 
         // If true, this is a singleton input.
         is_singleton: $is_singleton:tt,
 
+        // If true, a field is annotated with `#[key]`.
+        has_key: $has_key:tt,
+
+        // The index and type of the `#[key]` field (`0` and `()` if there is none).
+        key_field: ($key_field_index:tt, $key_field_ty:ty),
+
         // If true, generate a debug impl.
         generate_debug_impl: $generate_debug_impl:tt,
 
@@ -72,6 +87,7 @@ macro_rules! setup_input_struct {
             impl $zalsa_struct::Configuration for $Configuration {
                 const DEBUG_NAME: &'static str = stringify!($Struct);
                 const FIELD_DEBUG_NAMES: &'static [&'static str] = &[$(stringify!($field_id)),*];
+                const HAS_KEY: bool = $has_key;
                 type Singleton = $zalsa::macro_if! {if $is_singleton {$zalsa::input::Singleton} else {$zalsa::input::NotSingleton}};
 
                 /// The input struct (which wraps an `Id`)
@@ -82,6 +98,25 @@ macro_rules! setup_input_struct {
 
                 /// A array of [`StampedValue<()>`](`StampedValue`) tuples, one per each of the value fields.
                 type Stamps = $zalsa::Array<$zalsa::Stamp, $N>;
+
+                type Key = $key_field_ty;
+
+                fn key(fields: &Self::Fields) -> Self::Key {
+                    $zalsa::macro_if! {
+                        if $has_key {
+                            Clone::clone(&fields.$key_field_index)
+                        } else {
+                            _ = fields;
+                        }
+                    }
+                }
+
+                fn field_debug(fields: &Self::Fields, field_index: usize) -> Option<&dyn std::fmt::Debug> {
+                    match field_index {
+                        $($field_index => Some(&fields.$field_index),)*
+                        _ => unreachable!("field index out of bounds"),
+                    }
+                }
             }
 
             impl $Configuration {
@@ -93,6 +128,10 @@ macro_rules! setup_input_struct {
                     })
                 }
 
+                pub fn ingredient_in<'scope>(scope: &$zalsa::WriteScope<'scope>) -> &'scope $zalsa_struct::IngredientImpl<Self> {
+                    scope.ingredient(&<$zalsa_struct::JarImpl<$Configuration>>::default())
+                }
+
                 pub fn ingredient_mut(db: &mut dyn $zalsa::Database) -> (&mut $zalsa_struct::IngredientImpl<Self>, &mut $zalsa::Runtime) {
                     let zalsa_mut = db.zalsa_mut();
                     let index = zalsa_mut.add_or_lookup_jar_by_type(&<$zalsa_struct::JarImpl<$Configuration>>::default());
@@ -127,6 +166,10 @@ macro_rules! setup_input_struct {
                 fn lookup_ingredient_index(aux: &dyn $zalsa::JarAux) -> core::option::Option<$zalsa::IngredientIndex> {
                     aux.lookup_jar_by_type(&<$zalsa_struct::JarImpl<$Configuration>>::default())
                 }
+
+                fn register_ingredients(db: &dyn $zalsa::Database) {
+                    $Configuration::ingredient(db);
+                }
             }
 
             impl $Struct {
@@ -139,12 +182,54 @@ macro_rules! setup_input_struct {
                     Self::builder($($required_field_id,)*).new(db)
                 }
 
+                /// Registers `provider` to pull the fields of inputs of this type from an external
+                /// source when they may be stale; see
+                /// [`IngredientImpl::set_provider`](`salsa::plumbing::input::IngredientImpl::set_provider`).
+                pub fn set_provider<$Db>(
+                    db: &mut $Db,
+                    provider: impl Fn(Self, &($($field_ty,)*)) -> Option<(($($field_ty,)*), salsa::Durability)> + Send + Sync + 'static,
+                )
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + salsa::Database,
+                {
+                    let (ingredient, _) = $Configuration::ingredient_mut(db.as_dyn_database_mut());
+                    ingredient.set_provider(provider);
+                }
+
                 pub fn builder($($required_field_id: $required_field_ty),*) -> <Self as $zalsa_struct::HasBuilder>::Builder
                 {
                     builder::new_builder($($zalsa::maybe_default!($field_option, $field_ty, $field_id,)),*)
                 }
 
+                $zalsa::macro_if! {
+                    if $is_singleton {} else {
+                        /// Creates an input from each of `builders`, returned in order.
+                        /// Faster than calling `new` on each builder when creating many inputs,
+                        /// and emits a single [`DidCreateInputs`](`salsa::EventKind::DidCreateInputs`)
+                        /// event for all of them.
+                        pub fn builder_many<$Db>(
+                            db: &$Db,
+                            builders: impl IntoIterator<Item = <Self as $zalsa_struct::HasBuilder>::Builder>,
+                        ) -> Vec<Self>
+                        where
+                            // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                            $Db: ?Sized + salsa::Database,
+                        {
+                            let current_revision = $zalsa::current_revision(db);
+                            let ingredient = $Configuration::ingredient(db.as_dyn_database());
+                            ingredient.new_inputs(
+                                db.as_dyn_database(),
+                                builders
+                                    .into_iter()
+                                    .map(|builder| builder::builder_into_inner(builder, current_revision)),
+                            )
+                        }
+                    }
+                }
+
                 $(
+                    $(#[$field_getter_attr])*
                     $field_getter_vis fn $field_getter_id<'db, $Db>(self, db: &'db $Db) -> $zalsa::maybe_cloned_ty!($field_option, 'db, $field_ty)
                     where
                         // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
@@ -164,12 +249,14 @@ macro_rules! setup_input_struct {
                 )*
 
                 $(
+                    $(#[$field_setter_attr])*
                     #[must_use]
                     $field_setter_vis fn $field_setter_id<'db, $Db>(self, db: &'db mut $Db) -> impl salsa::Setter<FieldTy = $field_ty> + 'db
                     where
                         // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
                         $Db: ?Sized + $zalsa::Database,
                     {
+                        $Configuration::ingredient(db.as_dyn_database()).will_set_field(db.as_dyn_database(), self, $field_index);
                         let (ingredient, revision) = $Configuration::ingredient_mut(db.as_dyn_database_mut());
                         $zalsa::input::SetterImpl::new(
                             revision,
@@ -181,6 +268,74 @@ macro_rules! setup_input_struct {
                     }
                 )*
 
+                $(
+                    $(#[$field_scoped_setter_attr])*
+                    #[must_use]
+                    $field_scoped_setter_vis fn $field_scoped_setter_id<'scope>(self, scope: &'scope $zalsa::WriteScope<'_>) -> impl salsa::Setter<FieldTy = $field_ty> + 'scope {
+                        $zalsa::input::ScopedSetterImpl::new(
+                            scope,
+                            self,
+                            $field_index,
+                            $Configuration::ingredient_in(scope),
+                            |fields, f| std::mem::replace(&mut fields.$field_index, f),
+                        )
+                    }
+                )*
+
+                $(
+                    $delta_field_vis fn $delta_field_setter_id<$Db>(self, db: &mut $Db, edit: salsa::TextEdit)
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $Configuration::ingredient(db.as_dyn_database()).will_set_field(db.as_dyn_database(), self, $delta_field_index);
+                        let (ingredient, runtime) = $Configuration::ingredient_mut(db.as_dyn_database_mut());
+                        ingredient.edit_field(
+                            runtime,
+                            self,
+                            $delta_field_index,
+                            edit,
+                            |fields| &mut fields.$delta_field_index,
+                        )
+                    }
+
+                    $delta_field_vis fn $delta_field_range_id<'db, $Db>(self, db: &'db $Db, range: std::ops::Range<usize>) -> &'db <$delta_field_ty as salsa::Editable>::Slice
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        let fields = $Configuration::ingredient(db.as_dyn_database()).field_range(
+                            db.as_dyn_database(),
+                            self,
+                            $delta_field_index,
+                            range.clone(),
+                        );
+                        salsa::Editable::slice(&fields.$delta_field_index, range)
+                    }
+                )*
+
+                $zalsa::macro_if! { $has_key =>
+                    /// Returns the input created by `get_or_create` with the key `key`, if any.
+                    pub fn get_by_key<$Db>(db: &$Db, key: &$key_field_ty) -> Option<Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database,
+                    {
+                        $Configuration::ingredient(db.as_dyn_database()).get_by_key(db.as_dyn_database(), key)
+                    }
+
+                    /// Returns the input created by `get_or_create` with the same key,
+                    /// or creates it with these fields if there is none.
+                    /// Safe to call concurrently: only one input is created per key.
+                    pub fn get_or_create<$Db>(db: &$Db, $($required_field_id: $required_field_ty),*) -> Self
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database,
+                    {
+                        Self::builder($($required_field_id,)*).get_or_create(db)
+                    }
+                }
+
                 $zalsa::macro_if! { $is_singleton =>
                     pub fn try_get<$Db>(db: &$Db) -> Option<Self>
                     where
@@ -237,6 +392,22 @@ macro_rules! setup_input_struct {
                     let (fields, stamps) = builder::builder_into_inner(self, current_revision);
                     ingredient.new_input(db.as_dyn_database(), fields, stamps)
                 }
+
+                $zalsa::macro_if! { $has_key =>
+                    /// Returns the input created by `get_or_create` with the same key,
+                    /// or creates it with the set values if there is none.
+                    #[must_use]
+                    pub fn get_or_create<$Db>(self, db: &$Db) -> $Struct
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + salsa::Database
+                    {
+                        let current_revision = $zalsa::current_revision(db);
+                        let ingredient = $Configuration::ingredient(db.as_dyn_database());
+                        let (fields, stamps) = builder::builder_into_inner(self, current_revision);
+                        ingredient.get_or_create(db.as_dyn_database(), fields, stamps)
+                    }
+                }
             }
 
             mod builder {
@@ -42,8 +42,8 @@ macro_rules! setup_interned_struct {
         // Field names
         field_ids: [$($field_id:ident),*],
 
-        // Names for field setter methods (typically `set_foo`)
-        field_getters: [$($field_getter_vis:vis $field_getter_id:ident),*],
+        // Names for field getter methods (typically `foo`), preceded by their doc attributes
+        field_getters: [$($(#[$field_getter_attr:meta])* $field_getter_vis:vis $field_getter_id:ident),*],
 
         // Field types
         field_tys: [$($field_ty:ty),*],
@@ -60,6 +60,13 @@ macro_rules! setup_interned_struct {
         // If true, generate a debug impl.
         generate_debug_impl: $generate_debug_impl:tt,
 
+        // If true, the user gave `data = $DataStruct`: generate a struct of that name
+        // holding a reference to each field, and a `data` method returning it.
+        data_struct: ($has_data_struct:tt, $DataStruct:ident),
+
+        // If true, generate the `index` and `count` accessors.
+        dense: $dense:tt,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -78,6 +85,14 @@ macro_rules! setup_interned_struct {
             std::marker::PhantomData < & $interior_lt salsa::plumbing::interned::Value <$StructWithStatic> >
         );
 
+        salsa::plumbing::macro_if! { $has_data_struct =>
+            #[doc = concat!("All fields of a [`", stringify!($Struct), "`], as returned by its `data` method.")]
+            #[derive(Copy, Clone)]
+            $vis struct $DataStruct<$db_lt> {
+                $($field_getter_vis $field_id: &$db_lt $field_ty,)*
+            }
+        }
+
         const _: () = {
             use salsa::plumbing as $zalsa;
             use $zalsa::interned as $zalsa_struct;
@@ -174,6 +189,10 @@ macro_rules! setup_interned_struct {
                 fn lookup_ingredient_index(aux: &dyn $zalsa::JarAux) -> core::option::Option<$zalsa::IngredientIndex> {
                     aux.lookup_jar_by_type(&<$zalsa_struct::JarImpl<$Configuration>>::default())
                 }
+
+                fn register_ingredients(db: &dyn $zalsa::Database) {
+                    $Configuration::ingredient(db);
+                }
             }
 
             unsafe impl< $($db_lt_arg)? > $zalsa::Update for $Struct< $($db_lt_arg)? > {
@@ -201,7 +220,38 @@ macro_rules! setup_interned_struct {
                         StructKey::<$db_lt>($($field_id,)* std::marker::PhantomData::default()), |_, data| ($($zalsa::interned::Lookup::into_owned(data.$field_index),)*))
                 }
 
+                /// Like the constructor, but if the value is not interned yet, it gets the given
+                /// durability instead of one inferred from the active query or the
+                /// [database default](`salsa::Database::set_default_intern_durability`).
+                pub fn new_with_durability<$Db, $($indexed_ty: $zalsa::interned::Lookup<$field_ty> + std::hash::Hash,)*>(db: &$db_lt $Db,  $($field_id: $indexed_ty,)* durability: salsa::Durability) -> Self
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + salsa::Database,
+                    $(
+                        $field_ty: $zalsa::interned::HashEqLike<$indexed_ty>,
+                    )*
+                {
+                    $Configuration::ingredient(db).intern_with_durability(db.as_dyn_database(),
+                        StructKey::<$db_lt>($($field_id,)* std::marker::PhantomData::default()), durability, |_, data| ($($zalsa::interned::Lookup::into_owned(data.$field_index),)*))
+                }
+
+                /// Returns the value with the given fields if it was interned already,
+                /// without interning it. If it was not, calling this within a query is
+                /// reported as an untracked read, since the value could be interned later.
+                pub fn get<$Db, $($indexed_ty: $zalsa::interned::Lookup<$field_ty> + std::hash::Hash,)*>(db: &$db_lt $Db,  $($field_id: $indexed_ty),*) -> Option<Self>
+                where
+                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                    $Db: ?Sized + salsa::Database,
+                    $(
+                        $field_ty: $zalsa::interned::HashEqLike<$indexed_ty>,
+                    )*
+                {
+                    $Configuration::ingredient(db).lookup(db.as_dyn_database(),
+                        StructKey::<$db_lt>($($field_id,)* std::marker::PhantomData::default()))
+                }
+
                 $(
+                    $(#[$field_getter_attr])*
                     $field_getter_vis fn $field_getter_id<$Db>(self, db: &'db $Db) -> $zalsa::maybe_cloned_ty!($field_option, 'db, $field_ty)
                     where
                         // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
@@ -216,6 +266,43 @@ macro_rules! setup_interned_struct {
                     }
                 )*
 
+                $zalsa::macro_if! { $has_data_struct =>
+                    /// Returns all fields at once, with a single lookup.
+                    $vis fn data<$Db>(self, db: &'db $Db) -> $DataStruct<'db>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        let fields = $Configuration::ingredient(db).fields(db.as_dyn_database(), self);
+                        $DataStruct {
+                            $($field_id: &fields.$field_index,)*
+                        }
+                    }
+                }
+
+                $zalsa::macro_if! { $dense =>
+                    /// The position of this value among all values of this type, in the order
+                    /// they were interned. Indices start at 0 and are contiguous, so they are
+                    /// suitable for indexing side tables.
+                    $vis fn index<$Db>(self, db: &'db $Db) -> u32
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $Configuration::ingredient(db).index(db.as_dyn_database(), $zalsa::AsId::as_id(&self))
+                    }
+
+                    /// The number of values of this type interned so far; every existing
+                    /// value's [`index`](`Self::index`) is less than this.
+                    $vis fn count<$Db>(db: &'db $Db) -> u32
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $Configuration::ingredient(db).count(db.as_dyn_database())
+                    }
+                }
+
                 /// Default debug formatting for this struct (may be useful if you define your own `Debug` impl)
                 pub fn default_debug_fmt(this: Self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                     $zalsa::with_attached_database(|db| {
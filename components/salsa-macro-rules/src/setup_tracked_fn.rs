@@ -28,6 +28,16 @@ macro_rules! setup_tracked_fn {
         // Types of the function arguments (may reference `$generics`).
         input_tys: [$($input_ty:ty),*],
 
+        // An identifier for each trailing argument marked `#[salsa::not_key]`,
+        // which are not part of the key and are passed to executions through `$CONTEXT`.
+        context_ids: [$($context_id:ident),*],
+
+        // Types of the `#[salsa::not_key]` arguments (shared references).
+        context_tys: [$($context_ty:ty),*],
+
+        // True if there are `#[salsa::not_key]` arguments.
+        contextual: $contextual:tt,
+
         // Return type of the function (may reference `$generics`).
         output_ty: $output_ty:ty,
 
@@ -40,9 +50,21 @@ macro_rules! setup_tracked_fn {
         // Name of cycle recovery strategy variant to use.
         cycle_recovery_strategy: $cycle_recovery_strategy:ident,
 
+        // How long the function may execute (an `Option<Duration>` expression).
+        timeout: ($($timeout:tt)*),
+
+        // Path to the function computing the value of a timed out execution.
+        timeout_result_fn: ($($timeout_result_fn:tt)*),
+
+        // Executions faster than this do not memoize their value (an `Option<Duration>` expression).
+        adaptive: ($($adaptive:tt)*),
+
         // If true, this is specifiable.
         is_specifiable: $is_specifiable:tt,
 
+        // If true, generate `specify_unchecked`.
+        is_specifiable_unchecked: $is_specifiable_unchecked:tt,
+
         // If true, don't backdate the value when the new value compares equal to the old value.
         no_eq: $no_eq:tt,
 
@@ -52,9 +74,50 @@ macro_rules! setup_tracked_fn {
         // LRU capacity (a literal, maybe 0)
         lru: $lru:tt,
 
+        // If true, memoized values may be dropped by `Database::trim_memory` (the `weak` flag).
+        weak: $weak:tt,
+
+        // True if the function returns `()`.
+        unit: $unit:tt,
+
         // True if we `return_ref` flag was given to the function
         return_ref: $return_ref:tt,
 
+        // True if the `strict_revisions` flag was given to the function,
+        // which then returns a `salsa::RevisionRef` rather than a reference
+        strict_revisions: $strict_revisions:tt,
+
+        // True if the `returns(arc)` option was given to the function
+        return_arc: $return_arc:tt,
+
+        // If true, equal output values are shared across keys (the `dedupe` flag).
+        dedupe: $dedupe:tt,
+
+        // If true, backdating compares hashes of the old and new values (the `fingerprint` flag).
+        fingerprint: $fingerprint:tt,
+
+        // If true, values are stored in an `Arc` (implied by `dedupe`, `fingerprint` and `return_arc`).
+        shared: $shared:tt,
+
+        // If true, values are memoized in the form given by the codec (the `store_with` option).
+        stored: $stored:tt,
+
+        // Path to the `store_with` codec, or `()` if there is none.
+        codec: ($($codec:tt)*),
+
+        // If true, backdating compares values with the function given by the `compare_with` option.
+        compared: $compared:tt,
+
+        // Path to the `compare_with` function, or `()` if there is none.
+        compare_fn: ($($compare_fn:tt)*),
+
+        // If true, the function returns a `Result` whose errors are shared (the `result` flag);
+        // `$output_ty` is then `Result<T, Arc<E>>`, while the user's function returns `Result<T, E>`.
+        result: $result:tt,
+
+        // If true, errors are recomputed in every new revision (the `result(retry)` flag).
+        retry_errors: $retry_errors:tt,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -64,6 +127,7 @@ macro_rules! setup_tracked_fn {
             $InternedData:ident,
             $FN_CACHE:ident,
             $INTERN_CACHE:ident,
+            $CONTEXT:ident,
             $inner:ident,
         ]
     ) => {
@@ -73,11 +137,24 @@ macro_rules! setup_tracked_fn {
         $vis fn $fn_name<$db_lt>(
             $db: &$db_lt dyn $Db,
             $($input_id: $input_ty,)*
+            $($context_id: $context_ty,)*
         ) -> salsa::plumbing::macro_if! {
             if $return_ref {
-                &$db_lt $output_ty
+                salsa::plumbing::macro_if! {
+                    if $strict_revisions {
+                        salsa::RevisionRef<$db_lt, $output_ty>
+                    } else {
+                        &$db_lt $output_ty
+                    }
+                }
             } else {
-                $output_ty
+                salsa::plumbing::macro_if! {
+                    if $return_arc {
+                        std::sync::Arc<$output_ty>
+                    } else {
+                        $output_ty
+                    }
+                }
             }
         } {
             use salsa::plumbing as $zalsa;
@@ -87,6 +164,12 @@ macro_rules! setup_tracked_fn {
             static $FN_CACHE: $zalsa::IngredientCache<$zalsa::function::IngredientImpl<$Configuration>> =
                 $zalsa::IngredientCache::new();
 
+            $zalsa::macro_if! { $contextual =>
+                std::thread_local! {
+                    static $CONTEXT: $zalsa::function::Context = const { $zalsa::function::Context::new() };
+                }
+            }
+
             $zalsa::macro_if! {
                 if $needs_interner {
                     #[derive(Copy, Clone)]
@@ -130,6 +213,9 @@ macro_rules! setup_tracked_fn {
                 fn fn_ingredient(db: &dyn $Db) -> &$zalsa::function::IngredientImpl<$Configuration> {
                     $FN_CACHE.get_or_create(db.as_dyn_database(), || {
                         <dyn $Db as $Db>::zalsa_db(db);
+                        $zalsa::macro_if! { if $needs_interner {} else {
+                            <$InternedData as $zalsa::SalsaStructInDb>::register_ingredients(db.as_dyn_database());
+                        } }
                         db.zalsa().add_or_lookup_jar_by_type(&$Configuration)
                     })
                 }
@@ -154,10 +240,32 @@ macro_rules! setup_tracked_fn {
 
                 type Input<$db_lt> = ($($input_ty),*);
 
-                type Output<$db_lt> = $output_ty;
+                type Output<$db_lt> = $zalsa::macro_if! {
+                    if $shared {
+                        std::sync::Arc<$output_ty>
+                    } else {
+                        $zalsa::macro_if! {
+                            if $stored {
+                                <$($codec)* as salsa::Codec<$output_ty>>::Stored
+                            } else {
+                                $output_ty
+                            }
+                        }
+                    }
+                };
 
                 const CYCLE_STRATEGY: $zalsa::CycleRecoveryStrategy = $zalsa::CycleRecoveryStrategy::$cycle_recovery_strategy;
 
+                const TIMEOUT: Option<std::time::Duration> = $($timeout)*;
+
+                const ADAPTIVE: Option<std::time::Duration> = $($adaptive)*;
+
+                const WEAK: bool = $weak;
+
+                const UNIT: bool = $unit;
+
+                const CONTEXTUAL: bool = $contextual;
+
                 fn should_backdate_value(
                     old_value: &Self::Output<'_>,
                     new_value: &Self::Output<'_>,
@@ -166,7 +274,84 @@ macro_rules! setup_tracked_fn {
                         if $no_eq {
                             false
                         } else {
-                            $zalsa::should_backdate_value(old_value, new_value)
+                            $zalsa::macro_if! {
+                                if $compared {
+                                    $zalsa::macro_if! {
+                                        if $shared {
+                                            $($compare_fn)*(&**old_value, &**new_value)
+                                        } else {
+                                            $($compare_fn)*(old_value, new_value)
+                                        }
+                                    }
+                                } else {
+                                    $zalsa::should_backdate_value(old_value, new_value)
+                                }
+                            }
+                        }
+                    }
+                }
+
+                fn dedupe_hash(value: &Self::Output<'_>) -> Option<u64> {
+                    $zalsa::macro_if! {
+                        if $dedupe {
+                            Some($zalsa::function::dedupe_hash(value))
+                        } else {
+                            $zalsa::macro_if! {
+                                if $result {
+                                    $zalsa::function::error_hash(value)
+                                } else {
+                                    None
+                                }
+                            }
+                        }
+                    }
+                }
+
+                fn dedupe_with<$db_lt>(
+                    candidate: &Self::Output<$db_lt>,
+                    value: Self::Output<$db_lt>,
+                ) -> Result<Self::Output<$db_lt>, Self::Output<$db_lt>> {
+                    $zalsa::macro_if! {
+                        if $dedupe {
+                            $zalsa::function::dedupe_with(candidate, value)
+                        } else {
+                            $zalsa::macro_if! {
+                                if $result {
+                                    $zalsa::function::share_error(candidate, value)
+                                } else {
+                                    Err(value)
+                                }
+                            }
+                        }
+                    }
+                }
+
+                fn fingerprint(value: &Self::Output<'_>) -> Option<u64> {
+                    $zalsa::macro_if! {
+                        if $fingerprint {
+                            Some($zalsa::function::fingerprint(value))
+                        } else {
+                            None
+                        }
+                    }
+                }
+
+                fn should_retry(value: &Self::Output<'_>) -> bool {
+                    $zalsa::macro_if! {
+                        if $retry_errors {
+                            value.is_err()
+                        } else {
+                            false
+                        }
+                    }
+                }
+
+                fn share_value<$db_lt>(value: &Self::Output<$db_lt>) -> Option<Self::Output<$db_lt>> {
+                    $zalsa::macro_if! {
+                        if $shared {
+                            Some(std::sync::Arc::clone(value))
+                        } else {
+                            None
                         }
                     }
                 }
@@ -174,15 +359,87 @@ macro_rules! setup_tracked_fn {
                 fn execute<$db_lt>($db: &$db_lt Self::DbView, ($($input_id),*): ($($input_ty),*)) -> Self::Output<$db_lt> {
                     $($inner_fn)*
 
-                    $inner($db, $($input_id),*)
+                    $zalsa::macro_if! { $contextual =>
+                        // SAFETY: `$CONTEXT` is only entered with the arguments of this function.
+                        let ($($context_id,)*) = unsafe {
+                            $zalsa::function::Context::get::<($($context_ty,)*)>(&$CONTEXT)
+                        };
+                    }
+
+                    $zalsa::macro_if! {
+                        if $shared {
+                            std::sync::Arc::new($inner($db, $($input_id,)* $($context_id,)*))
+                        } else {
+                            $zalsa::macro_if! {
+                                if $result {
+                                    $zalsa::function::share_result($inner($db, $($input_id,)* $($context_id,)*))
+                                } else {
+                                    $zalsa::macro_if! {
+                                        if $stored {
+                                            <$($codec)* as salsa::Codec<$output_ty>>::encode(&$inner($db, $($input_id,)* $($context_id,)*))
+                                        } else {
+                                            $inner($db, $($input_id,)* $($context_id,)*)
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                 }
 
+                // The default cycle recovery function panics, so wrapping its result is unreachable.
+                #[allow(unreachable_code)]
                 fn recover_from_cycle<$db_lt>(
                     db: &$db_lt dyn $Db,
                     cycle: &$zalsa::Cycle,
                     ($($input_id),*): ($($input_ty),*)
                 ) -> Self::Output<$db_lt> {
-                    $($cycle_recovery_fn)*(db, cycle, $($input_id),*)
+                    $zalsa::macro_if! {
+                        if $shared {
+                            std::sync::Arc::new($($cycle_recovery_fn)*(db, cycle, $($input_id),*))
+                        } else {
+                            $zalsa::macro_if! {
+                                if $result {
+                                    $zalsa::function::share_result($($cycle_recovery_fn)*(db, cycle, $($input_id),*))
+                                } else {
+                                    $zalsa::macro_if! {
+                                        if $stored {
+                                            <$($codec)* as salsa::Codec<$output_ty>>::encode(&$($cycle_recovery_fn)*(db, cycle, $($input_id),*))
+                                        } else {
+                                            $($cycle_recovery_fn)*(db, cycle, $($input_id),*)
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Without a timeout, the default function panics, so wrapping its result is unreachable.
+                #[allow(unreachable_code)]
+                fn recover_from_timeout<$db_lt>(
+                    db: &$db_lt dyn $Db,
+                    ($($input_id),*): ($($input_ty),*)
+                ) -> Self::Output<$db_lt> {
+                    $zalsa::macro_if! {
+                        if $shared {
+                            std::sync::Arc::new($($timeout_result_fn)*(db, $($input_id),*))
+                        } else {
+                            $zalsa::macro_if! {
+                                if $result {
+                                    $zalsa::function::share_result($($timeout_result_fn)*(db, $($input_id),*))
+                                } else {
+                                    $zalsa::macro_if! {
+                                        if $stored {
+                                            <$($codec)* as salsa::Codec<$output_ty>>::encode(&$($timeout_result_fn)*(db, $($input_id),*))
+                                        } else {
+                                            $($timeout_result_fn)*(db, $($input_id),*)
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                 }
 
                 fn id_to_input<$db_lt>(db: &$db_lt Self::DbView, key: salsa::Id) -> Self::Input<$db_lt> {
@@ -190,7 +447,7 @@ macro_rules! setup_tracked_fn {
                         if $needs_interner {
                             $Configuration::intern_ingredient(db).data(db.as_dyn_database(), key).clone()
                         } else {
-                            $zalsa::FromId::from_id(key)
+                            $zalsa::FromIdWithDb::from_id(key, db.as_dyn_database())
                         }
                     }
                 }
@@ -202,29 +459,33 @@ macro_rules! setup_tracked_fn {
                     aux: &dyn $zalsa::JarAux,
                     first_index: $zalsa::IngredientIndex,
                 ) -> Vec<Box<dyn $zalsa::Ingredient>> {
-                    let struct_index = $zalsa::macro_if! {
+                    let struct_indices = $zalsa::macro_if! {
                         if $needs_interner {
-                            first_index.successor(0)
+                            vec![first_index.successor(0)]
                         } else {
-                            <$InternedData as $zalsa::SalsaStructInDb>::lookup_ingredient_index(aux)
-                                .expect(
-                                    "Salsa struct is passed as an argument of a tracked function, but its ingredient hasn't been added!"
-                                )
+                            <$InternedData as $zalsa::SalsaStructInDb>::lookup_ingredient_indices(aux)
                         }
                     };
+                    assert!(
+                        !struct_indices.is_empty(),
+                        "Salsa struct is passed as an argument of a tracked function, but its ingredient hasn't been added!"
+                    );
 
                     let fn_ingredient = <$zalsa::function::IngredientImpl<$Configuration>>::new(
-                        struct_index,
+                        &struct_indices,
                         first_index,
                         aux,
                     );
-                    fn_ingredient.set_capacity($lru);
+                    fn_ingredient.set_capacity($zalsa::macro_if! {
+                        if0 $lru { aux.default_lru_capacity() } else { $lru }
+                    });
                     $zalsa::macro_if! {
                         if $needs_interner {
                             vec![
                                 Box::new(fn_ingredient),
                                 Box::new(<$zalsa::interned::IngredientImpl<$Configuration>>::new(
-                                    first_index.successor(0)
+                                    first_index.successor(0),
+                                    aux,
                                 )),
                             ]
                         } else {
@@ -245,8 +506,13 @@ macro_rules! setup_tracked_fn {
                 pub fn accumulated<$db_lt, A: salsa::Accumulator>(
                     $db: &$db_lt dyn $Db,
                     $($input_id: $input_ty,)*
+                    $($context_id: $context_ty,)*
                 ) -> Vec<A> {
                     use salsa::plumbing as $zalsa;
+                    $zalsa::macro_if! { $contextual =>
+                        let context = ($($context_id,)*);
+                        let _context = $zalsa::function::Context::enter(&$CONTEXT, &context);
+                    }
                     let key = $zalsa::macro_if! {
                         if $needs_interner {
                             $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
@@ -258,6 +524,200 @@ macro_rules! setup_tracked_fn {
                     $Configuration::fn_ingredient($db).accumulated_by::<A>($db, key)
                 }
 
+                /// The key of this function's memoized value for the given arguments,
+                /// e.g. for use with [`Database::checksum`](`salsa::Database::checksum`).
+                ///
+                /// Returns `None` if the function takes several arguments and was never
+                /// called with these; they are looked up, but not interned.
+                pub fn database_key_index<$db_lt>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $input_ty,)*
+                ) -> Option<salsa::DatabaseKeyIndex> {
+                    use salsa::plumbing as $zalsa;
+                    let key = $zalsa::macro_if! {
+                        if $needs_interner {
+                            $Configuration::intern_ingredient($db).lookup($db.as_dyn_database(), ($($input_id),*))?.0
+                        } else {
+                            $zalsa::AsId::as_id(&($($input_id),*))
+                        }
+                    };
+
+                    Some($Configuration::fn_ingredient($db).database_key_index(key))
+                }
+
+                /// Like calling the function, but returns `Err` instead of starting new work
+                /// if another handle is waiting to write to the database,
+                /// see [`CancellationMode::Cooperative`](`salsa::CancellationMode::Cooperative`).
+                pub fn try_call<$db_lt>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $input_ty,)*
+                    $($context_id: $context_ty,)*
+                ) -> Result<salsa::plumbing::macro_if! {
+                    if $return_ref {
+                        salsa::plumbing::macro_if! {
+                            if $strict_revisions {
+                                salsa::RevisionRef<$db_lt, $output_ty>
+                            } else {
+                                &$db_lt $output_ty
+                            }
+                        }
+                    } else {
+                        salsa::plumbing::macro_if! {
+                            if $return_arc {
+                                std::sync::Arc<$output_ty>
+                            } else {
+                                $output_ty
+                            }
+                        }
+                    }
+                }, salsa::Cancelled> {
+                    $db.check_cancelled()?;
+                    Ok($fn_name($db, $($input_id,)* $($context_id,)*))
+                }
+
+                $zalsa::macro_if! { $result =>
+                    /// True if this function's value for the given arguments is an error,
+                    /// without cloning the value.
+                    pub fn is_err<$db_lt>(
+                        $db: &$db_lt dyn $Db,
+                        $($input_id: $input_ty,)*
+                        $($context_id: $context_ty,)*
+                    ) -> bool {
+                        use salsa::plumbing as $zalsa;
+                        $zalsa::macro_if! { $contextual =>
+                            let context = ($($context_id,)*);
+                            let _context = $zalsa::function::Context::enter(&$CONTEXT, &context);
+                        }
+                        let key = $zalsa::macro_if! {
+                            if $needs_interner {
+                                $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                            } else {
+                                $zalsa::AsId::as_id(&($($input_id),*))
+                            }
+                        };
+
+                        $zalsa::attach($db, || $Configuration::fn_ingredient($db).fetch($db, key).is_err())
+                    }
+                }
+
+                /// Pins this function's value for the given arguments, and those of the queries
+                /// it read, up to `depth` dependency edges away, so that the LRU and
+                /// `Database::trim_memory` never drop them, e.g. for the entry points of an editor.
+                ///
+                /// The dependencies are those of the last recorded execution, so call this
+                /// after the value was computed. Calling it again for the same arguments replaces
+                /// the pinned subtree. Pins last until `unpin_subtree` is called or the database is dropped.
+                pub fn pin_subtree<$db_lt>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $input_ty,)*
+                    depth: usize,
+                ) {
+                    use salsa::plumbing as $zalsa;
+                    let key = $zalsa::macro_if! {
+                        if $needs_interner {
+                            $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                        } else {
+                            $zalsa::AsId::as_id(&($($input_id),*))
+                        }
+                    };
+
+                    $Configuration::fn_ingredient($db).pin_subtree($db.as_dyn_database(), key, depth)
+                }
+
+                /// Unpins the values pinned by `pin_subtree` for the given arguments,
+                /// so that they can be evicted again unless another subtree pins them.
+                pub fn unpin_subtree<$db_lt>(
+                    $db: &$db_lt dyn $Db,
+                    $($input_id: $input_ty,)*
+                ) {
+                    use salsa::plumbing as $zalsa;
+                    let key = $zalsa::macro_if! {
+                        if $needs_interner {
+                            $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                        } else {
+                            $zalsa::AsId::as_id(&($($input_id),*))
+                        }
+                    };
+
+                    $Configuration::fn_ingredient($db).unpin_subtree($db.as_dyn_database(), key)
+                }
+
+                $zalsa::if_dependency_inspection! {
+                    /// The queries read by the last recorded execution of this function for the
+                    /// given arguments, e.g. to prefetch them on background threads.
+                    ///
+                    /// The memoized value is not validated first, so the result can be stale:
+                    /// it may describe an execution from an earlier revision, whose next execution
+                    /// reads different queries. It is empty if the function was never executed
+                    /// for these arguments or the value was evicted.
+                    pub fn dependencies<$db_lt>(
+                        $db: &$db_lt dyn $Db,
+                        $($input_id: $input_ty,)*
+                    ) -> Vec<salsa::DatabaseKeyIndex> {
+                        use salsa::plumbing as $zalsa;
+                        let key = $zalsa::macro_if! {
+                            if $needs_interner {
+                                $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                            } else {
+                                $zalsa::AsId::as_id(&($($input_id),*))
+                            }
+                        };
+
+                        $Configuration::fn_ingredient($db).last_dependencies($db, key)
+                    }
+                }
+
+                $zalsa::macro_if! { $is_specifiable_unchecked =>
+                    /// Assigns `value` as the result of this function for the given arguments,
+                    /// from within another tracked function. Unlike `specify`, the arguments
+                    /// need not be a tracked struct created by the calling function, so this
+                    /// can seed values keyed by interned structs.
+                    ///
+                    /// The value stays valid in later revisions as long as the calling function
+                    /// is up to date or assigns it again; it becomes stale, and this function's
+                    /// body runs on the next read, if the calling function re-executes without
+                    /// assigning it.
+                    ///
+                    /// Unlike `specify`, nothing checks that results still do not depend on
+                    /// evaluation order: that holds only if, in every revision, no query reads
+                    /// this function's value for these arguments before it is assigned (or all
+                    /// such reads would have obtained the same value). Breaking this is not
+                    /// memory-unsafe, as the values obtained by earlier reads are kept alive
+                    /// until the next revision.
+                    pub fn specify_unchecked<$db_lt>(
+                        $db: &$db_lt dyn $Db,
+                        $($input_id: $input_ty,)*
+                        value: $output_ty,
+                    ) {
+                        use salsa::plumbing as $zalsa;
+                        let key = $zalsa::macro_if! {
+                            if $needs_interner {
+                                $Configuration::intern_ingredient($db).intern_id($db.as_dyn_database(), ($($input_id),*), |_, data| data)
+                            } else {
+                                $zalsa::AsId::as_id(&($($input_id),*))
+                            }
+                        };
+
+                        $Configuration::fn_ingredient($db).specify_unchecked_and_record(
+                            $db,
+                            key,
+                            $zalsa::macro_if! {
+                                if $shared {
+                                    std::sync::Arc::new(value)
+                                } else {
+                                    $zalsa::macro_if! {
+                                        if $stored {
+                                            <$($codec)* as salsa::Codec<$output_ty>>::encode(&value)
+                                        } else {
+                                            value
+                                        }
+                                    }
+                                }
+                            },
+                        )
+                    }
+                }
+
                 $zalsa::macro_if! { $is_specifiable =>
                     pub fn specify<$db_lt>(
                         $db: &$db_lt dyn $Db,
@@ -268,17 +728,57 @@ macro_rules! setup_tracked_fn {
                         $Configuration::fn_ingredient($db).specify_and_record(
                             $db,
                             key,
-                            value,
+                            $zalsa::macro_if! {
+                                if $shared {
+                                    std::sync::Arc::new(value)
+                                } else {
+                                    $zalsa::macro_if! {
+                                        if $stored {
+                                            <$($codec)* as salsa::Codec<$output_ty>>::encode(&value)
+                                        } else {
+                                            value
+                                        }
+                                    }
+                                }
+                            },
                         )
                     }
                 }
 
-                $zalsa::macro_if! { if0 $lru { } else {
-                    #[allow(dead_code)]
-                    fn set_lru_capacity(db: &dyn $Db, value: usize) {
-                        $Configuration::fn_ingredient(db).set_capacity(value);
-                    }
-                } }
+                /// Sets the number of values of this function that are kept in memory,
+                /// evicting the least recently used ones that no longer fit;
+                /// `0` keeps all values. Initially, this is the function's `lru` option, if any.
+                pub fn set_lru_capacity(db: &mut dyn $Db, value: usize) {
+                    $Configuration::fn_ingredient(db).set_lru_capacity(db.as_dyn_database(), value);
+                }
+
+                /// Calls `visitor` with each memoized value of this function, in the form in
+                /// which it is stored, e.g. to persist them;
+                /// see [`IngredientImpl::visit_memos`](`salsa::plumbing::function::IngredientImpl::visit_memos`).
+                pub fn visit_memos<$db_lt>(
+                    $db: &$db_lt dyn $Db,
+                    visitor: impl FnMut(salsa::plumbing::function::MemoEntry<'_, salsa::plumbing::macro_if! {
+                        if $shared {
+                            std::sync::Arc<$output_ty>
+                        } else {
+                            salsa::plumbing::macro_if! {
+                                if $stored {
+                                    <$($codec)* as salsa::Codec<$output_ty>>::Stored
+                                } else {
+                                    $output_ty
+                                }
+                            }
+                        }
+                    }>),
+                ) {
+                    use salsa::plumbing as $zalsa;
+                    $Configuration::fn_ingredient($db).visit_memos($db.zalsa(), visitor)
+                }
+            }
+
+            $zalsa::macro_if! { $contextual =>
+                let context = ($($context_id,)*);
+                let _context = $zalsa::function::Context::enter(&$CONTEXT, &context);
             }
 
             $zalsa::attach($db, || {
@@ -294,10 +794,46 @@ macro_rules! setup_tracked_fn {
                 };
 
                 $zalsa::macro_if! {
-                    if $return_ref {
-                        result
+                    if $shared {
+                        $zalsa::macro_if! {
+                            if $return_ref {
+                                $zalsa::macro_if! {
+                                    if $strict_revisions {
+                                        $zalsa::revision_ref($db.as_dyn_database(), &**result)
+                                    } else {
+                                        &**result
+                                    }
+                                }
+                            } else {
+                                $zalsa::macro_if! {
+                                    if $return_arc {
+                                        std::sync::Arc::clone(result)
+                                    } else {
+                                        <$output_ty as std::clone::Clone>::clone(&**result)
+                                    }
+                                }
+                            }
+                        }
                     } else {
-                        <$output_ty as std::clone::Clone>::clone(result)
+                        $zalsa::macro_if! {
+                            if $return_ref {
+                                $zalsa::macro_if! {
+                                    if $strict_revisions {
+                                        $zalsa::revision_ref($db.as_dyn_database(), result)
+                                    } else {
+                                        result
+                                    }
+                                }
+                            } else {
+                                $zalsa::macro_if! {
+                                    if $stored {
+                                        <$($codec)* as salsa::Codec<$output_ty>>::decode(result)
+                                    } else {
+                                        <$output_ty as std::clone::Clone>::clone(result)
+                                    }
+                                }
+                            }
+                        }
                     }
                 }
             })
@@ -20,8 +20,8 @@ macro_rules! setup_tracked_struct {
         // Field names
         field_ids: [$($field_id:ident),*],
 
-        // Field names
-        field_getters: [$($field_getter_vis:vis $field_getter_id:ident),*],
+        // Names for field getter methods (typically `foo`), preceded by their doc attributes
+        field_getters: [$($(#[$field_getter_attr:meta])* $field_getter_vis:vis $field_getter_id:ident),*],
 
         // Field types, may reference `db_lt`
         field_tys: [$($field_ty:ty),*],
@@ -35,7 +35,8 @@ macro_rules! setup_tracked_struct {
         // A set of "field options". Each field option is a tuple `(maybe_clone, maybe_backdate)` where:
         //
         // * `maybe_clone` is either the identifier `clone` or `no_clone`
-        // * `maybe_backdate` is either the identifier `backdate` or `no_backdate`
+        // * `maybe_backdate` is either the identifier `backdate`, `no_backdate`,
+        //   or `elements` (for `#[elements]` fields, backdated element by element)
         //
         // These are used to drive conditional logic for each field via recursive macro invocation
         // (see e.g. @maybe_clone below).
@@ -44,9 +45,24 @@ macro_rules! setup_tracked_struct {
         // Number of fields
         num_fields: $N:literal,
 
+        // The `#[elements]` fields: index, visibility and name of the element getter, and type
+        element_fields: [$(($element_index:tt, $element_getter_vis:vis $element_getter_id:ident, $element_ty:ty)),*],
+
+        // If true, some fields are `#[elements]` fields
+        has_element_fields: $has_element_fields:tt,
+
         // If true, generate a debug impl.
         generate_debug_impl: $generate_debug_impl:tt,
 
+        // If true, fields are compared with their old values when first read (the `lazy_update` flag).
+        lazy_update: $lazy_update:tt,
+
+        // If true, this is a singleton tracked struct.
+        is_singleton: $is_singleton:tt,
+
+        // If true, the constructor takes a key identifying the struct (the `identity_key` flag).
+        identity_key: $identity_key:tt,
+
         // Annoyingly macro-rules hygiene does not extend to items defined in the macro.
         // We have the procedural macro generate names for those items that are
         // not used elsewhere in the user's code.
@@ -99,16 +115,45 @@ macro_rules! setup_tracked_struct {
                 }
 
                 fn id_fields(fields: &Self::Fields<'_>) -> impl std::hash::Hash {
-                    ( $( &fields.$id_field_index ),* )
+                    // A singleton keeps its identity whatever its fields.
+                    $zalsa::macro_if! {
+                        if $is_singleton {
+                            ()
+                        } else {
+                            ( $( &fields.$id_field_index ),* )
+                        }
+                    }
                 }
 
                 fn new_revisions(current_revision: $Revision) -> Self::Revisions {
                     $zalsa::Array::new([current_revision; $N])
                 }
 
+                fn new_element_revisions(
+                    fields: &Self::Fields<'_>,
+                    current_revision: $Revision,
+                ) -> Box<[Vec<$Revision>]> {
+                    $zalsa::macro_if! {
+                        if $has_element_fields {
+                            Box::new([
+                                $(
+                                    $crate::maybe_element_revisions!(
+                                        $field_option,
+                                        fields.$field_index,
+                                        current_revision,
+                                    ),
+                                )*
+                            ])
+                        } else {
+                            Box::default()
+                        }
+                    }
+                }
+
                 unsafe fn update_fields<$db_lt>(
                     current_revision: $Revision,
                     revisions: &mut Self::Revisions,
+                    element_revisions: &mut [Vec<$Revision>],
                     old_fields: *mut Self::Fields<$db_lt>,
                     new_fields: Self::Fields<$db_lt>,
                 ) {
@@ -121,12 +166,47 @@ macro_rules! setup_tracked_struct {
                                 (*old_fields).$field_index,
                                 new_fields.$field_index,
                                 revisions[$field_index],
+                                element_revisions[$field_index],
                                 current_revision,
                                 $zalsa,
                             );
                         )*
                     }
                 }
+
+                const LAZY_UPDATE: bool = $lazy_update;
+
+                fn field_changed<$db_lt>(
+                    old_fields: &Self::Fields<$db_lt>,
+                    new_fields: &Self::Fields<$db_lt>,
+                    field_index: usize,
+                ) -> bool {
+                    use $zalsa::LazyEqFallback as _;
+                    match field_index {
+                        $(
+                            $field_index => $crate::maybe_changed!(
+                                $field_option,
+                                $field_ty,
+                                old_fields.$field_index,
+                                new_fields.$field_index,
+                                $zalsa,
+                            ),
+                        )*
+                        _ => unreachable!("field index out of bounds"),
+                    }
+                }
+
+                const SINGLETON: bool = $is_singleton;
+
+                fn field_debug<'a, $db_lt>(
+                    fields: &'a Self::Fields<$db_lt>,
+                    field_index: usize,
+                ) -> &'a dyn std::fmt::Debug {
+                    match field_index {
+                        $($field_index => &fields.$field_index,)*
+                        _ => unreachable!("field index out of bounds"),
+                    }
+                }
             }
 
             impl $Configuration {
@@ -155,12 +235,20 @@ macro_rules! setup_tracked_struct {
                 fn lookup_ingredient_index(aux: &dyn $zalsa::JarAux) -> core::option::Option<$zalsa::IngredientIndex> {
                     aux.lookup_jar_by_type(&<$zalsa_struct::JarImpl<$Configuration>>::default())
                 }
+
+                fn register_ingredients(db: &dyn $zalsa::Database) {
+                    $Configuration::ingredient(db);
+                }
             }
 
             impl $zalsa::TrackedStructInDb for $Struct<'_> {
                 fn database_key_index(db: &dyn $zalsa::Database, id: $zalsa::Id) -> $zalsa::DatabaseKeyIndex {
                     $Configuration::ingredient(db).database_key_index(id)
                 }
+
+                fn adopt(db: &dyn $zalsa::Database, id: $zalsa::Id) {
+                    $Configuration::ingredient(db).adopt(db, id)
+                }
             }
 
             unsafe impl Send for $Struct<'_> {}
@@ -187,18 +275,41 @@ macro_rules! setup_tracked_struct {
             }
 
             impl<$db_lt> $Struct<$db_lt> {
-                pub fn $new_fn<$Db>(db: &$db_lt $Db, $($field_id: $field_ty),*) -> Self
-                where
-                    // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
-                    $Db: ?Sized + $zalsa::Database,
-                {
-                    $Configuration::ingredient(db.as_dyn_database()).new_struct(
-                        db.as_dyn_database(),
-                        ($($field_id,)*)
-                    )
+                $zalsa::macro_if! {
+                    if $identity_key {
+                        /// Creates the struct identified by `identity_key` within the current query,
+                        /// or reuses the one created with an equal key in the previous revision.
+                        pub fn $new_fn<$Db>(
+                            db: &$db_lt $Db,
+                            identity_key: impl std::hash::Hash,
+                            $($field_id: $field_ty),*
+                        ) -> Self
+                        where
+                            // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                            $Db: ?Sized + $zalsa::Database,
+                        {
+                            $Configuration::ingredient(db.as_dyn_database()).new_struct_with_identity_key(
+                                db.as_dyn_database(),
+                                &identity_key,
+                                ($($field_id,)*)
+                            )
+                        }
+                    } else {
+                        pub fn $new_fn<$Db>(db: &$db_lt $Db, $($field_id: $field_ty),*) -> Self
+                        where
+                            // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                            $Db: ?Sized + $zalsa::Database,
+                        {
+                            $Configuration::ingredient(db.as_dyn_database()).new_struct(
+                                db.as_dyn_database(),
+                                ($($field_id,)*)
+                            )
+                        }
+                    }
                 }
 
                 $(
+                    $(#[$field_getter_attr])*
                     $field_getter_vis fn $field_getter_id<$Db>(self, db: &$db_lt $Db) -> $crate::maybe_cloned_ty!($field_option, $db_lt, $field_ty)
                     where
                         // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
@@ -214,6 +325,41 @@ macro_rules! setup_tracked_struct {
                     }
                 )*
 
+                $(
+                    $element_getter_vis fn $element_getter_id<$Db>(
+                        self,
+                        db: &$db_lt $Db,
+                        index: usize,
+                    ) -> Option<&$db_lt <$element_ty as $zalsa_struct::Elements>::Element>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        let db = db.as_dyn_database();
+                        let fields = $Configuration::ingredient(db).field_element(db, self, $element_index, index);
+                        fields.$element_index.get(index)
+                    }
+                )*
+
+                $zalsa::macro_if! { $is_singleton =>
+                    pub fn try_get<$Db>(db: &$db_lt $Db) -> Option<Self>
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        $Configuration::ingredient(db.as_dyn_database()).get_singleton(db.as_dyn_database())
+                    }
+
+                    #[track_caller]
+                    pub fn get<$Db>(db: &$db_lt $Db) -> Self
+                    where
+                        // FIXME(rust-lang/rust#65991): The `db` argument *should* have the type `dyn Database`
+                        $Db: ?Sized + $zalsa::Database,
+                    {
+                        Self::try_get(db).unwrap()
+                    }
+                }
+
                 /// Default debug formatting for this struct (may be useful if you define your own `Debug` impl)
                 pub fn default_debug_fmt(this: Self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                     $zalsa::with_attached_database(|db| {
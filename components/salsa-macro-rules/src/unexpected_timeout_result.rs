@@ -0,0 +1,13 @@
+// Macro that generates the body of the timeout result function
+// for functions without a timeout, which never time out. This has to be
+// a macro because it can take a variadic number of arguments.
+#[macro_export]
+macro_rules! unexpected_timeout_result {
+    ($db:ident, $($other_inputs:ident),*) => {
+        {
+            std::mem::drop($db);
+            std::mem::drop(($($other_inputs),*));
+            panic!("function without a timeout timed out")
+        }
+    }
+}
@@ -44,6 +44,28 @@ impl AllowedOptions for Accumulator {
     const LRU: bool = false;
     const CONSTRUCTOR_NAME: bool = false;
     const ID: bool = false;
+    const DEDUPE: bool = false;
+    const DENSE: bool = false;
+    const SPECIFY_UNCHECKED: bool = false;
+    const FINGERPRINT: bool = false;
+    const RETURNS: bool = false;
+    const TIMEOUT: bool = false;
+
+    const ADAPTIVE: bool = false;
+
+    const RESULT: bool = false;
+
+    const LAZY_UPDATE: bool = false;
+
+    const WEAK: bool = false;
+
+    const STORE_WITH: bool = false;
+
+    const IDENTITY_KEY: bool = false;
+
+    const COMPARE_WITH: bool = false;
+
+    const STRICT_REVISIONS: bool = false;
 }
 
 struct StructMacro {
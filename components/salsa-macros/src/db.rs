@@ -84,11 +84,15 @@ impl DbMacro {
         let db = &input.ident;
         let zalsa = self.hygiene.ident("zalsa");
 
+        // For a generic database, `Storage<Self>` is only well-formed if the bounds
+        // of the struct imply those of its `Database` impl, so they suffice here too.
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
         Ok(quote! {
             const _: () = {
                 use salsa::plumbing as #zalsa;
 
-                unsafe impl #zalsa::HasStorage for #db {
+                unsafe impl #impl_generics #zalsa::HasStorage for #db #ty_generics #where_clause {
                     fn storage(&self) -> &#zalsa::Storage<Self> {
                         &self.#storage
                     }
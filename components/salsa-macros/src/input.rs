@@ -4,7 +4,7 @@ use crate::{
     salsa_struct::{SalsaStruct, SalsaStructAllowedOptions},
     token_stream_with_error,
 };
-use proc_macro2::TokenStream;
+use proc_macro2::{Literal, TokenStream};
 
 /// For an entity struct `Foo` with fields `f1: T1, ..., fN: TN`, we generate...
 ///
@@ -59,6 +59,34 @@ impl crate::options::AllowedOptions for InputStruct {
     const CONSTRUCTOR_NAME: bool = true;
 
     const ID: bool = false;
+
+    const DEDUPE: bool = false;
+
+    const DENSE: bool = false;
+
+    const SPECIFY_UNCHECKED: bool = false;
+
+    const FINGERPRINT: bool = false;
+
+    const RETURNS: bool = false;
+
+    const TIMEOUT: bool = false;
+
+    const ADAPTIVE: bool = false;
+
+    const RESULT: bool = false;
+
+    const LAZY_UPDATE: bool = false;
+
+    const WEAK: bool = false;
+
+    const STORE_WITH: bool = false;
+
+    const IDENTITY_KEY: bool = false;
+
+    const COMPARE_WITH: bool = false;
+
+    const STRICT_REVISIONS: bool = false;
 }
 
 impl SalsaStructAllowedOptions for InputStruct {
@@ -71,6 +99,14 @@ impl SalsaStructAllowedOptions for InputStruct {
     const ELIDABLE_LIFETIME: bool = false;
 
     const ALLOW_DEFAULT: bool = true;
+
+    const ALLOW_DELTA: bool = true;
+
+    const ALLOW_ELEMENTS: bool = false;
+
+    const ALLOW_KEY: bool = true;
+
+    const ARENA_FIELDS: bool = false;
 }
 
 struct Macro {
@@ -93,12 +129,21 @@ impl Macro {
         let num_fields = salsa_struct.num_fields();
         let field_vis = salsa_struct.field_vis();
         let field_getter_ids = salsa_struct.field_getter_ids();
+        let field_getter_docs = salsa_struct.field_getter_docs();
         let field_setter_ids = salsa_struct.field_setter_ids();
+        let field_setter_docs = salsa_struct.field_setter_docs();
+        let field_scoped_setter_ids = salsa_struct.field_scoped_setter_ids();
         let required_fields = salsa_struct.required_fields();
         let field_options = salsa_struct.field_options();
         let field_tys = salsa_struct.field_tys();
         let field_durability_ids = salsa_struct.field_durability_ids();
+        let delta_fields = salsa_struct.delta_fields();
         let is_singleton = self.args.singleton.is_some();
+        let has_key = salsa_struct.key_field().is_some();
+        let (key_field_index, key_field_ty) = match salsa_struct.key_field() {
+            Some((index, ty)) => (index, quote!(#ty)),
+            None => (Literal::usize_unsuffixed(0), quote!(())),
+        };
         let generate_debug_impl = salsa_struct.generate_debug_impl();
 
         let zalsa = self.hygiene.ident("zalsa");
@@ -118,14 +163,18 @@ impl Macro {
                     new_fn: #new_fn,
                     field_options: [#(#field_options),*],
                     field_ids: [#(#field_ids),*],
-                    field_getters: [#(#field_vis #field_getter_ids),*],
-                    field_setters: [#(#field_vis #field_setter_ids),*],
+                    field_getters: [#(#field_getter_docs #field_vis #field_getter_ids),*],
+                    field_setters: [#(#field_setter_docs #field_vis #field_setter_ids),*],
+                    field_scoped_setters: [#(#field_setter_docs #field_vis #field_scoped_setter_ids),*],
                     field_tys: [#(#field_tys),*],
                     field_indices: [#(#field_indices),*],
                     required_fields: [#(#required_fields),*],
                     field_durability_ids: [#(#field_durability_ids),*],
+                    delta_fields: [#(#delta_fields),*],
                     num_fields: #num_fields,
                     is_singleton: #is_singleton,
+                    has_key: #has_key,
+                    key_field: (#key_field_index, #key_field_ty),
                     generate_debug_impl: #generate_debug_impl,
                     unused_names: [
                         #zalsa,
@@ -60,6 +60,34 @@ impl crate::options::AllowedOptions for InternedStruct {
     const CONSTRUCTOR_NAME: bool = true;
 
     const ID: bool = true;
+
+    const DEDUPE: bool = false;
+
+    const DENSE: bool = true;
+
+    const SPECIFY_UNCHECKED: bool = false;
+
+    const FINGERPRINT: bool = false;
+
+    const RETURNS: bool = false;
+
+    const TIMEOUT: bool = false;
+
+    const ADAPTIVE: bool = false;
+
+    const RESULT: bool = false;
+
+    const LAZY_UPDATE: bool = false;
+
+    const WEAK: bool = false;
+
+    const STORE_WITH: bool = false;
+
+    const IDENTITY_KEY: bool = false;
+
+    const COMPARE_WITH: bool = false;
+
+    const STRICT_REVISIONS: bool = false;
 }
 
 impl SalsaStructAllowedOptions for InternedStruct {
@@ -72,6 +100,14 @@ impl SalsaStructAllowedOptions for InternedStruct {
     const ELIDABLE_LIFETIME: bool = true;
 
     const ALLOW_DEFAULT: bool = false;
+
+    const ALLOW_DELTA: bool = false;
+
+    const ALLOW_ELEMENTS: bool = false;
+
+    const ALLOW_KEY: bool = false;
+
+    const ARENA_FIELDS: bool = true;
 }
 
 struct Macro {
@@ -88,7 +124,8 @@ impl Macro {
         let attrs = &self.struct_item.attrs;
         let vis = &self.struct_item.vis;
         let struct_ident = &self.struct_item.ident;
-        let struct_data_ident = format_ident!("{}Data", struct_ident);
+        // The fields tuple is named in the generated code; keep it distinct from the user's `data` struct.
+        let struct_data_ident = self.hygiene.ident(&format!("{}Data", struct_ident));
         let db_lt = db_lifetime::db_lifetime(&self.struct_item.generics);
         let new_fn = salsa_struct.constructor_name();
         let field_ids = salsa_struct.field_ids();
@@ -96,12 +133,19 @@ impl Macro {
         let num_fields = salsa_struct.num_fields();
         let field_vis = salsa_struct.field_vis();
         let field_getter_ids = salsa_struct.field_getter_ids();
+        let field_getter_docs = salsa_struct.field_getter_docs();
         let field_options = salsa_struct.field_options();
         let field_tys = salsa_struct.field_tys();
         let field_indexed_tys = salsa_struct.field_indexed_tys();
         let generate_debug_impl = salsa_struct.generate_debug_impl();
         let has_lifetime = salsa_struct.generate_lifetime();
         let id = salsa_struct.id();
+        let (has_data_struct, data_struct) = match &self.args.data {
+            Some(data) => (true, data.clone()),
+            None => (false, self.hygiene.ident("DataStruct")),
+        };
+
+        let dense = self.args.dense.is_some();
 
         let (db_lt_arg, cfg, interior_lt) = if has_lifetime {
             (
@@ -141,12 +185,14 @@ impl Macro {
                     new_fn: #new_fn,
                     field_options: [#(#field_options),*],
                     field_ids: [#(#field_ids),*],
-                    field_getters: [#(#field_vis #field_getter_ids),*],
+                    field_getters: [#(#field_getter_docs #field_vis #field_getter_ids),*],
                     field_tys: [#(#field_tys),*],
                     field_indices: [#(#field_indices),*],
                     field_indexed_tys: [#(#field_indexed_tys),*],
                     num_fields: #num_fields,
                     generate_debug_impl: #generate_debug_impl,
+                    data_struct: (#has_data_struct, #data_struct),
+                    dense: #dense,
                     unused_names: [
                         #zalsa,
                         #zalsa_struct,
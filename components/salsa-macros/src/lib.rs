@@ -44,6 +44,7 @@ mod input;
 mod interned;
 mod options;
 mod salsa_struct;
+mod supertype;
 mod tracked;
 mod tracked_fn;
 mod tracked_impl;
@@ -76,7 +77,7 @@ pub fn tracked(args: TokenStream, input: TokenStream) -> TokenStream {
     tracked::tracked(args, input)
 }
 
-#[proc_macro_derive(Update)]
+#[proc_macro_derive(Update, attributes(update))]
 pub fn update(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as syn::DeriveInput);
     match update::update_derive(item) {
@@ -85,6 +86,16 @@ pub fn update(input: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_derive(Supertype)]
+pub fn supertype(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as syn::DeriveInput);
+    match supertype::supertype_derive(item) {
+        Ok(tokens) => tokens.into(),
+        // Unlike attributes, derives must not re-emit their input.
+        Err(error) => error.into_compile_error().into(),
+    }
+}
+
 pub(crate) fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenStream {
     tokens.extend(TokenStream::from(error.into_compile_error()));
     tokens
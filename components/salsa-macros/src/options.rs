@@ -35,7 +35,7 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `no_clone` identifier.
     pub no_clone: Option<syn::Ident>,
 
-    /// The `singleton` option is used on input with only one field
+    /// The `singleton` option is used on inputs and tracked structs with only one instance
     /// It allows the creation of convenient methods
     pub singleton: Option<syn::Ident>,
 
@@ -51,6 +51,7 @@ pub(crate) struct Options<A: AllowedOptions> {
     pub db_path: Option<syn::Path>,
 
     /// The `recovery_fn = <path>` option is used to indicate the recovery function.
+    /// `recovery_fn = default` recovers with the `Default` value of the output instead.
     ///
     /// If this is `Some`, the value is the `<path>`.
     pub recovery_fn: Option<syn::Path>,
@@ -78,6 +79,98 @@ pub(crate) struct Options<A: AllowedOptions> {
     /// If this is `Some`, the value is the `<ident>`.
     pub id: Option<syn::Path>,
 
+    /// The `dedupe` option is used to signal that a tracked function should
+    /// share equal output values across keys.
+    ///
+    /// If this is `Some`, the value is the `dedupe` identifier.
+    pub dedupe: Option<syn::Ident>,
+
+    /// Signals `dense` for an interned struct:
+    /// ids are numbered contiguously from 0 and `index`/`count` accessors are generated.
+    pub dense: Option<syn::Ident>,
+
+    /// Signals `specify_unchecked`:
+    /// generates a `specify_unchecked` function that can assign values for any key.
+    pub specify_unchecked: Option<syn::Ident>,
+
+    /// The `fingerprint` option is used to signal that a tracked function should
+    /// compare a hash of its new and old values, rather than the values themselves,
+    /// when deciding whether to backdate a re-executed memo (trusting that different
+    /// values have different 64-bit hashes, see `salsa::plumbing::function::fingerprint`).
+    ///
+    /// If this is `Some`, the value is the `fingerprint` identifier.
+    pub fingerprint: Option<syn::Ident>,
+
+    /// The `returns(<mode>)` option selects how a tracked function returns its value.
+    /// Currently the only mode is `arc`: the value is stored in an `Arc` and the
+    /// function returns a clone of that `Arc`.
+    ///
+    /// If this is `Some`, the value is the `<mode>` identifier.
+    pub returns: Option<syn::Ident>,
+
+    /// The `timeout = "<duration>"` option is used to bound how long a tracked function
+    /// may execute, e.g. `timeout = "500ms"`.
+    ///
+    /// If this is `Some`, the value is the `<duration>` literal and its parsed value.
+    pub timeout: Option<(syn::LitStr, std::time::Duration)>,
+
+    /// The `timeout_result = <path>` option is used to indicate the function computing
+    /// the value of a tracked function that timed out.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub timeout_result: Option<syn::Path>,
+
+    /// The `adaptive` option is used to signal that a tracked function should only
+    /// memoize its value if executing it takes longer than a threshold,
+    /// given as `adaptive = "<duration>"` or else a default.
+    ///
+    /// If this is `Some`, the value is the `adaptive` identifier and the threshold, if given.
+    pub adaptive: Option<(syn::Ident, Option<std::time::Duration>)>,
+
+    /// The `result` option is used to signal that a tracked function returns a `Result`
+    /// whose errors are stored in an `Arc` and shared across keys;
+    /// with `result(retry)`, errors are also recomputed in every new revision.
+    ///
+    /// If this is `Some`, the value is the `result` identifier and the `retry` identifier, if given.
+    pub result: Option<(syn::Ident, Option<syn::Ident>)>,
+
+    /// The `lazy_update` option is used to signal that a re-created tracked struct
+    /// should compare each field with its old value when the field is first read,
+    /// rather than comparing all fields eagerly.
+    ///
+    /// If this is `Some`, the value is the `lazy_update` identifier.
+    pub lazy_update: Option<syn::Ident>,
+
+    /// The `weak` option is used to signal that the memoized values of a tracked function
+    /// may be dropped by `Database::trim_memory` and recomputed when next needed.
+    ///
+    /// If this is `Some`, the value is the `weak` identifier.
+    pub weak: Option<syn::Ident>,
+
+    /// The `store_with = <path>` option is used to indicate a `salsa::Codec` that converts
+    /// the values of a tracked function to the form in which they are memoized, and back.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub store_with: Option<syn::Path>,
+
+    /// The `identity_key` option is used to signal that a tracked struct is identified by a key
+    /// given to its constructor, rather than by its `#[id]` fields and the order of creation.
+    ///
+    /// If this is `Some`, the value is the `identity_key` identifier.
+    pub identity_key: Option<syn::Ident>,
+
+    /// The `compare_with = <path>` option is used to indicate a function comparing the old and
+    /// new values of a tracked function when deciding whether to backdate, instead of `Eq`.
+    ///
+    /// If this is `Some`, the value is the `<path>`.
+    pub compare_with: Option<syn::Path>,
+
+    /// The `strict_revisions` option is used with `return_ref` to signal that a tracked function
+    /// returns a `salsa::RevisionRef`, which panics if it is used after its revision, rather than a reference.
+    ///
+    /// If this is `Some`, the value is the `strict_revisions` identifier.
+    pub strict_revisions: Option<syn::Ident>,
+
     /// Remember the `A` parameter, which plays no role after parsing.
     phantom: PhantomData<A>,
 }
@@ -99,6 +192,21 @@ impl<A: AllowedOptions> Default for Options<A> {
             lru: Default::default(),
             singleton: Default::default(),
             id: Default::default(),
+            dedupe: Default::default(),
+            dense: Default::default(),
+            specify_unchecked: Default::default(),
+            fingerprint: Default::default(),
+            returns: Default::default(),
+            timeout: Default::default(),
+            timeout_result: Default::default(),
+            adaptive: Default::default(),
+            result: Default::default(),
+            lazy_update: Default::default(),
+            weak: Default::default(),
+            store_with: Default::default(),
+            identity_key: Default::default(),
+            compare_with: Default::default(),
+            strict_revisions: Default::default(),
         }
     }
 }
@@ -118,6 +226,20 @@ pub(crate) trait AllowedOptions {
     const LRU: bool;
     const CONSTRUCTOR_NAME: bool;
     const ID: bool;
+    const DEDUPE: bool;
+    const DENSE: bool;
+    const SPECIFY_UNCHECKED: bool;
+    const FINGERPRINT: bool;
+    const RETURNS: bool;
+    const TIMEOUT: bool;
+    const ADAPTIVE: bool;
+    const RESULT: bool;
+    const LAZY_UPDATE: bool;
+    const WEAK: bool;
+    const STORE_WITH: bool;
+    const IDENTITY_KEY: bool;
+    const COMPARE_WITH: bool;
+    const STRICT_REVISIONS: bool;
 }
 
 type Equals = syn::Token![=];
@@ -308,6 +430,258 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
                         "`id` option not allowed here",
                     ));
                 }
+            } else if ident == "dedupe" {
+                if A::DEDUPE {
+                    if let Some(old) = std::mem::replace(&mut options.dedupe, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `dedupe` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`dedupe` option not allowed here",
+                    ));
+                }
+            } else if ident == "dense" {
+                if A::DENSE {
+                    if let Some(old) = std::mem::replace(&mut options.dense, Some(ident)) {
+                        return Err(syn::Error::new(old.span(), "option `dense` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`dense` option not allowed here",
+                    ));
+                }
+            } else if ident == "specify_unchecked" {
+                if A::SPECIFY_UNCHECKED {
+                    if let Some(old) =
+                        std::mem::replace(&mut options.specify_unchecked, Some(ident))
+                    {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `specify_unchecked` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`specify_unchecked` option not allowed here",
+                    ));
+                }
+            } else if ident == "fingerprint" {
+                if A::FINGERPRINT {
+                    if let Some(old) = std::mem::replace(&mut options.fingerprint, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `fingerprint` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`fingerprint` option not allowed here",
+                    ));
+                }
+            } else if ident == "returns" {
+                if A::RETURNS {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let value = syn::Ident::parse(&content)?;
+                    if value != "arc" {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "expected `arc`, the only supported return mode",
+                        ));
+                    }
+                    if let Some(old) = std::mem::replace(&mut options.returns, Some(value)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `returns` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`returns` option not allowed here",
+                    ));
+                }
+            } else if ident == "timeout" {
+                if A::TIMEOUT {
+                    let _eq = Equals::parse(input)?;
+                    let lit = input.parse::<syn::LitStr>()?;
+                    let duration = parse_duration(&lit)?;
+                    if let Some((old, _)) =
+                        std::mem::replace(&mut options.timeout, Some((lit, duration)))
+                    {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `timeout` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`timeout` option not allowed here",
+                    ));
+                }
+            } else if ident == "adaptive" {
+                if A::ADAPTIVE {
+                    let threshold = if input.peek(syn::Token![=]) {
+                        let _eq = Equals::parse(input)?;
+                        let lit = input.parse::<syn::LitStr>()?;
+                        Some(parse_duration(&lit)?)
+                    } else {
+                        None
+                    };
+                    if let Some((old, _)) =
+                        std::mem::replace(&mut options.adaptive, Some((ident, threshold)))
+                    {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `adaptive` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`adaptive` option not allowed here",
+                    ));
+                }
+            } else if ident == "result" {
+                if A::RESULT {
+                    let retry = if input.peek(syn::token::Paren) {
+                        let content;
+                        syn::parenthesized!(content in input);
+                        let value = syn::Ident::parse(&content)?;
+                        if value != "retry" {
+                            return Err(syn::Error::new(
+                                value.span(),
+                                "expected `retry`, the only supported result mode",
+                            ));
+                        }
+                        Some(value)
+                    } else {
+                        None
+                    };
+                    if let Some((old, _)) =
+                        std::mem::replace(&mut options.result, Some((ident, retry)))
+                    {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `result` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`result` option not allowed here",
+                    ));
+                }
+            } else if ident == "lazy_update" {
+                if A::LAZY_UPDATE {
+                    if let Some(old) = std::mem::replace(&mut options.lazy_update, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `lazy_update` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`lazy_update` option not allowed here",
+                    ));
+                }
+            } else if ident == "strict_revisions" {
+                if A::STRICT_REVISIONS {
+                    if let Some(old) = std::mem::replace(&mut options.strict_revisions, Some(ident))
+                    {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `strict_revisions` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`strict_revisions` option not allowed here",
+                    ));
+                }
+            } else if ident == "weak" {
+                if A::WEAK {
+                    if let Some(old) = std::mem::replace(&mut options.weak, Some(ident)) {
+                        return Err(syn::Error::new(old.span(), "option `weak` provided twice"));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`weak` option not allowed here",
+                    ));
+                }
+            } else if ident == "timeout_result" {
+                if A::TIMEOUT {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = std::mem::replace(&mut options.timeout_result, Some(path)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `timeout_result` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`timeout_result` option not allowed here",
+                    ));
+                }
+            } else if ident == "identity_key" {
+                if A::IDENTITY_KEY {
+                    if let Some(old) = std::mem::replace(&mut options.identity_key, Some(ident)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `identity_key` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`identity_key` option not allowed here",
+                    ));
+                }
+            } else if ident == "store_with" {
+                if A::STORE_WITH {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = std::mem::replace(&mut options.store_with, Some(path)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `store_with` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`store_with` option not allowed here",
+                    ));
+                }
+            } else if ident == "compare_with" {
+                if A::COMPARE_WITH {
+                    let _eq = Equals::parse(input)?;
+                    let path = syn::Path::parse(input)?;
+                    if let Some(old) = std::mem::replace(&mut options.compare_with, Some(path)) {
+                        return Err(syn::Error::new(
+                            old.span(),
+                            "option `compare_with` provided twice",
+                        ));
+                    }
+                } else {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "`compare_with` option not allowed here",
+                    ));
+                }
             } else {
                 return Err(syn::Error::new(
                     ident.span(),
@@ -325,3 +699,23 @@ impl<A: AllowedOptions> syn::parse::Parse for Options<A> {
         Ok(options)
     }
 }
+
+/// Parses a duration like `"500ms"`: an integer followed by one of the units `ns`, `us`, `ms` or `s`.
+fn parse_duration(lit: &syn::LitStr) -> syn::Result<std::time::Duration> {
+    let value = lit.value();
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let amount = digits.parse::<u64>().ok();
+    let duration = match (amount, &value[digits.len()..]) {
+        (Some(amount), "ns") => std::time::Duration::from_nanos(amount),
+        (Some(amount), "us") => std::time::Duration::from_micros(amount),
+        (Some(amount), "ms") => std::time::Duration::from_millis(amount),
+        (Some(amount), "s") => std::time::Duration::from_secs(amount),
+        _ => {
+            return Err(syn::Error::new(
+                lit.span(),
+                "expected a duration such as `\"500ms\"`, with unit `ns`, `us`, `ms` or `s`",
+            ))
+        }
+    };
+    Ok(duration)
+}
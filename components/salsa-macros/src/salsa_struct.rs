@@ -52,6 +52,18 @@ pub(crate) trait SalsaStructAllowedOptions: AllowedOptions {
 
     /// Are `#[default]` fields allowed?
     const ALLOW_DEFAULT: bool;
+
+    /// Are `#[delta]` fields allowed?
+    const ALLOW_DELTA: bool;
+
+    /// Are `#[elements]` fields allowed?
+    const ALLOW_ELEMENTS: bool;
+
+    /// Is a `#[key]` field allowed?
+    const ALLOW_KEY: bool;
+
+    /// Are fields of type `&'db T` stored in an arena (see `salsa::plumbing::interned::ArenaRef`)?
+    const ARENA_FIELDS: bool;
 }
 
 pub(crate) struct SalsaField<'s> {
@@ -61,8 +73,18 @@ pub(crate) struct SalsaField<'s> {
     pub(crate) has_default_attr: bool,
     pub(crate) has_ref_attr: bool,
     pub(crate) has_no_eq_attr: bool,
+    pub(crate) has_delta_attr: bool,
+    pub(crate) has_elements_attr: bool,
+    pub(crate) has_key_attr: bool,
+    pub(crate) returns: Option<syn::Ident>,
+    /// True if the field has type `&'db T` and is stored in an arena.
+    pub(crate) borrowed: bool,
     get_name: syn::Ident,
     set_name: syn::Ident,
+    /// Documentation for the getter given with `#[get_doc = ".."]`, replacing the field's docs.
+    get_doc: Option<syn::Expr>,
+    /// Documentation for the setters given with `#[set_doc = ".."]`, replacing the field's docs.
+    set_doc: Option<syn::Expr>,
 }
 
 const BANNED_FIELD_NAMES: &[&str] = &["from", "new"];
@@ -73,12 +95,24 @@ pub(crate) const FIELD_OPTION_ATTRIBUTES: &[(&str, fn(&syn::Attribute, &mut Sals
     ("default", |_, ef| ef.has_default_attr = true),
     ("return_ref", |_, ef| ef.has_ref_attr = true),
     ("no_eq", |_, ef| ef.has_no_eq_attr = true),
+    ("delta", |_, ef| ef.has_delta_attr = true),
+    ("elements", |_, ef| ef.has_elements_attr = true),
+    ("key", |_, ef| ef.has_key_attr = true),
+    ("returns", |attr, ef| {
+        ef.returns = Some(attr.parse_args().unwrap());
+    }),
     ("get", |attr, ef| {
         ef.get_name = attr.parse_args().unwrap();
     }),
     ("set", |attr, ef| {
         ef.set_name = attr.parse_args().unwrap();
     }),
+    ("get_doc", |attr, ef| {
+        ef.get_doc = Some(attr.meta.require_name_value().unwrap().value.clone());
+    }),
+    ("set_doc", |attr, ef| {
+        ef.set_doc = Some(attr.meta.require_name_value().unwrap().value.clone());
+    }),
 ];
 
 impl<'s, A> SalsaStruct<'s, A>
@@ -93,12 +127,22 @@ where
             ));
         };
 
-        let fields = n
+        let mut fields: Vec<SalsaField> = n
             .named
             .iter()
             .map(SalsaField::new)
             .collect::<syn::Result<_>>()?;
 
+        if A::ARENA_FIELDS {
+            let db_lt = db_lifetime::db_lifetime(&struct_item.generics);
+            for f in &mut fields {
+                f.borrowed = matches!(
+                    &f.field.ty,
+                    syn::Type::Reference(r) if r.mutability.is_none() && r.lifetime.as_ref() == Some(&db_lt)
+                );
+            }
+        }
+
         let this = Self {
             struct_item,
             args,
@@ -107,6 +151,10 @@ where
 
         this.maybe_disallow_id_fields()?;
         this.maybe_disallow_default_fields()?;
+        this.maybe_disallow_delta_fields()?;
+        this.maybe_disallow_elements_fields()?;
+        this.maybe_disallow_key_fields()?;
+        this.check_returns_fields()?;
 
         this.check_generics()?;
 
@@ -179,6 +227,109 @@ where
         Ok(())
     }
 
+    /// Disallow `#[delta]` attributes on the fields of this struct.
+    ///
+    /// If an `#[delta]` field is found, return an error.
+    fn maybe_disallow_delta_fields(&self) -> syn::Result<()> {
+        if A::ALLOW_DELTA {
+            return Ok(());
+        }
+
+        for ef in &self.fields {
+            if ef.has_delta_attr {
+                return Err(syn::Error::new_spanned(
+                    ef.field,
+                    format!("`#[delta]` cannot be used with `#[salsa::{}]`", A::KIND),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disallow `#[elements]` attributes on the fields of this struct,
+    /// as well as combining them with `#[id]` or `#[no_eq]`.
+    ///
+    /// If such a field is found, return an error.
+    fn maybe_disallow_elements_fields(&self) -> syn::Result<()> {
+        for ef in &self.fields {
+            if !ef.has_elements_attr {
+                continue;
+            }
+
+            let message = if !A::ALLOW_ELEMENTS {
+                format!("`#[elements]` cannot be used with `#[salsa::{}]`", A::KIND)
+            } else if ef.has_id_attr {
+                "`#[elements]` cannot be used with `#[id]`".to_string()
+            } else if ef.has_no_eq_attr {
+                "`#[elements]` cannot be used with `#[no_eq]`".to_string()
+            } else {
+                continue;
+            };
+            return Err(syn::Error::new_spanned(ef.field, message));
+        }
+
+        Ok(())
+    }
+
+    /// Disallow `#[key]` attributes on the fields of this struct, unless allowed,
+    /// as well as more than one `#[key]` field or combining it with `#[default]`.
+    ///
+    /// If such a field is found, return an error.
+    fn maybe_disallow_key_fields(&self) -> syn::Result<()> {
+        let mut key_fields = self.fields.iter().filter(|ef| ef.has_key_attr);
+        let Some(ef) = key_fields.next() else {
+            return Ok(());
+        };
+
+        let message = if !A::ALLOW_KEY {
+            format!("`#[key]` cannot be used with `#[salsa::{}]`", A::KIND)
+        } else if ef.has_default_attr {
+            "`#[key]` cannot be used with `#[default]`".to_string()
+        } else if let Some(ef) = key_fields.next() {
+            return Err(syn::Error::new_spanned(
+                ef.field,
+                "only one field can be marked `#[key]`",
+            ));
+        } else {
+            return Ok(());
+        };
+        Err(syn::Error::new_spanned(ef.field, message))
+    }
+
+    /// Check that `#[returns(..)]` attributes name a supported mode
+    /// and are not combined with `#[return_ref]`, `#[delta]` or `#[elements]`.
+    fn check_returns_fields(&self) -> syn::Result<()> {
+        for ef in &self.fields {
+            let Some(mode) = &ef.returns else {
+                continue;
+            };
+
+            if mode != "arc" {
+                return Err(syn::Error::new_spanned(
+                    mode,
+                    "expected `arc`, the only supported return mode",
+                ));
+            }
+
+            let other = if ef.has_ref_attr {
+                "return_ref"
+            } else if ef.has_delta_attr {
+                "delta"
+            } else if ef.has_elements_attr {
+                "elements"
+            } else {
+                continue;
+            };
+            return Err(syn::Error::new_spanned(
+                ef.field,
+                format!("`#[returns(arc)]` cannot be used with `#[{other}]`"),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Check that the generic parameters look as expected for this kind of struct.
     fn check_generics(&self) -> syn::Result<()> {
         if A::HAS_LIFETIME {
@@ -218,6 +369,15 @@ where
             .collect()
     }
 
+    /// Returns the index and type of the `#[key]` field, if any.
+    pub(crate) fn key_field(&self) -> Option<(Literal, &syn::Type)> {
+        self.fields
+            .iter()
+            .zip(0..)
+            .find(|(f, _)| f.has_key_attr)
+            .map(|(f, index)| (Literal::usize_unsuffixed(index), &f.field.ty))
+    }
+
     pub(crate) fn required_fields(&self) -> Vec<TokenStream> {
         self.fields
             .iter()
@@ -226,7 +386,7 @@ where
                     None
                 } else {
                     let ident = f.field.ident.as_ref().unwrap();
-                    let ty = &f.field.ty;
+                    let ty = f.stored_ty();
                     Some(quote!(#ident #ty))
                 }
             })
@@ -241,10 +401,27 @@ where
         self.fields.iter().map(|f| &f.get_name).collect()
     }
 
+    /// The doc attributes of each getter: those of the field, unless overridden with `#[get_doc]`.
+    pub(crate) fn field_getter_docs(&self) -> Vec<TokenStream> {
+        self.fields.iter().map(|f| f.docs(&f.get_doc)).collect()
+    }
+
+    /// The doc attributes of each setter: those of the field, unless overridden with `#[set_doc]`.
+    pub(crate) fn field_setter_docs(&self) -> Vec<TokenStream> {
+        self.fields.iter().map(|f| f.docs(&f.set_doc)).collect()
+    }
+
     pub(crate) fn field_setter_ids(&self) -> Vec<&syn::Ident> {
         self.fields.iter().map(|f| &f.set_name).collect()
     }
 
+    pub(crate) fn field_scoped_setter_ids(&self) -> Vec<syn::Ident> {
+        self.fields
+            .iter()
+            .map(|f| quote::format_ident!("{}_in", f.set_name))
+            .collect()
+    }
+
     pub(crate) fn field_durability_ids(&self) -> Vec<syn::Ident> {
         self.fields
             .iter()
@@ -252,8 +429,45 @@ where
             .collect()
     }
 
-    pub(crate) fn field_tys(&self) -> Vec<&syn::Type> {
-        self.fields.iter().map(|f| &f.field.ty).collect()
+    /// For each `#[delta]` field, returns its index, visibility, the name of the edit setter
+    /// (typically `set_foo_edit`), the name of the range getter (typically `foo_range`), and its type.
+    pub(crate) fn delta_fields(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .zip(0..)
+            .filter(|(f, _)| f.has_delta_attr)
+            .map(|(f, index)| {
+                let index = Literal::usize_unsuffixed(index);
+                let vis = &f.field.vis;
+                let setter = quote::format_ident!("{}_edit", f.set_name);
+                let getter = quote::format_ident!("{}_range", f.get_name);
+                let ty = &f.field.ty;
+                quote!((#index, #vis #setter, #getter, #ty))
+            })
+            .collect()
+    }
+
+    /// For each `#[elements]` field, returns its index, visibility, the name of the element getter
+    /// (typically `foo_element`), and its type.
+    pub(crate) fn element_fields(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .zip(0..)
+            .filter(|(f, _)| f.has_elements_attr)
+            .map(|(f, index)| {
+                let index = Literal::usize_unsuffixed(index);
+                let vis = &f.field.vis;
+                let getter = quote::format_ident!("{}_element", f.get_name);
+                let ty = &f.field.ty;
+                quote!((#index, #vis #getter, #ty))
+            })
+            .collect()
+    }
+
+    /// The types of the fields as stored, i.e. wrapped in an `Arc` for `#[returns(arc)]` fields
+    /// and in an `ArenaRef` for borrowed fields.
+    pub(crate) fn field_tys(&self) -> Vec<syn::Type> {
+        self.fields.iter().map(SalsaField::stored_ty).collect()
     }
 
     pub(crate) fn field_indexed_tys(&self) -> Vec<syn::Ident> {
@@ -268,7 +482,9 @@ where
         self.fields
             .iter()
             .map(|f| {
-                let clone_ident = if f.has_ref_attr {
+                let clone_ident = if f.borrowed {
+                    syn::Ident::new("borrowed", Span::call_site())
+                } else if f.has_ref_attr {
                     syn::Ident::new("no_clone", Span::call_site())
                 } else {
                     syn::Ident::new("clone", Span::call_site())
@@ -276,6 +492,8 @@ where
 
                 let backdate_ident = if f.has_no_eq_attr {
                     syn::Ident::new("no_backdate", Span::call_site())
+                } else if f.has_elements_attr {
+                    syn::Ident::new("elements", Span::call_site())
                 } else {
                     syn::Ident::new("backdate", Span::call_site())
                 };
@@ -322,8 +540,15 @@ impl<'s> SalsaField<'s> {
             has_ref_attr: false,
             has_default_attr: false,
             has_no_eq_attr: false,
+            has_delta_attr: false,
+            has_elements_attr: false,
+            has_key_attr: false,
+            returns: None,
+            borrowed: false,
             get_name,
             set_name,
+            get_doc: None,
+            set_doc: None,
         };
 
         // Scan the attributes and look for the salsa attributes:
@@ -337,4 +562,34 @@ impl<'s> SalsaField<'s> {
 
         Ok(result)
     }
+
+    /// The `#[doc]` attributes for an accessor of this field: `doc_override` if given,
+    /// otherwise the doc comments of the field.
+    fn docs(&self, doc_override: &Option<syn::Expr>) -> TokenStream {
+        match doc_override {
+            Some(doc) => quote!(#[doc = #doc]),
+            None => {
+                let docs = self
+                    .field
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path().is_ident("doc"));
+                quote!(#(#docs)*)
+            }
+        }
+    }
+
+    /// The type of the field as stored in the struct.
+    fn stored_ty(&self) -> syn::Type {
+        let ty = &self.field.ty;
+        if let (true, syn::Type::Reference(r)) = (self.borrowed, ty) {
+            let lt = &r.lifetime;
+            let elem = &r.elem;
+            parse_quote!(salsa::plumbing::interned::ArenaRef<#lt, #elem>)
+        } else if self.returns.is_some() {
+            parse_quote!(::std::sync::Arc<#ty>)
+        } else {
+            ty.clone()
+        }
+    }
 }
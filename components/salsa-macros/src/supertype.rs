@@ -0,0 +1,174 @@
+use heck::ToSnakeCase;
+use proc_macro2::TokenStream;
+
+use crate::hygiene::Hygiene;
+
+/// For an enum whose variants each wrap one salsa struct (input, interned or tracked),
+/// generates what is needed to pass the enum to a tracked function,
+/// along with conversions between the enum and its variants.
+pub(crate) fn supertype_derive(input: syn::DeriveInput) -> syn::Result<TokenStream> {
+    let hygiene = Hygiene::from2(&input);
+
+    let syn::Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`derive(Supertype)` only supports enums",
+        ));
+    };
+    if input.generics.type_params().next().is_some()
+        || input.generics.const_params().next().is_some()
+    {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`derive(Supertype)` only supports a lifetime parameter",
+        ));
+    }
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`derive(Supertype)` needs at least one variant",
+        ));
+    }
+
+    let mut variants = vec![];
+    for variant in &data.variants {
+        match &variant.fields {
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                variants.push((&variant.ident, &fields.unnamed[0].ty));
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "`derive(Supertype)` variants must wrap exactly one salsa struct, e.g. `File(File)`",
+                ))
+            }
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let variant_idents: Vec<_> = variants.iter().map(|(ident, _)| *ident).collect();
+    let variant_tys: Vec<_> = variants.iter().map(|(_, ty)| *ty).collect();
+    let as_variant_fns: Vec<_> = variant_idents
+        .iter()
+        .map(|ident| {
+            syn::Ident::new(
+                &format!("as_{}", ident.to_string().to_snake_case()),
+                ident.span(),
+            )
+        })
+        .collect();
+
+    let zalsa = hygiene.ident("zalsa");
+    let id = hygiene.ident("id");
+    let db = hygiene.ident("db");
+    let aux = hygiene.ident("aux");
+    let owner = hygiene.ident("owner");
+    let value = hygiene.ident("value");
+    let cache = hygiene.ident("CACHE");
+
+    let tokens = quote! {
+        const _: () = {
+            use salsa::plumbing as #zalsa;
+
+            impl #impl_generics #zalsa::AsId for #ident #ty_generics #where_clause {
+                fn as_id(&self) -> #zalsa::Id {
+                    match self {
+                        #(
+                            Self::#variant_idents(#value) => #zalsa::AsId::as_id(#value),
+                        )*
+                    }
+                }
+            }
+
+            impl #impl_generics #zalsa::FromIdWithDb for #ident #ty_generics #where_clause {
+                fn from_id(#id: #zalsa::Id, #db: &dyn #zalsa::Database) -> Self {
+                    let #owner = #zalsa::owner_ingredient(#db, #id);
+                    #(
+                        {
+                            static #cache: #zalsa::IngredientIndicesCache =
+                                #zalsa::IngredientIndicesCache::new();
+                            let indices = #cache.get_or_create(#db, || {
+                                #zalsa::ingredient_indices::<#variant_tys>(#db)
+                            });
+                            if indices.contains(&#owner) {
+                                return Self::#variant_idents(
+                                    <#variant_tys as #zalsa::FromIdWithDb>::from_id(#id, #db),
+                                );
+                            }
+                        }
+                    )*
+                    panic!(
+                        "`{:?}` does not belong to a variant of `{}`",
+                        #id,
+                        stringify!(#ident),
+                    )
+                }
+            }
+
+            impl #impl_generics #zalsa::SalsaStructInDb for #ident #ty_generics #where_clause {
+                fn lookup_ingredient_index(
+                    _: &dyn #zalsa::JarAux,
+                ) -> core::option::Option<#zalsa::IngredientIndex> {
+                    None
+                }
+
+                fn lookup_ingredient_indices(
+                    #aux: &dyn #zalsa::JarAux,
+                ) -> Vec<#zalsa::IngredientIndex> {
+                    let mut indices = Vec::new();
+                    #(
+                        indices.extend(
+                            <#variant_tys as #zalsa::SalsaStructInDb>::lookup_ingredient_indices(#aux),
+                        );
+                    )*
+                    indices
+                }
+
+                fn register_ingredients(#db: &dyn #zalsa::Database) {
+                    #(
+                        <#variant_tys as #zalsa::SalsaStructInDb>::register_ingredients(#db);
+                    )*
+                }
+            }
+
+            #(
+                impl #impl_generics From<#variant_tys> for #ident #ty_generics #where_clause {
+                    fn from(#value: #variant_tys) -> Self {
+                        Self::#variant_idents(#value)
+                    }
+                }
+
+                /// Fails with the original value if it is another variant.
+                impl #impl_generics TryFrom<#ident #ty_generics> for #variant_tys #where_clause {
+                    type Error = #ident #ty_generics;
+
+                    #[allow(unreachable_patterns)]
+                    fn try_from(#value: #ident #ty_generics) -> Result<Self, Self::Error> {
+                        match #value {
+                            #ident::#variant_idents(#value) => Ok(#value),
+                            _ => Err(#value),
+                        }
+                    }
+                }
+            )*
+
+            impl #impl_generics #ident #ty_generics #where_clause {
+                #(
+                    /// Returns the wrapped struct if this is the
+                    #[doc = concat!("`", stringify!(#variant_idents), "`")]
+                    /// variant.
+                    #[allow(unreachable_patterns)]
+                    pub fn #as_variant_fns(self) -> Option<#variant_tys> {
+                        match self {
+                            Self::#variant_idents(#value) => Some(#value),
+                            _ => None,
+                        }
+                    }
+                )*
+            }
+        };
+    };
+
+    Ok(crate::debug::dump_tokens(&input.ident, tokens))
+}
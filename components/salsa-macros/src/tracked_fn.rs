@@ -1,6 +1,6 @@
 use proc_macro2::{Literal, Span, TokenStream};
 use quote::ToTokens;
-use syn::{spanned::Spanned, ItemFn};
+use syn::{spanned::Spanned, visit_mut::VisitMut, ItemFn};
 
 use crate::{db_lifetime, fn_util, hygiene::Hygiene, options::Options};
 
@@ -48,6 +48,34 @@ impl crate::options::AllowedOptions for TrackedFn {
     const CONSTRUCTOR_NAME: bool = false;
 
     const ID: bool = false;
+
+    const DEDUPE: bool = true;
+
+    const DENSE: bool = false;
+
+    const SPECIFY_UNCHECKED: bool = true;
+
+    const FINGERPRINT: bool = true;
+
+    const RETURNS: bool = true;
+
+    const TIMEOUT: bool = true;
+
+    const ADAPTIVE: bool = true;
+
+    const RESULT: bool = true;
+
+    const LAZY_UPDATE: bool = false;
+
+    const WEAK: bool = true;
+
+    const STORE_WITH: bool = true;
+
+    const IDENTITY_KEY: bool = false;
+
+    const COMPARE_WITH: bool = true;
+
+    const STRICT_REVISIONS: bool = true;
 }
 
 struct Macro {
@@ -62,19 +90,40 @@ struct ValidFn<'item> {
 
 #[allow(non_snake_case)]
 impl Macro {
-    fn try_fn(&self, item: syn::ItemFn) -> syn::Result<TokenStream> {
+    fn try_fn(&self, mut item: syn::ItemFn) -> syn::Result<TokenStream> {
+        let contexts = self.take_not_key_args(&mut item)?;
         let ValidFn { db_ident, db_path } = self.validity_check(&item)?;
 
         let attrs = &item.attrs;
         let fn_name = &item.sig.ident;
         let vis = &item.vis;
         let db_lt = db_lifetime::db_lifetime(&item.sig.generics);
-        let input_ids = self.input_ids(&item);
-        let input_tys = self.input_tys(&item)?;
+        let mut input_ids = self.input_ids(&item);
+        let mut input_tys = self.input_tys(&item)?;
+        let context_ids = input_ids.split_off(input_ids.len() - contexts);
+        let context_tys = input_tys.split_off(input_tys.len() - contexts);
+        let contextual = contexts > 0;
         let output_ty = self.output_ty(&db_lt, &item)?;
+        let result = self.args.result.is_some();
+        let retry_errors = matches!(self.args.result, Some((_, Some(_))));
+        let output_ty = if result {
+            shared_error_ty(output_ty)?
+        } else {
+            output_ty
+        };
         let (cycle_recovery_fn, cycle_recovery_strategy) = self.cycle_recovery();
+        let (timeout, timeout_result_fn) = self.timeout();
+        let adaptive = self.adaptive();
         let is_specifiable = self.args.specify.is_some();
+        let is_specifiable_unchecked = self.args.specify_unchecked.is_some();
         let no_eq = self.args.no_eq.is_some();
+        let weak = self.args.weak.is_some();
+        let unit = match &item.sig.output {
+            syn::ReturnType::Default => true,
+            syn::ReturnType::Type(_, ty) => {
+                matches!(&**ty, syn::Type::Tuple(tuple) if tuple.elems.is_empty())
+            }
+        };
 
         let mut inner_fn = item.clone();
         inner_fn.vis = syn::Visibility::Inherited;
@@ -85,9 +134,10 @@ impl Macro {
         let InternedData = self.hygiene.ident("InternedData");
         let FN_CACHE = self.hygiene.ident("FN_CACHE");
         let INTERN_CACHE = self.hygiene.ident("INTERN_CACHE");
+        let CONTEXT = self.hygiene.ident("CONTEXT");
         let inner = &inner_fn.sig.ident;
 
-        let function_type = function_type(&item);
+        let function_type = function_type(&item, contexts);
 
         if is_specifiable {
             match function_type {
@@ -108,6 +158,98 @@ impl Macro {
             ));
         }
 
+        if let (Some(_), Some(token)) = (&self.args.lru, &self.args.specify_unchecked) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `specify_unchecked` and `lru` options cannot be used together",
+            ));
+        }
+
+        if let (Some(_), Some(token)) = (&self.args.no_eq, &self.args.fingerprint) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `fingerprint` and `no_eq` options cannot be used together",
+            ));
+        }
+
+        if let (None, Some(token)) = (&self.args.return_ref, &self.args.strict_revisions) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `strict_revisions` option requires `return_ref`",
+            ));
+        }
+
+        if let (Some(_), Some(token)) = (&self.args.return_ref, &self.args.returns) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `returns` and `return_ref` options cannot be used together",
+            ));
+        }
+
+        if let Some((token, _)) = &self.args.result {
+            for (option, name) in [
+                (&self.args.dedupe, "dedupe"),
+                (&self.args.fingerprint, "fingerprint"),
+                (&self.args.returns, "returns"),
+            ] {
+                if option.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        token,
+                        format!("the `result` and `{name}` options cannot be used together"),
+                    ));
+                }
+            }
+        }
+
+        if let Some(codec) = &self.args.store_with {
+            for (option, name) in [
+                (self.args.return_ref.as_ref(), "return_ref"),
+                (self.args.dedupe.as_ref(), "dedupe"),
+                (self.args.fingerprint.as_ref(), "fingerprint"),
+                (self.args.returns.as_ref(), "returns"),
+                (self.args.result.as_ref().map(|(token, _)| token), "result"),
+            ] {
+                if option.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        codec,
+                        format!("the `store_with` and `{name}` options cannot be used together"),
+                    ));
+                }
+            }
+        }
+
+        if let Some(compare_with) = &self.args.compare_with {
+            for (option, name) in [
+                (self.args.no_eq.is_some(), "no_eq"),
+                (self.args.fingerprint.is_some(), "fingerprint"),
+                (self.args.result.is_some(), "result"),
+                (self.args.store_with.is_some(), "store_with"),
+            ] {
+                if option {
+                    return Err(syn::Error::new_spanned(
+                        compare_with,
+                        format!("the `compare_with` and `{name}` options cannot be used together"),
+                    ));
+                }
+            }
+        }
+
+        match (&self.args.timeout, &self.args.timeout_result) {
+            (Some((lit, _)), None) => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "the `timeout` option requires a `timeout_result` function",
+                ))
+            }
+            (None, Some(path)) => {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "the `timeout_result` option requires a `timeout`",
+                ))
+            }
+            _ => {}
+        }
+
         let needs_interner = match function_type {
             FunctionType::Constant | FunctionType::RequiresInterning => true,
             FunctionType::SalsaStruct => false,
@@ -116,6 +258,22 @@ impl Macro {
         let lru = Literal::usize_unsuffixed(self.args.lru.unwrap_or(0));
 
         let return_ref: bool = self.args.return_ref.is_some();
+        let strict_revisions: bool = self.args.strict_revisions.is_some();
+
+        let dedupe: bool = self.args.dedupe.is_some();
+        let fingerprint: bool = self.args.fingerprint.is_some();
+        let return_arc: bool = self.args.returns.is_some();
+        let shared = dedupe || fingerprint || return_arc;
+        let stored: bool = self.args.store_with.is_some();
+        let codec = match &self.args.store_with {
+            Some(codec) => quote!(#codec),
+            None => quote!(()),
+        };
+        let compared: bool = self.args.compare_with.is_some();
+        let compare_fn = match &self.args.compare_with {
+            Some(compare_fn) => quote!(#compare_fn),
+            None => quote!(()),
+        };
 
         Ok(crate::debug::dump_tokens(
             fn_name,
@@ -128,21 +286,42 @@ impl Macro {
                 db: #db_ident,
                 input_ids: [#(#input_ids),*],
                 input_tys: [#(#input_tys),*],
+                context_ids: [#(#context_ids),*],
+                context_tys: [#(#context_tys),*],
+                contextual: #contextual,
                 output_ty: #output_ty,
                 inner_fn: { #inner_fn },
                 cycle_recovery_fn: #cycle_recovery_fn,
                 cycle_recovery_strategy: #cycle_recovery_strategy,
+                timeout: #timeout,
+                timeout_result_fn: #timeout_result_fn,
+                adaptive: #adaptive,
                 is_specifiable: #is_specifiable,
+                is_specifiable_unchecked: #is_specifiable_unchecked,
                 no_eq: #no_eq,
                 needs_interner: #needs_interner,
                 lru: #lru,
+                weak: #weak,
+                unit: #unit,
                 return_ref: #return_ref,
+                strict_revisions: #strict_revisions,
+                return_arc: #return_arc,
+                dedupe: #dedupe,
+                fingerprint: #fingerprint,
+                shared: #shared,
+                stored: #stored,
+                codec: (#codec),
+                compared: #compared,
+                compare_fn: (#compare_fn),
+                result: #result,
+                retry_errors: #retry_errors,
                 unused_names: [
                     #zalsa,
                     #Configuration,
                     #InternedData,
                     #FN_CACHE,
                     #INTERN_CACHE,
+                    #CONTEXT,
                     #inner,
                 ]
             }],
@@ -165,9 +344,83 @@ impl Macro {
         Ok(ValidFn { db_ident, db_path })
     }
 
+    /// Removes the `#[salsa::not_key]` attributes from the arguments of `item`,
+    /// returning the number of arguments that had one, which must be trailing shared references.
+    ///
+    /// They must not mention the `'db` lifetime either: the memoized value, which outlives
+    /// the call, could otherwise borrow from them. Anonymous lifetimes are distinct from
+    /// every lifetime of the return type, so the function cannot return such a borrow.
+    fn take_not_key_args(&self, item: &mut syn::ItemFn) -> syn::Result<usize> {
+        let db_lt = item
+            .sig
+            .generics
+            .lifetimes()
+            .next()
+            .map(|param| param.lifetime.clone());
+        let mut contexts = 0;
+        for (index, input) in item.sig.inputs.iter_mut().enumerate() {
+            let syn::FnArg::Typed(typed) = input else {
+                continue;
+            };
+            let not_key_attrs = typed
+                .attrs
+                .iter()
+                .filter(|attr| is_not_key_attr(attr))
+                .count();
+            typed.attrs.retain(|attr| !is_not_key_attr(attr));
+            if not_key_attrs == 0 {
+                if contexts > 0 {
+                    return Err(syn::Error::new_spanned(
+                        typed,
+                        "arguments marked `#[salsa::not_key]` must come after all other arguments",
+                    ));
+                }
+                continue;
+            }
+            if index == 0 {
+                return Err(syn::Error::new_spanned(
+                    typed,
+                    "the database argument cannot be marked `#[salsa::not_key]`",
+                ));
+            }
+            if !matches!(&*typed.ty, syn::Type::Reference(reference) if reference.mutability.is_none())
+            {
+                return Err(syn::Error::new_spanned(
+                    &typed.ty,
+                    "arguments marked `#[salsa::not_key]` must be shared references",
+                ));
+            }
+            if let Some(db_lt) = &db_lt {
+                let mut finder = FindLifetime {
+                    lifetime: db_lt,
+                    found: None,
+                };
+                finder.visit_type_mut(&mut typed.ty);
+                if let Some(found) = finder.found {
+                    return Err(syn::Error::new_spanned(
+                        found,
+                        format!(
+                            "arguments marked `#[salsa::not_key]` cannot mention `{db_lt}`, \
+                             as the memoized value could then borrow from them"
+                        ),
+                    ));
+                }
+            }
+            contexts += 1;
+        }
+        Ok(contexts)
+    }
+
     fn cycle_recovery(&self) -> (TokenStream, TokenStream) {
         if let Some(recovery_fn) = &self.args.recovery_fn {
-            (quote!((#recovery_fn)), quote!(Fallback))
+            if recovery_fn.is_ident("default") {
+                (
+                    quote!((salsa::plumbing::default_cycle_recovery!)),
+                    quote!(Fallback),
+                )
+            } else {
+                (quote!((#recovery_fn)), quote!(Fallback))
+            }
         } else {
             (
                 quote!((salsa::plumbing::unexpected_cycle_recovery!)),
@@ -176,6 +429,39 @@ impl Macro {
         }
     }
 
+    fn timeout(&self) -> (TokenStream, TokenStream) {
+        if let (Some((_, duration)), Some(timeout_result)) =
+            (&self.args.timeout, &self.args.timeout_result)
+        {
+            let nanos =
+                Literal::u64_unsuffixed(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX));
+            (
+                quote!((Some(std::time::Duration::from_nanos(#nanos)))),
+                quote!((#timeout_result)),
+            )
+        } else {
+            (
+                quote!((None)),
+                quote!((salsa::plumbing::unexpected_timeout_result!)),
+            )
+        }
+    }
+
+    fn adaptive(&self) -> TokenStream {
+        match &self.args.adaptive {
+            Some((_, Some(threshold))) => {
+                let nanos = Literal::u64_unsuffixed(
+                    u64::try_from(threshold.as_nanos()).unwrap_or(u64::MAX),
+                );
+                quote!((Some(std::time::Duration::from_nanos(#nanos))))
+            }
+            Some((_, None)) => {
+                quote!((Some(salsa::plumbing::function::DEFAULT_ADAPTIVE_THRESHOLD)))
+            }
+            None => quote!((None)),
+        }
+    }
+
     fn input_ids(&self, item: &ItemFn) -> Vec<syn::Ident> {
         fn_util::input_ids(&self.hygiene, &item.sig, 1)
     }
@@ -189,6 +475,32 @@ impl Macro {
     }
 }
 
+/// Rewrites the return type `Result<T, E>` of a `result` function to `Result<T, Arc<E>>`,
+/// the type of its memoized values.
+fn shared_error_ty(mut output_ty: syn::Type) -> syn::Result<syn::Type> {
+    let error_ty = match &mut output_ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => match path.segments.last_mut() {
+            Some(syn::PathSegment {
+                ident,
+                arguments: syn::PathArguments::AngleBracketed(args),
+            }) if ident == "Result" && args.args.len() == 2 => match &mut args.args[1] {
+                syn::GenericArgument::Type(error_ty) => Some(error_ty),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    };
+    let Some(error_ty) = error_ty else {
+        return Err(syn::Error::new_spanned(
+            &output_ty,
+            "functions with the `result` option must return a `Result<T, E>`",
+        ));
+    };
+    *error_ty = parse_quote!(std::sync::Arc<#error_ty>);
+    Ok(output_ty)
+}
+
 #[derive(Debug, PartialEq, Eq, Hash)]
 enum FunctionType {
     Constant,
@@ -196,8 +508,9 @@ enum FunctionType {
     RequiresInterning,
 }
 
-fn function_type(item_fn: &syn::ItemFn) -> FunctionType {
-    match item_fn.sig.inputs.len() {
+/// The kind of key of a function, whose last `contexts` arguments are not part of it.
+fn function_type(item_fn: &syn::ItemFn, contexts: usize) -> FunctionType {
+    match item_fn.sig.inputs.len() - contexts {
         0 => unreachable!(
             "functions have been checked to have at least a database argument by this point"
         ),
@@ -207,6 +520,25 @@ fn function_type(item_fn: &syn::ItemFn) -> FunctionType {
     }
 }
 
+/// Finds the first occurrence of `lifetime` in a type.
+struct FindLifetime<'lt> {
+    lifetime: &'lt syn::Lifetime,
+    found: Option<syn::Lifetime>,
+}
+
+impl syn::visit_mut::VisitMut for FindLifetime<'_> {
+    fn visit_lifetime_mut(&mut self, i: &mut syn::Lifetime) {
+        if self.found.is_none() && i == self.lifetime {
+            self.found = Some(i.clone());
+        }
+    }
+}
+
+pub(crate) fn is_not_key_attr(attr: &syn::Attribute) -> bool {
+    let segments = &attr.path().segments;
+    segments.len() == 2 && segments[0].ident == "salsa" && segments[1].ident == "not_key"
+}
+
 pub fn check_db_argument<'arg>(
     fn_arg: &'arg syn::FnArg,
     explicit_lt: Option<&'arg syn::LifetimeParam>,
@@ -148,6 +148,21 @@ impl Macro {
 
         let (db_ident, db_ty) = self.check_db_argument(&fn_item.sig.inputs[1])?;
 
+        for input in &fn_item.sig.inputs {
+            if let syn::FnArg::Typed(typed) = input {
+                if let Some(attr) = typed
+                    .attrs
+                    .iter()
+                    .find(|attr| crate::tracked_fn::is_not_key_attr(attr))
+                {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "`#[salsa::not_key]` is only supported on tracked functions, not tracked methods",
+                    ));
+                }
+            }
+        }
+
         let input_ids: Vec<syn::Ident> = crate::fn_util::input_ids(&self.hygiene, &fn_item.sig, 2);
         let input_tys = crate::fn_util::input_tys(&fn_item.sig, 2)?;
         let output_ty = crate::fn_util::output_ty(db_lt, &fn_item.sig)?;
@@ -184,12 +199,14 @@ impl Macro {
     ) -> syn::Result<Option<&'syn syn::Lifetime>> {
         // Either the impl XOR the fn can have generics, and it must be at most a lifetime
         let mut db_lt = None;
-        for param in impl_item
+        let impl_params = impl_item.generics.params.iter().map(|param| (param, true));
+        let fn_params = fn_item
+            .sig
             .generics
             .params
             .iter()
-            .chain(fn_item.sig.generics.params.iter())
-        {
+            .map(|param| (param, false));
+        for (param, on_impl) in impl_params.chain(fn_params) {
             match param {
                 syn::GenericParam::Lifetime(lt) => {
                     if db_lt.is_none() {
@@ -208,6 +225,15 @@ impl Macro {
                         ));
                     }
                 }
+                // Each tracked method is memoized by a single function ingredient,
+                // which cannot be generic.
+                _ if on_impl => {
+                    return Err(syn::Error::new_spanned(
+                        param,
+                        "tracked methods cannot be defined in impls with non-lifetime generic parameters; \
+                         write one impl per type argument instead (e.g. `impl Trait<A> for Struct`)",
+                    ));
+                }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         param,
@@ -295,7 +321,15 @@ impl Macro {
     ) -> syn::Result<()> {
         if let Some(return_ref) = &args.return_ref {
             if let syn::ReturnType::Type(_, t) = &mut sig.output {
-                **t = parse_quote!(& #db_lt #t)
+                if args.strict_revisions.is_some() {
+                    let db_lt = match db_lt {
+                        Some(db_lt) => db_lt.clone(),
+                        None => parse_quote!('_),
+                    };
+                    **t = parse_quote!(salsa::RevisionRef<#db_lt, #t>)
+                } else {
+                    **t = parse_quote!(& #db_lt #t)
+                }
             } else {
                 return Err(syn::Error::new_spanned(
                     return_ref,
@@ -303,6 +337,16 @@ impl Macro {
                 ));
             };
         }
+        if let Some(returns) = &args.returns {
+            if let syn::ReturnType::Type(_, t) = &mut sig.output {
+                **t = parse_quote!(::std::sync::Arc<#t>)
+            } else {
+                return Err(syn::Error::new_spanned(
+                    returns,
+                    "returns attribute requires explicit return type",
+                ));
+            };
+        }
         Ok(())
     }
 }
@@ -54,6 +54,34 @@ impl crate::options::AllowedOptions for TrackedStruct {
     const CONSTRUCTOR_NAME: bool = true;
 
     const ID: bool = false;
+
+    const DEDUPE: bool = false;
+
+    const DENSE: bool = false;
+
+    const SPECIFY_UNCHECKED: bool = false;
+
+    const FINGERPRINT: bool = false;
+
+    const RETURNS: bool = false;
+
+    const TIMEOUT: bool = false;
+
+    const ADAPTIVE: bool = false;
+
+    const RESULT: bool = false;
+
+    const LAZY_UPDATE: bool = true;
+
+    const WEAK: bool = false;
+
+    const STORE_WITH: bool = false;
+
+    const IDENTITY_KEY: bool = true;
+
+    const COMPARE_WITH: bool = false;
+
+    const STRICT_REVISIONS: bool = false;
 }
 
 impl SalsaStructAllowedOptions for TrackedStruct {
@@ -66,6 +94,14 @@ impl SalsaStructAllowedOptions for TrackedStruct {
     const ELIDABLE_LIFETIME: bool = false;
 
     const ALLOW_DEFAULT: bool = false;
+
+    const ALLOW_DELTA: bool = false;
+
+    const ALLOW_ELEMENTS: bool = true;
+
+    const ALLOW_KEY: bool = false;
+
+    const ARENA_FIELDS: bool = false;
 }
 
 struct Macro {
@@ -87,12 +123,40 @@ impl Macro {
         let field_ids = salsa_struct.field_ids();
         let field_vis = salsa_struct.field_vis();
         let field_getter_ids = salsa_struct.field_getter_ids();
+        let field_getter_docs = salsa_struct.field_getter_docs();
         let field_indices = salsa_struct.field_indices();
         let id_field_indices = salsa_struct.id_field_indices();
         let num_fields = salsa_struct.num_fields();
         let field_options = salsa_struct.field_options();
         let field_tys = salsa_struct.field_tys();
         let generate_debug_impl = salsa_struct.generate_debug_impl();
+        let element_fields = salsa_struct.element_fields();
+        let has_element_fields = !element_fields.is_empty();
+        let lazy_update = self.args.lazy_update.is_some();
+        let is_singleton = self.args.singleton.is_some();
+        let identity_key = self.args.identity_key.is_some();
+
+        if let (Some(token), true) = (&self.args.lazy_update, has_element_fields) {
+            return Err(syn::Error::new_spanned(
+                token,
+                "the `lazy_update` option cannot be used with `#[elements]` fields",
+            ));
+        }
+
+        if let Some(token) = &self.args.identity_key {
+            if is_singleton {
+                return Err(syn::Error::new_spanned(
+                    token,
+                    "the `identity_key` and `singleton` options cannot be used together",
+                ));
+            }
+            if !id_field_indices.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    token,
+                    "the `identity_key` option cannot be used with `#[id]` fields",
+                ));
+            }
+        }
 
         let zalsa = self.hygiene.ident("zalsa");
         let zalsa_struct = self.hygiene.ident("zalsa_struct");
@@ -112,13 +176,18 @@ impl Macro {
                     db_lt: #db_lt,
                     new_fn: #new_fn,
                     field_ids: [#(#field_ids),*],
-                    field_getters: [#(#field_vis #field_getter_ids),*],
+                    field_getters: [#(#field_getter_docs #field_vis #field_getter_ids),*],
                     field_tys: [#(#field_tys),*],
                     field_indices: [#(#field_indices),*],
                     id_field_indices: [#(#id_field_indices),*],
                     field_options: [#(#field_options),*],
                     num_fields: #num_fields,
+                    element_fields: [#(#element_fields),*],
+                    has_element_fields: #has_element_fields,
                     generate_debug_impl: #generate_debug_impl,
+                    lazy_update: #lazy_update,
+                    is_singleton: #is_singleton,
+                    identity_key: #identity_key,
                     unused_names: [
                         #zalsa,
                         #zalsa_struct,
@@ -46,35 +46,62 @@ pub(crate) fn update_derive(input: syn::DeriveInput) -> syn::Result<TokenStream>
             // For each field, invoke `maybe_update` recursively to update its value.
             // Or the results together (using `|`, not `||`, to avoid shortcircuiting)
             // to get the final return value.
-            let update_fields = variant.bindings().iter().zip(0..).fold(
+            let update_fields = variant.bindings().iter().zip(0..).try_fold(
                 quote!(false),
                 |tokens, (binding, index)| {
                     let field_ty = &binding.ast().ty;
                     let field_index = Literal::usize_unsuffixed(index);
 
-                    quote! {
-                        #tokens |
-                            unsafe {
-                                salsa::plumbing::UpdateDispatch::<#field_ty>::maybe_update(
-                                    #binding,
-                                    #new_value.#field_index,
-                                )
-                            }
-                    }
+                    let update_field = match update_option(binding.ast())? {
+                        Some(UpdateOption::With(path)) => quote! {
+                            #path(#binding, #new_value.#field_index)
+                        },
+                        Some(UpdateOption::DynEq(path)) => quote! {
+                            salsa::plumbing::update_dyn_eq::<#field_ty, _>(
+                                #binding,
+                                #new_value.#field_index,
+                                |old, new| #path(old, new),
+                            )
+                        },
+                        None => quote! {
+                            salsa::plumbing::UpdateDispatch::<#field_ty>::maybe_update(
+                                #binding,
+                                #new_value.#field_index,
+                            )
+                        },
+                    };
+
+                    Ok::<_, syn::Error>(quote! {
+                        #tokens | unsafe { #update_field }
+                    })
                 },
-            );
+            )?;
 
-            quote!(
+            Ok(quote!(
                 #variant_pat => {
                     #make_new_value
                     #update_fields
                 }
-            )
+            ))
         })
+        .collect::<syn::Result<_>>()?;
+
+    // Every type parameter must itself be `Update` so that fields mentioning it
+    // dispatch to their `Update` impl (rather than requiring `'static`).
+    let mut generics = input.generics.clone();
+    let type_params: Vec<_> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
         .collect();
+    let where_clause = generics.make_where_clause();
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(parse_quote!(#param: salsa::Update));
+    }
 
     let ident = &input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let tokens = quote! {
         #[allow(clippy::all)]
         unsafe impl #impl_generics salsa::Update for #ident #ty_generics #where_clause {
@@ -90,3 +117,42 @@ pub(crate) fn update_derive(input: syn::DeriveInput) -> syn::Result<TokenStream>
 
     Ok(crate::debug::dump_tokens(&input.ident, tokens))
 }
+
+/// An option given with `#[update(..)]` on a field.
+enum UpdateOption {
+    /// `#[update(with = path)]`: the function must have the signature
+    /// `unsafe fn(*mut T, T) -> bool`, with the same contract as `salsa::Update::maybe_update`.
+    With(syn::Path),
+
+    /// `#[update(dyn_eq = path)]`: the field is a `Box<dyn Trait>` or `Option<Box<dyn Trait>>`
+    /// and the function, with the signature `fn(&dyn Trait, &dyn Trait) -> bool`,
+    /// compares the trait objects (see `salsa::plumbing::update_dyn_eq`).
+    DynEq(syn::Path),
+}
+
+/// Returns the option given with `#[update(..)]` on `field`, if any.
+fn update_option(field: &syn::Field) -> syn::Result<Option<UpdateOption>> {
+    let mut option = None;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("update") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            let make_option = if meta.path.is_ident("with") {
+                UpdateOption::With
+            } else if meta.path.is_ident("dyn_eq") {
+                UpdateOption::DynEq
+            } else {
+                return Err(meta
+                    .error("unrecognized option, expected `with = <path>` or `dyn_eq = <path>`"));
+            };
+            if option.is_some() {
+                return Err(meta.error("only one of `with` and `dyn_eq` may be provided"));
+            }
+            option = Some(make_option(meta.value()?.parse::<syn::Path>()?));
+            Ok(())
+        })?;
+    }
+    Ok(option)
+}
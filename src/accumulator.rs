@@ -11,7 +11,8 @@ use accumulated::AnyAccumulated;
 
 use crate::{
     cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, Ingredient, Jar, MaybeChangedAfter},
+    hash::FxHashSet,
+    ingredient::{fmt_index, Ingredient, IngredientKind, Jar, MaybeChangedAfter},
     plumbing::JarAux,
     zalsa::IngredientIndex,
     zalsa_local::QueryOrigin,
@@ -30,6 +31,21 @@ pub trait Accumulator: Clone + Debug + Send + Sync + Any + Sized {
     fn accumulate<Db>(self, db: &Db)
     where
         Db: ?Sized + Database;
+
+    /// The values accumulated so far by the active tracked function, followed by
+    /// those accumulated by the queries it has read so far (transitively),
+    /// in the order they were executed.
+    ///
+    /// The values accumulated by the active query are not finalized, so more values
+    /// may be accumulated afterwards. If any of the queries read so far has accumulated values,
+    /// this is reported as an untracked read, like reading the values accumulated by a query.
+    ///
+    /// # Panics
+    ///
+    /// If no tracked function is executing.
+    fn accumulated_so_far<Db>(db: &Db) -> Vec<Self>
+    where
+        Db: ?Sized + Database;
 }
 
 pub struct JarImpl<A: Accumulator> {
@@ -93,6 +109,76 @@ impl<A: Accumulator> IngredientImpl<A> {
     pub fn index(&self) -> IngredientIndex {
         self.index
     }
+
+    pub fn accumulated_so_far(&self, db: &dyn Database) -> Vec<A> {
+        let mut output = vec![];
+        let Ok(inputs) = db.zalsa_local().accumulated_so_far(self.index, &mut output) else {
+            panic!("cannot read accumulated values outside of an active tracked function");
+        };
+        if !inputs.is_empty() {
+            // As in `accumulated_by`, the values accumulated by other queries may change
+            // without their return values changing, so we cannot track them precisely.
+            db.report_untracked_read();
+        }
+        extend_with_accumulated_by(db, self.index, &inputs, &mut output);
+        output
+    }
+}
+
+/// Extends `output` with the values accumulated to the accumulator `index` by the queries
+/// `roots` and their inputs, in execution order.
+pub(crate) fn extend_with_accumulated_by<A: Accumulator>(
+    db: &dyn Database,
+    index: IngredientIndex,
+    roots: &[DatabaseKeyIndex],
+    output: &mut Vec<A>,
+) {
+    let zalsa = db.zalsa();
+    let mut visited: FxHashSet<DatabaseKeyIndex> = FxHashSet::default();
+    let mut stack: Vec<DatabaseKeyIndex> = roots.iter().rev().copied().collect();
+
+    // Do a depth-first earch across the dependencies of `roots`, reading the values accumulated by
+    // each dependency.
+    while let Some(k) = stack.pop() {
+        // Already visited `k`?
+        if !visited.insert(k) {
+            continue;
+        }
+
+        let ingredient = zalsa.lookup_ingredient(k.ingredient_index);
+        // Extend `output` with any values accumulated by `k`.
+        let (accumulated_map, input) = ingredient.accumulated(db, k.key_index);
+        if let Some(accumulated_map) = accumulated_map {
+            accumulated_map.extend_with_accumulated(index, output);
+        }
+        // Skip over the inputs because we know that the entire sub-graph has no accumulated values
+        if input.is_empty() {
+            continue;
+        }
+
+        // Find the inputs of `k` and push them onto the stack.
+        //
+        // Careful: to ensure the user gets a consistent ordering in their
+        // output vector, we want to push in execution order, so reverse order to
+        // ensure the first child that was executed will be the first child popped
+        // from the stack.
+        let Some(origin) = ingredient.origin(db, k.key_index) else {
+            continue;
+        };
+
+        if let QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges) = &origin {
+            stack.reserve(edges.input_outputs.len());
+        }
+
+        stack.extend(
+            origin
+                .inputs()
+                .filter_map(|input| TryInto::<DatabaseKeyIndex>::try_into(input).ok())
+                .rev(),
+        );
+
+        visited.reserve(stack.len());
+    }
 }
 
 impl<A: Accumulator> Ingredient for IngredientImpl<A> {
@@ -148,6 +234,10 @@ impl<A: Accumulator> Ingredient for IngredientImpl<A> {
     fn debug_name(&self) -> &'static str {
         A::DEBUG_NAME
     }
+
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::Accumulator
+    }
 }
 
 impl<A> std::fmt::Debug for IngredientImpl<A>
@@ -1,4 +1,5 @@
 use std::ops::Not;
+use std::time::Instant;
 
 use crossbeam::atomic::AtomicCell;
 
@@ -8,6 +9,7 @@ use crate::tracked_struct::{DisambiguatorMap, IdentityHash, IdentityMap};
 use crate::zalsa_local::QueryEdge;
 use crate::{
     accumulator::accumulated_map::{AccumulatedMap, InputAccumulatedValues},
+    channel::Channels,
     durability::Durability,
     hash::FxIndexSet,
     key::{DatabaseKeyIndex, InputDependencyIndex},
@@ -23,6 +25,9 @@ pub(crate) struct ActiveQuery {
     /// Minimum durability of inputs observed so far.
     pub(crate) durability: Durability,
 
+    /// Channels of the inputs observed so far.
+    pub(crate) channels: Channels,
+
     /// Maximum revision of all inputs observed. If we observe an
     /// untracked read, this will be set to the most recent revision.
     pub(crate) changed_at: Revision,
@@ -61,13 +66,18 @@ pub(crate) struct ActiveQuery {
     /// [`InputAccumulatedValues::Empty`] if any input read during the query's execution
     /// has any accumulated values.
     pub(super) accumulated_inputs: InputAccumulatedValues,
+
+    /// When this query, or a query it is executing for, times out; see
+    /// [`ZalsaLocal::unwind_if_timed_out`](`crate::zalsa_local::ZalsaLocal::unwind_if_timed_out`).
+    pub(crate) deadline: Option<Instant>,
 }
 
 impl ActiveQuery {
-    pub(super) fn new(database_key_index: DatabaseKeyIndex) -> Self {
+    pub(super) fn new(database_key_index: DatabaseKeyIndex, deadline: Option<Instant>) -> Self {
         ActiveQuery {
             database_key_index,
             durability: Durability::MAX,
+            channels: Channels::EMPTY,
             changed_at: Revision::start(),
             input_outputs: FxIndexSet::default(),
             untracked_read: false,
@@ -76,18 +86,21 @@ impl ActiveQuery {
             tracked_struct_ids: Default::default(),
             accumulated: Default::default(),
             accumulated_inputs: Default::default(),
+            deadline,
         }
     }
 
     pub(super) fn add_read(
         &mut self,
-        input: InputDependencyIndex,
+        input: QueryEdge,
         durability: Durability,
+        channels: Channels,
         revision: Revision,
         accumulated: InputAccumulatedValues,
     ) {
-        self.input_outputs.insert(QueryEdge::Input(input));
+        self.input_outputs.insert(input);
         self.durability = self.durability.min(durability);
+        self.channels = self.channels.union(channels);
         self.changed_at = self.changed_at.max(revision);
         self.accumulated_inputs |= accumulated;
     }
@@ -95,12 +108,14 @@ impl ActiveQuery {
     pub(super) fn add_untracked_read(&mut self, changed_at: Revision) {
         self.untracked_read = true;
         self.durability = Durability::MIN;
+        self.channels = Channels::ALL;
         self.changed_at = changed_at;
     }
 
     pub(super) fn add_synthetic_read(&mut self, durability: Durability, revision: Revision) {
         self.untracked_read = true;
         self.durability = self.durability.min(durability);
+        self.channels = Channels::ALL;
         self.changed_at = self.changed_at.max(revision);
     }
 
@@ -114,6 +129,21 @@ impl ActiveQuery {
         self.input_outputs.contains(&QueryEdge::Output(key))
     }
 
+    /// The queries read so far, in the order they were read; empty if none of them
+    /// has any accumulated values.
+    pub(super) fn inputs_with_accumulated(&self) -> Vec<DatabaseKeyIndex> {
+        if self.accumulated_inputs.is_empty() {
+            return vec![];
+        }
+        self.input_outputs
+            .iter()
+            .filter_map(|edge| match *edge {
+                QueryEdge::Input(input) | QueryEdge::InputRange(input, _) => input.try_into().ok(),
+                QueryEdge::Output(_) => None,
+            })
+            .collect()
+    }
+
     pub(crate) fn into_revisions(self) -> QueryRevisions {
         let edges = QueryEdges::new(self.input_outputs);
         let origin = if self.untracked_read {
@@ -130,6 +160,7 @@ impl ActiveQuery {
             changed_at: self.changed_at,
             origin,
             durability: self.durability,
+            channels: self.channels,
             tracked_struct_ids: self.tracked_struct_ids,
             accumulated_inputs: AtomicCell::new(self.accumulated_inputs),
             accumulated,
@@ -141,9 +172,11 @@ impl ActiveQuery {
     pub(super) fn add_from(&mut self, other: &ActiveQuery) {
         self.changed_at = self.changed_at.max(other.changed_at);
         self.durability = self.durability.min(other.durability);
+        self.channels = self.channels.union(other.channels);
         self.untracked_read |= other.untracked_read;
         self.input_outputs
             .extend(other.input_outputs.iter().copied());
+        self.accumulated_inputs |= other.accumulated_inputs;
     }
 
     /// Removes the participants in `cycle` from my dependencies.
@@ -155,15 +188,105 @@ impl ActiveQuery {
         }
     }
 
-    /// Copy the changed-at, durability, and dependencies from `cycle_query`.
+    /// Copy the changed-at, durability, and dependencies from `cycle_query`,
+    /// noting whether any of those dependencies have accumulated values.
     /// Used during cycle recovery, see [`Runtime::unblock_cycle_and_maybe_throw`].
     pub(crate) fn take_inputs_from(&mut self, cycle_query: &ActiveQuery) {
         self.changed_at = cycle_query.changed_at;
         self.durability = cycle_query.durability;
+        self.channels = cycle_query.channels;
         self.input_outputs.clone_from(&cycle_query.input_outputs);
+        self.accumulated_inputs |= cycle_query.accumulated_inputs;
     }
 
     pub(super) fn disambiguate(&mut self, key: IdentityHash) -> Disambiguator {
         self.disambiguator_map.disambiguate(key)
     }
 }
+
+/// A snapshot of the queries being executed on the current thread,
+/// innermost first. Captured with [`Backtrace::capture`].
+#[derive(Clone, Debug)]
+pub struct Backtrace(Box<[BacktraceFrame]>);
+
+/// One active query in a [`Backtrace`].
+#[derive(Copy, Clone, Debug)]
+pub struct BacktraceFrame {
+    database_key_index: DatabaseKeyIndex,
+    durability: Durability,
+    changed_at: Revision,
+    in_cycle: bool,
+}
+
+impl Backtrace {
+    /// Captures the query stack of the current thread.
+    /// Returns `None` if no database is attached (i.e., no query is running).
+    pub fn capture() -> Option<Self> {
+        crate::attach::with_attached_database(|db| {
+            db.zalsa_local()
+                .with_query_stack(|stack| Backtrace::from_stack(stack))
+        })
+    }
+
+    /// The backtrace of the query stack `stack`, outermost query first.
+    pub(crate) fn from_stack(stack: &[ActiveQuery]) -> Self {
+        Backtrace(
+            stack
+                .iter()
+                .rev()
+                .map(|query| BacktraceFrame {
+                    database_key_index: query.database_key_index,
+                    durability: query.durability,
+                    changed_at: query.changed_at,
+                    in_cycle: query.cycle.is_some(),
+                })
+                .collect(),
+        )
+    }
+
+    /// The captured frames, innermost query first.
+    pub fn frames(&self) -> &[BacktraceFrame] {
+        &self.0
+    }
+}
+
+impl BacktraceFrame {
+    /// The query being executed.
+    pub fn database_key_index(&self) -> DatabaseKeyIndex {
+        self.database_key_index
+    }
+
+    /// Minimum durability of the inputs the query has read so far.
+    pub fn durability(&self) -> Durability {
+        self.durability
+    }
+
+    /// Maximum revision in which an input the query has read so far last changed.
+    pub fn changed_at(&self) -> Revision {
+        self.changed_at
+    }
+
+    /// True if the query was found to participate in a cycle
+    /// and its result will come from cycle recovery.
+    pub fn in_cycle(&self) -> bool {
+        self.in_cycle
+    }
+}
+
+impl std::fmt::Display for Backtrace {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(fmt, "query stacktrace:")?;
+        for (idx, frame) in self.0.iter().enumerate() {
+            write!(
+                fmt,
+                "{idx:>4}: {:?} (durability: {:?}, changed_at: {:?}",
+                frame.database_key_index, frame.durability, frame.changed_at
+            )?;
+            if frame.in_cycle {
+                write!(fmt, ", in cycle")?;
+            }
+            writeln!(fmt, ")")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    alloc::{self, Layout},
+    cell::Cell,
+    ptr::{self, NonNull},
+};
+
+use parking_lot::Mutex;
+
+/// Size of the chunks the arena allocates from the global allocator,
+/// unless a single value needs more.
+const CHUNK_SIZE: usize = 4096;
+
+thread_local! {
+    /// The arena of the interned ingredient that is assembling a new value on this thread, if any.
+    static ASSEMBLING: Cell<*const Arena> = const { Cell::new(ptr::null()) }
+}
+
+/// A bump allocator for data borrowed by interned values, see [`crate::interned::ArenaRef`].
+///
+/// Memory is only freed when the arena is dropped, along with the ingredient that owns it.
+/// Values are never dropped, so only types without drop glue may be stored.
+#[derive(Default)]
+pub struct Arena {
+    chunks: Mutex<Chunks>,
+}
+
+#[derive(Default)]
+struct Chunks {
+    /// Start of the unused part of the current chunk.
+    next: Option<NonNull<u8>>,
+
+    /// Number of unused bytes in the current chunk.
+    remaining: usize,
+
+    /// Every chunk allocated so far, to be freed on drop.
+    allocated: Vec<(NonNull<u8>, Layout)>,
+}
+
+// SAFETY: the chunks are only accessed while holding the lock,
+// and the memory handed out is never mutated afterwards.
+unsafe impl Send for Arena {}
+unsafe impl Sync for Arena {}
+
+impl Arena {
+    /// Allocates uninitialized memory for `layout`, which must have a non-zero size.
+    /// The memory remains valid until the arena is dropped.
+    pub(crate) fn alloc(&self, layout: Layout) -> NonNull<u8> {
+        debug_assert!(layout.size() > 0);
+        let mut chunks = self.chunks.lock();
+
+        if let Some(next) = chunks.next {
+            let offset = next.as_ptr().align_offset(layout.align());
+            if let Some(needed) = offset.checked_add(layout.size()) {
+                if needed <= chunks.remaining {
+                    chunks.remaining -= needed;
+                    // SAFETY: `needed` bytes past `next` are within the current chunk.
+                    unsafe {
+                        let ptr = NonNull::new_unchecked(next.as_ptr().add(offset));
+                        chunks.next = Some(NonNull::new_unchecked(ptr.as_ptr().add(layout.size())));
+                        return ptr;
+                    }
+                }
+            }
+        }
+
+        let chunk_layout = Layout::from_size_align(CHUNK_SIZE.max(layout.size()), layout.align())
+            .expect("arena chunk too large");
+        // SAFETY: `chunk_layout` has a non-zero size.
+        let Some(chunk) = NonNull::new(unsafe { alloc::alloc(chunk_layout) }) else {
+            alloc::handle_alloc_error(chunk_layout)
+        };
+        chunks.allocated.push((chunk, chunk_layout));
+        chunks.remaining = chunk_layout.size() - layout.size();
+        // SAFETY: the chunk holds at least `layout.size()` bytes.
+        chunks.next = Some(unsafe { NonNull::new_unchecked(chunk.as_ptr().add(layout.size())) });
+        chunk
+    }
+
+    /// Invokes `op`; for its duration, [`with_assembling`] gives access to this arena.
+    pub(crate) fn assembling<R>(&self, op: impl FnOnce() -> R) -> R {
+        struct Reset(*const Arena);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                ASSEMBLING.with(|assembling| assembling.set(self.0));
+            }
+        }
+
+        let _reset = Reset(ASSEMBLING.with(|assembling| assembling.replace(self)));
+        op()
+    }
+}
+
+/// Invokes `op` with the arena of the interned ingredient that is assembling a new value
+/// on this thread.
+///
+/// # Panics
+///
+/// If no new value is being assembled, e.g. when converting a lookup key by hand.
+pub(crate) fn with_assembling<R>(op: impl FnOnce(&Arena) -> R) -> R {
+    let arena = ASSEMBLING.with(Cell::get);
+    assert!(
+        !arena.is_null(),
+        "borrowed fields can only be created while interning a new value"
+    );
+    // SAFETY: `Arena::assembling` only sets the pointer while the arena is borrowed.
+    op(unsafe { &*arena })
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        for (chunk, layout) in self.chunks.get_mut().allocated.drain(..) {
+            // SAFETY: each chunk was allocated with its layout, and is freed once.
+            unsafe { alloc::dealloc(chunk.as_ptr(), layout) };
+        }
+    }
+}
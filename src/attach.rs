@@ -1,4 +1,4 @@
-use std::{cell::Cell, ptr::NonNull};
+use std::{cell::Cell, marker::PhantomData, ptr::NonNull};
 
 use crate::Database;
 
@@ -16,59 +16,55 @@ thread_local! {
 struct Attached {
     /// Pointer to the currently attached database.
     database: Cell<Option<NonNull<dyn Database>>>,
+
+    /// Number of live [`AttachGuard`]s, including the one that attached `database`.
+    depth: Cell<usize>,
 }
 
 impl Attached {
     const fn new() -> Self {
         Self {
             database: Cell::new(None),
+            depth: Cell::new(0),
         }
     }
 
-    fn attach<Db, R>(&self, db: &Db, op: impl FnOnce() -> R) -> R
-    where
-        Db: ?Sized + Database,
-    {
-        struct DbGuard<'s> {
-            state: Option<&'s Attached>,
-        }
-
-        impl<'s> DbGuard<'s> {
-            fn new(attached: &'s Attached, db: &dyn Database) -> Self {
-                if let Some(current_db) = attached.database.get() {
-                    let new_db = NonNull::from(db);
-
-                    // Already attached? Assert that the database has not changed.
-                    // NOTE: It's important to use `addr_eq` here because `NonNull::eq`
-                    // not only compares the address but also the type's metadata.
-                    if !std::ptr::addr_eq(current_db.as_ptr(), new_db.as_ptr()) {
-                        panic!(
-                            "Cannot change database mid-query. current: {current_db:?}, new: {new_db:?}",
-                        );
-                    }
-
-                    Self { state: None }
-                } else {
-                    // Otherwise, set the database.
-                    attached.database.set(Some(NonNull::from(db)));
-                    Self {
-                        state: Some(attached),
-                    }
-                }
+    /// Attaches `db` unless it is already attached; returns whether it was attached
+    /// by this call and the number of guards that were live before it.
+    fn enter(&self, db: &dyn Database) -> (bool, usize) {
+        let depth = self.depth.get();
+        self.depth.set(depth + 1);
+        let new_db = NonNull::from(db);
+        if let Some(current_db) = self.database.get() {
+            // Already attached? Assert that the database has not changed.
+            // NOTE: It's important to use `addr_eq` here because `NonNull::eq`
+            // not only compares the address but also the type's metadata.
+            if !std::ptr::addr_eq(current_db.as_ptr(), new_db.as_ptr()) {
+                self.depth.set(depth);
+                panic!(
+                    "Cannot change database mid-query. current: {current_db:?}, new: {new_db:?}",
+                );
             }
+            (false, depth)
+        } else {
+            // Otherwise, set the database.
+            self.database.set(Some(new_db));
+            (true, depth)
         }
+    }
 
-        impl Drop for DbGuard<'_> {
-            fn drop(&mut self) {
-                // Reset database to null if we did anything in `DbGuard::new`.
-                if let Some(attached) = self.state {
-                    attached.database.set(None);
-                }
-            }
+    /// Undoes the matching call to `enter`.
+    fn exit(&self, attached: bool, depth: usize) {
+        debug_assert_eq!(
+            self.depth.get(),
+            depth + 1,
+            "attach guards must be dropped in the reverse order of their creation"
+        );
+        self.depth.set(depth);
+        // Reset database to null if we did anything in `enter`.
+        if attached {
+            self.database.set(None);
         }
-
-        let _guard = DbGuard::new(self, db.as_dyn_database());
-        op()
     }
 
     /// Access the "attached" database. Returns `None` if no database is attached.
@@ -76,19 +72,66 @@ impl Attached {
     fn with<R>(&self, op: impl FnOnce(&dyn Database) -> R) -> Option<R> {
         let db = self.database.get()?;
 
-        // SAFETY: We always attach the database in for the entire duration of a function,
+        // SAFETY: We always attach the database in for the entire duration of a function
+        // (or, for `attach_guard`, the caller promises to drop the guard in time),
         // so it cannot become "unattached" while this function is running.
         Some(op(unsafe { db.as_ref() }))
     }
 }
 
+/// Keeps a database attached to the current thread until dropped, see [`attach_guard`].
+#[must_use = "the database is detached when the guard is dropped"]
+pub struct AttachGuard<'db> {
+    attached: bool,
+    depth: usize,
+    // Borrows the database, and is neither `Send` nor `Sync` as it refers to thread-local state.
+    _db: PhantomData<(&'db dyn Database, *const ())>,
+}
+
+impl<'db> AttachGuard<'db> {
+    fn new(db: &'db dyn Database) -> Self {
+        let (attached, depth) = ATTACHED.with(|a| a.enter(db));
+        Self {
+            attached,
+            depth,
+            _db: PhantomData,
+        }
+    }
+}
+
+impl Drop for AttachGuard<'_> {
+    fn drop(&mut self) {
+        ATTACHED.with(|a| a.exit(self.attached, self.depth))
+    }
+}
+
 /// Attach the database to the current thread and execute `op`.
 /// Panics if a different database has already been attached.
 pub fn attach<R, Db>(db: &Db, op: impl FnOnce() -> R) -> R
 where
     Db: ?Sized + Database,
 {
-    ATTACHED.with(|a| a.attach(db, op))
+    let _guard = AttachGuard::new(db.as_dyn_database());
+    op()
+}
+
+/// Attach the database to the current thread until the returned guard is dropped,
+/// like [`attach`] but without having to move the code into a closure.
+/// Panics if a different database has already been attached.
+///
+/// Guards must be dropped in the reverse order of their creation
+/// (checked with a debug assertion).
+///
+/// # Safety
+///
+/// The guard must not be leaked (e.g. with [`std::mem::forget`]): while it is live,
+/// [`with_attached_database`] hands out references to `db`, so it has to be dropped
+/// before `db` is moved or dropped.
+pub unsafe fn attach_guard<Db>(db: &Db) -> AttachGuard<'_>
+where
+    Db: ?Sized + Database,
+{
+    AttachGuard::new(db.as_dyn_database())
 }
 
 /// Access the "attached" database. Returns `None` if no database is attached.
@@ -0,0 +1,96 @@
+//! Loops over the code paths whose performance salsa treats as part of its contract,
+//! so that downstream projects can benchmark exactly these paths (e.g. with criterion)
+//! and notice when an upgrade regresses them.
+//!
+//! ```rust,ignore
+//! let fixture = salsa::bench::Fixture::new();
+//! c.bench_function("fetch_hot", |b| b.iter(|| salsa::bench::fetch_hot_loop(&fixture, 100)));
+//! ```
+//!
+//! The functions and queries behind these loops may change between releases,
+//! the paths they exercise will not.
+
+use std::hint::black_box;
+
+use crate::{Database, DatabaseImpl};
+
+#[salsa::input]
+struct BenchInput {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned]
+struct BenchInterned<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn text_len(db: &dyn Database, input: BenchInput) -> usize {
+    input.text(db).len()
+}
+
+/// A database set up for the loops in this module: an input whose length
+/// has been memoized and an interned string.
+pub struct Fixture {
+    db: DatabaseImpl,
+    input: BenchInput,
+    interned_text: String,
+}
+
+impl Fixture {
+    /// Creates the database, memoizes the query read by [`fetch_hot_loop`]
+    /// and interns the value looked up by [`interned_lookup_loop`].
+    pub fn new() -> Self {
+        let db = DatabaseImpl::new();
+        let interned_text = "hello, world!".to_string();
+        let input = BenchInput::new(&db, interned_text.clone());
+        text_len(&db, input);
+        BenchInterned::new(&db, interned_text.clone());
+        Self {
+            db,
+            input,
+            interned_text,
+        }
+    }
+
+    /// The database of this fixture.
+    pub fn db(&self) -> &DatabaseImpl {
+        &self.db
+    }
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fetches a memoized value that is valid in the current revision `iterations` times,
+/// outside of any query. Returns the sum of the values so the loop is not optimized away.
+pub fn fetch_hot_loop(fixture: &Fixture, iterations: usize) -> usize {
+    (0..iterations)
+        .map(|_| text_len(black_box(&fixture.db), black_box(fixture.input)))
+        .sum()
+}
+
+/// Interns a value that is already interned `iterations` times, outside of any query.
+/// Returns the number of distinct ids obtained, which is 1.
+pub fn interned_lookup_loop(fixture: &Fixture, iterations: usize) -> usize {
+    let mut ids = Vec::new();
+    for _ in 0..iterations {
+        let interned = BenchInterned::new(black_box(&fixture.db), fixture.interned_text.as_str());
+        if !ids.contains(&interned) {
+            ids.push(interned);
+        }
+    }
+    ids.len()
+}
+
+/// Reads an input field `iterations` times, outside of any query.
+/// Returns the sum of the lengths of the values read.
+pub fn input_read_loop(fixture: &Fixture, iterations: usize) -> usize {
+    (0..iterations)
+        .map(|_| black_box(fixture.input).text(black_box(&fixture.db)).len())
+        .sum()
+}
@@ -1,8 +1,11 @@
 use std::{
+    cell::Cell,
     fmt,
-    panic::{self, UnwindSafe},
+    panic::{self, AssertUnwindSafe, UnwindSafe},
 };
 
+use crate::key::DatabaseKeyIndex;
+
 /// A panic payload indicating that execution of a salsa query was cancelled.
 ///
 /// This can occur for a few reasons:
@@ -19,10 +22,18 @@ pub enum Cancelled {
     /// The query was blocked on another thread, and that thread panicked.
     #[non_exhaustive]
     PropagatedPanic,
+
+    /// The query was blocked on another thread for longer than the
+    /// [`deadlock_timeout`](`crate::StorageBuilder::deadlock_timeout`),
+    /// with [`panic_on_deadlock`](`crate::StorageBuilder::panic_on_deadlock`) set.
+    #[non_exhaustive]
+    DeadlockDetected,
 }
 
 impl Cancelled {
     pub(crate) fn throw(self) -> ! {
+        #[cfg(feature = "tracing")]
+        tracing::info!(reason = ?self, "cancelled");
         // We use resume and not panic here to avoid running the panic
         // hook (that is, to avoid collecting and printing backtrace).
         std::panic::resume_unwind(Box::new(self));
@@ -43,11 +54,82 @@ impl Cancelled {
     }
 }
 
+/// The panic payload used to unwind a query that ran past its `timeout`,
+/// up to the execution of the query `database_key_index`.
+pub(crate) struct TimedOut {
+    database_key_index: DatabaseKeyIndex,
+}
+
+thread_local! {
+    /// True while this thread unwinds with a [`TimedOut`] payload.
+    static UNWINDING: Cell<bool> = const { Cell::new(false) };
+}
+
+impl TimedOut {
+    pub(crate) fn new(database_key_index: DatabaseKeyIndex) -> Self {
+        Self { database_key_index }
+    }
+
+    pub(crate) fn throw(self) -> ! {
+        tracing::debug!("{:?}: timed out", self.database_key_index);
+        UNWINDING.set(true);
+        std::panic::resume_unwind(Box::new(self))
+    }
+
+    /// True if the current thread is unwinding because a query timed out,
+    /// rather than because of a panic; used to tell the queries it abandons apart.
+    pub(crate) fn is_unwinding() -> bool {
+        UNWINDING.get()
+    }
+
+    /// Runs `execute`, which executes the query `database_key_index`, and
+    /// catches the unwinding started when that query times out.
+    pub(crate) fn catch<T>(
+        database_key_index: DatabaseKeyIndex,
+        execute: impl FnOnce() -> T,
+    ) -> Result<T, TimedOut> {
+        match panic::catch_unwind(AssertUnwindSafe(execute)) {
+            Ok(v) => Ok(v),
+            Err(payload) => match payload.downcast::<TimedOut>() {
+                Ok(timed_out) if timed_out.database_key_index == database_key_index => {
+                    UNWINDING.set(false);
+                    Err(*timed_out)
+                }
+                Ok(timed_out) => panic::resume_unwind(timed_out),
+                Err(payload) => panic::resume_unwind(payload),
+            },
+        }
+    }
+}
+
+/// How a database reacts when a query is running while another handle wants to write.
+/// Chosen when the storage is created, see [`Storage::with_cancellation_mode`](`crate::Storage::with_cancellation_mode`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CancellationMode {
+    /// Running queries are cancelled by unwinding with a [`Cancelled`] payload,
+    /// which can be caught with [`Cancelled::catch`]. This is the default.
+    #[default]
+    Unwind,
+
+    /// No unwinding: queries that have started run to completion (against the revision
+    /// they started in), while the writer waits for them. New work should be started
+    /// through fallible entry points, such as the `try_call` function generated for every
+    /// tracked function or [`Database::check_cancelled`](`crate::Database::check_cancelled`),
+    /// which return `Err(Cancelled::PendingWrite)` once a write is pending.
+    ///
+    /// Functions declared with a `timeout` are not unwound either: they run to completion.
+    ///
+    /// Suitable for `panic = "abort"` builds.
+    Cooperative,
+}
+
 impl std::fmt::Display for Cancelled {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let why = match self {
             Cancelled::PendingWrite => "pending write",
             Cancelled::PropagatedPanic => "propagated panic",
+            Cancelled::DeadlockDetected => "suspected deadlock",
         };
         f.write_str("cancelled because of ")?;
         f.write_str(why)
@@ -0,0 +1,96 @@
+use std::fmt;
+
+/// A group of inputs whose changes are tracked separately from those of other groups,
+/// e.g. the files open in an editor; see [`Database::set_channel`](`crate::Database::set_channel`).
+///
+/// Like [durabilities](`crate::Durability`), channels cut the work of "revalidating" queries
+/// in a new revision: a query that only read inputs of some channels is known to be up to date,
+/// without walking its dependencies, as long as no input of those channels changed.
+/// Unlike durabilities, channels are not ordered, so changing the inputs of one channel
+/// does not invalidate the queries that only read inputs of other channels.
+///
+/// Inputs are in [`Channel::DEFAULT`] unless assigned to another one. Up to 63 channels
+/// are supported: declare them as constants, e.g.
+/// `const OPEN_FILES: Channel = Channel::new(1);`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Channel(u8);
+
+impl Channel {
+    /// The channel of inputs that were not assigned to another one.
+    pub const DEFAULT: Channel = Channel(0);
+
+    /// Number of channels, including the one reserved for changes other than input writes.
+    pub(crate) const LEN: usize = 64;
+
+    /// The channel with the given index, which must be less than 63.
+    /// The index `0` is [`Channel::DEFAULT`].
+    pub const fn new(index: u8) -> Self {
+        assert!(
+            (index as usize) < Self::LEN - 1,
+            "channel indices must be less than 63"
+        );
+        Channel(index)
+    }
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Channel::DEFAULT
+    }
+}
+
+impl fmt::Debug for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Channel").field(&self.0).finish()
+    }
+}
+
+/// The set of channels of the inputs a value was computed from.
+///
+/// Changes other than input writes (e.g. synthetic writes or invalidated ingredients)
+/// advance every channel, including one reserved for the reads of values that are not
+/// inputs (e.g. interned values), so that such reads still make the set non-empty.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Channels(u64);
+
+impl Channels {
+    /// No channels: the value was computed without reading anything.
+    pub(crate) const EMPTY: Channels = Channels(0);
+
+    /// Every channel: the value must be revalidated whenever any input changed,
+    /// e.g. because it read untracked state.
+    pub(crate) const ALL: Channels = Channels(u64::MAX);
+
+    /// The channel reserved for reads of values other than inputs, advanced only by
+    /// changes other than input writes.
+    pub(crate) const OTHER: Channels = Channels(1 << (Channel::LEN - 1));
+
+    pub(crate) const fn of(channel: Channel) -> Self {
+        Channels(1 << channel.0)
+    }
+
+    pub(crate) fn union(self, other: Channels) -> Self {
+        Channels(self.0 | other.0)
+    }
+
+    pub(crate) fn is_subset(self, other: Channels) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    /// The indices of the channels in this set.
+    pub(crate) fn indices(self) -> impl Iterator<Item = usize> {
+        (0..Channel::LEN).filter(move |&index| self.0 & (1 << index) != 0)
+    }
+}
+
+impl Default for Channels {
+    fn default() -> Self {
+        Channels::of(Channel::DEFAULT)
+    }
+}
+
+impl fmt::Debug for Channels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.indices()).finish()
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    cell::Cell,
+    fmt::{self, Write},
+    hash::{BuildHasher, Hash, Hasher},
+};
+
+use rustc_hash::{FxBuildHasher, FxHashSet, FxHasher};
+
+use crate::{key::DatabaseKeyIndex, Database};
+
+thread_local! {
+    /// Set while a checksum is computed, see [`ids_redacted`].
+    static REDACT_IDS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// True while a checksum is computed on this thread. [`Id`](`crate::Id`)s are then
+/// formatted without their value, since it depends on the order in which values were
+/// created and so differs between an incremental and a from-scratch run.
+pub(crate) fn ids_redacted() -> bool {
+    REDACT_IDS.with(Cell::get)
+}
+
+/// Redacts `Id`s on this thread until dropped.
+struct RedactIds {
+    previous: bool,
+}
+
+impl RedactIds {
+    fn new() -> Self {
+        Self {
+            previous: REDACT_IDS.with(|redact| redact.replace(true)),
+        }
+    }
+}
+
+impl Drop for RedactIds {
+    fn drop(&mut self) {
+        REDACT_IDS.with(|redact| redact.set(self.previous));
+    }
+}
+
+/// A deterministic hash of the `Debug` output of `value`, used by [`Ingredient::value_hash`].
+///
+/// Salsa structs in `value` are formatted with their fields (the database is attached
+/// while a checksum is computed) rather than their `Id`.
+///
+/// [`Ingredient::value_hash`]: `crate::ingredient::Ingredient::value_hash`
+pub(crate) fn debug_hash(value: &dyn fmt::Debug) -> u64 {
+    struct HashWriter(FxHasher);
+
+    impl Write for HashWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            self.0.write(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    let mut writer = HashWriter(FxHasher::default());
+    write!(writer, "{value:?}").expect("a `Debug` implementation returned an error");
+    writer.0.finish()
+}
+
+/// Computes the checksum described in [`Database::checksum`].
+///
+/// Each visited query contributes the debug name of its ingredient and the hash of its
+/// value (or a marker if it has none). Ids are deliberately left out, both of the visited
+/// queries and within their values (see [`ids_redacted`]).
+pub(crate) fn checksum(
+    db: &dyn Database,
+    roots: &[DatabaseKeyIndex],
+    include_dependencies: bool,
+) -> u64 {
+    let zalsa = db.zalsa();
+    let mut hasher = FxHasher::default();
+    let mut visited = FxHashSet::default();
+    let mut stack: Vec<DatabaseKeyIndex> = roots.iter().rev().copied().collect();
+
+    let _redact = RedactIds::new();
+    crate::attach::attach(db, || {
+        while let Some(key) = stack.pop() {
+            if !visited.insert(key) {
+                continue;
+            }
+
+            let ingredient = zalsa.lookup_ingredient(key.ingredient_index);
+            ingredient.debug_name().hash(&mut hasher);
+            ingredient.value_hash(db, key.key_index).hash(&mut hasher);
+
+            if include_dependencies {
+                if let Some(origin) = ingredient.origin(db, key.key_index) {
+                    // Push in reverse so that dependencies are visited in the order they were read.
+                    let inputs: Vec<_> = origin
+                        .inputs()
+                        .filter_map(|input| input.database_key_index())
+                        .collect();
+                    stack.extend(inputs.into_iter().rev());
+                }
+            }
+        }
+    });
+
+    FxBuildHasher.hash_one(hasher.finish())
+}
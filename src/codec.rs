@@ -0,0 +1,18 @@
+use std::fmt;
+
+/// Converts the values of a tracked function declared with
+/// `#[salsa::tracked(store_with = MyCodec)]` to the form in which they are memoized, and back;
+/// e.g. to keep large values that are rarely read compressed in memory.
+///
+/// A value is encoded once each time the function executes, and decoded each time
+/// the function is called; the decoded value is not cached.
+pub trait Codec<T> {
+    /// The memoized form of the values. Unless the function is declared with `no_eq`,
+    /// it must implement `Eq`: to decide whether a re-executed value changed, salsa compares
+    /// the encoded values, so equal values should be encoded equally.
+    type Stored: fmt::Debug + Send + Sync;
+
+    fn encode(value: &T) -> Self::Stored;
+
+    fn decode(stored: &Self::Stored) -> T;
+}
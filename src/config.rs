@@ -0,0 +1,125 @@
+use std::{
+    any::Any,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use crate::{
+    array::Array,
+    id::{AsId, FromId},
+    input::{singleton::NotSingleton, Configuration, IngredientImpl, JarImpl},
+    runtime::{stamp, Stamp},
+    zalsa::Zalsa,
+    Database, Durability, Id,
+};
+
+/// The configuration of an input ingredient holding the configuration value of type `T`,
+/// see [`Database::set_config`].
+struct Config<T>(PhantomData<T>);
+
+/// The single input holding the configuration value of type `T`.
+struct ConfigInput<T> {
+    id: Id,
+    phantom: PhantomData<fn() -> T>,
+}
+
+// Implemented by hand, as derives would require `T` to implement the traits too.
+impl<T> Clone for ConfigInput<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ConfigInput<T> {}
+
+impl<T> PartialEq for ConfigInput<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for ConfigInput<T> {}
+
+impl<T> Hash for ConfigInput<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<T> fmt::Debug for ConfigInput<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Config").field(&self.id).finish()
+    }
+}
+
+impl<T> FromId for ConfigInput<T> {
+    fn from_id(id: Id) -> Self {
+        Self {
+            id,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> AsId for ConfigInput<T> {
+    fn as_id(&self) -> Id {
+        self.id
+    }
+}
+
+impl<T: Any + Send + Sync> Configuration for Config<T> {
+    const DEBUG_NAME: &'static str = "Config";
+    const FIELD_DEBUG_NAMES: &'static [&'static str] = &["value"];
+    const HAS_KEY: bool = true;
+    type Singleton = NotSingleton;
+    type Struct = ConfigInput<T>;
+    type Fields = (Option<T>,);
+    type Stamps = Array<Stamp, 1>;
+    type Key = ();
+
+    fn key(_fields: &Self::Fields) -> Self::Key {}
+
+    fn field_debug(_fields: &Self::Fields, _field_index: usize) -> Option<&dyn fmt::Debug> {
+        None
+    }
+}
+
+fn ingredient<T: Any + Send + Sync>(zalsa: &Zalsa) -> &IngredientImpl<Config<T>> {
+    let index = zalsa.add_or_lookup_jar_by_type(&JarImpl::<Config<T>>::default());
+    zalsa.lookup_ingredient(index).assert_type()
+}
+
+/// Returns the input holding the value of type `T`, creating it without a value if needed.
+/// Until a value is set, the input has HIGH durability, so that setting one invalidates
+/// the queries that found none.
+fn config_input<T: Any + Send + Sync>(db: &dyn Database) -> ConfigInput<T> {
+    let zalsa = db.zalsa();
+    let ingredient = ingredient::<T>(zalsa);
+    let stamps = Array::new([stamp(zalsa.current_revision(), Durability::HIGH)]);
+    ingredient.get_or_create(db, (None,), stamps)
+}
+
+/// See `<dyn Database>::config`.
+pub(crate) fn config<T: Any + Send + Sync>(db: &dyn Database) -> Option<&T> {
+    let input = config_input::<T>(db);
+    ingredient::<T>(db.zalsa()).field(db, input, 0).0.as_ref()
+}
+
+/// See [`Database::set_config_with_durability`].
+pub(crate) fn set_config<T: Any + Send + Sync>(
+    db: &mut dyn Database,
+    value: T,
+    durability: Durability,
+) {
+    let input = config_input::<T>(db);
+    ingredient::<T>(db.zalsa()).will_set_field(db, input, 0);
+    let zalsa_mut = db.zalsa_mut();
+    let index = zalsa_mut.add_or_lookup_jar_by_type(&JarImpl::<Config<T>>::default());
+    let (ingredient, runtime) = zalsa_mut.lookup_ingredient_mut(index);
+    ingredient
+        .assert_type_mut::<IngredientImpl<Config<T>>>()
+        .set_field(runtime, input, 0, Some(durability), |fields| {
+            fields.0 = Some(value);
+        });
+}
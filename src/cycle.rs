@@ -18,20 +18,29 @@ use std::{panic::AssertUnwindSafe, sync::Arc};
 /// the [salsa book](https://https://salsa-rs.github.io/salsa/cycles.html).
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Cycle {
-    participants: CycleParticipants,
+    // We want `Cycle` to be thin
+    data: Arc<CycleData>,
 }
 
-// We want `Cycle`` to be thin
-pub(crate) type CycleParticipants = Arc<Box<[DatabaseKeyIndex]>>;
+#[derive(PartialEq, Eq, Hash)]
+struct CycleData {
+    participants: Box<[DatabaseKeyIndex]>,
+    trigger: DatabaseKeyIndex,
+}
 
 impl Cycle {
-    pub(crate) fn new(participants: CycleParticipants) -> Self {
-        Self { participants }
+    pub(crate) fn new(participants: Box<[DatabaseKeyIndex]>, trigger: DatabaseKeyIndex) -> Self {
+        Self {
+            data: Arc::new(CycleData {
+                participants,
+                trigger,
+            }),
+        }
     }
 
     /// True if two `Cycle` values represent the same cycle.
     pub(crate) fn is(&self, cycle: &Cycle) -> bool {
-        Arc::ptr_eq(&self.participants, &cycle.participants)
+        Arc::ptr_eq(&self.data, &cycle.data)
     }
 
     pub(crate) fn throw(self) -> ! {
@@ -54,7 +63,24 @@ impl Cycle {
     /// is arbitrary but deterministic, but the ordering is otherwise determined
     /// by the execution.
     pub fn participant_keys(&self) -> impl Iterator<Item = DatabaseKeyIndex> + '_ {
-        self.participants.iter().copied()
+        self.data.participants.iter().copied()
+    }
+
+    /// The participant that triggered the cycle: the query that was invoked
+    /// again while it was already executing, closing the cycle.
+    pub fn trigger(&self) -> DatabaseKeyIndex {
+        self.data.trigger
+    }
+
+    /// Returns the participants in the same order as [`Self::participant_keys`],
+    /// along with the debug name of the function (or other ingredient) each belongs to.
+    pub fn participants_with_names(
+        &self,
+        db: &dyn Database,
+    ) -> Vec<(DatabaseKeyIndex, &'static str)> {
+        self.participant_keys()
+            .map(|key| (key, key.ingredient_index.debug_name(db)))
+            .collect()
     }
 
     /// Returns a vector with the debug information for
@@ -79,11 +105,13 @@ impl std::fmt::Debug for Cycle {
             f.debug_struct("UnexpectedCycle")
                 .field("all_participants", &self.all_participants(db))
                 .field("unexpected_participants", &self.unexpected_participants(db))
+                .field("trigger", &self.trigger())
                 .finish()
         })
         .unwrap_or_else(|| {
             f.debug_struct("Cycle")
-                .field("participants", &self.participants)
+                .field("participants", &self.data.participants)
+                .field("trigger", &self.data.trigger)
                 .finish()
         })
     }
@@ -1,8 +1,14 @@
 use std::{any::Any, borrow::Cow};
 
 use crate::{
+    id::AsId,
+    ingredient::IngredientInfo,
+    memo_stats::MemoStats,
+    salsa_struct::SalsaStructInDb,
     zalsa::{IngredientIndex, ZalsaDatabase},
-    Durability, Event, Revision,
+    Cancelled, ChangeCause, Channel, CompactionReport, DatabaseKeyIndex, Durability,
+    DurabilityExplanation, Event, EventKind, ExternalFingerprintFn, MemoryPressure, MemoryReport,
+    PinnedRevision, Revision, ThreadStats, WriteScope,
 };
 
 /// The trait implemented by all Salsa databases.
@@ -27,10 +33,124 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
     /// will block until that snapshot is dropped -- if that snapshot
     /// is owned by the current thread, this could trigger deadlock.
     fn synthetic_write(&mut self, durability: Durability) {
+        will_change_inputs(
+            self.as_dyn_database(),
+            ChangeCause::SyntheticWrite { durability },
+        );
         let zalsa_mut = self.zalsa_mut();
         zalsa_mut.report_tracked_write(durability);
     }
 
+    /// Performs all the writes made by `op` in a single new revision,
+    /// cancelling other handles to the database only once.
+    ///
+    /// Queries cannot be executed during the transaction: attempting to do so
+    /// unwinds with [`Cancelled::PendingWrite`](`crate::Cancelled::PendingWrite`).
+    /// In [`CancellationMode::Cooperative`](`crate::CancellationMode::Cooperative`),
+    /// the fallible entry points return that error instead.
+    ///
+    /// # Panics
+    ///
+    /// If called from within `op` (transactions cannot be nested).
+    fn transaction<R>(&mut self, op: impl FnOnce(&mut Self) -> R) -> R
+    where
+        Self: Sized,
+    {
+        will_change_inputs(self.as_dyn_database(), ChangeCause::Transaction);
+        self.zalsa_mut().begin_transaction();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| op(self)));
+        self.zalsa_mut().end_transaction();
+        result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+
+    /// Forces the queries that read values of the salsa struct `S` to be re-validated,
+    /// without affecting other queries as a [`Self::synthetic_write`] would.
+    ///
+    /// This starts a new revision in which all values of `S` are considered changed.
+    /// Queries that read one of them are re-executed when they are next called
+    /// (and backdated, if they produce the same value); queries that did not are still
+    /// validated cheaply if their durability is higher than that of the values of `S`.
+    ///
+    /// # Panics
+    ///
+    /// If `S` is not an interned struct, the only kind of struct that currently supports this.
+    fn invalidate_ingredient<S>(&mut self)
+    where
+        Self: Sized,
+        S: SalsaStructInDb,
+    {
+        let Some(index) = self.zalsa().lookup_struct_ingredient::<S>() else {
+            // No value of `S` was created, so no query can have read one.
+            return;
+        };
+        will_change_inputs(
+            self.as_dyn_database(),
+            ChangeCause::InvalidateIngredient {
+                ingredient_index: index,
+            },
+        );
+        let zalsa = self.zalsa_mut();
+        let revision = zalsa.current_revision();
+        let durability = zalsa.lookup_ingredient_mut(index).0.invalidate(revision);
+        zalsa.report_tracked_write(durability);
+    }
+
+    /// Assigns `input` to `channel`, e.g. to track the changes to the files open in an editor
+    /// separately from those to other files; see [`Channel`]. Inputs are in
+    /// [`Channel::DEFAULT`] until assigned to another channel.
+    ///
+    /// The queries that read `input` are re-executed when next called, to record its new channel.
+    ///
+    /// # Panics
+    ///
+    /// If `input` is not an input struct.
+    fn set_channel<S>(&mut self, input: S, channel: Channel)
+    where
+        Self: Sized,
+        S: AsId,
+    {
+        let id = input.as_id();
+        let index = self
+            .zalsa()
+            .table()
+            .owner(id)
+            .unwrap_or_else(|| panic!("`{id:?}` is not a value of this database"));
+        will_change_inputs(
+            self.as_dyn_database(),
+            ChangeCause::SetChannel {
+                input: DatabaseKeyIndex {
+                    ingredient_index: index,
+                    key_index: id,
+                },
+                channel,
+            },
+        );
+        let zalsa = self.zalsa_mut();
+        let (ingredient, runtime) = zalsa.lookup_ingredient_mut(index);
+        ingredient.set_channel(runtime, id, channel);
+    }
+
+    /// Sets the configuration value of type `T` (e.g. feature flags or a target triple),
+    /// read with `db.as_dyn_database().config::<T>()`, with HIGH durability:
+    /// configuration is expected to change rarely, so queries that only read
+    /// configuration and other high-durability inputs are validated cheaply.
+    fn set_config<T>(&mut self, value: T)
+    where
+        Self: Sized,
+        T: Any + Send + Sync,
+    {
+        self.set_config_with_durability(value, Durability::HIGH)
+    }
+
+    /// Sets the configuration value of type `T`, with the given `durability`.
+    fn set_config_with_durability<T>(&mut self, value: T, durability: Durability)
+    where
+        Self: Sized,
+        T: Any + Send + Sync,
+    {
+        crate::config::set_config(self.as_dyn_database_mut(), value, durability)
+    }
+
     /// Reports that the query depends on some state unknown to salsa.
     ///
     /// Queries which report untracked reads will be re-executed in the next
@@ -41,6 +161,30 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
         zalsa_local.report_untracked_read(db.zalsa().current_revision())
     }
 
+    /// Reports that the query depends on `key`, a resource outside of salsa such as
+    /// a configuration file, whose state is summarized by `fingerprint_fn(key)`.
+    ///
+    /// Unlike [`Self::report_untracked_read`], the query is not re-executed in every new revision:
+    /// when it is validated, the fingerprint is computed again (at most once per revision),
+    /// and the query is only re-executed if the fingerprint changed.
+    /// Changes to external resources are only noticed once a new revision starts,
+    /// e.g. with [`Self::synthetic_write`].
+    fn report_external_dependency(&self, key: &str, fingerprint_fn: ExternalFingerprintFn) {
+        crate::external::report_external_dependency(self.as_dyn_database(), key, fingerprint_fn)
+    }
+
+    /// Explains the durability of the memoized value `key`: returns the lowest durability
+    /// among its transitive dependencies, which is the highest durability the value can have,
+    /// along with a chain of dependencies leading to an input with that durability.
+    ///
+    /// Like [`DatabaseKeyIndex::debug`], this is intended for tooling, e.g. to report that
+    /// a query is low-durability because it reads a given input.
+    /// The memo is *not* validated first, so the result describes the last execution of `key`.
+    /// Returns `None` if `key` has no memoized value.
+    fn max_durability_of(&self, key: DatabaseKeyIndex) -> Option<DurabilityExplanation> {
+        crate::durability::max_durability_of(self.as_dyn_database(), key)
+    }
+
     /// Return the "debug name" (i.e., the struct name, etc) for an "ingredient",
     /// which are the fine-grained components we use to track data. This is intended
     /// for debugging and the contents of the returned string are not semver-guaranteed.
@@ -54,6 +198,52 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
         )
     }
 
+    /// Enables or disables deterministic mode, for all handles to this database.
+    ///
+    /// Intended for reproducing bugs that depend on how parallel work is scheduled.
+    /// In deterministic mode:
+    ///
+    /// * [`par_map`](`crate::par_map`) runs the operation for each element in turn,
+    ///   in order, on the calling thread;
+    /// * the participants of a cycle are ordered by their [`DatabaseKeyIndex`], so that
+    ///   the [`Cycle`](`crate::Cycle`) seen by recovery functions does not depend on which
+    ///   thread detected it.
+    ///
+    /// Threads spawned by the user (with forked handles) are still scheduled by the OS.
+    fn set_deterministic(&self, deterministic: bool) {
+        self.zalsa().runtime().set_deterministic(deterministic)
+    }
+
+    /// Sets the durability of values interned outside of any query, for all handles
+    /// to this database; it defaults to the highest durability.
+    ///
+    /// Values interned while a query is executing instead take the durability of the
+    /// inputs that query has read so far. Either way, interning a value that already exists
+    /// reports a dependency of its durability.
+    ///
+    /// The durability of a value is decided when it is first interned.
+    fn set_default_intern_durability(&self, durability: Durability) {
+        self.zalsa()
+            .runtime()
+            .set_default_intern_durability(durability)
+    }
+
+    /// Returns statistics about the time this handle spent in salsa since it was
+    /// created or [`Self::reset_thread_stats`] was last called, such as how long it was
+    /// blocked waiting for queries executing on other threads.
+    ///
+    /// Each handle (e.g., each clone of a database moved to another thread) has its own statistics.
+    fn thread_stats(&self) -> ThreadStats {
+        self.zalsa_local().thread_stats()
+    }
+
+    /// Resets the statistics returned by [`Self::thread_stats`], returning their previous value.
+    ///
+    /// Useful to attribute the time to individual requests.
+    fn reset_thread_stats(&self) -> ThreadStats {
+        self.zalsa_local().reset_thread_stats()
+    }
+
     /// Starts unwinding the stack if the current revision is cancelled.
     ///
     /// This method can be called by query implementations that perform
@@ -63,6 +253,9 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
     /// Cancellation will automatically be triggered by salsa on any query
     /// invocation.
     ///
+    /// Does nothing in [`CancellationMode::Cooperative`](`crate::CancellationMode::Cooperative`);
+    /// use [`Self::check_cancelled`] there.
+    ///
     /// This method should not be overridden by `Database` implementors. A
     /// `salsa_event` is emitted when this method is called, so that should be
     /// used instead.
@@ -72,6 +265,133 @@ pub trait Database: Send + AsDynDatabase + Any + ZalsaDatabase {
         zalsa_local.unwind_if_revision_cancelled(db);
     }
 
+    /// Returns `Err(Cancelled::PendingWrite)` if another handle is waiting to write,
+    /// without unwinding. This is how long-running work checks for cancellation
+    /// in [`CancellationMode::Cooperative`](`crate::CancellationMode::Cooperative`).
+    fn check_cancelled(&self) -> Result<(), Cancelled> {
+        if self.zalsa_local().is_cancelled(self.zalsa()) {
+            Err(Cancelled::PendingWrite)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Pins the current revision of this handle until the returned guard is dropped,
+    /// so that several queries executed in sequence, e.g. on a background thread,
+    /// observe one consistent revision.
+    ///
+    /// Like any handle, a pinned one makes writers wait until it is dropped; unlike
+    /// other handles, its queries are not cancelled by the pending write, so they run
+    /// to completion rather than unwinding with [`Cancelled::PendingWrite`]. When they block
+    /// on a query that another handle stopped executing because it was cancelled, they
+    /// execute that query themselves. Queries attempted during a [`Self::transaction`] are
+    /// still cancelled, as the revision is only partially written.
+    ///
+    /// Writing while a handle pinned on the same thread is alive deadlocks, as for any
+    /// other handle; drop the guard (and the handle) before writing.
+    fn pin_revision(&self) -> PinnedRevision<'_> {
+        PinnedRevision::new(self.as_dyn_database())
+    }
+
+    /// Computes a stable hash over the memoized values of the queries `roots`.
+    ///
+    /// Intended for checking, e.g. in CI, that an incremental run and a from-scratch run
+    /// agree. Values are hashed through their `Debug` output, with salsa structs formatted
+    /// by their fields rather than their `Id`, which differs between such runs (custom `Debug`
+    /// implementations should likewise not print `Id`s other than through their `Debug`).
+    /// Queries that have not been executed contribute only the fact that they have no value.
+    /// Queries are not executed by this method.
+    fn checksum(&self, roots: &[DatabaseKeyIndex]) -> u64 {
+        crate::checksum::checksum(self.as_dyn_database(), roots, false)
+    }
+
+    /// Like [`Self::checksum`], but also hashes the values of every query that `roots`
+    /// (transitively) depend on, visited in the order they were read.
+    fn checksum_with_dependencies(&self, roots: &[DatabaseKeyIndex]) -> u64 {
+        crate::checksum::checksum(self.as_dyn_database(), roots, true)
+    }
+
+    /// Describes each ingredient of the database, in order of their [`IngredientIndex`]:
+    /// the parts of the salsa structs, tracked functions and accumulators used so far.
+    /// Ingredients are registered when first used, so items that were never used are missing.
+    ///
+    /// Like [`Self::memory_report`], this walks over every value in the database
+    /// to count the memos of each tracked function.
+    fn ingredients(&self) -> Vec<IngredientInfo> {
+        crate::ingredient::ingredients(self.as_dyn_database())
+    }
+
+    /// Reports the memory used by each ingredient of the database,
+    /// i.e. by the values of each salsa struct and the memos of each tracked function.
+    ///
+    /// This walks over every value in the database, so it is best called occasionally,
+    /// e.g. to export metrics.
+    fn memory_report(&self) -> MemoryReport {
+        crate::memory_report::memory_report(self.as_dyn_database())
+    }
+
+    /// Counts the memos of each tracked function, grouped by the salsa struct they are
+    /// attached to, e.g. to pass them to
+    /// [`StorageBuilder::memo_layout_hint`](`crate::StorageBuilder::memo_layout_hint`)
+    /// in later runs; see [`crate::memo_stats`].
+    ///
+    /// Like [`Self::memory_report`], this walks over every value in the database.
+    fn collect_memo_stats(&self) -> MemoStats {
+        crate::memo_stats::collect_memo_stats(self.as_dyn_database())
+    }
+
+    /// Drops memoized values to reduce memory usage, e.g. when the system is low on memory.
+    /// The values are recomputed when next needed; queries that depend on them are not invalidated.
+    ///
+    /// With [`MemoryPressure::Low`], only the values of `#[salsa::tracked(weak)]` functions
+    /// are dropped; with [`MemoryPressure::High`], so are those of functions with an LRU capacity.
+    /// Values assigned by `specify` and values computed with untracked reads are always kept.
+    ///
+    /// The memory is freed immediately if this is the only handle to the database,
+    /// and otherwise when the next revision starts.
+    fn trim_memory(&mut self, pressure: MemoryPressure) {
+        crate::memory_report::trim_memory(self.as_dyn_database_mut(), pressure)
+    }
+
+    /// Reclaims memory that the database holds on to without needing it, e.g. while the
+    /// application is idle: frees replaced memos, drops bookkeeping about values that are
+    /// no longer memoized (such as the entries of LRU lists), and shrinks the tables of
+    /// each ingredient to fit. Unlike [`Self::trim_memory`], no memoized value is dropped.
+    ///
+    /// Like [`Self::memory_report`], this walks over every value in the database.
+    /// Replaced memos are only freed if this is the only handle to the database.
+    fn compact(&mut self) -> CompactionReport {
+        crate::memory_report::compact(self.as_dyn_database_mut())
+    }
+
+    /// Returns the `n` memoized values that took the longest to compute, in total
+    /// over all their executions, along with their execution times.
+    ///
+    /// Like [`Self::memory_report`], this walks over every value in the database.
+    #[cfg(feature = "query_timing")]
+    fn top_expensive_queries(&self, n: usize) -> Vec<(DatabaseKeyIndex, crate::ExecutionTime)> {
+        crate::query_timing::top_expensive_queries(self.as_dyn_database(), n)
+    }
+
+    /// Starts a new revision and invokes `op` with a [`WriteScope`], through which
+    /// inputs can be set from several threads at once, e.g. using [`std::thread::scope`].
+    ///
+    /// Like any write, this cancels and waits for all other handles to the database,
+    /// so no queries run while the scope exists.
+    fn write_scope<R>(&mut self, op: impl FnOnce(&WriteScope<'_>) -> R) -> R
+    where
+        Self: Sized,
+    {
+        will_change_inputs(self.as_dyn_database(), ChangeCause::WriteScope);
+        let zalsa = &*self.zalsa_mut();
+        let changes = zalsa.runtime().changes();
+        changes.begin_batch();
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| op(&WriteScope::new(zalsa))));
+        changes.end_batch(zalsa.current_revision());
+        result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+
     /// Execute `op` with the database in thread-local storage for debug print-outs.
     fn attach<R>(&self, op: impl FnOnce(&Self) -> R) -> R
     where
@@ -103,6 +423,12 @@ pub fn current_revision<Db: ?Sized + Database>(db: &Db) -> Revision {
     db.zalsa().current_revision()
 }
 
+/// Emits a [`WillChangeInputs`](`crate::EventKind::WillChangeInputs`) event,
+/// before `db` cancels other handles to start the write.
+fn will_change_inputs(db: &dyn Database, cause: ChangeCause) {
+    crate::event::emit(db, &|| Event::new(EventKind::WillChangeInputs { cause }));
+}
+
 impl dyn Database {
     /// Upcasts `self` to the given view.
     ///
@@ -113,4 +439,12 @@ impl dyn Database {
     pub fn as_view<DbView: ?Sized + Database>(&self) -> &DbView {
         self.zalsa().views().try_view_as(self).unwrap()
     }
+
+    /// Returns the configuration value of type `T`, or `None` if none was set with
+    /// [`Database::set_config`], and makes the active query depend on it.
+    ///
+    /// Each type is held by its own input, so queries only depend on the values they read.
+    pub fn config<T: Any + Send + Sync>(&self) -> Option<&T> {
+        crate::config::config(self)
+    }
 }
@@ -1,4 +1,7 @@
-use crate::{self as salsa, Database, Event, Storage};
+use crate::{
+    self as salsa, memo_stats::MemoStats, CancellationMode, Database, Event, Storage,
+    StorageBuilder,
+};
 
 #[salsa::db]
 /// Default database implementation that you can use if you don't
@@ -14,6 +17,19 @@ impl DatabaseImpl {
         Self::default()
     }
 
+    /// Configure a new database, e.g.
+    /// `DatabaseImpl::builder().interned_shards(8).default_lru(256).build()`.
+    pub fn builder() -> DatabaseImplBuilder {
+        DatabaseImplBuilder {
+            storage: Storage::builder(),
+        }
+    }
+
+    /// Create a new database with the given [`CancellationMode`].
+    pub fn with_cancellation_mode(mode: CancellationMode) -> Self {
+        Self::builder().cancellation_mode(mode).build()
+    }
+
     pub fn storage(&self) -> &Storage<Self> {
         &self.storage
     }
@@ -26,3 +42,70 @@ impl Database for DatabaseImpl {
         tracing::debug!("salsa_event({:?})", event());
     }
 }
+
+/// Configures a new [`DatabaseImpl`], see [`DatabaseImpl::builder`]
+/// and [`StorageBuilder`] for the options.
+pub struct DatabaseImplBuilder {
+    storage: StorageBuilder<DatabaseImpl>,
+}
+
+impl DatabaseImplBuilder {
+    /// See [`StorageBuilder::parallelism`].
+    pub fn parallelism(self, threads: usize) -> Self {
+        Self {
+            storage: self.storage.parallelism(threads),
+        }
+    }
+
+    /// See [`StorageBuilder::interned_shards`].
+    pub fn interned_shards(self, shards: usize) -> Self {
+        Self {
+            storage: self.storage.interned_shards(shards),
+        }
+    }
+
+    /// See [`StorageBuilder::default_lru`].
+    pub fn default_lru(self, capacity: usize) -> Self {
+        Self {
+            storage: self.storage.default_lru(capacity),
+        }
+    }
+
+    /// See [`StorageBuilder::memo_layout_hint`].
+    pub fn memo_layout_hint(self, stats: MemoStats) -> Self {
+        Self {
+            storage: self.storage.memo_layout_hint(stats),
+        }
+    }
+
+    /// See [`StorageBuilder::event_handler`].
+    pub fn event_handler(self, handler: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        Self {
+            storage: self.storage.event_handler(handler),
+        }
+    }
+
+    /// See [`StorageBuilder::event_handler_with_db`].
+    pub fn event_handler_with_db(
+        self,
+        handler: impl Fn(&dyn Database, &Event) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            storage: self.storage.event_handler_with_db(handler),
+        }
+    }
+
+    /// See [`StorageBuilder::cancellation_mode`].
+    pub fn cancellation_mode(self, mode: CancellationMode) -> Self {
+        Self {
+            storage: self.storage.cancellation_mode(mode),
+        }
+    }
+
+    /// Creates the database.
+    pub fn build(self) -> DatabaseImpl {
+        DatabaseImpl {
+            storage: self.storage.build(),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Finding which memoized values changed across revisions, e.g. to update
+//! only the parts of a UI that depend on them.
+
+use crate::{Database, DatabaseKeyIndex, Revision};
+
+/// The current revision of `db`, to pass to [`changed_outputs`] later on.
+pub fn current_revision(db: &dyn Database) -> Revision {
+    db.zalsa().current_revision()
+}
+
+/// The keys of the memoized values that changed after the revision `since`, that is,
+/// that were re-executed since then and produced a value that could not be backdated.
+///
+/// Keys are ordered by ingredient, so the keys of each tracked function are adjacent.
+/// Memos are not validated first: a value whose inputs changed is only reported once
+/// it has been re-executed, e.g. by calling the tracked function again.
+pub fn changed_outputs(db: &dyn Database, since: Revision) -> Vec<DatabaseKeyIndex> {
+    let zalsa = db.zalsa();
+    let mut keys = vec![];
+
+    // SAFETY: `current_revision` is the current revision of the database owning the table.
+    unsafe {
+        zalsa.table().for_each_memo(
+            zalsa.current_revision(),
+            &mut |struct_index, id, memo_ingredient_index, memo| {
+                if memo.changed_at() > since {
+                    keys.push(DatabaseKeyIndex {
+                        ingredient_index: zalsa
+                            .ingredient_index_for_memo(struct_index, memo_ingredient_index),
+                        key_index: id,
+                    });
+                }
+            },
+        );
+    }
+
+    keys.sort_by_key(|key| (key.ingredient_index, key.key_index));
+    keys
+}
@@ -1,8 +1,14 @@
+use rustc_hash::FxHashSet;
+
+use crate::{Database, DatabaseKeyIndex};
+
 /// Describes how likely a value is to change—how "durable" it is.
 ///
-/// By default, inputs have `Durability::LOW` and interned values have
-/// `Durability::HIGH`. But inputs can be explicitly set with other
-/// durabilities.
+/// By default, inputs have `Durability::LOW` and values interned outside
+/// of queries have `Durability::HIGH`. But inputs can be explicitly set with other
+/// durabilities, and interned values can be created with
+/// `new_with_durability` or take the durability set with
+/// [`Database::set_default_intern_durability`](`crate::Database::set_default_intern_durability`).
 ///
 /// We use durabilities to optimize the work of "revalidating" a query
 /// after some input has changed. Ordinarily, in a new revision,
@@ -75,3 +81,58 @@ impl Default for Durability {
         Durability::LOW
     }
 }
+
+/// The durability of a memoized value, and the dependency it got it from;
+/// see [`Database::max_durability_of`](`crate::Database::max_durability_of`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DurabilityExplanation {
+    /// The lowest durability among the transitive dependencies of the value,
+    /// which is the highest durability the value can have.
+    pub durability: Durability,
+
+    /// A chain of dependencies with that durability: the value read the first one,
+    /// each one after that was read by the one before, and the last one is an input
+    /// (or a query that read untracked state).
+    ///
+    /// Empty if the value itself read untracked state, or has no dependencies.
+    pub path: Vec<DatabaseKeyIndex>,
+}
+
+pub(crate) fn max_durability_of(
+    db: &dyn Database,
+    key: DatabaseKeyIndex,
+) -> Option<DurabilityExplanation> {
+    let zalsa = db.zalsa();
+    let durability_of = |key: DatabaseKeyIndex| {
+        zalsa
+            .lookup_ingredient(key.ingredient_index)
+            .durability(db, key.key_index)
+    };
+    let durability = durability_of(key)?;
+
+    // The durability of a memo is the lowest durability of the values it read, so it already
+    // summarizes its whole dependency closure: following any dependency with the same durability
+    // leads to the cause, without visiting the rest of the closure.
+    let mut path = vec![];
+    let mut visited = FxHashSet::default();
+    let mut current = key;
+    visited.insert(current);
+    while let Some(origin) = zalsa
+        .lookup_ingredient(current.ingredient_index)
+        .origin(db, current.key_index)
+    {
+        let next = origin
+            .inputs()
+            .filter_map(|input| input.database_key_index())
+            .find(|&input| !visited.contains(&input) && durability_of(input) == Some(durability));
+        let Some(next) = next else {
+            break;
+        };
+        visited.insert(next);
+        path.push(next);
+        current = next;
+    }
+
+    Some(DurabilityExplanation { durability, path })
+}
@@ -1,14 +1,28 @@
-use std::thread::ThreadId;
+use std::{
+    ops::BitOr,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::ThreadId,
+};
+
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
 
 use crate::{
     key::DatabaseKeyIndex,
     key::{InputDependencyIndex, OutputDependencyIndex},
+    Channel, Database, DeadlockReport, Durability, IngredientIndex,
 };
 
 /// The `Event` struct identifies various notable things that can
 /// occur during salsa execution. Instances of this struct are given
 /// to `salsa_event`.
-#[derive(Debug)]
+///
+/// Keys within events can be formatted with [`DatabaseKeyIndex::debug`],
+/// which does not require the database to be attached.
+#[derive(Clone, Debug)]
 pub struct Event {
     /// The id of the thread that triggered the event.
     pub thread_id: ThreadId,
@@ -27,7 +41,23 @@ impl Event {
 }
 
 /// An enum identifying the various kinds of events that can occur.
-#[derive(Debug)]
+///
+/// # Ordering with memo visibility
+///
+/// Events are emitted synchronously, on the thread that triggers them, so that
+/// handlers building an index of the database observe the following order:
+///
+/// * [`WillExecute`](`Self::WillExecute`) is emitted before the function executes,
+///   while its old memo (if any) is still in place: the new memo only becomes visible
+///   to other threads after the handlers have returned.
+/// * [`DidDiscard`](`Self::DidDiscard`) for a memoized value is emitted once the memo
+///   has been removed, so no thread can read it anymore.
+/// * `DidDiscard` for a tracked struct is emitted once the struct and all its memos
+///   have been discarded (after their own `DidDiscard` events), and before its id can
+///   be reused by a new tracked struct.
+/// * Interned ids are never reused, not even after the ingredient is reset,
+///   so interning emits no events.
+#[derive(Clone, Debug)]
 pub enum EventKind {
     /// Occurs when we found that all inputs to a memoized value are
     /// up-to-date and hence the value can be re-used without
@@ -37,6 +67,13 @@ pub enum EventKind {
     DidValidateMemoizedValue {
         /// The database-key for the affected value. Implements `Debug`.
         database_key: DatabaseKeyIndex,
+
+        /// How the value was found to be up-to-date.
+        kind: ValidationKind,
+
+        /// The number of dependency edges that were walked to validate the value
+        /// (always `0` unless `kind` is [`ValidationKind::Deep`]).
+        edges_traversed: usize,
     },
 
     /// Indicates that another thread (with id `other_thread_id`) is processing the
@@ -53,9 +90,21 @@ pub enum EventKind {
         database_key: DatabaseKeyIndex,
     },
 
+    /// Indicates that this thread has been blocked on another thread for longer than the
+    /// [`deadlock_timeout`](`crate::StorageBuilder::deadlock_timeout`).
+    ///
+    /// Emitted once per wait, with the lock on the dependency graph held:
+    /// handlers must not execute queries.
+    DidDetectDeadlock {
+        /// The chain of waits the thread is blocked in.
+        report: DeadlockReport,
+    },
+
     /// Indicates that the function for this query will be executed.
     /// This is either because it has never executed before or because
     /// its inputs may be out of date.
+    ///
+    /// See the [ordering](`Self#ordering-with-memo-visibility`) with respect to other threads.
     WillExecute {
         /// The database-key for the affected value. Implements `Debug`.
         database_key: DatabaseKeyIndex,
@@ -70,6 +119,34 @@ pub enum EventKind {
     /// and panic with a sentinel value of type [`Cancelled`](`crate::Cancelled`).
     DidSetCancellationFlag,
 
+    /// Indicates that the function for this query ran past its `timeout` and was unwound.
+    /// The result of its `timeout_result` function is memoized instead, until the next revision.
+    DidTimeOut {
+        /// The database-key for the affected value. Implements `Debug`.
+        database_key: DatabaseKeyIndex,
+    },
+
+    /// Inputs were created in bulk, e.g. with the generated `builder_many` function.
+    /// Emitted once for all of them.
+    DidCreateInputs {
+        /// The ingredient of the input struct.
+        ingredient_index: IngredientIndex,
+
+        /// The number of inputs created.
+        count: usize,
+    },
+
+    /// Inputs will change, invalidating the memoized values that read them.
+    ///
+    /// Emitted for every write, before it takes effect: outside of a transaction or
+    /// write scope, before other handles are cancelled and the new revision starts.
+    /// Handlers cannot prevent the write, but can record it, e.g. to keep an audit
+    /// trail of why memoized values were invalidated.
+    WillChangeInputs {
+        /// The write that changes the inputs.
+        cause: ChangeCause,
+    },
+
     /// Discovered that a query used to output a given output but no longer does.
     WillDiscardStaleOutput {
         /// Key for the query that is executing and which no longer outputs the given value.
@@ -80,6 +157,8 @@ pub enum EventKind {
     },
 
     /// Tracked structs or memoized data were discarded (freed).
+    ///
+    /// See the [ordering](`Self#ordering-with-memo-visibility`) with respect to other threads.
     DidDiscard {
         /// Value being discarded.
         key: DatabaseKeyIndex,
@@ -94,3 +173,206 @@ pub enum EventKind {
         accumulator: InputDependencyIndex,
     },
 }
+
+/// How a memoized value was validated; see [`EventKind::DidValidateMemoizedValue`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationKind {
+    /// No input with the value's durability, or of the channels it read, changed since
+    /// it was last verified, so the value was validated without looking at its dependencies.
+    Shallow,
+
+    /// The value's dependencies were walked, in order, and none of them had changed.
+    Deep,
+
+    /// The value was assigned with `specify` by a query that was itself validated.
+    Assigned,
+}
+
+/// The write that changes inputs; see [`EventKind::WillChangeInputs`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ChangeCause {
+    /// A field of an input is set, or edited, with its generated setter
+    /// (or with [`Database::set_config`](`crate::Database::set_config`)).
+    SetField {
+        /// The field of the input. Implements `Debug`.
+        field: DatabaseKeyIndex,
+    },
+
+    /// An input is assigned to another channel with
+    /// [`Database::set_channel`](`crate::Database::set_channel`).
+    SetChannel {
+        /// The input. Implements `Debug`.
+        input: DatabaseKeyIndex,
+
+        /// The channel it is assigned to.
+        channel: Channel,
+    },
+
+    /// [`Database::synthetic_write`](`crate::Database::synthetic_write`) was called.
+    SyntheticWrite {
+        /// The durability of the inputs that are considered changed.
+        durability: Durability,
+    },
+
+    /// All values of a salsa struct are considered changed, see
+    /// [`Database::invalidate_ingredient`](`crate::Database::invalidate_ingredient`).
+    InvalidateIngredient {
+        /// The ingredient of the salsa struct.
+        ingredient_index: IngredientIndex,
+    },
+
+    /// A [transaction](`crate::Database::transaction`) starts.
+    /// The fields set within it are reported individually.
+    Transaction,
+
+    /// A [write scope](`crate::Database::write_scope`) starts.
+    /// The fields set within it are not reported individually, as the scope
+    /// is shared between threads, but are delivered to the listeners registered
+    /// with [`Storage::on_change`](`crate::Storage::on_change`) when it ends.
+    WriteScope,
+}
+
+/// A set of [`EventKind`]s, used to register a subscriber for only the events it is interested in
+/// (see [`Storage::subscribe`](`crate::Storage::subscribe`)).
+///
+/// Sets are combined with `|`, e.g. `EventFilter::WILL_EXECUTE | EventFilter::DID_DISCARD`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EventFilter(u32);
+
+impl EventFilter {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u32::MAX);
+
+    pub const DID_VALIDATE_MEMOIZED_VALUE: Self = Self(1 << 0);
+    pub const WILL_BLOCK_ON: Self = Self(1 << 1);
+    pub const WILL_EXECUTE: Self = Self(1 << 2);
+    pub const WILL_CHECK_CANCELLATION: Self = Self(1 << 3);
+    pub const DID_SET_CANCELLATION_FLAG: Self = Self(1 << 4);
+    pub const WILL_DISCARD_STALE_OUTPUT: Self = Self(1 << 5);
+    pub const DID_DISCARD: Self = Self(1 << 6);
+    pub const DID_DISCARD_ACCUMULATED: Self = Self(1 << 7);
+    pub const DID_TIME_OUT: Self = Self(1 << 8);
+    pub const DID_CREATE_INPUTS: Self = Self(1 << 9);
+    pub const DID_DETECT_DEADLOCK: Self = Self(1 << 10);
+    pub const WILL_CHANGE_INPUTS: Self = Self(1 << 11);
+
+    /// True if `kind` is in this set.
+    pub fn matches(self, kind: &EventKind) -> bool {
+        self.intersects(kind.filter())
+    }
+
+    fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for EventFilter {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl EventKind {
+    /// The [`EventFilter`] containing just this kind of event.
+    pub fn filter(&self) -> EventFilter {
+        match self {
+            EventKind::DidValidateMemoizedValue { .. } => EventFilter::DID_VALIDATE_MEMOIZED_VALUE,
+            EventKind::WillBlockOn { .. } => EventFilter::WILL_BLOCK_ON,
+            EventKind::DidDetectDeadlock { .. } => EventFilter::DID_DETECT_DEADLOCK,
+            EventKind::WillExecute { .. } => EventFilter::WILL_EXECUTE,
+            EventKind::WillCheckCancellation => EventFilter::WILL_CHECK_CANCELLATION,
+            EventKind::DidSetCancellationFlag => EventFilter::DID_SET_CANCELLATION_FLAG,
+            EventKind::DidTimeOut { .. } => EventFilter::DID_TIME_OUT,
+            EventKind::DidCreateInputs { .. } => EventFilter::DID_CREATE_INPUTS,
+            EventKind::WillChangeInputs { .. } => EventFilter::WILL_CHANGE_INPUTS,
+            EventKind::WillDiscardStaleOutput { .. } => EventFilter::WILL_DISCARD_STALE_OUTPUT,
+            EventKind::DidDiscard { .. } => EventFilter::DID_DISCARD,
+            EventKind::DidDiscardAccumulated { .. } => EventFilter::DID_DISCARD_ACCUMULATED,
+        }
+    }
+}
+
+/// Identifies a subscriber registered with [`Storage::subscribe`](`crate::Storage::subscribe`),
+/// so that it can later be unsubscribed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriberId(u64);
+
+type SubscriberFn = dyn Fn(&dyn Database, &Event) + Send + Sync;
+
+type SubscriberList = Vec<(SubscriberId, EventFilter, Arc<SubscriberFn>)>;
+
+/// The event subscribers of a database, shared by all its handles.
+///
+/// Emitting an event only reads an atomic bitmask of the kinds that some subscriber
+/// is interested in and, if the event is among them, an `ArcSwap` of the subscriber list;
+/// registering and unregistering subscribers swap in a new list.
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    /// Union of the filters of all subscribers.
+    interest: AtomicU32,
+    next_id: AtomicU64,
+    list: ArcSwap<SubscriberList>,
+
+    /// Held while swapping in a new list and storing its `interest`, so that concurrent
+    /// (un)subscriptions cannot store the interest of a list that was already replaced.
+    update: Mutex<()>,
+}
+
+impl Subscribers {
+    pub(crate) fn subscribe(
+        &self,
+        filter: EventFilter,
+        subscriber: impl Fn(&dyn Database, &Event) + Send + Sync + 'static,
+    ) -> SubscriberId {
+        let id = SubscriberId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let subscriber: Arc<SubscriberFn> = Arc::new(subscriber);
+        self.update(|list| list.push((id, filter, subscriber)));
+        id
+    }
+
+    pub(crate) fn unsubscribe(&self, id: SubscriberId) -> bool {
+        let mut found = false;
+        self.update(|list| {
+            let len = list.len();
+            list.retain(|&(other, _, _)| other != id);
+            found = list.len() != len;
+        });
+        found
+    }
+
+    /// Swaps in the list modified by `op`, along with its interest.
+    fn update(&self, op: impl FnOnce(&mut SubscriberList)) {
+        let _guard = self.update.lock();
+        let mut list = Vec::clone(&self.list.load());
+        op(&mut list);
+        let interest = list
+            .iter()
+            .fold(EventFilter::NONE, |acc, &(_, filter, _)| acc | filter);
+        self.list.store(Arc::new(list));
+        self.interest.store(interest.0, Ordering::Release);
+    }
+}
+
+/// Reports `event` to the database's `salsa_event` method and to any interested subscribers.
+/// All events within salsa are emitted through this function.
+pub(crate) fn emit(db: &dyn Database, event: &dyn Fn() -> Event) {
+    let subscribers = db.zalsa().subscribers();
+    let interest = EventFilter(subscribers.interest.load(Ordering::Acquire));
+    if interest == EventFilter::NONE {
+        return db.salsa_event(event);
+    }
+
+    let event = event();
+    if interest.matches(&event.kind) {
+        for (_, filter, subscriber) in subscribers.list.load().iter() {
+            if filter.matches(&event.kind) {
+                subscriber(db, &event);
+            }
+        }
+    }
+    db.salsa_event(&|| event.clone());
+}
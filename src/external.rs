@@ -0,0 +1,223 @@
+use std::fmt;
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+use crate::{
+    accumulator::accumulated_map::InputAccumulatedValues,
+    channel::Channels,
+    cycle::CycleRecoveryStrategy,
+    ingredient::{Ingredient, IngredientKind, Jar, MaybeChangedAfter},
+    key::InputDependencyIndex,
+    plumbing::JarAux,
+    zalsa::{IngredientCache, IngredientIndex},
+    zalsa_local::QueryOrigin,
+    Database, DatabaseKeyIndex, Durability, Id, Revision,
+};
+
+/// Computes the fingerprint of an external resource, given its key.
+pub type ExternalFingerprintFn = fn(&str) -> u64;
+
+/// Implements [`Database::report_external_dependency`].
+pub(crate) fn report_external_dependency(
+    db: &dyn Database,
+    key: &str,
+    fingerprint_fn: ExternalFingerprintFn,
+) {
+    static CACHE: IngredientCache<ExternalIngredient> = IngredientCache::new();
+    let ingredient = CACHE.get_or_create(db, || db.zalsa().add_or_lookup_jar_by_type(&ExternalJar));
+
+    let current_revision = db.zalsa().current_revision();
+    let id = ingredient.intern(key, fingerprint_fn, current_revision);
+    let changed_at = ingredient.changed_at(id, current_revision);
+    db.zalsa_local().report_tracked_read(
+        InputDependencyIndex::new(ingredient.index, id),
+        Durability::LOW,
+        Channels::ALL,
+        changed_at,
+        InputAccumulatedValues::Empty,
+    );
+}
+
+struct ExternalJar;
+
+impl Jar for ExternalJar {
+    fn create_ingredients(
+        &self,
+        _aux: &dyn JarAux,
+        first_index: IngredientIndex,
+    ) -> Vec<Box<dyn Ingredient>> {
+        vec![Box::new(ExternalIngredient {
+            index: first_index,
+            resources: Default::default(),
+        })]
+    }
+
+    fn salsa_struct_type_id(&self) -> Option<std::any::TypeId> {
+        None
+    }
+}
+
+/// The external resources that queries reported to depend on,
+/// each identified by the index of its key.
+struct ExternalIngredient {
+    index: IngredientIndex,
+    resources: Mutex<Resources>,
+}
+
+#[derive(Default)]
+struct Resources {
+    ids: FxHashMap<String, Id>,
+    entries: Vec<Resource>,
+}
+
+struct Resource {
+    key: String,
+    fingerprint_fn: ExternalFingerprintFn,
+    fingerprint: u64,
+
+    /// The last revision in which the fingerprint changed.
+    changed_at: Revision,
+
+    /// The last revision in which the fingerprint was computed.
+    verified_at: Revision,
+}
+
+impl ExternalIngredient {
+    /// Returns the id of the resource `key`, registering it if needed.
+    /// If it was already registered, `fingerprint_fn` replaces its old fingerprint function.
+    fn intern(&self, key: &str, fingerprint_fn: ExternalFingerprintFn, revision: Revision) -> Id {
+        let mut resources = self.resources.lock();
+        if let Some(&id) = resources.ids.get(key) {
+            resources.entries[id.as_u32() as usize].fingerprint_fn = fingerprint_fn;
+            return id;
+        }
+
+        let id = Id::from_u32(resources.entries.len() as u32);
+        resources.ids.insert(key.to_string(), id);
+        resources.entries.push(Resource {
+            key: key.to_string(),
+            fingerprint_fn,
+            fingerprint: fingerprint_fn(key),
+            changed_at: revision,
+            verified_at: revision,
+        });
+        id
+    }
+
+    /// The last revision in which the fingerprint of resource `id` changed,
+    /// recomputing the fingerprint once per revision.
+    fn changed_at(&self, id: Id, current_revision: Revision) -> Revision {
+        let (key, fingerprint_fn) = {
+            let resources = self.resources.lock();
+            let resource = &resources.entries[id.as_u32() as usize];
+            if resource.verified_at == current_revision {
+                return resource.changed_at;
+            }
+            (resource.key.clone(), resource.fingerprint_fn)
+        };
+
+        // Compute the fingerprint without holding the lock, as it may read from disk.
+        let fingerprint = fingerprint_fn(&key);
+
+        let mut resources = self.resources.lock();
+        let resource = &mut resources.entries[id.as_u32() as usize];
+        if resource.verified_at != current_revision {
+            if resource.fingerprint != fingerprint {
+                resource.fingerprint = fingerprint;
+                resource.changed_at = current_revision;
+            }
+            resource.verified_at = current_revision;
+        }
+        resource.changed_at
+    }
+}
+
+impl Ingredient for ExternalIngredient {
+    fn ingredient_index(&self) -> IngredientIndex {
+        self.index
+    }
+
+    fn maybe_changed_after(
+        &self,
+        db: &dyn Database,
+        input: Id,
+        revision: Revision,
+    ) -> MaybeChangedAfter {
+        let current_revision = db.zalsa().current_revision();
+        MaybeChangedAfter::from(self.changed_at(input, current_revision) > revision)
+    }
+
+    fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
+        CycleRecoveryStrategy::Panic
+    }
+
+    fn id_generation(&self, _db: &dyn Database, id: Id) -> Option<u16> {
+        // Resources are identified by the index of their key, not by a slot in the table.
+        ((id.as_u32() as usize) < self.resources.lock().entries.len()).then_some(0)
+    }
+
+    fn durability(&self, _db: &dyn Database, _key_index: Id) -> Option<Durability> {
+        Some(Durability::LOW)
+    }
+
+    fn origin(&self, _db: &dyn Database, _key_index: Id) -> Option<QueryOrigin> {
+        None
+    }
+
+    fn mark_validated_output(
+        &self,
+        _db: &dyn Database,
+        _executor: DatabaseKeyIndex,
+        _output_key: Id,
+    ) {
+        panic!("external resources are never outputs of a query")
+    }
+
+    fn remove_stale_output(
+        &self,
+        _db: &dyn Database,
+        _executor: DatabaseKeyIndex,
+        _stale_output_key: Id,
+    ) {
+        panic!("external resources are never outputs of a query")
+    }
+
+    fn requires_reset_for_new_revision(&self) -> bool {
+        false
+    }
+
+    fn reset_for_new_revision(&mut self) {
+        panic!("unexpected call to `reset_for_new_revision`")
+    }
+
+    fn fmt_index(&self, index: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match index {
+            Some(id) => {
+                let resources = self.resources.lock();
+                write!(
+                    fmt,
+                    "external({:?})",
+                    resources.entries[id.as_u32() as usize].key
+                )
+            }
+            None => write!(fmt, "external()"),
+        }
+    }
+
+    fn debug_name(&self) -> &'static str {
+        "external"
+    }
+
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::External
+    }
+}
+
+impl fmt::Debug for ExternalIngredient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(std::any::type_name::<Self>())
+            .field("index", &self.index)
+            .finish()
+    }
+}
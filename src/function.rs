@@ -1,32 +1,48 @@
-use std::{any::Any, fmt, sync::Arc};
+use std::{any::Any, fmt, sync::Arc, time::Duration};
 
 use crate::{
     accumulator::accumulated_map::{AccumulatedMap, InputAccumulatedValues},
     cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, MaybeChangedAfter},
+    ingredient::{fmt_index, IngredientKind, MaybeChangedAfter},
     key::DatabaseKeyIndex,
     plumbing::JarAux,
     salsa_struct::SalsaStructInDb,
     zalsa::{IngredientIndex, MemoIngredientIndex, Zalsa},
     zalsa_local::QueryOrigin,
-    Cycle, Database, Id, Revision,
+    Cycle, Database, Durability, Id, MemoryPressure, Revision,
 };
 
-use self::delete::DeletedEntries;
+pub use self::visit::MemoEntry;
+
+use self::{
+    dedupe::DedupTable, delete::DeletedEntries, fingerprint::FingerprintTable, pin::PinnedKeys,
+    weak::WeakKeys,
+};
 
 use super::ingredient::Ingredient;
 
 mod accumulated;
 mod backdate;
+mod compact;
+pub(crate) mod context;
+pub(crate) mod dedupe;
 mod delete;
 mod diff_outputs;
 mod execute;
 mod fetch;
+pub(crate) mod fingerprint;
 mod inputs;
 mod lru;
 mod maybe_changed_after;
 mod memo;
+mod pin;
 mod specify;
+mod visit;
+mod weak;
+
+/// The threshold used by `#[salsa::tracked(adaptive)]` functions
+/// when none is given with `adaptive = "<duration>"`.
+pub const DEFAULT_ADAPTIVE_THRESHOLD: Duration = Duration::from_micros(1);
 
 pub trait Configuration: Any {
     const DEBUG_NAME: &'static str;
@@ -49,6 +65,30 @@ pub trait Configuration: Any {
     /// (and, if so, how).
     const CYCLE_STRATEGY: CycleRecoveryStrategy;
 
+    /// For functions declared with `timeout = "..."`, how long an execution may run
+    /// before it is unwound and [`Self::recover_from_timeout`] is used instead.
+    const TIMEOUT: Option<Duration>;
+
+    /// For functions declared with `adaptive`, executions that take less time than this
+    /// do not memoize their value: only the dependencies are kept, and the function is
+    /// executed again the next time its value is needed.
+    const ADAPTIVE: Option<Duration>;
+
+    /// For functions declared with `weak`, memoized values may be dropped at any time
+    /// by [`Database::trim_memory`](`crate::Database::trim_memory`), and the function
+    /// is executed again the next time its value is needed.
+    const WEAK: bool;
+
+    /// True for functions returning `()`, which are run for their side-channel outputs
+    /// (accumulated values, tracked structs, specified values). Their values need not be
+    /// compared to be backdated, and are never dropped to free memory as that frees nothing.
+    const UNIT: bool;
+
+    /// True for functions with arguments marked `#[salsa::not_key]`, which are only
+    /// available while the function is called: such functions are never executed
+    /// to verify the values of the queries that read them.
+    const CONTEXTUAL: bool;
+
     /// Invokes after a new result `new_value`` has been computed for which an older memoized
     /// value existed `old_value`. Returns true if the new value is equal to the older one
     /// and hence should be "backdated" (i.e., marked as having last changed in an older revision,
@@ -57,6 +97,34 @@ pub trait Configuration: Any {
     /// This invokes user's code in form of the `Eq` impl.
     fn should_backdate_value(old_value: &Self::Output<'_>, new_value: &Self::Output<'_>) -> bool;
 
+    /// For functions declared with `#[salsa::tracked(dedupe)]`, returns a hash of `value`
+    /// used to find equal values computed for other keys; `None` otherwise.
+    /// For functions declared with `#[salsa::tracked(result)]`, returns a hash of the error,
+    /// if `value` is one, so that equal errors share their allocation.
+    fn dedupe_hash(value: &Self::Output<'_>) -> Option<u64>;
+
+    /// Invoked with a previously memoized `candidate` whose hash matches that of `value`.
+    /// If the two are equal, returns `Ok` with a value sharing `candidate`'s allocation;
+    /// otherwise gives back `value`.
+    ///
+    /// This invokes user's code in form of the `Eq` impl.
+    fn dedupe_with<'db>(
+        candidate: &Self::Output<'db>,
+        value: Self::Output<'db>,
+    ) -> Result<Self::Output<'db>, Self::Output<'db>>;
+
+    /// For functions declared with `#[salsa::tracked(fingerprint)]`, returns a hash of `value`
+    /// that is compared instead of the value itself when backdating; `None` otherwise.
+    fn fingerprint(value: &Self::Output<'_>) -> Option<u64>;
+
+    /// For functions declared with `#[salsa::tracked(result(retry))]`, true if `value`
+    /// is an error, which is then recomputed in the next revision.
+    fn should_retry(value: &Self::Output<'_>) -> bool;
+
+    /// Returns a value sharing `value`'s allocation, if values are stored in an `Arc`
+    /// (for functions declared with `#[salsa::tracked(dedupe)]` or `#[salsa::tracked(fingerprint)]`).
+    fn share_value<'db>(value: &Self::Output<'db>) -> Option<Self::Output<'db>>;
+
     /// Convert from the id used internally to the value that execute is expecting.
     /// This is a no-op if the input to the function is a salsa struct.
     fn id_to_input(db: &Self::DbView, key: Id) -> Self::Input<'_>;
@@ -76,6 +144,15 @@ pub trait Configuration: Any {
         cycle: &Cycle,
         input: Self::Input<'db>,
     ) -> Self::Output<'db>;
+
+    /// If there is a [`Self::TIMEOUT`], invoked when an execution for `input` ran out of time
+    /// to find out what value it should have.
+    ///
+    /// This invokes the `timeout_result` function given by the user.
+    fn recover_from_timeout<'db>(
+        db: &'db Self::DbView,
+        input: Self::Input<'db>,
+    ) -> Self::Output<'db>;
 }
 
 /// Function ingredients are the "workhorse" of salsa.
@@ -94,8 +171,8 @@ pub struct IngredientImpl<C: Configuration> {
     /// Used to construct `DatabaseKeyIndex` values.
     index: IngredientIndex,
 
-    /// The index for the memo/sync tables
-    memo_ingredient_index: MemoIngredientIndex,
+    /// The indices for the memo/sync tables of the structs the function takes
+    memo_ingredient_indices: MemoIngredientIndices,
 
     /// Used to find memos to throw out when we have too many memoized values.
     lru: lru::Lru,
@@ -113,6 +190,36 @@ pub struct IngredientImpl<C: Configuration> {
     /// we don't know that we can trust the database to give us the same runtime
     /// everytime and so forth.
     deleted_entries: DeletedEntries<C>,
+
+    /// For `#[salsa::tracked(dedupe)]` functions, maps the hash of each memoized value
+    /// to the keys holding it, so that equal values can share one allocation.
+    /// Empty for all other functions.
+    dedup_table: DedupTable,
+
+    /// For `#[salsa::tracked(fingerprint)]` functions, the fingerprint of each memoized value.
+    /// Empty for all other functions.
+    fingerprints: FingerprintTable,
+
+    /// For `#[salsa::tracked(weak)]` functions, the keys whose value can be dropped
+    /// by [`Database::trim_memory`](`crate::Database::trim_memory`).
+    /// Empty for all other functions.
+    weak_keys: WeakKeys,
+
+    /// The keys pinned with `pin_subtree`, whose values are never evicted.
+    pinned_keys: PinnedKeys,
+
+    /// The number of cache hits, misses and evictions.
+    #[cfg(feature = "metrics")]
+    counters: crate::metrics::CacheCounters,
+}
+
+/// The memo ingredient indices of a function, see [`IngredientImpl::new`].
+enum MemoIngredientIndices {
+    /// The index of the struct owning the keys, and of the memos in its memo table.
+    Single(IngredientIndex, MemoIngredientIndex),
+
+    /// Indexed by the ingredient index of the struct owning the key.
+    PerStruct(Box<[Option<MemoIngredientIndex>]>),
 }
 
 /// True if `old_value == new_value`. Invoked by the generated
@@ -126,12 +233,65 @@ impl<C> IngredientImpl<C>
 where
     C: Configuration,
 {
-    pub fn new(struct_index: IngredientIndex, index: IngredientIndex, aux: &dyn JarAux) -> Self {
+    /// Creates the ingredient of a function whose keys belong to the structs `struct_indices`:
+    /// one struct, or each variant of a `#[derive(salsa::Supertype)]` enum.
+    pub fn new(
+        struct_indices: &[IngredientIndex],
+        index: IngredientIndex,
+        aux: &dyn JarAux,
+    ) -> Self {
+        let memo_ingredient_indices = match struct_indices {
+            [struct_index] => MemoIngredientIndices::Single(
+                *struct_index,
+                aux.next_memo_ingredient_index(*struct_index, index, C::DEBUG_NAME),
+            ),
+            _ => {
+                let len = struct_indices
+                    .iter()
+                    .map(|struct_index| struct_index.as_usize() + 1)
+                    .max()
+                    .unwrap_or(0);
+                let mut indices = vec![None; len].into_boxed_slice();
+                for &struct_index in struct_indices {
+                    indices[struct_index.as_usize()] =
+                        Some(aux.next_memo_ingredient_index(struct_index, index, C::DEBUG_NAME));
+                }
+                MemoIngredientIndices::PerStruct(indices)
+            }
+        };
         Self {
             index,
-            memo_ingredient_index: aux.next_memo_ingredient_index(struct_index, index),
+            memo_ingredient_indices,
             lru: Default::default(),
             deleted_entries: Default::default(),
+            dedup_table: Default::default(),
+            fingerprints: Default::default(),
+            weak_keys: Default::default(),
+            pinned_keys: Default::default(),
+            #[cfg(feature = "metrics")]
+            counters: Default::default(),
+        }
+    }
+
+    /// True if the ids allocated by the struct ingredient `struct_index` are keys of this function.
+    fn is_keyed_on(&self, struct_index: IngredientIndex) -> bool {
+        match &self.memo_ingredient_indices {
+            MemoIngredientIndices::Single(index, _) => *index == struct_index,
+            MemoIngredientIndices::PerStruct(indices) => {
+                matches!(indices.get(struct_index.as_usize()), Some(Some(_)))
+            }
+        }
+    }
+
+    /// The index of the memos of this function in the memo table of `id`.
+    fn memo_ingredient_index(&self, zalsa: &Zalsa, id: Id) -> MemoIngredientIndex {
+        match &self.memo_ingredient_indices {
+            MemoIngredientIndices::Single(_, index) => *index,
+            MemoIngredientIndices::PerStruct(indices) => zalsa
+                .table()
+                .owner(id)
+                .and_then(|owner| indices.get(owner.as_usize()).copied().flatten())
+                .unwrap_or_else(|| panic!("`{id:?}` is not a key of `{}`", C::DEBUG_NAME)),
         }
     }
 
@@ -146,6 +306,15 @@ where
         self.lru.set_capacity(capacity);
     }
 
+    /// Sets the LRU capacity at runtime, evicting the values of the least recently used keys
+    /// that no longer fit. A capacity of `0` disables LRU.
+    pub fn set_lru_capacity(&self, db: &dyn Database, capacity: usize) {
+        let zalsa = db.zalsa();
+        for evicted in self.lru.set_capacity(capacity) {
+            self.evict_value_from_memo_for(zalsa, evicted);
+        }
+    }
+
     /// Returns a reference to the memo value that lives as long as self.
     /// This is UNSAFE: the caller is responsible for ensuring that the
     /// memo will not be released so long as the `&self` is valid.
@@ -203,6 +372,25 @@ where
         C::CYCLE_STRATEGY
     }
 
+    fn id_generation(&self, db: &dyn Database, id: Id) -> Option<u16> {
+        let zalsa = db.zalsa();
+        let owner = zalsa.table().owner(id)?;
+        if !self.is_keyed_on(owner) {
+            return None;
+        }
+        zalsa.lookup_ingredient(owner).id_generation(db, id)
+    }
+
+    fn value_hash(&self, db: &dyn Database, key: Id) -> Option<u64> {
+        let memo = self.get_memo_from_table_for(db.zalsa(), key)?;
+        Some(crate::checksum::debug_hash(memo.value.as_ref()?))
+    }
+
+    fn durability(&self, db: &dyn Database, key: Id) -> Option<Durability> {
+        let memo = self.get_memo_from_table_for(db.zalsa(), key)?;
+        Some(memo.revisions.durability)
+    }
+
     fn origin(&self, db: &dyn Database, key: Id) -> Option<QueryOrigin> {
         self.origin(db.zalsa(), key)
     }
@@ -235,6 +423,31 @@ where
         std::mem::take(&mut self.deleted_entries);
     }
 
+    fn trim_memory(&self, db: &dyn Database, pressure: MemoryPressure) {
+        self.trim_memory(db.zalsa(), pressure);
+    }
+
+    #[cfg(feature = "metrics")]
+    fn cache_stats(&self) -> Option<crate::metrics::CacheStats> {
+        Some(self.counters.stats())
+    }
+
+    fn pin(&self, _db: &dyn Database, key: Id) {
+        self.pinned_keys.insert(key);
+    }
+
+    fn unpin(&self, _db: &dyn Database, key: Id) {
+        self.pinned_keys.remove(key);
+    }
+
+    fn compact(&self, db: &dyn Database) -> usize {
+        self.compact(db.zalsa())
+    }
+
+    fn free_deleted_entries(&mut self) -> usize {
+        self.deleted_entries.free()
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(C::DEBUG_NAME, index, fmt)
     }
@@ -243,6 +456,10 @@ where
         C::DEBUG_NAME
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::TrackedFn
+    }
+
     fn accumulated<'db>(
         &'db self,
         db: &'db dyn Database,
@@ -1,11 +1,9 @@
 use super::{Configuration, IngredientImpl};
 use crate::accumulator::accumulated_map::InputAccumulatedValues;
-use crate::zalsa_local::QueryOrigin;
 use crate::{
     accumulator::{self, accumulated_map::AccumulatedMap},
-    hash::FxHashSet,
     zalsa::ZalsaDatabase,
-    AsDynDatabase, DatabaseKeyIndex, Id,
+    AsDynDatabase, Id,
 };
 
 impl<C> IngredientImpl<C>
@@ -42,53 +40,12 @@ where
         self.fetch(db, key);
 
         let db = db.as_dyn_database();
-        let db_key = self.database_key_index(key);
-        let mut visited: FxHashSet<DatabaseKeyIndex> = FxHashSet::default();
-        let mut stack: Vec<DatabaseKeyIndex> = vec![db_key];
-
-        // Do a depth-first earch across the dependencies of `key`, reading the values accumulated by
-        // each dependency.
-        while let Some(k) = stack.pop() {
-            // Already visited `k`?
-            if !visited.insert(k) {
-                continue;
-            }
-
-            let ingredient = zalsa.lookup_ingredient(k.ingredient_index);
-            // Extend `output` with any values accumulated by `k`.
-            let (accumulated_map, input) = ingredient.accumulated(db, k.key_index);
-            if let Some(accumulated_map) = accumulated_map {
-                accumulated_map.extend_with_accumulated(accumulator.index(), &mut output);
-            }
-            // Skip over the inputs because we know that the entire sub-graph has no accumulated values
-            if input.is_empty() {
-                continue;
-            }
-
-            // Find the inputs of `k` and push them onto the stack.
-            //
-            // Careful: to ensure the user gets a consistent ordering in their
-            // output vector, we want to push in execution order, so reverse order to
-            // ensure the first child that was executed will be the first child popped
-            // from the stack.
-            let Some(origin) = ingredient.origin(db, k.key_index) else {
-                continue;
-            };
-
-            if let QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges) = &origin {
-                stack.reserve(edges.input_outputs.len());
-            }
-
-            stack.extend(
-                origin
-                    .inputs()
-                    .filter_map(|input| TryInto::<DatabaseKeyIndex>::try_into(input).ok())
-                    .rev(),
-            );
-
-            visited.reserve(stack.len());
-        }
-
+        accumulator::extend_with_accumulated_by(
+            db,
+            accumulator.index(),
+            &[self.database_key_index(key)],
+            &mut output,
+        );
         output
     }
 
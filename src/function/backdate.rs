@@ -15,18 +15,30 @@ where
         revisions: &mut QueryRevisions,
         value: &C::Output<'_>,
     ) {
-        if let Some(old_value) = &old_memo.value {
+        // All unit values are equal, so there is nothing to compare.
+        let unchanged = C::UNIT
+            || old_memo
+                .value
+                .as_ref()
+                .is_some_and(|old_value| C::should_backdate_value(old_value, value));
+        if unchanged {
             // Careful: if the value became less durable than it
             // used to be, that is a "breaking change" that our
             // consumers must be aware of. Becoming *more* durable
             // is not. See the test `constant_to_non_constant`.
+            // Likewise for reading inputs of channels it did not read before.
             if revisions.durability >= old_memo.revisions.durability
-                && C::should_backdate_value(old_value, value)
+                && revisions.channels.is_subset(old_memo.revisions.channels)
             {
                 tracing::debug!(
                     "value is equal, back-dating to {:?}",
                     old_memo.revisions.changed_at,
                 );
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    changed_at = old_memo.revisions.changed_at.as_usize(),
+                    "backdated"
+                );
 
                 assert!(old_memo.revisions.changed_at <= revisions.changed_at);
                 revisions.changed_at = old_memo.revisions.changed_at;
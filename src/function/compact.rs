@@ -0,0 +1,48 @@
+use crate::{zalsa::Zalsa, Id};
+
+use super::{memo::Memo, Configuration, IngredientImpl};
+
+/// The number of bytes released by shrinking a collection of `T`s
+/// from `capacity` to `new_capacity` elements.
+pub(super) fn reclaimed<T>(capacity: usize, new_capacity: usize) -> usize {
+    capacity.saturating_sub(new_capacity) * std::mem::size_of::<T>()
+}
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Drops the entries of the side tables (LRU list, dedup table, fingerprints,
+    /// weak and pinned keys) for keys whose memo no longer holds a value,
+    /// e.g. because it was evicted or its tracked struct was deleted,
+    /// and shrinks those tables to fit.
+    pub(super) fn compact(&self, zalsa: &Zalsa) -> usize {
+        let has_memo = |id| self.memo_if_in_use(zalsa, id).is_some();
+        let has_value = |id| {
+            self.memo_if_in_use(zalsa, id)
+                .is_some_and(|memo| memo.value.is_some())
+        };
+
+        self.lru.compact(has_value)
+            + self.dedup_table.compact(has_value)
+            + self.fingerprints.compact(has_value)
+            + self.weak_keys.compact(has_value)
+            + self.pinned_keys.compact(has_memo)
+    }
+
+    /// Like [`Self::get_memo_from_table_for`], but returns `None`
+    /// if `id` is a tracked struct that was deleted.
+    fn memo_if_in_use<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        id: Id,
+    ) -> Option<std::sync::Arc<Memo<C::Output<'db>>>> {
+        // SAFETY: We supply the current revision of the database owning the table.
+        unsafe {
+            zalsa
+                .table()
+                .memos_if_in_use(id, zalsa.current_revision())?
+        };
+        self.get_memo_from_table_for(zalsa, id)
+    }
+}
@@ -0,0 +1,60 @@
+use std::{cell::Cell, thread::LocalKey};
+
+/// The arguments of a tracked function that are marked `#[salsa::not_key]`, made available
+/// to its executions on the thread calling it. Each such function has its own thread-local.
+pub struct Context {
+    arguments: Cell<*const ()>,
+}
+
+impl Context {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            arguments: Cell::new(std::ptr::null()),
+        }
+    }
+
+    /// Makes `arguments` available to the executions of the function started
+    /// until the returned guard is dropped.
+    pub fn enter<T>(key: &'static LocalKey<Context>, arguments: &T) -> ContextGuard {
+        let previous = key.with(|context| {
+            context
+                .arguments
+                .replace(arguments as *const T as *const ())
+        });
+        ContextGuard { key, previous }
+    }
+
+    /// The arguments given to the innermost call of the function on this thread.
+    ///
+    /// # Panics
+    ///
+    /// If the function is not being called on this thread.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the type of the arguments given to [`Self::enter`] for `key`.
+    pub unsafe fn get<T: Copy>(key: &'static LocalKey<Context>) -> T {
+        let arguments = key.with(|context| context.arguments.get());
+        assert!(
+            !arguments.is_null(),
+            "a function with `#[salsa::not_key]` arguments was executed outside of a call"
+        );
+        // SAFETY: The pointer was set by `enter` with a `T`, and the guard
+        // resetting it has not been dropped, so the arguments are still borrowed.
+        unsafe { *(arguments as *const T) }
+    }
+}
+
+/// Restores the arguments of an enclosing call when dropped, see [`Context::enter`].
+pub struct ContextGuard {
+    key: &'static LocalKey<Context>,
+    previous: *const (),
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        self.key
+            .with(|context| context.arguments.set(self.previous));
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::hash_map::Entry,
+    hash::{BuildHasher, Hash},
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+use rustc_hash::{FxBuildHasher, FxHashMap};
+
+use crate::{zalsa::Zalsa, Id};
+
+use super::{compact::reclaimed, Configuration, IngredientImpl};
+
+/// Hash used by `#[salsa::tracked(dedupe)]` functions to find equal values.
+/// Invoked by the generated code for `dedupe_hash` so as to give a better
+/// error message when the output type is not `Hash`.
+pub fn dedupe_hash<T: Hash>(value: &Arc<T>) -> u64 {
+    FxBuildHasher.hash_one(&**value)
+}
+
+/// Returns a clone of `candidate` if it is equal to `value`, and `value` otherwise.
+/// Invoked by the generated code for `dedupe_with` so as to give a better
+/// error message when the output type is not `Eq`.
+pub fn dedupe_with<T: Eq>(candidate: &Arc<T>, value: Arc<T>) -> Result<Arc<T>, Arc<T>> {
+    if Arc::ptr_eq(candidate, &value) || **candidate == *value {
+        Ok(candidate.clone())
+    } else {
+        Err(value)
+    }
+}
+
+/// Stores the error of `value`, if any, in an `Arc`.
+/// Invoked by the generated code for `#[salsa::tracked(result)]` functions.
+pub fn share_result<T, E>(value: Result<T, E>) -> Result<T, Arc<E>> {
+    value.map_err(Arc::new)
+}
+
+/// Hash used by `#[salsa::tracked(result)]` functions to find equal errors;
+/// `None` if `value` is not an error.
+pub fn error_hash<T, E: Hash>(value: &Result<T, Arc<E>>) -> Option<u64> {
+    value.as_ref().err().map(dedupe_hash)
+}
+
+/// Returns an error sharing `candidate`'s allocation if `value` is an equal error,
+/// and `value` otherwise.
+pub fn share_error<T, E: Eq>(
+    candidate: &Result<T, Arc<E>>,
+    value: Result<T, Arc<E>>,
+) -> Result<Result<T, Arc<E>>, Result<T, Arc<E>>> {
+    match (candidate, value) {
+        (Err(candidate), Err(error)) => dedupe_with(candidate, error).map(Err).map_err(Err),
+        (_, value) => Err(value),
+    }
+}
+
+/// The keys of a deduplicating function, grouped by the hash of their memoized value.
+///
+/// A key is moved to another group when its value changes, and removed when its
+/// value is evicted. The keys of deleted tracked structs are only removed by
+/// [`Self::compact`]; until then, their lookups find no memo and are skipped.
+#[derive(Default)]
+pub(super) struct DedupTable {
+    map: Mutex<DedupMap>,
+}
+
+#[derive(Default)]
+struct DedupMap {
+    keys_by_hash: FxHashMap<u64, Vec<Id>>,
+    hashes: FxHashMap<Id, u64>,
+}
+
+impl DedupMap {
+    fn remove(&mut self, id: Id) -> Option<u64> {
+        let hash = self.hashes.remove(&id)?;
+        if let Entry::Occupied(mut entry) = self.keys_by_hash.entry(hash) {
+            entry.get_mut().retain(|&other| other != id);
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+        }
+        Some(hash)
+    }
+}
+
+impl DedupTable {
+    /// The keys other than `id` whose memoized value had the hash `hash`.
+    fn candidates(&self, hash: u64, id: Id) -> Vec<Id> {
+        let map = self.map.lock();
+        let Some(ids) = map.keys_by_hash.get(&hash) else {
+            return vec![];
+        };
+        ids.iter().copied().filter(|&other| other != id).collect()
+    }
+
+    /// Records that the memoized value of `id` has the hash `hash`.
+    fn record(&self, id: Id, hash: u64) {
+        let mut map = self.map.lock();
+        if map.hashes.get(&id) == Some(&hash) {
+            return;
+        }
+        map.remove(id);
+        map.keys_by_hash.entry(hash).or_default().push(id);
+        map.hashes.insert(id, hash);
+    }
+
+    /// Forgets the value of `id`, e.g. because it was evicted.
+    pub(super) fn remove(&self, id: Id) {
+        self.map.lock().remove(id);
+    }
+
+    /// Removes the keys for which `keep` returns false and shrinks the table to fit.
+    /// Returns the number of bytes reclaimed.
+    pub(super) fn compact(&self, keep: impl Fn(Id) -> bool) -> usize {
+        let mut map = self.map.lock();
+        let map = &mut *map;
+        let capacity = map.keys_by_hash.capacity();
+        let hashes_capacity = map.hashes.capacity();
+        let mut bytes = 0;
+        map.keys_by_hash.retain(|_, ids| {
+            let ids_capacity = ids.capacity();
+            ids.retain(|&id| keep(id));
+            ids.shrink_to_fit();
+            bytes += reclaimed::<Id>(ids_capacity, ids.capacity());
+            !ids.is_empty()
+        });
+        map.hashes.retain(|&id, _| keep(id));
+        map.keys_by_hash.shrink_to_fit();
+        map.hashes.shrink_to_fit();
+        bytes
+            + reclaimed::<(u64, Vec<Id>)>(capacity, map.keys_by_hash.capacity())
+            + reclaimed::<(Id, u64)>(hashes_capacity, map.hashes.capacity())
+    }
+}
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// If this function deduplicates its values, looks for a memoized value equal to
+    /// `value` and, if one exists, returns a value sharing its allocation.
+    /// In any case, records `id` as holding a value with `value`'s hash.
+    ///
+    /// The values are compared without holding the lock on the table,
+    /// as the comparison runs user code.
+    pub(super) fn dedupe<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        id: Id,
+        mut value: C::Output<'db>,
+    ) -> C::Output<'db> {
+        let Some(hash) = C::dedupe_hash(&value) else {
+            return value;
+        };
+
+        for other in self.dedup_table.candidates(hash, id) {
+            let Some(memo) = self.get_memo_from_table_for(zalsa, other) else {
+                continue;
+            };
+            let Some(candidate) = &memo.value else {
+                continue;
+            };
+            match C::dedupe_with(candidate, value) {
+                Ok(shared) => {
+                    value = shared;
+                    break;
+                }
+                // A hash collision, or a key whose value has changed since.
+                Err(pending) => value = pending,
+            }
+        }
+        self.dedup_table.record(id, hash);
+
+        value
+    }
+}
@@ -1,5 +1,7 @@
 use crossbeam::queue::SegQueue;
 
+use crate::table::memo::Memo;
+
 use super::{memo::ArcMemo, Configuration};
 
 /// Stores the list of memos that have been deleted so they can be freed
@@ -22,4 +24,13 @@ impl<C: Configuration> DeletedEntries<C> {
         let memo = unsafe { std::mem::transmute::<ArcMemo<'db, C>, ArcMemo<'static, C>>(memo) };
         self.seg_queue.push(memo);
     }
+
+    /// Frees the deleted memos, returning the number of bytes they used.
+    pub(super) fn free(&mut self) -> usize {
+        let mut bytes = 0;
+        while let Some(memo) = self.seg_queue.pop() {
+            bytes += memo.memory_usage();
+        }
+        bytes
+    }
 }
@@ -45,7 +45,7 @@ where
     fn report_stale_output(db: &C::DbView, key: DatabaseKeyIndex, output: OutputDependencyIndex) {
         let db = db.as_dyn_database();
 
-        db.salsa_event(&|| {
+        crate::event::emit(db, &|| {
             Event::new(EventKind::WillDiscardStaleOutput {
                 execute_key: key,
                 output_key: output,
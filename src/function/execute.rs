@@ -1,7 +1,10 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use crate::{
-    zalsa::ZalsaDatabase, zalsa_local::ActiveQueryGuard, Cycle, Database, Event, EventKind,
+    cancelled::TimedOut,
+    zalsa::{Zalsa, ZalsaDatabase},
+    zalsa_local::{ActiveQueryGuard, QueryOrigin},
+    AsDynDatabase, Cycle, Event, EventKind, Id,
 };
 
 use super::{memo::Memo, Configuration, IngredientImpl};
@@ -23,15 +26,23 @@ where
         &'db self,
         db: &'db C::DbView,
         active_query: ActiveQueryGuard<'_>,
-        opt_old_memo: Option<Arc<Memo<C::Output<'_>>>>,
+        opt_old_memo: Option<Arc<Memo<C::Output<'db>>>>,
     ) -> &'db Memo<C::Output<'db>> {
         let zalsa = db.zalsa();
         let revision_now = zalsa.current_revision();
         let database_key_index = active_query.database_key_index;
 
         tracing::info!("{:?}: executing query", database_key_index);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!(
+            "execute",
+            query = C::DEBUG_NAME,
+            id = database_key_index.key_index.as_u32(),
+            revision = revision_now.as_usize(),
+        )
+        .entered();
 
-        db.salsa_event(&|| {
+        crate::event::emit(db.as_dyn_database(), &|| {
             Event::new(EventKind::WillExecute {
                 database_key: database_key_index,
             })
@@ -47,9 +58,25 @@ where
         // stale, or value is absent. Let's execute!
         let database_key_index = active_query.database_key_index;
         let id = database_key_index.key_index;
-        let value = match Cycle::catch(|| C::execute(db, C::id_to_input(db, id))) {
-            Ok(v) => v,
-            Err(cycle) => {
+        if let Some(timeout) = C::TIMEOUT {
+            active_query.set_timeout(timeout);
+        }
+        let started_at = Instant::now();
+        let result = TimedOut::catch(database_key_index, || {
+            Cycle::catch(|| C::execute(db, C::id_to_input(db, id)))
+        });
+        let mut value = match result {
+            Ok(Ok(v)) => v,
+            Err(TimedOut { .. }) => {
+                crate::event::emit(db.as_dyn_database(), &|| {
+                    Event::new(EventKind::DidTimeOut {
+                        database_key: database_key_index,
+                    })
+                });
+                active_query.report_timed_out(revision_now);
+                C::recover_from_timeout(db, C::id_to_input(db, id))
+            }
+            Ok(Err(cycle)) => {
                 tracing::debug!(
                     "{database_key_index:?}: caught cycle {cycle:?}, have strategy {:?}",
                     C::CYCLE_STRATEGY
@@ -59,6 +86,13 @@ where
                     crate::cycle::CycleRecoveryStrategy::Fallback => {
                         if let Some(c) = active_query.take_cycle() {
                             assert!(c.is(&cycle));
+                            #[cfg(feature = "tracing")]
+                            tracing::info!(
+                                participants = cycle.participant_keys().count(),
+                                trigger = ?cycle.trigger(),
+                                "recovering from cycle"
+                            );
+                            active_query.discard_accumulated();
                             C::recover_from_cycle(db, &cycle, C::id_to_input(db, id))
                         } else {
                             // we are not a participant in this cycle
@@ -71,19 +105,93 @@ where
                 }
             }
         };
+        let elapsed = started_at.elapsed();
+        if C::should_retry(&value) {
+            active_query.report_retry(revision_now);
+        }
         let mut revisions = active_query.pop();
+        let fingerprints = self.record_fingerprint(id, &value);
 
         // If the new value is equal to the old one, then it didn't
         // really change, even if some of its inputs have. So we can
         // "backdate" its `changed_at` revision to be the same as the
         // old value.
         if let Some(old_memo) = &opt_old_memo {
-            self.backdate_if_appropriate(old_memo, &mut revisions, &value);
+            // An assigned value was not computed from our inputs,
+            // so the new value may differ even if none of them changed.
+            if let QueryOrigin::Assigned(_) = old_memo.revisions.origin {
+                revisions.changed_at = revision_now;
+            }
+            value = self.backdate_by_fingerprint(old_memo, fingerprints, &mut revisions, value);
             self.diff_outputs(db, database_key_index, old_memo, &mut revisions);
         }
 
         tracing::debug!("{database_key_index:?}: read_upgrade: result.revisions = {revisions:#?}");
 
-        self.insert_memo(zalsa, id, Memo::new(Some(value), revision_now, revisions))
+        let value = self.dedupe(zalsa, id, value);
+        #[allow(unused_mut)]
+        let mut memo = Memo::new(Some(value), revision_now, revisions);
+        #[cfg(feature = "query_timing")]
+        {
+            let old_time = opt_old_memo.as_ref().and_then(|old| old.execution_time);
+            memo.execution_time = Some(old_time.unwrap_or_default().record(elapsed));
+        }
+        match C::ADAPTIVE {
+            Some(threshold) if !C::UNIT && elapsed < threshold && self.can_skip_value(&memo) => {
+                tracing::debug!("{database_key_index:?}: not memoizing value, took {elapsed:?}");
+                self.insert_memo_without_value(zalsa, id, memo)
+            }
+            _ => {
+                if C::WEAK && !C::UNIT {
+                    self.weak_keys.insert(id);
+                }
+                self.insert_memo(zalsa, id, memo)
+            }
+        }
+    }
+
+    /// True if the value of `memo` can be recomputed from its dependencies alone,
+    /// so that it need not be kept once the current revision ends.
+    fn can_skip_value(&self, memo: &Memo<C::Output<'_>>) -> bool {
+        match &memo.revisions.origin {
+            QueryOrigin::Derived(edges) => edges.outputs().next().is_none(),
+            QueryOrigin::DerivedUntracked(_)
+            | QueryOrigin::Assigned(_)
+            | QueryOrigin::BaseInput => false,
+        }
+    }
+
+    /// Stores a copy of `memo` without its value, so that only its dependencies are kept,
+    /// and returns a reference to `memo` itself.
+    ///
+    /// If the function was already executed without memoizing its value in this revision,
+    /// the copy stored then is kept, as it has the same dependencies.
+    fn insert_memo_without_value<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        id: Id,
+        memo: Memo<C::Output<'db>>,
+    ) -> &'db Memo<C::Output<'db>> {
+        let memo = Arc::new(memo);
+        let db_memo = unsafe {
+            // Unsafety conditions: the memo is added to the deleted entries below,
+            // which are only cleared when a new revision starts.
+            self.extend_memo_lifetime(&memo)
+        };
+        let stored = self.get_memo_from_table_for(zalsa, id);
+        let reusable = stored.is_some_and(|stored| {
+            stored.value.is_none() && stored.verified_at.load() == memo.verified_at.load()
+        });
+        if !reusable {
+            if let Some(old_value) =
+                self.insert_memo_into_table_for(zalsa, id, Arc::new(memo.without_value()))
+            {
+                self.deleted_entries.push(old_value);
+            }
+        }
+        self.deleted_entries.push(memo);
+        self.dedup_table.remove(id);
+        self.fingerprints.remove(id);
+        db_memo
     }
 }
@@ -17,15 +17,19 @@ where
             value,
             durability,
             changed_at,
+            channels,
         } = memo.revisions.stamped_value(memo.value.as_ref().unwrap());
 
-        if let Some(evicted) = self.lru.record_use(id) {
-            self.evict_value_from_memo_for(zalsa, evicted);
+        if !C::UNIT {
+            if let Some(evicted) = self.lru.record_use(id) {
+                self.evict_value_from_memo_for(zalsa, evicted);
+            }
         }
 
         zalsa_local.report_tracked_read(
             self.database_key_index(id).into(),
             durability,
+            channels,
             changed_at,
             match &memo.revisions.accumulated {
                 Some(_) => InputAccumulatedValues::Any,
@@ -33,6 +37,11 @@ where
             },
         );
 
+        #[cfg(feature = "testing")]
+        zalsa_local.record_fetch(self.database_key_index(id), || {
+            crate::attach::attach(db, || format!("{value:?}"))
+        });
+
         value
     }
 
@@ -57,6 +66,9 @@ where
             if memo.value.is_some()
                 && self.shallow_verify_memo(db, zalsa, self.database_key_index(id), memo)
             {
+                #[cfg(feature = "metrics")]
+                self.counters.record_hit();
+
                 // Unsafety invariant: memo is present in memo_map and we have verified that it is
                 // still valid for the current revision.
                 return unsafe { Some(self.extend_memo_lifetime(memo)) };
@@ -74,7 +86,7 @@ where
             db.as_dyn_database(),
             zalsa_local,
             database_key_index,
-            self.memo_ingredient_index,
+            self.memo_ingredient_index(zalsa, id),
         )?;
 
         // Push the query on the stack.
@@ -85,12 +97,18 @@ where
         let opt_old_memo = self.get_memo_from_table_for(zalsa, id);
         if let Some(old_memo) = &opt_old_memo {
             if old_memo.value.is_some() && self.deep_verify_memo(db, old_memo, &active_query) {
+                #[cfg(feature = "metrics")]
+                self.counters.record_hit();
+
                 // Unsafety invariant: memo is present in memo_map and we have verified that it is
                 // still valid for the current revision.
                 return unsafe { Some(self.extend_memo_lifetime(old_memo)) };
             }
         }
 
+        #[cfg(feature = "metrics")]
+        self.counters.record_miss();
+
         Some(self.execute(db, active_query, opt_old_memo))
     }
 }
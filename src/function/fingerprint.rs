@@ -0,0 +1,112 @@
+use std::{
+    hash::{BuildHasher, BuildHasherDefault, DefaultHasher, Hash},
+    sync::Arc,
+};
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+use crate::{zalsa_local::QueryRevisions, Id};
+
+use super::{compact::reclaimed, memo::Memo, Configuration, IngredientImpl};
+
+/// Hash used by `#[salsa::tracked(fingerprint)]` functions to compare their old and new values.
+/// Invoked by the generated code for `fingerprint` so as to give a better
+/// error message when the output type is not `Hash`.
+///
+/// Unlike the hash used for deduplication, equal fingerprints are trusted without
+/// comparing the values, so this uses SipHash rather than the weaker FxHash.
+/// Still, two different values have the same 64-bit fingerprint with a probability
+/// of about 2^-64; the new value is then discarded for the old one, and the queries
+/// that read it are not re-executed.
+pub fn fingerprint<T: Hash>(value: &Arc<T>) -> u64 {
+    BuildHasherDefault::<DefaultHasher>::default().hash_one(&**value)
+}
+
+/// The fingerprint of the memoized value for each key of a fingerprinting function.
+///
+/// An entry is removed when the value of its memo is evicted. The entries of deleted
+/// tracked structs are only removed by [`Self::compact`].
+#[derive(Default)]
+pub(super) struct FingerprintTable {
+    map: Mutex<FxHashMap<Id, u64>>,
+}
+
+impl FingerprintTable {
+    /// Forgets the fingerprint of the value for `id`, which is no longer memoized.
+    pub(super) fn remove(&self, id: Id) {
+        self.map.lock().remove(&id);
+    }
+
+    /// Removes the keys for which `keep` returns false and shrinks the table to fit.
+    /// Returns the number of bytes reclaimed.
+    pub(super) fn compact(&self, keep: impl Fn(Id) -> bool) -> usize {
+        let mut map = self.map.lock();
+        let capacity = map.capacity();
+        map.retain(|&id, _| keep(id));
+        map.shrink_to_fit();
+        reclaimed::<(Id, u64)>(capacity, map.capacity())
+    }
+}
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Records the fingerprint of the value about to be memoized for `id`, if this
+    /// function fingerprints its values, and returns it along with the fingerprint
+    /// of the previous value, if any.
+    pub(super) fn record_fingerprint(
+        &self,
+        id: Id,
+        value: &C::Output<'_>,
+    ) -> Option<(u64, Option<u64>)> {
+        let fingerprint = C::fingerprint(value)?;
+        let old_fingerprint = self.fingerprints.map.lock().insert(id, fingerprint);
+        Some((fingerprint, old_fingerprint))
+    }
+
+    /// Like [`Self::backdate_if_appropriate`], but for functions that fingerprint their values:
+    /// the values are equal if their fingerprints are. In that case, returns the old value,
+    /// so that the new memo shares its allocation; otherwise returns `value`.
+    ///
+    /// `fingerprints` are as returned by [`Self::record_fingerprint`] for `value`.
+    /// Falls back to comparing the values if there is no fingerprint for the old one.
+    pub(super) fn backdate_by_fingerprint<'db>(
+        &self,
+        old_memo: &Memo<C::Output<'db>>,
+        fingerprints: Option<(u64, Option<u64>)>,
+        revisions: &mut QueryRevisions,
+        value: C::Output<'db>,
+    ) -> C::Output<'db> {
+        let (Some(old_value), Some((fingerprint, Some(old_fingerprint)))) =
+            (&old_memo.value, fingerprints)
+        else {
+            self.backdate_if_appropriate(old_memo, revisions, &value);
+            return value;
+        };
+
+        if revisions.durability >= old_memo.revisions.durability
+            && revisions.channels.is_subset(old_memo.revisions.channels)
+            && fingerprint == old_fingerprint
+        {
+            tracing::debug!(
+                "fingerprint is equal, back-dating to {:?}",
+                old_memo.revisions.changed_at,
+            );
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                changed_at = old_memo.revisions.changed_at.as_usize(),
+                fingerprint = true,
+                "backdated"
+            );
+
+            assert!(old_memo.revisions.changed_at <= revisions.changed_at);
+            revisions.changed_at = old_memo.revisions.changed_at;
+            if let Some(shared) = C::share_value(old_value) {
+                return shared;
+            }
+        }
+        value
+    }
+}
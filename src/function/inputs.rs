@@ -10,4 +10,40 @@ where
         self.get_memo_from_table_for(zalsa, key)
             .map(|m| m.revisions.origin.clone())
     }
+
+    /// The queries read by the last recorded execution of `key`, in the order they were read.
+    ///
+    /// The memo is *not* validated, so the result may describe an execution from an earlier
+    /// revision, and is empty if `key` was never executed or its memo was evicted.
+    /// Reads of whole tables (e.g. iterating a tracked struct's instances) are omitted.
+    #[cfg(feature = "dependency_inspection")]
+    pub fn last_dependencies(&self, db: &C::DbView, key: Id) -> Vec<crate::key::DatabaseKeyIndex> {
+        use crate::zalsa::ZalsaDatabase;
+
+        let Some(origin) = self.origin(db.zalsa(), key) else {
+            return vec![];
+        };
+        origin
+            .inputs()
+            .filter_map(|input| input.database_key_index())
+            .collect()
+    }
+}
+
+/// Expands to its input if salsa was built with the `dependency_inspection` feature,
+/// and to nothing otherwise. Used by the macro-generated code.
+#[cfg(feature = "dependency_inspection")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __if_dependency_inspection {
+    ($($t:tt)*) => {
+        $($t)*
+    };
+}
+
+#[cfg(not(feature = "dependency_inspection"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __if_dependency_inspection {
+    ($($t:tt)*) => {};
 }
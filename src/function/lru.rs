@@ -1,5 +1,7 @@
 use crate::{hash::FxLinkedHashSet, Id};
 
+use super::compact::reclaimed;
+
 use crossbeam::atomic::AtomicCell;
 use parking_lot::Mutex;
 
@@ -27,12 +29,32 @@ impl Lru {
         None
     }
 
-    pub(super) fn set_capacity(&self, capacity: usize) {
+    /// Removes all keys, returning them from least to most recently used.
+    pub(super) fn take(&self) -> FxLinkedHashSet<Id> {
+        std::mem::take(&mut *self.set.lock())
+    }
+
+    /// Removes the keys for which `keep` returns false, preserving the order of the others,
+    /// and shrinks the list to fit. Returns the number of bytes reclaimed.
+    pub(super) fn compact(&self, keep: impl Fn(Id) -> bool) -> usize {
+        let mut set = self.set.lock();
+        let capacity = set.capacity();
+        set.retain_with_order(|&id| keep(id));
+        set.shrink_to_fit();
+        reclaimed::<Id>(capacity, set.capacity())
+    }
+
+    /// Sets the capacity, returning the least recently used keys that no longer fit.
+    pub(super) fn set_capacity(&self, capacity: usize) -> Vec<Id> {
         self.capacity.store(capacity);
 
+        let mut set = self.set.lock();
         if capacity == 0 {
-            let mut set = self.set.lock();
             *set = FxLinkedHashSet::default();
+            return vec![];
         }
+
+        let excess = set.len().saturating_sub(capacity);
+        (0..excess).filter_map(|_| set.pop_front()).collect()
     }
 }
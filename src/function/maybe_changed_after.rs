@@ -4,7 +4,7 @@ use crate::{
     key::DatabaseKeyIndex,
     zalsa::{Zalsa, ZalsaDatabase},
     zalsa_local::{ActiveQueryGuard, QueryEdge, QueryOrigin},
-    AsDynDatabase as _, Id, Revision,
+    AsDynDatabase as _, Id, Revision, ValidationKind,
 };
 
 use super::{memo::Memo, Configuration, IngredientImpl};
@@ -63,7 +63,7 @@ where
             db.as_dyn_database(),
             zalsa_local,
             database_key_index,
-            self.memo_ingredient_index,
+            self.memo_ingredient_index(zalsa, key_index),
         )?;
         let active_query = zalsa_local.push_query(database_key_index);
 
@@ -91,7 +91,8 @@ where
         // It is possible the result will be equal to the old value and hence
         // backdated. In that case, although we will have computed a new memo,
         // the value has not logically changed.
-        if old_memo.value.is_some() {
+        // Functions with `not_key` arguments cannot be executed here, without a caller.
+        if old_memo.value.is_some() && !C::CONTEXTUAL {
             let memo = self.execute(db, active_query, Some(old_memo));
             let changed_at = memo.revisions.changed_at;
 
@@ -132,6 +133,12 @@ where
             return true;
         }
 
+        #[cfg(feature = "testing")]
+        if zalsa.runtime().is_from_scratch() {
+            // Every value is recomputed once per revision.
+            return false;
+        }
+
         if memo.check_durability(zalsa) {
             // No input of the suitable durability has changed since last verified.
             let db = db.as_dyn_database();
@@ -140,6 +147,8 @@ where
                 revision_now,
                 database_key_index,
                 memo.revisions.accumulated_inputs.load(),
+                ValidationKind::Shallow,
+                0,
             );
             memo.mark_outputs_as_verified(db, database_key_index);
             return true;
@@ -174,6 +183,15 @@ where
             return true;
         }
 
+        #[cfg(feature = "testing")]
+        if zalsa.runtime().is_from_scratch()
+            && !matches!(old_memo.revisions.origin, QueryOrigin::BaseInput)
+        {
+            // Every value is recomputed once per revision.
+            return false;
+        }
+
+        let mut edges_traversed = 0;
         let inputs = match &old_memo.revisions.origin {
             QueryOrigin::Assigned(_) => {
                 // If the value was assigneed by another query,
@@ -207,6 +225,7 @@ where
                 let last_verified_at = old_memo.verified_at.load();
                 let mut inputs = InputAccumulatedValues::Empty;
                 for &edge in edges.input_outputs.iter() {
+                    edges_traversed += 1;
                     match edge {
                         QueryEdge::Input(dependency_index) => {
                             match dependency_index
@@ -220,6 +239,20 @@ where
                                 }
                             }
                         }
+                        QueryEdge::InputRange(dependency_index, range) => {
+                            match dependency_index.maybe_changed_after_range(
+                                db.as_dyn_database(),
+                                range,
+                                last_verified_at,
+                            ) {
+                                MaybeChangedAfter::Yes => {
+                                    return false;
+                                }
+                                MaybeChangedAfter::No(input_accumulated) => {
+                                    inputs |= input_accumulated;
+                                }
+                            }
+                        }
                         QueryEdge::Output(dependency_index) => {
                             // Subtle: Mark outputs as validated now, even though we may
                             // later find an input that requires us to re-execute the function.
@@ -251,6 +284,8 @@ where
             zalsa.current_revision(),
             database_key_index,
             inputs,
+            ValidationKind::Deep,
+            edges_traversed,
         );
         true
     }
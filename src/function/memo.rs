@@ -6,10 +6,10 @@ use std::sync::Arc;
 use crossbeam::atomic::AtomicCell;
 
 use crate::accumulator::accumulated_map::InputAccumulatedValues;
-use crate::zalsa_local::QueryOrigin;
+use crate::zalsa_local::{QueryEdge, QueryOrigin};
 use crate::{
     key::DatabaseKeyIndex, zalsa::Zalsa, zalsa_local::QueryRevisions, Event, EventKind, Id,
-    Revision,
+    Revision, ValidationKind,
 };
 
 use super::{Configuration, IngredientImpl};
@@ -28,7 +28,7 @@ impl<C: Configuration> IngredientImpl<C> {
 
     /// Convert from an internal memo (which uses `'static``) to one tied to self
     /// so it can be publicly released.
-    unsafe fn to_self<'db>(&'db self, memo: ArcMemo<'static, C>) -> ArcMemo<'db, C> {
+    pub(super) unsafe fn to_self<'db>(&'db self, memo: ArcMemo<'static, C>) -> ArcMemo<'db, C> {
         unsafe { std::mem::transmute(memo) }
     }
 
@@ -42,7 +42,7 @@ impl<C: Configuration> IngredientImpl<C> {
         let static_memo = unsafe { self.to_static(memo) };
         let old_static_memo = zalsa
             .memo_table_for(id)
-            .insert(self.memo_ingredient_index, static_memo)?;
+            .insert(self.memo_ingredient_index(zalsa, id), static_memo)?;
         unsafe { Some(self.to_self(old_static_memo)) }
     }
 
@@ -54,16 +54,22 @@ impl<C: Configuration> IngredientImpl<C> {
         zalsa: &'db Zalsa,
         id: Id,
     ) -> Option<ArcMemo<'db, C>> {
-        let static_memo = zalsa.memo_table_for(id).get(self.memo_ingredient_index)?;
+        let static_memo = zalsa
+            .memo_table_for(id)
+            .get(self.memo_ingredient_index(zalsa, id))?;
         unsafe { Some(self.to_self(static_memo)) }
     }
 
     /// Evicts the existing memo for the given key, replacing it
     /// with an equivalent memo that has no value. If the memo is untracked, BaseInput,
     /// or has values assigned as output of another query, this has no effect.
+    /// Neither does it if the key is pinned.
     pub(super) fn evict_value_from_memo_for<'db>(&'db self, zalsa: &'db Zalsa, id: Id) {
+        if self.pinned_keys.contains(id) {
+            return;
+        }
         zalsa.memo_table_for(id).map_memo::<Memo<C::Output<'_>>>(
-            self.memo_ingredient_index,
+            self.memo_ingredient_index(zalsa, id),
             |memo| {
                 match memo.revisions.origin {
                     QueryOrigin::Assigned(_)
@@ -76,33 +82,42 @@ impl<C: Configuration> IngredientImpl<C> {
                         memo
                     }
                     QueryOrigin::Derived(_) => {
-                        // QueryRevisions: !Clone to discourage cloning, we need it here though
-                        let &QueryRevisions {
-                            changed_at,
-                            durability,
-                            ref origin,
-                            ref tracked_struct_ids,
-                            ref accumulated,
-                            ref accumulated_inputs,
-                        } = &memo.revisions;
-                        // Re-assemble the memo but with the value set to `None`
-                        Arc::new(Memo::new(
-                            None,
-                            memo.verified_at.load(),
-                            QueryRevisions {
-                                changed_at,
-                                durability,
-                                origin: origin.clone(),
-                                tracked_struct_ids: tracked_struct_ids.clone(),
-                                accumulated: accumulated.clone(),
-                                accumulated_inputs: AtomicCell::new(accumulated_inputs.load()),
-                            },
-                        ))
+                        #[cfg(feature = "metrics")]
+                        if memo.value.is_some() {
+                            self.counters.record_eviction();
+                        }
+                        self.dedup_table.remove(id);
+                        self.fingerprints.remove(id);
+                        Arc::new(memo.without_value())
                     }
                 }
             },
         );
     }
+
+    /// Like [`Self::evict_value_from_memo_for`], but the old memo is kept alive in the
+    /// deleted entries, as other handles to the database may still be reading its value.
+    pub(super) fn trim_value_from_memo_for<'db>(&'db self, zalsa: &'db Zalsa, id: Id) {
+        if self.pinned_keys.contains(id) {
+            return;
+        }
+        zalsa
+            .memo_table_for(id)
+            .map_memo::<Memo<C::Output<'static>>>(self.memo_ingredient_index(zalsa, id), |memo| {
+                match memo.revisions.origin {
+                    QueryOrigin::Derived(_) if memo.value.is_some() => {
+                        #[cfg(feature = "metrics")]
+                        self.counters.record_eviction();
+                        self.dedup_table.remove(id);
+                        self.fingerprints.remove(id);
+                        let trimmed = Arc::new(memo.without_value());
+                        self.deleted_entries.push(unsafe { self.to_self(memo) });
+                        trimmed
+                    }
+                    _ => memo,
+                }
+            });
+    }
 }
 
 #[derive(Debug)]
@@ -116,12 +131,17 @@ pub(super) struct Memo<V> {
 
     /// Revision information
     pub(super) revisions: QueryRevisions,
+
+    /// The time spent executing the query, if it was executed.
+    #[cfg(feature = "query_timing")]
+    pub(super) execution_time: Option<crate::ExecutionTime>,
 }
 
 // Memo's are stored a lot, make sure their size is doesn't randomly increase.
 // #[cfg(test)]
+#[cfg(not(feature = "query_timing"))]
 const _: [(); std::mem::size_of::<Memo<std::num::NonZeroUsize>>()] =
-    [(); std::mem::size_of::<[usize; 12]>()];
+    [(); std::mem::size_of::<[usize; 13]>()];
 
 impl<V> Memo<V> {
     pub(super) fn new(value: Option<V>, revision_now: Revision, revisions: QueryRevisions) -> Self {
@@ -129,9 +149,46 @@ impl<V> Memo<V> {
             value,
             verified_at: AtomicCell::new(revision_now),
             revisions,
+            #[cfg(feature = "query_timing")]
+            execution_time: None,
         }
     }
-    /// True if this memo is known not to have changed based on its durability.
+
+    /// Returns an equivalent memo that has no value, keeping only its dependencies.
+    pub(super) fn without_value(&self) -> Self {
+        // QueryRevisions: !Clone to discourage cloning, we need it here though
+        let &QueryRevisions {
+            changed_at,
+            durability,
+            channels,
+            ref origin,
+            ref tracked_struct_ids,
+            ref accumulated,
+            ref accumulated_inputs,
+        } = &self.revisions;
+        #[allow(unused_mut)]
+        let mut memo = Memo::new(
+            None,
+            self.verified_at.load(),
+            QueryRevisions {
+                changed_at,
+                durability,
+                channels,
+                origin: origin.clone(),
+                tracked_struct_ids: tracked_struct_ids.clone(),
+                accumulated: accumulated.clone(),
+                accumulated_inputs: AtomicCell::new(accumulated_inputs.load()),
+            },
+        );
+        #[cfg(feature = "query_timing")]
+        {
+            memo.execution_time = self.execution_time;
+        }
+        memo
+    }
+
+    /// True if this memo is known not to have changed based on its durability,
+    /// or on the channels of its inputs.
     pub(super) fn check_durability(&self, zalsa: &Zalsa) -> bool {
         let last_changed = zalsa.last_changed_revision(self.revisions.durability);
         let verified_at = self.verified_at.load();
@@ -142,6 +199,7 @@ impl<V> Memo<V> {
             last_changed <= verified_at,
         );
         last_changed <= verified_at
+            || zalsa.last_changed_revision_in(self.revisions.channels) <= verified_at
     }
 
     /// Mark memo as having been verified in the `revision_now`, which should
@@ -152,10 +210,14 @@ impl<V> Memo<V> {
         revision_now: Revision,
         database_key_index: DatabaseKeyIndex,
         accumulated: InputAccumulatedValues,
+        kind: ValidationKind,
+        edges_traversed: usize,
     ) {
-        db.salsa_event(&|| {
+        crate::event::emit(db, &|| {
             Event::new(EventKind::DidValidateMemoizedValue {
                 database_key: database_key_index,
+                kind,
+                edges_traversed,
             })
         });
 
@@ -203,4 +265,23 @@ impl<V: Debug + Send + Sync + Any> crate::table::memo::Memo for Memo<V> {
     fn origin(&self) -> &QueryOrigin {
         &self.revisions.origin
     }
+
+    fn memory_usage(&self) -> usize {
+        let edges = match &self.revisions.origin {
+            QueryOrigin::Derived(edges) | QueryOrigin::DerivedUntracked(edges) => {
+                edges.input_outputs.len()
+            }
+            QueryOrigin::Assigned(_) | QueryOrigin::BaseInput => 0,
+        };
+        std::mem::size_of::<Self>() + edges * std::mem::size_of::<QueryEdge>()
+    }
+
+    fn changed_at(&self) -> Revision {
+        self.revisions.changed_at
+    }
+
+    #[cfg(feature = "query_timing")]
+    fn execution_time(&self) -> Option<crate::ExecutionTime> {
+        self.execution_time
+    }
 }
@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+
+use parking_lot::Mutex;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{key::DatabaseKeyIndex, Database, Id};
+
+use super::{compact::reclaimed, Configuration, IngredientImpl};
+
+/// The keys whose memoized value must not be evicted by the LRU
+/// or dropped by [`Database::trim_memory`](`crate::Database::trim_memory`),
+/// with the number of subtrees pinning each.
+#[derive(Default)]
+pub(super) struct PinnedKeys {
+    keys: Mutex<FxHashMap<Id, usize>>,
+
+    /// For each key of this function pinned with `pin_subtree`, the queries it pinned.
+    subtrees: Mutex<FxHashMap<Id, Vec<DatabaseKeyIndex>>>,
+}
+
+impl PinnedKeys {
+    pub(super) fn insert(&self, id: Id) {
+        *self.keys.lock().entry(id).or_default() += 1;
+    }
+
+    pub(super) fn remove(&self, id: Id) {
+        let mut keys = self.keys.lock();
+        if let Some(count) = keys.get_mut(&id) {
+            *count -= 1;
+            if *count == 0 {
+                keys.remove(&id);
+            }
+        }
+    }
+
+    pub(super) fn contains(&self, id: Id) -> bool {
+        self.keys.lock().contains_key(&id)
+    }
+
+    /// Removes the keys for which `keep` returns false and shrinks the set to fit.
+    /// Returns the number of bytes reclaimed.
+    pub(super) fn compact(&self, keep: impl Fn(Id) -> bool) -> usize {
+        let mut keys = self.keys.lock();
+        let capacity = keys.capacity();
+        keys.retain(|&id, _| keep(id));
+        keys.shrink_to_fit();
+        reclaimed::<Id>(capacity, keys.capacity())
+    }
+}
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Pins the memo for `key` and those of the queries it (transitively) read in its
+    /// last recorded execution, up to `depth` edges away; see the generated `pin_subtree`.
+    /// Replaces the subtree previously pinned for `key`, if any.
+    pub fn pin_subtree(&self, db: &dyn Database, key: Id, depth: usize) {
+        let pinned = pin_subtree(db, self.database_key_index(key), depth);
+        let previous = self.pinned_keys.subtrees.lock().insert(key, pinned);
+        if let Some(previous) = previous {
+            unpin(db, previous);
+        }
+    }
+
+    /// Unpins the memos pinned by [`Self::pin_subtree`] for `key`, if any;
+    /// see the generated `unpin_subtree`.
+    pub fn unpin_subtree(&self, db: &dyn Database, key: Id) {
+        let pinned = self.pinned_keys.subtrees.lock().remove(&key);
+        if let Some(pinned) = pinned {
+            unpin(db, pinned);
+        }
+    }
+}
+
+/// Pins the subtree of `root`, returning the queries pinned.
+fn pin_subtree(db: &dyn Database, root: DatabaseKeyIndex, depth: usize) -> Vec<DatabaseKeyIndex> {
+    let zalsa = db.zalsa();
+    let mut visited = FxHashSet::default();
+    let mut pinned = vec![];
+
+    // Visit breadth-first, so that each query is reached by its shortest path from the root.
+    let mut queue = VecDeque::from([(root, 0)]);
+    while let Some((key, distance)) = queue.pop_front() {
+        if !visited.insert(key) {
+            continue;
+        }
+
+        let ingredient = zalsa.lookup_ingredient(key.ingredient_index);
+        ingredient.pin(db, key.key_index);
+        pinned.push(key);
+
+        if distance < depth {
+            if let Some(origin) = ingredient.origin(db, key.key_index) {
+                queue.extend(
+                    origin
+                        .inputs()
+                        .filter_map(|input| input.database_key_index())
+                        .map(|input| (input, distance + 1)),
+                );
+            }
+        }
+    }
+
+    pinned
+}
+
+fn unpin(db: &dyn Database, pinned: Vec<DatabaseKeyIndex>) {
+    let zalsa = db.zalsa();
+    for key in pinned {
+        zalsa
+            .lookup_ingredient(key.ingredient_index)
+            .unpin(db, key.key_index);
+    }
+}
@@ -1,11 +1,9 @@
-use crossbeam::atomic::AtomicCell;
-
 use crate::{
     accumulator::accumulated_map::InputAccumulatedValues,
     tracked_struct::TrackedStructInDb,
     zalsa::ZalsaDatabase,
     zalsa_local::{QueryOrigin, QueryRevisions},
-    AsDynDatabase as _, Database, DatabaseKeyIndex, Id,
+    AsDynDatabase as _, Database, DatabaseKeyIndex, Id, ValidationKind,
 };
 
 use super::{memo::Memo, Configuration, IngredientImpl};
@@ -20,12 +18,11 @@ where
     where
         C::Input<'db>: TrackedStructInDb,
     {
-        let (zalsa, zalsa_local) = db.zalsas();
+        let zalsa_local = db.zalsa_local();
 
-        let (active_query_key, current_deps) = match zalsa_local.active_query() {
-            Some(v) => v,
-            None => panic!("can only use `specify` inside a tracked function"),
-        };
+        if zalsa_local.active_query().is_none() {
+            panic!("can only use `specify` inside a tracked function");
+        }
 
         // `specify` only works if the key is a tracked struct created in the current query.
         //
@@ -45,6 +42,45 @@ where
             panic!("can only use `specify` on salsa structs created during the current tracked fn");
         }
 
+        self.assign_and_record(db, key, value, database_key_index);
+    }
+
+    /// Like [`Self::specify_and_record`], but for any key, e.g. an interned struct,
+    /// and without checking that the key was created by the current query.
+    ///
+    /// The caller must uphold what the check ensures for `specify`, or results may depend
+    /// on which query first reads the value for `key` in a revision: no query reads the value
+    /// for `key` in the current revision before this call (or all such reads would have
+    /// obtained `value` anyway). Not upholding it is not memory-unsafe, as the memos that
+    /// earlier reads borrowed from are kept alive until the next revision.
+    ///
+    /// In later revisions, the value stays valid as long as the current query is found to be
+    /// up to date, or re-executes and specifies it again. If the current query re-executes
+    /// without specifying it, the value becomes stale and the function body runs on the next read.
+    pub fn specify_unchecked_and_record<'db>(
+        &'db self,
+        db: &'db C::DbView,
+        key: Id,
+        value: C::Output<'db>,
+    ) {
+        if db.zalsa_local().active_query().is_none() {
+            panic!("can only use `specify_unchecked` inside a tracked function");
+        }
+        self.assign_and_record(db, key, value, self.database_key_index(key));
+    }
+
+    /// Stores `value` as the value for `key`, assigned by the active query,
+    /// and records it as an output of that query.
+    fn assign_and_record<'db>(
+        &'db self,
+        db: &'db C::DbView,
+        key: Id,
+        value: C::Output<'db>,
+        database_key_index: DatabaseKeyIndex,
+    ) {
+        let (zalsa, zalsa_local) = db.zalsas();
+        let (active_query_key, current_deps) = zalsa_local.active_query().unwrap();
+
         // Subtle: we treat the "input" to a set query as if it were
         // volatile.
         //
@@ -68,22 +104,22 @@ where
         let mut revisions = QueryRevisions {
             changed_at: current_deps.changed_at,
             durability: current_deps.durability,
+            channels: current_deps.channels,
             origin: QueryOrigin::Assigned(active_query_key),
             tracked_struct_ids: Default::default(),
             accumulated: Default::default(),
             accumulated_inputs: Default::default(),
         };
 
+        let fingerprints = self.record_fingerprint(key, &value);
+        let mut value = value;
         if let Some(old_memo) = self.get_memo_from_table_for(zalsa, key) {
-            self.backdate_if_appropriate(&old_memo, &mut revisions, &value);
+            value = self.backdate_by_fingerprint(&old_memo, fingerprints, &mut revisions, value);
             self.diff_outputs(db, database_key_index, &old_memo, &mut revisions);
         }
 
-        let memo = Memo {
-            value: Some(value),
-            verified_at: AtomicCell::new(revision),
-            revisions,
-        };
+        let value = self.dedupe(zalsa, key, value);
+        let memo = Memo::new(Some(value), revision, revisions);
 
         tracing::debug!(
             "specify: about to add memo {:#?} for key {:?}",
@@ -130,6 +166,8 @@ where
             zalsa.current_revision(),
             database_key_index,
             InputAccumulatedValues::Empty,
+            ValidationKind::Assigned,
+            0,
         );
     }
 }
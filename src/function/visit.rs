@@ -0,0 +1,129 @@
+use crate::{
+    key::DatabaseKeyIndex,
+    zalsa::{IngredientIndex, MemoIngredientIndex, Zalsa},
+    zalsa_local::QueryOrigin,
+    Durability, Id, Revision,
+};
+
+use super::{memo::Memo, Configuration, IngredientImpl, MemoIngredientIndices};
+
+/// A memo of a tracked function, as passed to the visitor of
+/// [`IngredientImpl::visit_memos`].
+pub struct MemoEntry<'a, V> {
+    key: Id,
+    memo: &'a Memo<V>,
+}
+
+impl<'a, V> MemoEntry<'a, V> {
+    /// The key of the memo: the id of the function's argument,
+    /// or of the interned tuple of its arguments if it takes several.
+    pub fn key(&self) -> Id {
+        self.key
+    }
+
+    /// The memoized value, in the form in which it is stored (e.g. encoded with the
+    /// `store_with` codec), or `None` if it was evicted (e.g. by the LRU).
+    pub fn value(&self) -> Option<&'a V> {
+        self.memo.value.as_ref()
+    }
+
+    /// The last revision in which the value was verified to be up to date.
+    pub fn verified_at(&self) -> Revision {
+        self.memo.verified_at.load()
+    }
+
+    /// The revision in which the value last changed.
+    pub fn changed_at(&self) -> Revision {
+        self.memo.revisions.changed_at
+    }
+
+    /// The minimum durability of the inputs read to compute the value.
+    pub fn durability(&self) -> Durability {
+        self.memo.revisions.durability
+    }
+
+    /// The queries read to compute the value, in the order they were read.
+    /// Reads of whole tables (e.g. iterating a tracked struct's instances) are omitted.
+    pub fn dependencies(&self) -> impl Iterator<Item = DatabaseKeyIndex> + 'a {
+        self.memo
+            .revisions
+            .origin
+            .inputs()
+            .filter_map(|input| input.database_key_index())
+    }
+
+    /// True if computing the value read state unknown to salsa, so that it cannot
+    /// be verified from its dependencies and is recomputed in every new revision.
+    pub fn has_untracked_reads(&self) -> bool {
+        matches!(self.memo.revisions.origin, QueryOrigin::DerivedUntracked(_))
+    }
+
+    /// The query that assigned the value with `specify`, if the function was not executed.
+    pub fn assigned_by(&self) -> Option<DatabaseKeyIndex> {
+        match self.memo.revisions.origin {
+            QueryOrigin::Assigned(key) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Calls `visitor` with each memo of this function, e.g. to persist them.
+    ///
+    /// Only the values of the structs this function takes are walked. The memos are
+    /// collected first and `visitor` is called without holding any lock, so it may read
+    /// from the database. Since `zalsa` is borrowed from a database handle, no new
+    /// revision can start while visiting; memos inserted concurrently by other handles
+    /// executing queries may or may not be visited. Memos of tracked structs that were
+    /// deleted are skipped.
+    pub fn visit_memos<'db>(
+        &'db self,
+        zalsa: &'db Zalsa,
+        mut visitor: impl FnMut(MemoEntry<'_, C::Output<'db>>),
+    ) {
+        let table = zalsa.table();
+        let current_revision = zalsa.current_revision();
+        let mut memos = vec![];
+        for (struct_index, memo_ingredient_index) in self.memo_ingredient_indices_by_struct() {
+            for page_index in table.pages_of(struct_index) {
+                for id in table.ids_in_use(page_index) {
+                    // SAFETY: We supply the current revision of the database owning the table.
+                    // Unlike `Table::memos`, this does not read-lock tracked structs.
+                    let memo = unsafe { table.memos_if_in_use(id, current_revision) }
+                        .and_then(|memos| memos.get(memo_ingredient_index));
+                    if let Some(memo) = memo {
+                        // SAFETY: The memo was stored by this ingredient.
+                        memos.push((id, unsafe { self.to_self(memo) }));
+                    }
+                }
+            }
+        }
+
+        for (key, memo) in memos {
+            visitor(MemoEntry { key, memo: &memo });
+        }
+    }
+
+    /// The structs whose ids are keys of this function, with the index of the memos
+    /// of this function in their memo tables.
+    fn memo_ingredient_indices_by_struct(&self) -> Vec<(IngredientIndex, MemoIngredientIndex)> {
+        match &self.memo_ingredient_indices {
+            MemoIngredientIndices::Single(struct_index, memo_ingredient_index) => {
+                vec![(*struct_index, *memo_ingredient_index)]
+            }
+            MemoIngredientIndices::PerStruct(indices) => indices
+                .iter()
+                .enumerate()
+                .filter_map(|(struct_index, memo_ingredient_index)| {
+                    Some((
+                        IngredientIndex::from(struct_index),
+                        (*memo_ingredient_index)?,
+                    ))
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use parking_lot::Mutex;
+use rustc_hash::FxHashSet;
+
+use crate::{zalsa::Zalsa, Id, MemoryPressure};
+
+use super::{compact::reclaimed, Configuration, IngredientImpl};
+
+/// The keys whose memo holds a value, for `#[salsa::tracked(weak)]` functions.
+/// Entries are added when a value is memoized and removed when the values are
+/// dropped by [`Database::trim_memory`](`crate::Database::trim_memory`).
+#[derive(Default)]
+pub(super) struct WeakKeys {
+    keys: Mutex<FxHashSet<Id>>,
+}
+
+impl WeakKeys {
+    pub(super) fn insert(&self, id: Id) {
+        self.keys.lock().insert(id);
+    }
+
+    fn take(&self) -> FxHashSet<Id> {
+        std::mem::take(&mut *self.keys.lock())
+    }
+
+    /// Removes the keys for which `keep` returns false and shrinks the set to fit.
+    /// Returns the number of bytes reclaimed.
+    pub(super) fn compact(&self, keep: impl Fn(Id) -> bool) -> usize {
+        let mut keys = self.keys.lock();
+        let capacity = keys.capacity();
+        keys.retain(|&id| keep(id));
+        keys.shrink_to_fit();
+        reclaimed::<Id>(capacity, keys.capacity())
+    }
+}
+
+impl<C> IngredientImpl<C>
+where
+    C: Configuration,
+{
+    /// Drops the memoized values of a `weak` function and, under [`MemoryPressure::High`],
+    /// those tracked by the LRU, keeping their dependencies so that they are recomputed
+    /// when next needed.
+    pub(super) fn trim_memory(&self, zalsa: &Zalsa, pressure: MemoryPressure) {
+        if C::WEAK {
+            for id in self.weak_keys.take() {
+                self.trim_value_from_memo_for(zalsa, id);
+            }
+        }
+        if pressure == MemoryPressure::High {
+            for id in self.lru.take() {
+                self.trim_value_from_memo_for(zalsa, id);
+            }
+        }
+    }
+}
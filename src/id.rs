@@ -47,7 +47,11 @@ impl Id {
 
 impl Debug for Id {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Id({:x})", self.as_u32())
+        if crate::checksum::ids_redacted() {
+            f.write_str("Id(_)")
+        } else {
+            write!(f, "Id({:x})", self.as_u32())
+        }
     }
 }
 
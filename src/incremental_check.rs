@@ -0,0 +1,55 @@
+use crate::Database;
+
+/// Checks that the query results observed by `test` do not depend on what was memoized
+/// in earlier revisions: a heavyweight check for tests, to catch bugs such as reads
+/// that salsa does not track. Requires the `testing` feature.
+///
+/// `test` is run twice, each time on a new database:
+///
+/// * first as usual, incrementally;
+/// * then recomputing every query that is fetched once per revision, as if the database
+///   had been created from scratch with the inputs of that revision.
+///
+/// Each time `test` fetches a query (outside of any query) on the database it is given,
+/// the [`Debug`](`std::fmt::Debug`) output of its value, with the database attached,
+/// is recorded. `test` must behave the same on both runs, e.g. not depend on timing.
+///
+/// # Panics
+///
+/// If `test` panics, or if the two runs fetched different queries or observed values
+/// with different debug output.
+pub fn check_incremental<Db>(test: impl Fn(&mut Db))
+where
+    Db: Database + Default,
+{
+    let incremental = run(false, &test);
+    let from_scratch = run(true, &test);
+
+    for (index, (incremental, from_scratch)) in incremental.iter().zip(&from_scratch).enumerate() {
+        assert_eq!(
+            incremental.0, from_scratch.0,
+            "fetch #{index} is of a different query when recomputing from scratch"
+        );
+        assert_eq!(
+            incremental.1, from_scratch.1,
+            "fetch #{index} of {:?} observed a different value than recomputing from scratch",
+            incremental.0
+        );
+    }
+    assert_eq!(
+        incremental.len(),
+        from_scratch.len(),
+        "the test fetched a different number of queries when recomputing from scratch"
+    );
+}
+
+fn run<Db>(from_scratch: bool, test: &impl Fn(&mut Db)) -> Vec<(crate::DatabaseKeyIndex, String)>
+where
+    Db: Database + Default,
+{
+    let mut db = Db::default();
+    db.zalsa().runtime().set_from_scratch(from_scratch);
+    db.zalsa_local().start_recording_fetches();
+    test(&mut db);
+    db.zalsa_local().take_recorded_fetches()
+}
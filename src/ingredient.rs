@@ -5,10 +5,13 @@ use std::{
 
 use crate::{
     accumulator::accumulated_map::{AccumulatedMap, InputAccumulatedValues},
+    channel::Channel,
     cycle::CycleRecoveryStrategy,
+    input::edit::EditRange,
+    runtime::Runtime,
     zalsa::{IngredientIndex, MemoIngredientIndex},
     zalsa_local::QueryOrigin,
-    Database, DatabaseKeyIndex, Id,
+    Database, DatabaseKeyIndex, Durability, Id, MemoryPressure,
 };
 
 use super::Revision;
@@ -45,16 +48,37 @@ pub trait JarAux {
     ///
     /// * `struct_ingredient_index`, the index of the salsa struct the memo will be attached to
     /// * `ingredient_index`, the index of the tracked function whose data is stored in the memo
+    /// * `debug_name`, the debug name of the tracked function, used to apply the
+    ///   [memo layout hint](`crate::StorageBuilder::memo_layout_hint`)
     fn next_memo_ingredient_index(
         &self,
         struct_ingredient_index: IngredientIndex,
         ingredient_index: IngredientIndex,
+        debug_name: &'static str,
     ) -> MemoIngredientIndex;
+
+    /// The number of shards to use for the maps of interned ingredients,
+    /// if set with [`StorageBuilder::interned_shards`](`crate::StorageBuilder::interned_shards`)
+    /// or derived from [`StorageBuilder::parallelism`](`crate::StorageBuilder::parallelism`).
+    fn interned_shards(&self) -> Option<usize>;
+
+    /// The number of shards to use for other concurrent maps,
+    /// if derived from [`StorageBuilder::parallelism`](`crate::StorageBuilder::parallelism`).
+    fn shards(&self) -> Option<usize>;
+
+    /// The LRU capacity of tracked functions that do not set one,
+    /// see [`StorageBuilder::default_lru`](`crate::StorageBuilder::default_lru`).
+    fn default_lru_capacity(&self) -> usize;
 }
 
 pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
     fn debug_name(&self) -> &'static str;
 
+    /// What kind of salsa item this ingredient implements.
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::Other
+    }
+
     /// Has the value for `input` in this ingredient changed after `revision`?
     fn maybe_changed_after<'db>(
         &'db self,
@@ -63,6 +87,89 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
         revision: Revision,
     ) -> MaybeChangedAfter;
 
+    /// Have the bytes `range` of the value for `input` changed after `revision`?
+    /// (For `#[elements]` fields of tracked structs, `range` holds an element index instead.)
+    ///
+    /// Only `#[delta]` input fields and `#[elements]` tracked struct fields
+    /// track changes at this granularity;
+    /// by default this is the same as [`Self::maybe_changed_after`].
+    fn maybe_changed_after_range<'db>(
+        &'db self,
+        db: &'db dyn Database,
+        input: Id,
+        range: EditRange,
+        revision: Revision,
+    ) -> MaybeChangedAfter {
+        _ = range;
+        self.maybe_changed_after(db, input, revision)
+    }
+
+    /// Has this ingredient's table as a whole changed after `revision`?
+    /// Queries that read from the table without depending on a specific key,
+    /// like interning a value, depend on the table as a whole.
+    ///
+    /// Only interned ingredients (see [`Self::invalidate`]), singleton
+    /// tracked structs, which can be created and deleted, and inputs created
+    /// by key, which can be looked up before they exist, change this way.
+    fn table_maybe_changed_after(&self, revision: Revision) -> MaybeChangedAfter {
+        _ = revision;
+        MaybeChangedAfter::No(InputAccumulatedValues::Empty)
+    }
+
+    /// A deterministic hash of the value at `key_index`, used by [`Database::checksum`].
+    ///
+    /// Returns `Some` for the memoized values of tracked functions and for the fields
+    /// of inputs and tracked structs, hashing their `Debug` output.
+    fn value_hash(&self, db: &dyn Database, key_index: Id) -> Option<u64> {
+        _ = (db, key_index);
+        None
+    }
+
+    /// The generation of the slot of `id`, a key of this ingredient.
+    /// Bumped whenever the slot is freed and reused for a different value,
+    /// so that stale [`DatabaseKeyIndex::to_bits`] keys can be detected.
+    ///
+    /// Returns `None` if `id` is not a key of this ingredient (by default,
+    /// if this ingredient did not allocate it) or if its slot is currently free.
+    fn id_generation(&self, db: &dyn Database, id: Id) -> Option<u16> {
+        (db.zalsa().table().owner(id) == Some(self.ingredient_index())).then_some(0)
+    }
+
+    /// The durability reported when the value at `key_index` is read,
+    /// see [`Database::max_durability_of`].
+    ///
+    /// Returns `None` if there is no such value, or if reads from this ingredient
+    /// are not recorded per key (e.g. for interned values).
+    fn durability(&self, db: &dyn Database, key_index: Id) -> Option<Durability> {
+        _ = (db, key_index);
+        None
+    }
+
+    /// Considers every value of this ingredient as changed in `revision`, so that the queries
+    /// that read them are re-validated; see [`Database::invalidate_ingredient`].
+    /// Returns the highest durability of those values, for the caller to report a write of.
+    ///
+    /// # Panics
+    ///
+    /// If this ingredient does not support invalidation, which currently only interned structs do.
+    fn invalidate(&mut self, revision: Revision) -> Durability {
+        _ = revision;
+        panic!("`{}` cannot be invalidated", self.debug_name())
+    }
+
+    /// Assigns the input `id` of this ingredient to `channel`; see [`Database::set_channel`].
+    ///
+    /// # Panics
+    ///
+    /// If this ingredient is not an input struct, the only kind of value with a channel.
+    fn set_channel(&mut self, runtime: &mut Runtime, id: Id, channel: Channel) {
+        _ = (runtime, id, channel);
+        panic!(
+            "`{}` is not an input, so it has no channel",
+            self.debug_name()
+        )
+    }
+
     /// What were the inputs (if any) that were used to create the value at `key_index`.
     fn origin(&self, db: &dyn Database, key_index: Id) -> Option<QueryOrigin>;
 
@@ -124,6 +231,46 @@ pub trait Ingredient: Any + std::fmt::Debug + Send + Sync {
     /// [`IngredientRequiresReset::RESET_ON_NEW_REVISION`] to true.
     fn reset_for_new_revision(&mut self);
 
+    /// The cache statistics of a tracked function, see [`crate::metrics::gather_metrics`].
+    #[cfg(feature = "metrics")]
+    fn cache_stats(&self) -> Option<crate::metrics::CacheStats> {
+        None
+    }
+
+    /// Drops memoized values that can be recomputed, as requested by
+    /// [`Database::trim_memory`](`crate::Database::trim_memory`).
+    fn trim_memory(&self, db: &dyn Database, pressure: MemoryPressure) {
+        _ = (db, pressure);
+    }
+
+    /// Prevents the memoized value for `key_index` from being evicted or trimmed,
+    /// see the `pin_subtree` function generated for tracked functions.
+    /// Only tracked functions memoize values, so other ingredients have nothing to pin.
+    fn pin(&self, db: &dyn Database, key_index: Id) {
+        _ = (db, key_index);
+    }
+
+    /// Undoes one call to [`Self::pin`] for `key_index`;
+    /// the value can be evicted again once every pin is undone.
+    fn unpin(&self, db: &dyn Database, key_index: Id) {
+        _ = (db, key_index);
+    }
+
+    /// Drops bookkeeping about values that are no longer memoized and shrinks the tables
+    /// of this ingredient to fit, returning the number of bytes reclaimed.
+    /// Invoked by [`Database::compact`](`crate::Database::compact`).
+    fn compact(&self, db: &dyn Database) -> usize {
+        _ = db;
+        0
+    }
+
+    /// Frees the memos that were replaced while other handles to the database could still read them,
+    /// returning the number of bytes freed. Invoked by [`Database::trim_memory`](`crate::Database::trim_memory`)
+    /// and [`Database::compact`](`crate::Database::compact`) if there are no such handles.
+    fn free_deleted_entries(&mut self) -> usize {
+        0
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result;
 }
 
@@ -187,6 +334,69 @@ pub enum MaybeChangedAfter {
     Yes,
 }
 
+/// The kind of salsa item an ingredient implements, see [`Database::ingredients`](`crate::Database::ingredients`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IngredientKind {
+    /// The values of a `#[salsa::input]` struct.
+    Input,
+
+    /// One field of a `#[salsa::input]` struct.
+    InputField,
+
+    /// The values of a `#[salsa::tracked]` struct.
+    TrackedStruct,
+
+    /// One tracked field of a `#[salsa::tracked]` struct.
+    TrackedField,
+
+    /// The values of a `#[salsa::interned]` struct, or the arguments
+    /// of a tracked function taking more than one.
+    Interned,
+
+    /// A tracked function.
+    TrackedFn,
+
+    /// An accumulator.
+    Accumulator,
+
+    /// The external resources that queries depend on, see
+    /// [`Database::report_external_dependency`](`crate::Database::report_external_dependency`).
+    External,
+
+    /// An ingredient implemented outside of salsa.
+    Other,
+}
+
+/// Describes an ingredient of a database, see [`Database::ingredients`](`crate::Database::ingredients`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IngredientInfo {
+    pub index: IngredientIndex,
+
+    /// The debug name of the ingredient, see [`Database::ingredient_debug_name`](`crate::Database::ingredient_debug_name`).
+    pub debug_name: &'static str,
+
+    pub kind: IngredientKind,
+
+    /// The number of values memoized by a tracked function; `0` for other ingredients.
+    pub memos: usize,
+}
+
+pub(crate) fn ingredients(db: &dyn Database) -> Vec<IngredientInfo> {
+    let zalsa = db.zalsa();
+    crate::memory_report::memory_report(db)
+        .ingredients
+        .into_iter()
+        .map(|usage| IngredientInfo {
+            index: usage.ingredient_index,
+            debug_name: usage.debug_name,
+            kind: zalsa.lookup_ingredient(usage.ingredient_index).kind(),
+            memos: usage.memos,
+        })
+        .collect()
+}
+
 impl From<bool> for MaybeChangedAfter {
     fn from(value: bool) -> Self {
         match value {
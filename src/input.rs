@@ -1,33 +1,45 @@
 use std::{
     any::{Any, TypeId},
+    cell::UnsafeCell,
     fmt,
+    hash::Hash,
     ops::DerefMut,
 };
 
+pub mod edit;
 pub mod input_field;
 pub mod setter;
 pub mod singleton;
 
+use crossbeam::atomic::AtomicCell;
+use edit::{EditLog, EditRange, Editable, TextEdit};
 use input_field::FieldIngredientImpl;
+use parking_lot::Mutex;
 
 use crate::{
     accumulator::accumulated_map::InputAccumulatedValues,
+    channel::{Channel, Channels},
     cycle::CycleRecoveryStrategy,
+    hash::FxDashMap,
     id::{AsId, FromId},
-    ingredient::{fmt_index, Ingredient, MaybeChangedAfter},
+    ingredient::{fmt_index, Ingredient, IngredientKind, MaybeChangedAfter},
     input::singleton::{Singleton, SingletonChoice},
     key::{DatabaseKeyIndex, InputDependencyIndex},
     plumbing::{Jar, JarAux, Stamp},
     table::{memo::MemoTable, sync::SyncTable, Slot, Table},
     zalsa::{IngredientIndex, Zalsa},
     zalsa_local::QueryOrigin,
-    Database, Durability, Id, Revision, Runtime,
+    ChangeCause, Database, Durability, Event, EventKind, Id, Revision, Runtime,
 };
 
 pub trait Configuration: Any {
     const DEBUG_NAME: &'static str;
     const FIELD_DEBUG_NAMES: &'static [&'static str];
 
+    /// True if inputs are created with [`IngredientImpl::get_or_create`], by their [`Self::Key`]:
+    /// for structs with a `#[key]` field, and for the values of `set_config`.
+    const HAS_KEY: bool;
+
     /// The singleton state for this input if any.
     type Singleton: SingletonChoice + Send + Sync;
 
@@ -39,6 +51,16 @@ pub trait Configuration: Any {
 
     /// A array of [`StampedValue<()>`](`StampedValue`) tuples, one per each of the value fields.
     type Stamps: Send + Sync + fmt::Debug + DerefMut<Target = [Stamp]>;
+
+    /// The type of the `#[key]` field, or `()` if there is none.
+    type Key: Hash + Eq + Send + Sync;
+
+    /// Returns (a clone of) the `#[key]` field of `fields`.
+    fn key(fields: &Self::Fields) -> Self::Key;
+
+    /// The field `field_index` of `fields`, to format it; used by `Database::checksum`.
+    /// `None` for the values of `set_config`, which need not be `Debug`.
+    fn field_debug(fields: &Self::Fields, field_index: usize) -> Option<&dyn fmt::Debug>;
 }
 
 pub struct JarImpl<C: Configuration> {
@@ -56,10 +78,10 @@ impl<C: Configuration> Default for JarImpl<C> {
 impl<C: Configuration> Jar for JarImpl<C> {
     fn create_ingredients(
         &self,
-        _aux: &dyn JarAux,
+        aux: &dyn JarAux,
         struct_index: crate::zalsa::IngredientIndex,
     ) -> Vec<Box<dyn Ingredient>> {
-        let struct_ingredient: IngredientImpl<C> = IngredientImpl::new(struct_index);
+        let struct_ingredient: IngredientImpl<C> = IngredientImpl::new(struct_index, aux);
 
         std::iter::once(Box::new(struct_ingredient) as _)
             .chain((0..C::FIELD_DEBUG_NAMES.len()).map(|field_index| {
@@ -73,17 +95,51 @@ impl<C: Configuration> Jar for JarImpl<C> {
     }
 }
 
+/// Computes the fields of an input from an external source, see [`IngredientImpl::set_provider`].
+type Provider<C> = dyn Fn(
+        <C as Configuration>::Struct,
+        &<C as Configuration>::Fields,
+    ) -> Option<(<C as Configuration>::Fields, Durability)>
+    + Send
+    + Sync;
+
 pub struct IngredientImpl<C: Configuration> {
     ingredient_index: IngredientIndex,
     singleton: C::Singleton,
+
+    /// Consulted on reads to refresh the fields of inputs whose values may be stale.
+    provider: Option<Box<Provider<C>>>,
+
+    /// Held while refreshing the fields of an input from the provider.
+    refresh_lock: Mutex<()>,
+
+    /// The inputs created with [`Self::get_or_create`], by key;
+    /// `None` if the struct has no `#[key]` field.
+    keys: Option<FxDashMap<C::Key, Id>>,
+
+    /// Held while creating an input in [`Self::get_or_create`].
+    create_lock: Mutex<()>,
+
+    /// The last revision in which an input was created with [`Self::get_or_create`], if any.
+    keys_changed_at: AtomicCell<Option<Revision>>,
+
     _phantom: std::marker::PhantomData<C::Struct>,
 }
 
 impl<C: Configuration> IngredientImpl<C> {
-    pub fn new(index: IngredientIndex) -> Self {
+    pub fn new(index: IngredientIndex, aux: &dyn JarAux) -> Self {
+        let keys = C::HAS_KEY.then(|| match aux.shards() {
+            Some(shards) => FxDashMap::with_hasher_and_shard_amount(Default::default(), shards),
+            None => Default::default(),
+        });
         Self {
             ingredient_index: index,
             singleton: Default::default(),
+            provider: None,
+            refresh_lock: Default::default(),
+            keys,
+            create_lock: Default::default(),
+            keys_changed_at: AtomicCell::new(None),
             _phantom: std::marker::PhantomData,
         }
     }
@@ -103,21 +159,184 @@ impl<C: Configuration> IngredientImpl<C> {
         }
     }
 
+    /// Registers `provider` to pull the fields of this ingredient's inputs from an external source,
+    /// replacing any previous provider.
+    ///
+    /// The provider is consulted on the first read of an input in each revision in which
+    /// its values may have changed, that is, after a write with at least the durability of
+    /// its fields (e.g. a [`synthetic_write`](`crate::Database::synthetic_write`)).
+    /// It is given the input and its current fields and returns either `None`, if they are
+    /// unchanged, or the new fields and their durability.
+    ///
+    /// Values assigned with setters are kept until the next revision.
+    /// The provider must not read from the database.
+    pub fn set_provider(
+        &mut self,
+        provider: impl Fn(C::Struct, &C::Fields) -> Option<(C::Fields, Durability)>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.provider = Some(Box::new(provider));
+    }
+
+    /// If a provider is registered and the fields of `id` may be stale,
+    /// pulls them from the provider.
+    fn refresh_provided(&self, zalsa: &Zalsa, id: Id) {
+        let Some(provider) = &self.provider else {
+            return;
+        };
+
+        let current_revision = zalsa.current_revision();
+        let value = Self::data(zalsa, id);
+        if value.provided_at.load() == current_revision {
+            return;
+        }
+
+        let _guard = self.refresh_lock.lock();
+        if value.provided_at.load() == current_revision {
+            return;
+        }
+
+        // SAFETY: The state is only mutated while `provided_at` is not the current revision,
+        // which means that no references to it from this revision exist (all reads refresh
+        // first), and while holding `refresh_lock`, so no other thread is refreshing it.
+        // The rest of `value` may be borrowed, which is why the state is in an `UnsafeCell`.
+        let state = unsafe { &mut *value.state.get() };
+        let durability = state.stamps.iter().map(|stamp| stamp.durability).min();
+        let may_be_stale =
+            durability.is_some_and(|d| zalsa.last_changed_revision(d) > value.provided_at.load());
+        if may_be_stale {
+            if let Some((fields, durability)) = provider(FromId::from_id(id), &state.fields) {
+                tracing::debug!("{}({id:?}): refreshed from provider", C::DEBUG_NAME);
+                state.fields = fields;
+                for stamp in state.stamps.iter_mut() {
+                    stamp.durability = durability;
+                    stamp.changed_at = current_revision;
+                }
+                for log in &mut state.edits {
+                    log.replace(current_revision);
+                }
+            }
+        }
+        value.provided_at.store(current_revision);
+    }
+
     pub fn new_input(&self, db: &dyn Database, fields: C::Fields, stamps: C::Stamps) -> C::Struct {
         let (zalsa, zalsa_local) = db.zalsas();
+        let current_revision = zalsa.current_revision();
 
         let id = self.singleton.with_lock(|| {
-            zalsa_local.allocate(zalsa.table(), self.ingredient_index, |_| Value::<C> {
-                fields,
-                stamps,
-                memos: Default::default(),
-                syncs: Default::default(),
+            zalsa_local.allocate(zalsa.table(), self.ingredient_index, |_| {
+                Value::<C>::new(fields, stamps, current_revision)
             })
         });
 
         FromId::from_id(id)
     }
 
+    /// Returns the input created by [`Self::get_or_create`] with the same `#[key]` field
+    /// as `fields`, or creates it from `fields` and `stamps` if there is none.
+    ///
+    /// Concurrent calls with equal keys create a single input and all return it.
+    /// Inputs are found by the key they were created with,
+    /// even if their key field was set to a different value since.
+    pub fn get_or_create(
+        &self,
+        db: &dyn Database,
+        fields: C::Fields,
+        stamps: C::Stamps,
+    ) -> C::Struct {
+        let keys = self.keys();
+        let key = C::key(&fields);
+        if let Some(id) = keys.get(&key) {
+            return FromId::from_id(*id);
+        }
+
+        // Create the input without holding the lock of the shard of `keys`,
+        // checking again that no other thread created it in the meantime.
+        let _guard = self.create_lock.lock();
+        if let Some(id) = keys.get(&key) {
+            return FromId::from_id(*id);
+        }
+        let input = self.new_input(db, fields, stamps);
+        self.keys_changed_at
+            .store(Some(db.zalsa().current_revision()));
+        keys.insert(key, input.as_id());
+        input
+    }
+
+    /// Returns the input created by [`Self::get_or_create`] with the `#[key]` field `key`, if any.
+    ///
+    /// If there is none, the active query depends on the inputs created by `get_or_create`,
+    /// so that it is executed again once one is.
+    pub fn get_by_key(&self, db: &dyn Database, key: &C::Key) -> Option<C::Struct> {
+        if let Some(id) = self.keys().get(key) {
+            return Some(FromId::from_id(*id));
+        }
+        db.zalsa_local().report_tracked_read(
+            InputDependencyIndex::for_table(self.ingredient_index),
+            Durability::LOW,
+            Channels::ALL,
+            self.keys_changed_at.load().unwrap_or_else(Revision::start),
+            InputAccumulatedValues::Empty,
+        );
+        None
+    }
+
+    fn keys(&self) -> &FxDashMap<C::Key, Id> {
+        self.keys
+            .as_ref()
+            .expect("only structs with a `#[key]` field are created with `get_or_create`")
+    }
+
+    /// Creates an input for each of `values`, like [`Self::new_input`] but in bulk:
+    /// the table pages are reserved up-front and a single
+    /// [`DidCreateInputs`](`crate::EventKind::DidCreateInputs`) event is emitted.
+    /// Returns the new inputs in order.
+    pub fn new_inputs(
+        &self,
+        db: &dyn Database,
+        values: impl IntoIterator<Item = (C::Fields, C::Stamps)>,
+    ) -> Vec<C::Struct> {
+        let (zalsa, zalsa_local) = db.zalsas();
+        let current_revision = zalsa.current_revision();
+
+        let values: Vec<_> = values.into_iter().collect();
+        let ids = zalsa_local.allocate_many(
+            zalsa.table(),
+            self.ingredient_index,
+            values
+                .into_iter()
+                .map(|(fields, stamps)| move |_| Value::<C>::new(fields, stamps, current_revision)),
+        );
+
+        crate::event::emit(db, &|| {
+            Event::new(EventKind::DidCreateInputs {
+                ingredient_index: self.ingredient_index,
+                count: ids.len(),
+            })
+        });
+
+        ids.into_iter().map(FromId::from_id).collect()
+    }
+
+    /// Emits a [`WillChangeInputs`](`crate::EventKind::WillChangeInputs`) event for the
+    /// field `field_index` of `id`. Called by the generated setters before they start
+    /// a new revision, as [`Self::set_field`] has no access to the database.
+    pub fn will_set_field(&self, db: &dyn Database, id: C::Struct, field_index: usize) {
+        crate::event::emit(db, &|| {
+            Event::new(EventKind::WillChangeInputs {
+                cause: ChangeCause::SetField {
+                    field: DatabaseKeyIndex {
+                        ingredient_index: self.ingredient_index.successor(field_index),
+                        key_index: id.as_id(),
+                    },
+                },
+            })
+        });
+    }
+
     /// Change the value of the field `field_index` to a new value.
     ///
     /// # Parameters
@@ -135,22 +354,143 @@ impl<C: Configuration> IngredientImpl<C> {
         durability: Option<Durability>,
         setter: impl FnOnce(&mut C::Fields) -> R,
     ) -> R {
+        // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+        let result = unsafe { self.set_field_shared(runtime, id, field_index, durability, setter) };
+        runtime.deliver_changes();
+        result
+    }
+
+    /// Like [`Self::set_field`], but only requires shared references.
+    /// Used by the setters of a [`WriteScope`](`crate::WriteScope`).
+    ///
+    /// # Safety
+    ///
+    /// No query may be running and no other thread may be accessing the fields of
+    /// this ingredient: the caller either holds `&mut` on the runtime or the
+    /// write scope's lock for this ingredient.
+    pub(crate) unsafe fn set_field_shared<R>(
+        &self,
+        runtime: &Runtime,
+        id: C::Struct,
+        field_index: usize,
+        durability: Option<Durability>,
+        setter: impl FnOnce(&mut C::Fields) -> R,
+    ) -> R {
+        let id: Id = id.as_id();
+        let r = Self::data_raw(runtime.table(), id);
+
+        // SAFETY: Guaranteed by the caller.
+        // Also, we don't access any other data from the table while `r` is active.
+        let r = unsafe { &mut *r };
+        let state = r.state.get_mut();
+
+        let stamp = &mut state.stamps[field_index];
+
+        runtime.report_tracked_write_in(stamp.durability, stamp.channels);
+
+        stamp.durability = durability.unwrap_or(stamp.durability);
+        stamp.changed_at = runtime.current_revision();
+        r.provided_at.store(stamp.changed_at);
+
+        if let Some(log) = state.edits.get_mut(field_index) {
+            log.replace(stamp.changed_at);
+        }
+
+        runtime.changes().record(DatabaseKeyIndex {
+            ingredient_index: self.ingredient_index.successor(field_index),
+            key_index: id,
+        });
+
+        setter(&mut state.fields)
+    }
+
+    /// Apply `edit` to the `#[delta]` field `field_index`, preserving its durability.
+    ///
+    /// Unlike [`Self::set_field`], this records which bytes were changed,
+    /// so that reads of unaffected ranges (see [`Self::field_range`]) are not invalidated.
+    ///
+    /// # Parameters
+    ///
+    /// * `runtime`, the salsa runtime
+    /// * `id`, id of the input struct
+    /// * `field_index`, index of the field that will be edited
+    /// * `edit`, the edit to apply
+    /// * `field`, function that selects the element for `field_index` from the fields tuple
+    pub fn edit_field<F: Editable>(
+        &mut self,
+        runtime: &mut Runtime,
+        id: C::Struct,
+        field_index: usize,
+        edit: TextEdit,
+        field: impl FnOnce(&mut C::Fields) -> &mut F,
+    ) {
         let id: Id = id.as_id();
         let r = Self::data_raw(runtime.table(), id);
 
         // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
         // Also, we don't access any other data from the table while `r` is active.
         let r = unsafe { &mut *r };
+        let state = r.state.get_mut();
 
-        let stamp = &mut r.stamps[field_index];
+        let stamp = &state.stamps[field_index];
+        runtime.report_tracked_write_in(stamp.durability, stamp.channels);
 
-        if stamp.durability != Durability::MIN {
-            runtime.report_tracked_write(stamp.durability);
+        if state.edits.is_empty() {
+            state.edits = state
+                .stamps
+                .iter()
+                .map(|s| EditLog::new(s.changed_at))
+                .collect();
         }
 
-        stamp.durability = durability.unwrap_or(stamp.durability);
+        let stamp = &mut state.stamps[field_index];
         stamp.changed_at = runtime.current_revision();
-        setter(&mut r.fields)
+        state.edits[field_index].record(stamp.changed_at, &edit);
+        r.provided_at.store(stamp.changed_at);
+
+        field(&mut state.fields).apply_edit(&edit);
+
+        runtime.changes().record(DatabaseKeyIndex {
+            ingredient_index: self.ingredient_index.successor(field_index),
+            key_index: id,
+        });
+        runtime.deliver_changes();
+    }
+
+    /// Assigns the input `id` to `channel`; see [`Database::set_channel`].
+    ///
+    /// The queries that read the input recorded its former channel, so its fields are
+    /// considered changed: those queries are re-executed when next called.
+    pub fn set_channel(&mut self, runtime: &mut Runtime, id: Id, channel: Channel) {
+        let r = Self::data_raw(runtime.table(), id);
+
+        // SAFETY: We hold `&mut` on the runtime so no `&`-references can be active.
+        // Also, we don't access any other data from the table while `r` is active.
+        let state = unsafe { &mut *r }.state.get_mut();
+
+        let channels = Channels::of(channel);
+        let current_revision = runtime.current_revision();
+        for stamp in state.stamps.iter_mut() {
+            if stamp.channels != channels {
+                runtime.report_tracked_write_in(stamp.durability, stamp.channels);
+                stamp.channels = channels;
+                stamp.changed_at = current_revision;
+            }
+        }
+        for log in &mut state.edits {
+            log.replace(current_revision);
+        }
+    }
+
+    /// The channels reported when a field with the given stamp is read.
+    /// Fields pulled from a provider may change whenever an input of their durability
+    /// is written, whatever its channel.
+    fn read_channels(&self, stamp: &Stamp) -> Channels {
+        if self.provider.is_some() {
+            Channels::ALL
+        } else {
+            stamp.channels
+        }
     }
 
     /// Get the singleton input previously created.
@@ -173,24 +513,54 @@ impl<C: Configuration> IngredientImpl<C> {
         let (zalsa, zalsa_local) = db.zalsas();
         let field_ingredient_index = self.ingredient_index.successor(field_index);
         let id = id.as_id();
-        let value = Self::data(zalsa, id);
+        self.refresh_provided(zalsa, id);
+        let value = Self::data(zalsa, id).state();
         let stamp = &value.stamps[field_index];
         zalsa_local.report_tracked_read(
             InputDependencyIndex::new(field_ingredient_index, id),
             stamp.durability,
+            self.read_channels(stamp),
             stamp.changed_at,
             InputAccumulatedValues::Empty,
         );
         &value.fields
     }
 
+    /// Access the subrange `range` of the `#[delta]` field `field_index`.
+    ///
+    /// The active query only depends on the bytes in `range`: edits applied with
+    /// [`Self::edit_field`] elsewhere in the value do not invalidate it.
+    /// The caller is responible for selecting the appropriate element.
+    pub fn field_range<'db>(
+        &'db self,
+        db: &'db dyn crate::Database,
+        id: C::Struct,
+        field_index: usize,
+        range: std::ops::Range<usize>,
+    ) -> &'db C::Fields {
+        let (zalsa, zalsa_local) = db.zalsas();
+        let field_ingredient_index = self.ingredient_index.successor(field_index);
+        let id = id.as_id();
+        self.refresh_provided(zalsa, id);
+        let value = Self::data(zalsa, id).state();
+        let stamp = &value.stamps[field_index];
+        zalsa_local.report_tracked_range_read(
+            InputDependencyIndex::new(field_ingredient_index, id),
+            EditRange::from(range),
+            stamp.durability,
+            self.read_channels(stamp),
+            stamp.changed_at,
+        );
+        &value.fields
+    }
+
     /// Peek at the field values without recording any read dependency.
     /// Used for debug printouts.
     pub fn leak_fields<'db>(&'db self, db: &'db dyn Database, id: C::Struct) -> &'db C::Fields {
         let zalsa = db.zalsa();
         let id = id.as_id();
-        let value = Self::data(zalsa, id);
-        &value.fields
+        self.refresh_provided(zalsa, id);
+        &Self::data(zalsa, id).state().fields
     }
 }
 
@@ -210,6 +580,16 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
         MaybeChangedAfter::No(InputAccumulatedValues::Empty)
     }
 
+    fn table_maybe_changed_after(&self, revision: Revision) -> MaybeChangedAfter {
+        // An input may have been created in `revision` after the query missed it,
+        // as inputs are created without starting a new revision.
+        MaybeChangedAfter::from(
+            self.keys_changed_at
+                .load()
+                .is_some_and(|changed_at| revision <= changed_at),
+        )
+    }
+
     fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
         CycleRecoveryStrategy::Panic
     }
@@ -250,6 +630,15 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
         panic!("unexpected call to `reset_for_new_revision`")
     }
 
+    fn compact(&self, _db: &dyn Database) -> usize {
+        let Some(keys) = &self.keys else {
+            return 0;
+        };
+        let capacity = keys.capacity();
+        keys.shrink_to_fit();
+        (capacity - keys.capacity()) * std::mem::size_of::<(C::Key, Id)>()
+    }
+
     fn fmt_index(&self, index: Option<Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(C::DEBUG_NAME, index, fmt)
     }
@@ -257,6 +646,14 @@ impl<C: Configuration> Ingredient for IngredientImpl<C> {
     fn debug_name(&self) -> &'static str {
         C::DEBUG_NAME
     }
+
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::Input
+    }
+
+    fn set_channel(&mut self, runtime: &mut Runtime, id: Id, channel: Channel) {
+        self.set_channel(runtime, id, channel);
+    }
 }
 
 impl<C: Configuration> std::fmt::Debug for IngredientImpl<C> {
@@ -269,6 +666,31 @@ impl<C: Configuration> std::fmt::Debug for IngredientImpl<C> {
 
 #[derive(Debug)]
 pub struct Value<C>
+where
+    C: Configuration,
+{
+    /// The fields of this input struct, along with their stamps.
+    ///
+    /// Within a revision, they only change when they are first refreshed from the provider
+    /// (see [`IngredientImpl::set_provider`]), through a shared reference to the value.
+    state: UnsafeCell<State<C>>,
+
+    /// Memos
+    memos: MemoTable,
+
+    /// Syncs
+    syncs: SyncTable,
+
+    /// The revision in which the fields were last refreshed from the provider
+    /// (see [`IngredientImpl::set_provider`]) or set.
+    provided_at: AtomicCell<Revision>,
+}
+
+// SAFETY: The `state` is only mutated through a shared reference by `refresh_provided`,
+// which ensures that no other thread accesses it meanwhile.
+unsafe impl<C> Sync for Value<C> where C: Configuration {}
+
+struct State<C>
 where
     C: Configuration,
 {
@@ -281,24 +703,43 @@ where
     /// The revision and durability information for each field: when did this field last change.
     stamps: C::Stamps,
 
-    /// Memos
-    memos: MemoTable,
-
-    /// Syncs
-    syncs: SyncTable,
+    /// Edit logs, one per field. Empty until the first call to
+    /// [`IngredientImpl::edit_field`].
+    edits: Vec<EditLog>,
 }
 
 impl<C> Value<C>
 where
     C: Configuration,
 {
+    fn new(fields: C::Fields, stamps: C::Stamps, current_revision: Revision) -> Self {
+        Self {
+            state: UnsafeCell::new(State {
+                fields,
+                stamps,
+                edits: Default::default(),
+            }),
+            memos: Default::default(),
+            syncs: Default::default(),
+            provided_at: AtomicCell::new(current_revision),
+        }
+    }
+
+    /// The fields and stamps of this input. Callers must have refreshed them from the
+    /// provider in the current revision (see [`IngredientImpl::refresh_provided`]),
+    /// or hold `&mut` on the database.
+    fn state(&self) -> &State<C> {
+        // SAFETY: After refreshing, the state does not change within the revision.
+        unsafe { &*self.state.get() }
+    }
+
     /// Fields of this tracked struct.
     ///
     /// They can change across revisions, but they do not change within
     /// a particular revision.
     #[cfg(feature = "salsa_unstable")]
     pub fn fields(&self) -> &C::Fields {
-        &self.fields
+        &self.state().fields
     }
 }
 
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use crate::Revision;
+
+/// A structured change to an input field: the bytes in `range`
+/// are replaced with `replacement`.
+///
+/// Edits are applied with the `set_<field>_edit` setter generated for
+/// fields annotated with `#[delta]`. Unlike a whole-value `set_<field>`,
+/// an edit lets salsa know *which* part of the value changed, so that
+/// queries that only read an unaffected subrange (via `<field>_range`)
+/// are not invalidated.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, replacement: impl Into<String>) -> Self {
+        Self {
+            range,
+            replacement: replacement.into(),
+        }
+    }
+}
+
+/// Types that can be used as `#[delta]` input fields.
+pub trait Editable: Send + Sync {
+    /// The type of a subrange of the value (e.g., `str` for `String`).
+    type Slice: ?Sized;
+
+    /// Apply `edit` to `self`.
+    fn apply_edit(&mut self, edit: &TextEdit);
+
+    /// Return the subrange `range` of `self`.
+    fn slice(&self, range: Range<usize>) -> &Self::Slice;
+}
+
+impl Editable for String {
+    type Slice = str;
+
+    fn apply_edit(&mut self, edit: &TextEdit) {
+        self.replace_range(edit.range.clone(), &edit.replacement);
+    }
+
+    fn slice(&self, range: Range<usize>) -> &str {
+        &self[range]
+    }
+}
+
+/// A byte range recorded in a dependency edge
+/// (or, for `#[elements]` fields of tracked structs, a range of element indices).
+///
+/// Stored as a pair of `u32` to keep [`QueryEdge`](`crate::zalsa_local::QueryEdge`) small;
+/// offsets that do not fit saturate, which can only make the range *larger*
+/// (and hence the dependency more conservative).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EditRange {
+    start: u32,
+    end: u32,
+}
+
+impl EditRange {
+    pub(crate) fn start(self) -> usize {
+        self.start as usize
+    }
+}
+
+impl From<Range<usize>> for EditRange {
+    fn from(range: Range<usize>) -> Self {
+        Self {
+            start: u32::try_from(range.start).unwrap_or(0),
+            end: u32::try_from(range.end).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+/// Maximum number of edits remembered per field. Older edits are folded into
+/// the log's `floor`, which conservatively invalidates all ranges.
+const MAX_RECORDED_EDITS: usize = 64;
+
+/// The recent edits applied to a single `#[delta]` field.
+#[derive(Debug)]
+pub(crate) struct EditLog {
+    /// Any change at or before this revision is not described by `records`
+    /// and must be assumed to affect every range.
+    floor: Revision,
+
+    /// Edits applied after `floor`, in the order they were applied.
+    records: VecDeque<EditRecord>,
+}
+
+#[derive(Debug)]
+struct EditRecord {
+    revision: Revision,
+
+    /// The replaced byte range (in the coordinates in effect before the edit).
+    start: usize,
+    end: usize,
+
+    /// True if the replacement has a different length than the replaced range,
+    /// so that everything after the edit moved.
+    shifts: bool,
+}
+
+impl EditRecord {
+    /// Could this edit have changed the contents or position of `range`?
+    fn affects(&self, range: EditRange) -> bool {
+        let (range_start, range_end) = (range.start as usize, range.end as usize);
+        if self.start >= range_end {
+            // Entirely after the range.
+            false
+        } else if self.end <= range_start {
+            // Entirely before the range: only matters if it moved it.
+            self.shifts
+        } else {
+            true
+        }
+    }
+}
+
+impl EditLog {
+    pub(crate) fn new(floor: Revision) -> Self {
+        Self {
+            floor,
+            records: VecDeque::new(),
+        }
+    }
+
+    /// Record that `edit` was applied in `revision`.
+    pub(crate) fn record(&mut self, revision: Revision, edit: &TextEdit) {
+        if self.records.len() == MAX_RECORDED_EDITS {
+            let oldest = self.records.pop_front().unwrap();
+            self.floor = self.floor.max(oldest.revision);
+        }
+        self.records.push_back(EditRecord {
+            revision,
+            start: edit.range.start,
+            end: edit.range.end,
+            shifts: edit.replacement.len() != edit.range.len(),
+        });
+    }
+
+    /// Record that the whole value was replaced in `revision`.
+    pub(crate) fn replace(&mut self, revision: Revision) {
+        self.records.clear();
+        self.floor = revision;
+    }
+
+    /// True if the bytes in `range` may have changed after `revision`.
+    ///
+    /// `range` is expressed in the coordinates in effect at `revision`.
+    /// Edits after the end of `range` cannot affect it.
+    /// Edits that precede `range` and change the length of the value move it,
+    /// so they are considered changes as well;
+    /// this keeps the recorded range meaningful without having to rebase it.
+    pub(crate) fn changed_after(&self, range: EditRange, revision: Revision) -> bool {
+        self.floor > revision
+            || self
+                .records
+                .iter()
+                .any(|record| record.revision > revision && record.affects(range))
+    }
+}
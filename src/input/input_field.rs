@@ -1,9 +1,10 @@
 use crate::cycle::CycleRecoveryStrategy;
-use crate::ingredient::{fmt_index, Ingredient, MaybeChangedAfter};
+use crate::ingredient::{fmt_index, Ingredient, IngredientKind, MaybeChangedAfter};
+use crate::input::edit::EditRange;
 use crate::input::Configuration;
-use crate::zalsa::IngredientIndex;
+use crate::zalsa::{IngredientIndex, Zalsa};
 use crate::zalsa_local::QueryOrigin;
-use crate::{Database, DatabaseKeyIndex, Id, Revision};
+use crate::{Database, DatabaseKeyIndex, Durability, Id, Revision};
 use std::fmt;
 use std::marker::PhantomData;
 
@@ -19,6 +20,7 @@ use super::{IngredientImpl, Value};
 /// Altogether this makes the implementation somewhat simpler than tracked
 /// structs.
 pub struct FieldIngredientImpl<C: Configuration> {
+    struct_index: IngredientIndex,
     index: IngredientIndex,
     field_index: usize,
     phantom: PhantomData<fn() -> Value<C>>,
@@ -30,11 +32,20 @@ where
 {
     pub(super) fn new(struct_index: IngredientIndex, field_index: usize) -> Self {
         Self {
+            struct_index,
             index: struct_index.successor(field_index),
             field_index,
             phantom: PhantomData,
         }
     }
+
+    /// See [`IngredientImpl::set_provider`].
+    fn refresh_provided(&self, zalsa: &Zalsa, input: Id) {
+        zalsa
+            .lookup_ingredient(self.struct_index)
+            .assert_type::<IngredientImpl<C>>()
+            .refresh_provided(zalsa, input);
+    }
 }
 
 impl<C> Ingredient for FieldIngredientImpl<C>
@@ -56,11 +67,49 @@ where
         revision: Revision,
     ) -> MaybeChangedAfter {
         let zalsa = db.zalsa();
-        let value = <IngredientImpl<C>>::data(zalsa, input);
+        self.refresh_provided(zalsa, input);
+        let value = <IngredientImpl<C>>::data(zalsa, input).state();
 
         MaybeChangedAfter::from(value.stamps[self.field_index].changed_at > revision)
     }
 
+    fn maybe_changed_after_range(
+        &self,
+        db: &dyn Database,
+        input: Id,
+        range: EditRange,
+        revision: Revision,
+    ) -> MaybeChangedAfter {
+        let zalsa = db.zalsa();
+        self.refresh_provided(zalsa, input);
+        let value = <IngredientImpl<C>>::data(zalsa, input).state();
+
+        match value.edits.get(self.field_index) {
+            Some(log) => MaybeChangedAfter::from(log.changed_after(range, revision)),
+            None => MaybeChangedAfter::from(value.stamps[self.field_index].changed_at > revision),
+        }
+    }
+
+    fn durability(&self, db: &dyn Database, key_index: Id) -> Option<Durability> {
+        let zalsa = db.zalsa();
+        self.refresh_provided(zalsa, key_index);
+        let value = <IngredientImpl<C>>::data(zalsa, key_index).state();
+        Some(value.stamps[self.field_index].durability)
+    }
+
+    fn id_generation(&self, db: &dyn Database, id: Id) -> Option<u16> {
+        db.zalsa()
+            .lookup_ingredient(self.struct_index)
+            .id_generation(db, id)
+    }
+
+    fn value_hash(&self, db: &dyn Database, key_index: Id) -> Option<u64> {
+        let zalsa = db.zalsa();
+        self.refresh_provided(zalsa, key_index);
+        let value = <IngredientImpl<C>>::data(zalsa, key_index).state();
+        C::field_debug(&value.fields, self.field_index).map(crate::checksum::debug_hash)
+    }
+
     fn origin(&self, _db: &dyn Database, _key_index: Id) -> Option<QueryOrigin> {
         None
     }
@@ -96,6 +145,10 @@ where
     fn debug_name(&self) -> &'static str {
         C::FIELD_DEBUG_NAMES[self.field_index]
     }
+
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::InputField
+    }
 }
 
 impl<C> std::fmt::Debug for FieldIngredientImpl<C>
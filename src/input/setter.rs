@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
 use crate::input::{Configuration, IngredientImpl};
-use crate::{Durability, Runtime};
+use crate::{Durability, Runtime, WriteScope};
 
 /// Setter for a field of an input.
 pub trait Setter: Sized {
@@ -73,3 +73,78 @@ where
         })
     }
 }
+
+/// Setter for a field of an input within a [`WriteScope`].
+#[must_use]
+pub struct ScopedSetterImpl<'scope, C: Configuration, S, F> {
+    scope: &'scope WriteScope<'scope>,
+    id: C::Struct,
+    ingredient: &'scope IngredientImpl<C>,
+    durability: Option<Durability>,
+    field_index: usize,
+    setter: S,
+    phantom: PhantomData<fn(F)>,
+}
+
+impl<'scope, C, S, F> ScopedSetterImpl<'scope, C, S, F>
+where
+    C: Configuration,
+    S: FnOnce(&mut C::Fields, F) -> F,
+{
+    pub fn new(
+        scope: &'scope WriteScope<'scope>,
+        id: C::Struct,
+        field_index: usize,
+        ingredient: &'scope IngredientImpl<C>,
+        setter: S,
+    ) -> Self {
+        ScopedSetterImpl {
+            scope,
+            id,
+            field_index,
+            ingredient,
+            durability: None,
+            setter,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<C, S, F> Setter for ScopedSetterImpl<'_, C, S, F>
+where
+    C: Configuration,
+    S: FnOnce(&mut C::Fields, F) -> F,
+{
+    type FieldTy = F;
+
+    fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = Some(durability);
+        self
+    }
+
+    fn to(self, value: F) -> F {
+        let Self {
+            scope,
+            id,
+            ingredient,
+            durability,
+            field_index,
+            setter,
+            phantom: _,
+        } = self;
+
+        let _guard = scope.lock_ingredient(ingredient.ingredient_index);
+
+        // SAFETY: The scope guarantees that no queries are running,
+        // and we hold its lock for this ingredient.
+        unsafe {
+            ingredient.set_field_shared(
+                scope.zalsa().runtime(),
+                id,
+                field_index,
+                durability,
+                |tuple| setter(tuple, value),
+            )
+        }
+    }
+}
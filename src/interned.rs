@@ -1,8 +1,11 @@
+use crossbeam::atomic::AtomicCell;
 use dashmap::SharedValue;
 
 use crate::accumulator::accumulated_map::InputAccumulatedValues;
+use crate::arena::{self, Arena};
+use crate::channel::Channels;
 use crate::durability::Durability;
-use crate::ingredient::{fmt_index, MaybeChangedAfter};
+use crate::ingredient::{fmt_index, IngredientKind, MaybeChangedAfter};
 use crate::key::InputDependencyIndex;
 use crate::plumbing::{Jar, JarAux};
 use crate::table::memo::MemoTable;
@@ -16,6 +19,7 @@ use std::fmt;
 use std::hash::{BuildHasher, Hash, Hasher};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use super::hash::FxDashMap;
 use super::ingredient::Ingredient;
@@ -62,11 +66,20 @@ pub struct IngredientImpl<C: Configuration> {
     /// Deadlock requirement: We access `value_map` while holding lock on `key_map`, but not vice versa.
     key_map: FxDashMap<C::Fields<'static>, Id>,
 
+    /// Number of values interned so far; the next value gets this as its index.
+    count: AtomicU32,
+
+    /// Holds the data of [`ArenaRef`] fields, for as long as the ingredient exists.
+    arena: Arena,
+
     /// Stores the revision when this interned ingredient was last cleared.
     /// You can clear an interned table at any point, deleting all its entries,
     /// but that will make anything dependent on those entries dirty and in need
     /// of being recomputed.
     reset_at: Revision,
+
+    /// The highest durability of the values interned so far, see [`Ingredient::invalidate`].
+    max_durability: AtomicCell<Durability>,
 }
 
 /// Struct storing the interned fields.
@@ -75,6 +88,14 @@ where
     C: Configuration,
 {
     fields: C::Fields<'static>,
+
+    /// The position of this value in the order values were interned, see [`IngredientImpl::index`].
+    index: u32,
+
+    /// The durability reported to queries that intern this value,
+    /// decided when it was first interned.
+    durability: Durability,
+
     memos: MemoTable,
     syncs: SyncTable,
 }
@@ -101,10 +122,10 @@ impl<C: Configuration> Default for JarImpl<C> {
 impl<C: Configuration> Jar for JarImpl<C> {
     fn create_ingredients(
         &self,
-        _aux: &dyn JarAux,
+        aux: &dyn JarAux,
         first_index: IngredientIndex,
     ) -> Vec<Box<dyn Ingredient>> {
-        vec![Box::new(IngredientImpl::<C>::new(first_index)) as _]
+        vec![Box::new(IngredientImpl::<C>::new(first_index, aux)) as _]
     }
 
     fn salsa_struct_type_id(&self) -> Option<std::any::TypeId> {
@@ -116,11 +137,18 @@ impl<C> IngredientImpl<C>
 where
     C: Configuration,
 {
-    pub fn new(ingredient_index: IngredientIndex) -> Self {
+    pub fn new(ingredient_index: IngredientIndex, aux: &dyn JarAux) -> Self {
+        let key_map = match aux.interned_shards() {
+            Some(shards) => FxDashMap::with_hasher_and_shard_amount(Default::default(), shards),
+            None => Default::default(),
+        };
         Self {
             ingredient_index,
-            key_map: Default::default(),
+            key_map,
+            count: Default::default(),
+            arena: Default::default(),
             reset_at: Revision::start(),
+            max_durability: AtomicCell::new(Durability::MIN),
         }
     }
 
@@ -154,6 +182,22 @@ where
         C::struct_from_id(self.intern_id(db, key, assemble))
     }
 
+    /// Like [`Self::intern`], but if `key` is not yet interned,
+    /// the new value gets the given durability rather than an inferred one.
+    pub fn intern_with_durability<'db, Key>(
+        &'db self,
+        db: &'db dyn crate::Database,
+        key: Key,
+        durability: Durability,
+        assemble: impl FnOnce(Id, Key) -> C::Fields<'db>,
+    ) -> C::Struct<'db>
+    where
+        Key: Hash,
+        C::Fields<'db>: HashEqLike<Key>,
+    {
+        C::struct_from_id(self.intern_id_with_durability(db, key, Some(durability), assemble))
+    }
+
     /// Intern data to a unique reference.
     ///
     /// If `key` is already interned, returns the existing [`Id`] for the interned data without
@@ -177,13 +221,90 @@ where
         // so instead we go with this and transmute the lifetime in the `eq` closure
         C::Fields<'db>: HashEqLike<Key>,
     {
-        let zalsa_local = db.zalsa_local();
-        zalsa_local.report_tracked_read(
+        self.intern_id_with_durability(db, key, None, assemble)
+    }
+
+    /// Interns `key`, giving a new value the durability `durability`. If that is `None`,
+    /// it is inferred: the durability of the inputs read so far by the active query,
+    /// or [the default](`crate::Database::set_default_intern_durability`) outside of queries.
+    fn intern_id_with_durability<'db, Key>(
+        &'db self,
+        db: &'db dyn crate::Database,
+        key: Key,
+        durability: Option<Durability>,
+        assemble: impl FnOnce(Id, Key) -> C::Fields<'db>,
+    ) -> crate::Id
+    where
+        Key: Hash,
+        C::Fields<'db>: HashEqLike<Key>,
+    {
+        let id = self.lookup_or_insert(db, key, durability, assemble);
+        self.report_read(db, id);
+        id
+    }
+
+    /// Returns the value interned for `key`, if any, without interning it.
+    ///
+    /// Finding the value is a read of it, as when interning it. Whether `key` is interned
+    /// changes whenever a new value is interned, which is not tracked as such;
+    /// so not finding it within a query is reported as an untracked read.
+    pub fn lookup<'db, Key>(
+        &'db self,
+        db: &'db dyn crate::Database,
+        key: Key,
+    ) -> Option<C::Struct<'db>>
+    where
+        Key: Hash,
+        C::Fields<'db>: HashEqLike<Key>,
+    {
+        let data_hash = self.key_map.hasher().hash_one(&key);
+        let shard = &self.key_map.shards()[self.key_map.determine_shard(data_hash as _)];
+        let eq = |(data, _): &_| {
+            // SAFETY: it's safe to go from Data<'static> to Data<'db>
+            let data: &C::Fields<'db> = unsafe { std::mem::transmute(data) };
+            HashEqLike::eq(data, &key)
+        };
+
+        let lock = shard.read();
+        match lock.find(data_hash, eq) {
+            Some(bucket) => {
+                // SAFETY: Read lock on map is held during this block
+                let id = unsafe { *bucket.as_ref().1.get() };
+                drop(lock);
+                self.report_read(db, id);
+                Some(C::struct_from_id(id))
+            }
+            None => {
+                drop(lock);
+                db.report_untracked_read();
+                None
+            }
+        }
+    }
+
+    /// Records that the active query, if any, read the interned value `id`.
+    fn report_read(&self, db: &dyn crate::Database, id: Id) {
+        db.zalsa_local().report_tracked_read(
             InputDependencyIndex::for_table(self.ingredient_index),
-            Durability::MAX,
+            db.zalsa().table().get::<Value<C>>(id).durability,
+            Channels::OTHER,
             self.reset_at,
             InputAccumulatedValues::Empty,
         );
+    }
+
+    fn lookup_or_insert<'db, Key>(
+        &'db self,
+        db: &'db dyn crate::Database,
+        key: Key,
+        durability: Option<Durability>,
+        assemble: impl FnOnce(Id, Key) -> C::Fields<'db>,
+    ) -> crate::Id
+    where
+        Key: Hash,
+        C::Fields<'db>: HashEqLike<Key>,
+    {
+        let zalsa_local = db.zalsa_local();
 
         // Optimization to only get read lock on the map if the data has already been interned.
         let data_hash = self.key_map.hasher().hash_one(&key);
@@ -213,8 +334,22 @@ where
             Err(slot) => {
                 let zalsa = db.zalsa();
                 let table = zalsa.table();
+                let durability = durability.unwrap_or_else(|| match zalsa_local.active_query() {
+                    Some((_, stamp)) => stamp.durability,
+                    None => zalsa.runtime().default_intern_durability(),
+                });
+                _ = self
+                    .max_durability
+                    .fetch_update(|max| (durability > max).then_some(durability));
+                // The shard's write lock is held until the value is inserted,
+                // so every index taken here belongs to an allocated value.
+                let index = self.count.fetch_add(1, Ordering::Relaxed);
                 let id = zalsa_local.allocate(table, self.ingredient_index, |id| Value::<C> {
-                    fields: unsafe { self.to_internal_data(assemble(id, key)) },
+                    fields: unsafe {
+                        self.to_internal_data(self.arena.assembling(|| assemble(id, key)))
+                    },
+                    index,
+                    durability,
                     memos: Default::default(),
                     syncs: Default::default(),
                 });
@@ -253,6 +388,21 @@ where
         self.data(db, C::deref_struct(s))
     }
 
+    /// The index of the interned value `id`: values are numbered contiguously from 0,
+    /// in the order they were interned. Indices are not reused, even across a [`Self::reset`].
+    pub fn index(&self, db: &dyn Database, id: Id) -> u32 {
+        db.zalsa().table().get::<Value<C>>(id).index
+    }
+
+    /// The number of values interned so far.
+    ///
+    /// The count grows whenever a new value is interned, which is not tracked as such;
+    /// so calling this within a query is reported as an untracked read.
+    pub fn count(&self, db: &dyn Database) -> u32 {
+        db.report_untracked_read();
+        self.count.load(Ordering::Relaxed)
+    }
+
     pub fn reset(&mut self, revision: Revision) {
         assert!(revision > self.reset_at);
         self.reset_at = revision;
@@ -277,6 +427,17 @@ where
         MaybeChangedAfter::from(revision < self.reset_at)
     }
 
+    fn table_maybe_changed_after(&self, revision: Revision) -> MaybeChangedAfter {
+        MaybeChangedAfter::from(revision < self.reset_at)
+    }
+
+    fn invalidate(&mut self, revision: Revision) -> Durability {
+        // Unlike `reset`, the interned values are kept: only the queries
+        // that interned them are considered changed.
+        self.reset_at = revision;
+        self.max_durability.load()
+    }
+
     fn cycle_recovery_strategy(&self) -> crate::cycle::CycleRecoveryStrategy {
         crate::cycle::CycleRecoveryStrategy::Panic
     }
@@ -320,6 +481,12 @@ where
         panic!("unexpected call to `reset_for_new_revision`")
     }
 
+    fn compact(&self, _db: &dyn Database) -> usize {
+        let capacity = self.key_map.capacity();
+        self.key_map.shrink_to_fit();
+        (capacity - self.key_map.capacity()) * std::mem::size_of::<(C::Fields<'static>, Id)>()
+    }
+
     fn fmt_index(&self, index: Option<crate::Id>, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt_index(C::DEBUG_NAME, index, fmt)
     }
@@ -327,6 +494,10 @@ where
     fn debug_name(&self) -> &'static str {
         C::DEBUG_NAME
     }
+
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::Interned
+    }
 }
 
 impl<C> std::fmt::Debug for IngredientImpl<C>
@@ -426,6 +597,115 @@ where
     }
 }
 
+/// The type in which interned structs store fields declared as `&'db T`: a copy of
+/// the data that was interned, allocated in an arena owned by the interned ingredient.
+///
+/// Getters return the `&'db T` reference, so it cannot outlive the borrow of the database.
+/// Lookups take any `&T`; only interning a new value copies the data.
+pub struct ArenaRef<'db, T: ?Sized> {
+    data: &'db T,
+}
+
+impl<'db, T: ?Sized> ArenaRef<'db, T> {
+    pub fn get(self) -> &'db T {
+        self.data
+    }
+}
+
+impl<T: ?Sized> Clone for ArenaRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for ArenaRef<'_, T> {}
+
+impl<T: ?Sized> std::ops::Deref for ArenaRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.data
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for ArenaRef<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for ArenaRef<'_, T> {}
+
+impl<T: ?Sized + Hash> Hash for ArenaRef<'_, T> {
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        Hash::hash(self.data, h)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for ArenaRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.data, f)
+    }
+}
+
+/// Types that can be borrowed by interned structs through an [`ArenaRef`]:
+/// `str` and slices of `'static` types without drop glue.
+pub trait ArenaSlice: Hash + Eq + 'static {
+    /// Copies `self` into `arena`.
+    fn copy_into<'arena>(&self, arena: &'arena Arena) -> &'arena Self;
+}
+
+impl<T: Copy + Hash + Eq + 'static> ArenaSlice for [T] {
+    fn copy_into<'arena>(&self, arena: &'arena Arena) -> &'arena Self {
+        if std::mem::size_of_val(self) == 0 {
+            // SAFETY: any aligned pointer is valid for zero bytes.
+            return unsafe {
+                std::slice::from_raw_parts(std::ptr::NonNull::dangling().as_ptr(), self.len())
+            };
+        }
+        let ptr = arena.alloc(std::alloc::Layout::for_value(self)).cast::<T>();
+        // SAFETY: the allocation fits `self.len()` values of `T`, which are `Copy`.
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.as_ptr(), ptr.as_ptr(), self.len());
+            std::slice::from_raw_parts(ptr.as_ptr(), self.len())
+        }
+    }
+}
+
+impl ArenaSlice for str {
+    fn copy_into<'arena>(&self, arena: &'arena Arena) -> &'arena Self {
+        let bytes = self.as_bytes().copy_into(arena);
+        // SAFETY: the bytes were copied from a `str`.
+        unsafe { std::str::from_utf8_unchecked(bytes) }
+    }
+}
+
+impl<'a, T: ?Sized + ArenaSlice> HashEqLike<&'a T> for ArenaRef<'_, T> {
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        Hash::hash(self.data, h)
+    }
+
+    fn eq(&self, data: &&'a T) -> bool {
+        self.data == *data
+    }
+}
+
+impl<'db, T: ?Sized + ArenaSlice> Lookup<ArenaRef<'db, T>> for &T {
+    /// Copies `self` into the arena of the ingredient interning it.
+    ///
+    /// # Panics
+    ///
+    /// If called other than by the interned ingredient, when it assembles a new value.
+    fn into_owned(self) -> ArenaRef<'db, T> {
+        arena::with_assembling(|arena| {
+            // SAFETY: the arena belongs to the ingredient, so it lives as long as the database,
+            // and the value holding this reference can only be reached through a `'db` borrow.
+            let data = unsafe { &*std::ptr::from_ref(self.copy_into(arena)) };
+            ArenaRef { data }
+        })
+    }
+}
+
 impl Lookup<String> for &str {
     fn into_owned(self) -> String {
         self.to_owned()
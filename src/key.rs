@@ -1,8 +1,8 @@
 use core::fmt;
 
 use crate::{
-    accumulator::accumulated_map::InputAccumulatedValues, cycle::CycleRecoveryStrategy,
-    ingredient::MaybeChangedAfter, zalsa::IngredientIndex, Database, Id,
+    cycle::CycleRecoveryStrategy, ingredient::MaybeChangedAfter, input::edit::EditRange,
+    zalsa::IngredientIndex, Database, Id,
 };
 
 /// An integer that uniquely identifies a particular query instance within the
@@ -95,11 +95,41 @@ impl InputDependencyIndex {
                 .zalsa()
                 .lookup_ingredient(self.ingredient_index)
                 .maybe_changed_after(db, key_index, last_verified_at),
-            // Data in tables themselves remain valid until the table as a whole is reset.
-            None => MaybeChangedAfter::No(InputAccumulatedValues::Empty),
+            // Data in tables themselves remain valid until the table as a whole is invalidated.
+            None => db
+                .zalsa()
+                .lookup_ingredient(self.ingredient_index)
+                .table_maybe_changed_after(last_verified_at),
         }
     }
 
+    /// Like [`Self::maybe_changed_after`], but only considers the bytes in `range`.
+    pub(crate) fn maybe_changed_after_range(
+        &self,
+        db: &dyn Database,
+        range: EditRange,
+        last_verified_at: crate::Revision,
+    ) -> MaybeChangedAfter {
+        match self.key_index {
+            Some(key_index) => db
+                .zalsa()
+                .lookup_ingredient(self.ingredient_index)
+                .maybe_changed_after_range(db, key_index, range, last_verified_at),
+            None => db
+                .zalsa()
+                .lookup_ingredient(self.ingredient_index)
+                .table_maybe_changed_after(last_verified_at),
+        }
+    }
+
+    /// The key of the dependency, unless it is a whole table (see [`Self::for_table`]).
+    pub(crate) fn database_key_index(&self) -> Option<DatabaseKeyIndex> {
+        Some(DatabaseKeyIndex {
+            ingredient_index: self.ingredient_index,
+            key_index: self.key_index?,
+        })
+    }
+
     pub fn set_key_index(&mut self, key_index: Id) {
         self.key_index = Some(key_index);
     }
@@ -143,15 +173,99 @@ impl DatabaseKeyIndex {
     pub(crate) fn cycle_recovery_strategy(self, db: &dyn Database) -> CycleRecoveryStrategy {
         self.ingredient_index.cycle_recovery_strategy(db)
     }
+
+    /// Packs this key into an opaque `u64`, e.g. to store it outside of the database.
+    /// Use [`Self::from_bits`] to convert it back.
+    ///
+    /// The bits record the ingredient, the id, and the current generation of the id's slot,
+    /// so that a key whose tracked struct has since been deleted (and its id reused) is rejected.
+    /// They are only meaningful for `db` and databases with the same set of ingredients.
+    ///
+    /// # Panics
+    ///
+    /// If `self` does not belong to `db`, or `db` has more than `u16::MAX` ingredients.
+    pub fn to_bits(self, db: &dyn Database) -> u64 {
+        let ingredient = u16::try_from(self.ingredient_index.as_u32())
+            .expect("too many ingredients to pack a `DatabaseKeyIndex`");
+        let generation = Self::generation(db, self.ingredient_index, self.key_index)
+            .unwrap_or_else(|| panic!("`{self:?}` does not belong to this database"));
+        (u64::from(ingredient) << 48)
+            | (u64::from(generation) << 32)
+            | u64::from(self.key_index.as_u32())
+    }
+
+    /// Unpacks a key created by [`Self::to_bits`].
+    ///
+    /// Returns `None` if `db` has no such ingredient, if the id was never allocated,
+    /// or if the id's slot was freed or reused since the key was packed.
+    pub fn from_bits(bits: u64, db: &dyn Database) -> Option<Self> {
+        let ingredient_index = IngredientIndex::from((bits >> 48) as usize);
+        let generation = (bits >> 32) as u16;
+        let key_index = bits as u32;
+        if key_index > Id::MAX_U32 {
+            return None;
+        }
+        let key_index = Id::from_u32(key_index);
+        if Self::generation(db, ingredient_index, key_index)? != generation {
+            return None;
+        }
+        Some(Self {
+            ingredient_index,
+            key_index,
+        })
+    }
+
+    /// Formats this key with `db`, e.g. as `my_query(Id(0))`, without attaching `db`
+    /// to the current thread.
+    ///
+    /// The `Debug` impl of `DatabaseKeyIndex` only shows the ingredient and id when no
+    /// database is attached, which is often the case when events are emitted. Use this
+    /// in [`Database::salsa_event`](`crate::Database::salsa_event`) instead, passing the
+    /// database the method was called on:
+    ///
+    /// ```rust,ignore
+    /// fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+    ///     if let salsa::EventKind::WillExecute { database_key } = event().kind {
+    ///         eprintln!("executing {:?}", database_key.debug(self));
+    ///     }
+    /// }
+    /// ```
+    pub fn debug(self, db: &dyn Database) -> impl fmt::Debug + '_ {
+        struct KeyDebug<'db> {
+            key: DatabaseKeyIndex,
+            db: &'db dyn Database,
+        }
+
+        impl fmt::Debug for KeyDebug<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let ingredient = self.db.zalsa().lookup_ingredient(self.key.ingredient_index);
+                ingredient.fmt_index(Some(self.key.key_index), f)
+            }
+        }
+
+        KeyDebug { key: self, db }
+    }
+
+    /// The generation of `key_index`, or `None` if the ingredient does not exist,
+    /// the id is not one of its keys, or the id is not currently allocated.
+    fn generation(
+        db: &dyn Database,
+        ingredient_index: IngredientIndex,
+        key_index: Id,
+    ) -> Option<u16> {
+        let zalsa = db.zalsa();
+        if ingredient_index.as_usize() >= zalsa.ingredients_len() {
+            return None;
+        }
+        zalsa
+            .lookup_ingredient(ingredient_index)
+            .id_generation(db, key_index)
+    }
 }
 
 impl std::fmt::Debug for DatabaseKeyIndex {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        crate::attach::with_attached_database(|db| {
-            let ingredient = db.zalsa().lookup_ingredient(self.ingredient_index);
-            ingredient.fmt_index(Some(self.key_index), f)
-        })
-        .unwrap_or_else(|| {
+        crate::attach::with_attached_database(|db| self.debug(db).fmt(f)).unwrap_or_else(|| {
             f.debug_tuple("DatabaseKeyIndex")
                 .field(&self.ingredient_index)
                 .field(&self.key_index)
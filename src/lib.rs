@@ -1,59 +1,129 @@
+// Lets the salsa macros, which refer to `salsa::..`, be used within this crate.
+extern crate self as salsa;
+
 mod accumulator;
 mod active_query;
+mod arena;
 mod array;
 mod attach;
 mod cancelled;
+mod channel;
+mod checksum;
+mod codec;
+mod config;
 mod cycle;
 mod database;
 mod database_impl;
 mod durability;
 mod event;
+mod external;
 mod function;
 mod hash;
 mod id;
+#[cfg(feature = "testing")]
+mod incremental_check;
 mod ingredient;
 mod input;
 mod interned;
 mod key;
+mod memory_report;
 mod nonce;
 mod par_map;
+mod pinned_revision;
+#[cfg(feature = "query_timing")]
+mod query_timing;
 mod revision;
+mod revision_ref;
 mod runtime;
 mod salsa_struct;
+mod snapshot;
 mod storage;
 mod table;
 mod tracked_struct;
 mod update;
 mod views;
+mod write_scope;
 mod zalsa;
 mod zalsa_local;
 
 pub use self::accumulator::Accumulator;
+pub use self::active_query::Backtrace;
+pub use self::active_query::BacktraceFrame;
+pub use self::cancelled::CancellationMode;
 pub use self::cancelled::Cancelled;
+pub use self::channel::Channel;
+pub use self::codec::Codec;
 pub use self::cycle::Cycle;
 pub use self::database::AsDynDatabase;
 pub use self::database::Database;
-pub use self::database_impl::DatabaseImpl;
+pub use self::database_impl::{DatabaseImpl, DatabaseImplBuilder};
 pub use self::durability::Durability;
+pub use self::durability::DurabilityExplanation;
+pub use self::event::ChangeCause;
 pub use self::event::Event;
+pub use self::event::EventFilter;
 pub use self::event::EventKind;
+pub use self::event::SubscriberId;
+pub use self::event::ValidationKind;
+pub use self::external::ExternalFingerprintFn;
 pub use self::id::Id;
+#[cfg(feature = "testing")]
+pub use self::incremental_check::check_incremental;
+pub use self::ingredient::{IngredientInfo, IngredientKind};
+pub use self::input::edit::Editable;
+pub use self::input::edit::TextEdit;
 pub use self::input::setter::Setter;
 pub use self::key::DatabaseKeyIndex;
+pub use self::memory_report::{
+    CompactionReport, IngredientCompaction, IngredientMemoryUsage, MemoryPressure, MemoryReport,
+};
+pub use self::pinned_revision::PinnedRevision;
+#[cfg(feature = "query_timing")]
+pub use self::query_timing::ExecutionTime;
 pub use self::revision::Revision;
+pub use self::revision_ref::RevisionRef;
+pub use self::runtime::change_set::ChangeListenerId;
+pub use self::runtime::change_set::ChangeSet;
+pub use self::runtime::deadlock::BlockedThread;
+pub use self::runtime::deadlock::DeadlockReport;
 pub use self::runtime::Runtime;
+pub use self::salsa_struct::is_stale;
+pub use self::snapshot::Snapshot;
 pub use self::storage::Storage;
+pub use self::storage::StorageBuilder;
+pub use self::tracked_struct::adopt;
 pub use self::update::Update;
+pub use self::write_scope::WriteScope;
 pub use self::zalsa::IngredientIndex;
+pub use self::zalsa_local::ThreadStats;
+pub use crate::attach::attach_guard;
 pub use crate::attach::with_attached_database;
+pub use crate::attach::AttachGuard;
+pub use par_map::entries_par_chunks;
 pub use par_map::par_map;
 pub use salsa_macros::accumulator;
 pub use salsa_macros::db;
 pub use salsa_macros::input;
 pub use salsa_macros::interned;
 pub use salsa_macros::tracked;
+pub use salsa_macros::Supertype;
 pub use salsa_macros::Update;
 
+pub mod bench;
+
+pub mod diff;
+
+pub mod memo_stats;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "watch")]
+pub mod watch;
+
 pub mod prelude {
     pub use crate::Accumulator;
     pub use crate::Database;
@@ -83,25 +153,38 @@ pub mod plumbing {
     pub use crate::ingredient::JarAux;
     pub use crate::key::DatabaseKeyIndex;
     pub use crate::revision::Revision;
+    pub use crate::revision_ref::revision_ref;
     pub use crate::runtime::stamp;
     pub use crate::runtime::Runtime;
     pub use crate::runtime::Stamp;
     pub use crate::runtime::StampedValue;
+    pub use crate::salsa_struct::ingredient_indices;
+    pub use crate::salsa_struct::owner_ingredient;
+    pub use crate::salsa_struct::FromIdWithDb;
     pub use crate::salsa_struct::SalsaStructInDb;
     pub use crate::storage::HasStorage;
     pub use crate::storage::Storage;
+    pub use crate::tracked_struct::lazy::helper::Dispatch as LazyEqDispatch;
+    pub use crate::tracked_struct::lazy::helper::Fallback as LazyEqFallback;
     pub use crate::tracked_struct::TrackedStructInDb;
     pub use crate::update::always_update;
     pub use crate::update::helper::Dispatch as UpdateDispatch;
     pub use crate::update::helper::Fallback as UpdateFallback;
+    pub use crate::update::update_dyn_eq;
+    pub use crate::update::DynBox;
     pub use crate::update::Update;
     pub use crate::zalsa::views;
     pub use crate::zalsa::IngredientCache;
     pub use crate::zalsa::IngredientIndex;
+    pub use crate::zalsa::IngredientIndicesCache;
     pub use crate::zalsa::Zalsa;
     pub use crate::zalsa::ZalsaDatabase;
     pub use crate::zalsa_local::ZalsaLocal;
+    pub use crate::WriteScope;
+
+    pub use crate::__if_dependency_inspection as if_dependency_inspection;
 
+    pub use salsa_macro_rules::default_cycle_recovery;
     pub use salsa_macro_rules::macro_if;
     pub use salsa_macro_rules::maybe_backdate;
     pub use salsa_macro_rules::maybe_clone;
@@ -115,6 +198,7 @@ pub mod plumbing {
     pub use salsa_macro_rules::setup_tracked_fn;
     pub use salsa_macro_rules::setup_tracked_struct;
     pub use salsa_macro_rules::unexpected_cycle_recovery;
+    pub use salsa_macro_rules::unexpected_timeout_result;
 
     pub mod accumulator {
         pub use crate::accumulator::IngredientImpl;
@@ -123,6 +207,7 @@ pub mod plumbing {
 
     pub mod input {
         pub use crate::input::input_field::FieldIngredientImpl;
+        pub use crate::input::setter::ScopedSetterImpl;
         pub use crate::input::setter::SetterImpl;
         pub use crate::input::singleton::NotSingleton;
         pub use crate::input::singleton::Singleton;
@@ -134,6 +219,8 @@ pub mod plumbing {
     }
 
     pub mod interned {
+        pub use crate::interned::ArenaRef;
+        pub use crate::interned::ArenaSlice;
         pub use crate::interned::Configuration;
         pub use crate::interned::HashEqLike;
         pub use crate::interned::IngredientImpl;
@@ -143,11 +230,23 @@ pub mod plumbing {
     }
 
     pub mod function {
+        pub use crate::function::context::Context;
+        pub use crate::function::context::ContextGuard;
+        pub use crate::function::dedupe::dedupe_hash;
+        pub use crate::function::dedupe::dedupe_with;
+        pub use crate::function::dedupe::error_hash;
+        pub use crate::function::dedupe::share_error;
+        pub use crate::function::dedupe::share_result;
+        pub use crate::function::fingerprint::fingerprint;
         pub use crate::function::Configuration;
         pub use crate::function::IngredientImpl;
+        pub use crate::function::MemoEntry;
+        pub use crate::function::DEFAULT_ADAPTIVE_THRESHOLD;
     }
 
     pub mod tracked_struct {
+        pub use crate::tracked_struct::elements::update_elements;
+        pub use crate::tracked_struct::elements::Elements;
         pub use crate::tracked_struct::tracked_field::FieldIngredientImpl;
         pub use crate::tracked_struct::Configuration;
         pub use crate::tracked_struct::IngredientImpl;
@@ -0,0 +1,125 @@
+//! Statistics about the memos of tracked functions, used to lay out memo tables.
+//!
+//! The memos of the tracked functions taking a salsa struct are stored in a table attached
+//! to each value of that struct, in the order in which the functions were first used.
+//! Collecting [`MemoStats`] with [`Database::collect_memo_stats`] at the end of a
+//! representative run, and passing them to
+//! [`StorageBuilder::memo_layout_hint`](`crate::StorageBuilder::memo_layout_hint`)
+//! in later runs, places the memos of the most used functions first instead, which keeps
+//! the tables of most values short. Applications can ship the statistics with them,
+//! e.g. serialized with the `serde` feature.
+//!
+//! # Compatibility
+//!
+//! The statistics only name salsa structs and tracked functions by their debug names
+//! (see [`Database::ingredient_debug_name`]), so they remain usable across versions of
+//! salsa and of the application: entries for items that were renamed or removed are
+//! ignored, and items that are missing from the statistics are placed after the others.
+//! Hints never change the results of queries, only where their memos are stored.
+//! Future versions of salsa may add fields to these types; when deserializing, missing
+//! fields take their default values.
+
+use rustc_hash::FxHashMap;
+
+use crate::{zalsa::IngredientIndex, Database};
+
+/// The number of memos of each tracked function, grouped by the salsa struct they are
+/// attached to; see the [module documentation](`self`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[non_exhaustive]
+pub struct MemoStats {
+    /// One entry per salsa struct that tracked functions take as argument.
+    pub structs: Vec<StructMemoStats>,
+}
+
+/// The memos attached to the values of one salsa struct.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[non_exhaustive]
+pub struct StructMemoStats {
+    /// The debug name of the salsa struct.
+    pub name: String,
+
+    /// The tracked functions taking the salsa struct, most memoized first.
+    pub functions: Vec<FunctionMemoStats>,
+}
+
+/// The memos of one tracked function.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[non_exhaustive]
+pub struct FunctionMemoStats {
+    /// The debug name of the tracked function.
+    pub name: String,
+
+    /// The number of values memoized by the function.
+    pub memos: usize,
+}
+
+impl MemoStats {
+    /// The names of the functions taking the salsa struct `struct_name`, in the order
+    /// in which their memos should be stored, or `None` if the struct is not listed.
+    pub(crate) fn layout_of(&self, struct_name: &str) -> Option<Vec<&str>> {
+        let stats = self
+            .structs
+            .iter()
+            .find(|stats| stats.name == struct_name)?;
+        let mut functions: Vec<&FunctionMemoStats> = stats.functions.iter().collect();
+        functions.sort_by_key(|function| std::cmp::Reverse(function.memos));
+        Some(functions.iter().map(|function| &*function.name).collect())
+    }
+}
+
+/// See [`Database::collect_memo_stats`].
+pub(crate) fn collect_memo_stats(db: &dyn Database) -> MemoStats {
+    let zalsa = db.zalsa();
+    let current_revision = zalsa.current_revision();
+
+    let mut counts: FxHashMap<IngredientIndex, FxHashMap<IngredientIndex, usize>> =
+        FxHashMap::default();
+    // SAFETY: `current_revision` is the current revision of the database owning the table.
+    unsafe {
+        zalsa
+            .table()
+            .for_each_memo(current_revision, &mut |struct_index, _, memo_index, _| {
+                let function_index = zalsa.ingredient_index_for_memo(struct_index, memo_index);
+                *counts
+                    .entry(struct_index)
+                    .or_default()
+                    .entry(function_index)
+                    .or_default() += 1;
+            });
+    }
+
+    let structs = zalsa
+        .memo_ingredients()
+        .into_iter()
+        .map(|(struct_index, function_indices)| {
+            let counts = counts.remove(&struct_index).unwrap_or_default();
+            let mut functions: Vec<FunctionMemoStats> = function_indices
+                .into_iter()
+                .map(|function_index| FunctionMemoStats {
+                    name: zalsa
+                        .lookup_ingredient(function_index)
+                        .debug_name()
+                        .to_string(),
+                    memos: counts.get(&function_index).copied().unwrap_or(0),
+                })
+                .collect();
+            functions.sort_by(|a, b| b.memos.cmp(&a.memos).then_with(|| a.name.cmp(&b.name)));
+            StructMemoStats {
+                name: zalsa
+                    .lookup_ingredient(struct_index)
+                    .debug_name()
+                    .to_string(),
+                functions,
+            }
+        })
+        .collect();
+
+    MemoStats { structs }
+}
@@ -0,0 +1,185 @@
+use crate::{zalsa::IngredientIndex, Database};
+
+/// The memory used by a database, broken down by ingredient; see
+/// [`Database::memory_report`](`crate::Database::memory_report`).
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct MemoryReport {
+    /// One entry per ingredient, in order of their [`IngredientIndex`].
+    pub ingredients: Vec<IngredientMemoryUsage>,
+}
+
+impl MemoryReport {
+    /// The total number of bytes used by all ingredients.
+    pub fn total_bytes(&self) -> usize {
+        self.ingredients
+            .iter()
+            .map(IngredientMemoryUsage::total_bytes)
+            .sum()
+    }
+}
+
+/// The memory used by one ingredient, e.g. a salsa struct or a tracked function.
+///
+/// Sizes are shallow: heap allocations owned by fields and values (e.g. the
+/// contents of a `String` field) are not included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IngredientMemoryUsage {
+    pub ingredient_index: IngredientIndex,
+
+    /// The debug name of the ingredient, see [`Database::ingredient_debug_name`](`crate::Database::ingredient_debug_name`).
+    pub debug_name: &'static str,
+
+    /// The number of slots allocated for the values of a salsa struct,
+    /// including the slots of tracked structs that were deleted and can be reused.
+    pub slots: usize,
+
+    /// The bytes used by those slots, including the fields stored inline and the
+    /// bookkeeping salsa keeps for each value.
+    pub slot_bytes: usize,
+
+    /// The number of values memoized by a tracked function.
+    pub memos: usize,
+
+    /// The bytes used by those memos, including the dependencies recorded for each value.
+    pub memo_bytes: usize,
+}
+
+/// How urgently memory should be freed; see [`Database::trim_memory`](`crate::Database::trim_memory`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MemoryPressure {
+    /// Drops the memoized values of `#[salsa::tracked(weak)]` functions.
+    Low,
+
+    /// Also drops the memoized values of functions with an LRU capacity.
+    High,
+}
+
+impl IngredientMemoryUsage {
+    /// The number of bytes used by this ingredient.
+    pub fn total_bytes(&self) -> usize {
+        self.slot_bytes + self.memo_bytes
+    }
+}
+
+/// The memory reclaimed by [`Database::compact`](`crate::Database::compact`), broken down by ingredient.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct CompactionReport {
+    /// One entry per ingredient, in order of their [`IngredientIndex`].
+    pub ingredients: Vec<IngredientCompaction>,
+}
+
+impl CompactionReport {
+    /// The total number of bytes reclaimed from all ingredients.
+    pub fn total_bytes(&self) -> usize {
+        self.ingredients
+            .iter()
+            .map(|compaction| compaction.bytes_reclaimed)
+            .sum()
+    }
+}
+
+/// The memory reclaimed from one ingredient.
+///
+/// Like the sizes in an [`IngredientMemoryUsage`], this is shallow and, for hash tables,
+/// approximate: it counts the capacity released by each table, not its allocator overhead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IngredientCompaction {
+    pub ingredient_index: IngredientIndex,
+
+    /// The debug name of the ingredient, see [`Database::ingredient_debug_name`](`crate::Database::ingredient_debug_name`).
+    pub debug_name: &'static str,
+
+    /// The bytes freed by dropping replaced memos and stale bookkeeping,
+    /// and by shrinking the tables of this ingredient (including the memo tables
+    /// attached to the values of a salsa struct) to fit.
+    pub bytes_reclaimed: usize,
+}
+
+pub(crate) fn memory_report(db: &dyn Database) -> MemoryReport {
+    let zalsa = db.zalsa();
+    let current_revision = zalsa.current_revision();
+    let mut ingredients: Vec<IngredientMemoryUsage> = (0..zalsa.ingredients_len())
+        .map(|index| {
+            let ingredient_index = IngredientIndex::from(index);
+            IngredientMemoryUsage {
+                ingredient_index,
+                debug_name: zalsa.lookup_ingredient(ingredient_index).debug_name(),
+                slots: 0,
+                slot_bytes: 0,
+                memos: 0,
+                memo_bytes: 0,
+            }
+        })
+        .collect();
+
+    for page in zalsa.table().pages.iter() {
+        let struct_index = page.ingredient();
+        let slots = page.allocated();
+        let usage = &mut ingredients[struct_index.as_usize()];
+        usage.slots += slots;
+        usage.slot_bytes += slots * page.slot_size();
+
+        // SAFETY: `current_revision` is the current revision of the database owning the table.
+        unsafe {
+            page.for_each_memo(current_revision, &mut |_, memo_ingredient_index, memo| {
+                let index = zalsa.ingredient_index_for_memo(struct_index, memo_ingredient_index);
+                let usage = &mut ingredients[index.as_usize()];
+                usage.memos += 1;
+                usage.memo_bytes += memo.memory_usage();
+            });
+        }
+    }
+
+    MemoryReport { ingredients }
+}
+
+pub(crate) fn trim_memory(db: &mut dyn Database, pressure: MemoryPressure) {
+    let zalsa = db.zalsa();
+    for index in 0..zalsa.ingredients_len() {
+        zalsa
+            .lookup_ingredient(IngredientIndex::from(index))
+            .trim_memory(db, pressure);
+    }
+
+    // The dropped memos can only be freed once no other handle may be reading them;
+    // otherwise they are freed when the next revision starts.
+    if let Some(zalsa) = db.zalsa_mut_if_unshared() {
+        zalsa.free_deleted_entries();
+    }
+}
+
+pub(crate) fn compact(db: &mut dyn Database) -> CompactionReport {
+    let zalsa = db.zalsa();
+    let current_revision = zalsa.current_revision();
+    let mut ingredients: Vec<IngredientCompaction> = (0..zalsa.ingredients_len())
+        .map(|index| {
+            let ingredient_index = IngredientIndex::from(index);
+            let ingredient = zalsa.lookup_ingredient(ingredient_index);
+            IngredientCompaction {
+                ingredient_index,
+                debug_name: ingredient.debug_name(),
+                bytes_reclaimed: ingredient.compact(db),
+            }
+        })
+        .collect();
+
+    for page in zalsa.table().pages.iter() {
+        // SAFETY: `current_revision` is the current revision of the database owning the table.
+        let bytes = unsafe { page.compact_memos(current_revision) };
+        ingredients[page.ingredient().as_usize()].bytes_reclaimed += bytes;
+    }
+
+    // As in `trim_memory`, replaced memos can only be freed if no other handle may be reading them.
+    if let Some(zalsa) = db.zalsa_mut_if_unshared() {
+        for (ingredient_index, bytes) in zalsa.free_deleted_entries() {
+            ingredients[ingredient_index.as_usize()].bytes_reclaimed += bytes;
+        }
+    }
+
+    CompactionReport { ingredients }
+}
@@ -0,0 +1,227 @@
+//! Cache statistics shaped for monitoring systems like Prometheus,
+//! recorded with the `metrics` feature.
+//!
+//! ```rust,ignore
+//! let families = salsa::metrics::gather_metrics(&db);
+//! let body = salsa::metrics::encode_text(&families);
+//! ```
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{zalsa::IngredientIndex, Database};
+
+/// The number of cache hits, misses and evictions of a tracked function,
+/// since the database was created.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheStats {
+    /// The number of calls that returned a memoized value.
+    pub hits: u64,
+
+    /// The number of calls that executed the function.
+    pub misses: u64,
+
+    /// The number of memoized values dropped to free memory, by the LRU or by
+    /// [`Database::trim_memory`](`crate::Database::trim_memory`).
+    pub evictions: u64,
+}
+
+/// The counters behind [`CacheStats`], kept by each tracked function.
+#[derive(Default)]
+pub(crate) struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl CacheCounters {
+    pub(crate) fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_eviction(&self) {
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Whether the samples of a [`MetricFamily`] only ever increase.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+/// A named metric with one sample per ingredient (or a single sample).
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct MetricFamily {
+    /// The metric name, e.g. `salsa_cache_hits_total`.
+    pub name: &'static str,
+
+    /// A description of the metric.
+    pub help: &'static str,
+
+    pub kind: MetricKind,
+
+    pub samples: Vec<Sample>,
+}
+
+/// One value of a [`MetricFamily`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Sample {
+    /// Label names and values, e.g. `("ingredient", "parse")`.
+    pub labels: Vec<(&'static str, String)>,
+
+    pub value: f64,
+}
+
+impl MetricFamily {
+    fn new(name: &'static str, help: &'static str, kind: MetricKind) -> Self {
+        Self {
+            name,
+            help,
+            kind,
+            samples: vec![],
+        }
+    }
+}
+
+/// Formats the family in the Prometheus text exposition format.
+impl fmt::Display for MetricFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        };
+        writeln!(f, "# HELP {} {}", self.name, self.help)?;
+        writeln!(f, "# TYPE {} {kind}", self.name)?;
+        for sample in &self.samples {
+            write!(f, "{}", self.name)?;
+            for (i, (name, value)) in sample.labels.iter().enumerate() {
+                let separator = if i == 0 { "{" } else { "," };
+                let value = value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                write!(f, "{separator}{name}=\"{value}\"")?;
+            }
+            if !sample.labels.is_empty() {
+                write!(f, "}}")?;
+            }
+            writeln!(f, " {}", sample.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats `families` in the Prometheus text exposition format,
+/// e.g. to serve them from a `/metrics` endpoint.
+pub fn encode_text(families: &[MetricFamily]) -> String {
+    families.iter().map(|family| family.to_string()).collect()
+}
+
+/// Gathers the cache statistics of `db`:
+///
+/// * `salsa_revision`, the current revision;
+/// * `salsa_memos` and `salsa_memory_bytes`, the number of memoized values and
+///   the memory used by each ingredient, as in [`Database::memory_report`];
+/// * `salsa_cache_hits_total`, `salsa_cache_misses_total` and `salsa_cache_evictions_total`,
+///   the [`CacheStats`] of each tracked function.
+///
+/// Samples are labeled with the debug name and index of their ingredient.
+/// Like [`Database::memory_report`], this walks over every value in the database.
+pub fn gather_metrics(db: &dyn Database) -> Vec<MetricFamily> {
+    let zalsa = db.zalsa();
+    let labels = |index: IngredientIndex, debug_name: &str| {
+        vec![
+            ("ingredient", debug_name.to_string()),
+            ("index", index.as_usize().to_string()),
+        ]
+    };
+
+    let mut revision = MetricFamily::new(
+        "salsa_revision",
+        "The current revision of the database.",
+        MetricKind::Gauge,
+    );
+    revision.samples.push(Sample {
+        labels: vec![],
+        value: zalsa.current_revision().as_usize() as f64,
+    });
+
+    let mut memos = MetricFamily::new(
+        "salsa_memos",
+        "The number of memoized values.",
+        MetricKind::Gauge,
+    );
+    let mut memory = MetricFamily::new(
+        "salsa_memory_bytes",
+        "The memory used by the values and memos of an ingredient.",
+        MetricKind::Gauge,
+    );
+    for usage in db.memory_report().ingredients {
+        let labels = labels(usage.ingredient_index, usage.debug_name);
+        if usage.memos > 0 {
+            memos.samples.push(Sample {
+                labels: labels.clone(),
+                value: usage.memos as f64,
+            });
+        }
+        memory.samples.push(Sample {
+            labels,
+            value: usage.total_bytes() as f64,
+        });
+    }
+
+    let mut hits = MetricFamily::new(
+        "salsa_cache_hits_total",
+        "The number of calls that returned a memoized value.",
+        MetricKind::Counter,
+    );
+    let mut misses = MetricFamily::new(
+        "salsa_cache_misses_total",
+        "The number of calls that executed the function.",
+        MetricKind::Counter,
+    );
+    let mut evictions = MetricFamily::new(
+        "salsa_cache_evictions_total",
+        "The number of memoized values dropped to free memory.",
+        MetricKind::Counter,
+    );
+    for index in 0..zalsa.ingredients_len() {
+        let index = IngredientIndex::from(index);
+        let ingredient = zalsa.lookup_ingredient(index);
+        let Some(stats) = ingredient.cache_stats() else {
+            continue;
+        };
+        let labels = labels(index, ingredient.debug_name());
+        for (family, value) in [
+            (&mut hits, stats.hits),
+            (&mut misses, stats.misses),
+            (&mut evictions, stats.evictions),
+        ] {
+            family.samples.push(Sample {
+                labels: labels.clone(),
+                value: value as f64,
+            });
+        }
+    }
+
+    vec![revision, memos, memory, hits, misses, evictions]
+}
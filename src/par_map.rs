@@ -2,7 +2,7 @@ use std::ops::Deref;
 
 use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
 
-use crate::Database;
+use crate::{id::FromId, salsa_struct::SalsaStructInDb, Database};
 
 pub fn par_map<Db, D, E, C>(
     db: &Db,
@@ -15,6 +15,12 @@ where
     E: Send + Sync,
     C: FromParallelIterator<E>,
 {
+    if db.zalsa().runtime().is_deterministic() {
+        let inputs: Vec<D> = inputs.into_par_iter().collect();
+        let outputs: Vec<E> = inputs.into_iter().map(|element| op(db, element)).collect();
+        return outputs.into_par_iter().collect();
+    }
+
     let parallel_db = ParallelDb::Ref(db.as_dyn_database());
 
     inputs
@@ -26,6 +32,40 @@ where
         .collect()
 }
 
+/// Returns the values of the salsa struct `S` that are in use, split into chunks of at most
+/// `chunk_size` values that are collected in parallel, e.g. to analyze all of them with
+/// [`par_map`]. Tracked structs that were deleted are skipped.
+///
+/// Values are created without starting a new revision, which is not tracked as such;
+/// so calling this within a query is reported as an untracked read.
+///
+/// # Panics
+///
+/// If `chunk_size` is zero.
+pub fn entries_par_chunks<'db, S>(
+    db: &'db dyn Database,
+    chunk_size: usize,
+) -> impl ParallelIterator<Item = Vec<S>> + 'db
+where
+    S: SalsaStructInDb + FromId + Send + 'db,
+{
+    assert!(chunk_size > 0, "chunks must not be empty");
+    db.report_untracked_read();
+
+    let zalsa = db.zalsa();
+    let pages: Vec<usize> = zalsa
+        .lookup_struct_ingredients::<S>()
+        .into_iter()
+        .flat_map(|ingredient| zalsa.table().pages_of(ingredient))
+        .collect();
+    pages.into_par_iter().flat_map_iter(move |page_index| {
+        let ids = zalsa.table().ids_in_use(page_index);
+        ids.chunks(chunk_size)
+            .map(|chunk| chunk.iter().map(|&id| S::from_id(id)).collect())
+            .collect::<Vec<Vec<S>>>()
+    })
+}
+
 /// This enum _must not_ be public or used outside of `par_map`.
 enum ParallelDb<'db> {
     Ref(&'db dyn Database),
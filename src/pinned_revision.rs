@@ -0,0 +1,38 @@
+use crate::{Database, Revision};
+
+/// Keeps the revision of a database handle from changing, so that the queries executed
+/// through it while the guard lives observe one consistent revision;
+/// see [`Database::pin_revision`].
+pub struct PinnedRevision<'db> {
+    db: &'db dyn Database,
+    revision: Revision,
+}
+
+impl<'db> PinnedRevision<'db> {
+    pub(crate) fn new(db: &'db dyn Database) -> Self {
+        db.zalsa_local().pin_revision();
+        Self {
+            db,
+            revision: db.zalsa().current_revision(),
+        }
+    }
+
+    /// The revision that the queries observe.
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+}
+
+impl Drop for PinnedRevision<'_> {
+    fn drop(&mut self) {
+        self.db.zalsa_local().unpin_revision();
+    }
+}
+
+impl std::fmt::Debug for PinnedRevision<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PinnedRevision")
+            .field("revision", &self.revision)
+            .finish_non_exhaustive()
+    }
+}
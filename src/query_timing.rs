@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use crate::{key::DatabaseKeyIndex, Database};
+
+/// The time spent executing a tracked function for one key, recorded with the
+/// `query_timing` feature; see [`Database::top_expensive_queries`](`crate::Database::top_expensive_queries`).
+///
+/// Durations include the time spent in the queries that the function called
+/// and that were executed as part of it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExecutionTime {
+    /// The duration of the last execution.
+    pub last: Duration,
+
+    /// The total duration of all executions, since the value was first computed.
+    pub total: Duration,
+
+    /// The number of executions.
+    pub executions: u32,
+}
+
+impl ExecutionTime {
+    /// Returns these times updated with an execution that took `elapsed`.
+    pub(crate) fn record(self, elapsed: Duration) -> Self {
+        Self {
+            last: elapsed,
+            total: self.total + elapsed,
+            executions: self.executions + 1,
+        }
+    }
+}
+
+pub(crate) fn top_expensive_queries(
+    db: &dyn Database,
+    n: usize,
+) -> Vec<(DatabaseKeyIndex, ExecutionTime)> {
+    let zalsa = db.zalsa();
+    let mut queries = vec![];
+
+    // SAFETY: `current_revision` is the current revision of the database owning the table.
+    unsafe {
+        zalsa.table().for_each_memo(
+            zalsa.current_revision(),
+            &mut |struct_index, id, memo_ingredient_index, memo| {
+                let Some(time) = memo.execution_time() else {
+                    return;
+                };
+                let ingredient_index =
+                    zalsa.ingredient_index_for_memo(struct_index, memo_ingredient_index);
+                let database_key = DatabaseKeyIndex {
+                    ingredient_index,
+                    key_index: id,
+                };
+                queries.push((database_key, time));
+            },
+        );
+    }
+
+    queries.sort_by_key(|(_, time)| std::cmp::Reverse(time.total));
+    queries.truncate(n);
+    queries
+}
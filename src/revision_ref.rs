@@ -0,0 +1,81 @@
+use std::{fmt, ops::Deref};
+
+use crate::{zalsa::Zalsa, Database, Revision};
+
+/// A reference to a memoized value, returned by tracked functions declared with
+/// `#[salsa::tracked(return_ref, strict_revisions)]`.
+///
+/// Memoized values can be freed once a new revision starts, and the lifetime of a
+/// reference to one only lasts as long as the borrow of the database it was read from.
+/// If the reference nonetheless outlives its revision (e.g. because `unsafe` code
+/// extended its lifetime), dereferencing it panics instead of reading freed memory.
+pub struct RevisionRef<'db, T: ?Sized> {
+    value: &'db T,
+    zalsa: &'db Zalsa,
+    revision: Revision,
+}
+
+impl<'db, T: ?Sized> RevisionRef<'db, T> {
+    pub(crate) fn new(db: &'db dyn Database, value: &'db T) -> Self {
+        let zalsa = db.zalsa();
+        Self {
+            value,
+            zalsa,
+            revision: zalsa.current_revision(),
+        }
+    }
+
+    /// Returns the plain reference, after checking that the revision has not changed.
+    ///
+    /// # Panics
+    ///
+    /// If a new revision started since the value was returned.
+    #[track_caller]
+    pub fn get(this: Self) -> &'db T {
+        let current_revision = this.zalsa.current_revision();
+        assert_eq!(
+            this.revision, current_revision,
+            "reference returned in {:?} was used in {:?}",
+            this.revision, current_revision,
+        );
+        this.value
+    }
+}
+
+/// Wraps the memoized value returned by a `strict_revisions` tracked function.
+/// Used by the macro-generated code.
+#[inline]
+pub fn revision_ref<'db, T: ?Sized>(db: &'db dyn Database, value: &'db T) -> RevisionRef<'db, T> {
+    RevisionRef::new(db, value)
+}
+
+impl<T: ?Sized> Clone for RevisionRef<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for RevisionRef<'_, T> {}
+
+impl<T: ?Sized> Deref for RevisionRef<'_, T> {
+    type Target = T;
+
+    #[track_caller]
+    fn deref(&self) -> &T {
+        Self::get(*self)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RevisionRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: ?Sized + PartialEq> PartialEq for RevisionRef<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: ?Sized + Eq> Eq for RevisionRef<'_, T> {}
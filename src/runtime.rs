@@ -1,23 +1,30 @@
 use std::{
     mem,
     panic::panic_any,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
+    sync::atomic::{AtomicBool, Ordering},
     thread::ThreadId,
+    time::Instant,
 };
 
+use crossbeam::atomic::AtomicCell;
 use parking_lot::Mutex;
 
 use crate::{
-    active_query::ActiveQuery, cycle::CycleRecoveryStrategy, durability::Durability,
-    key::DatabaseKeyIndex, revision::AtomicRevision, table::Table, zalsa_local::ZalsaLocal,
-    Cancelled, Cycle, Database, Event, EventKind, Revision,
+    active_query::ActiveQuery,
+    channel::{Channel, Channels},
+    cycle::CycleRecoveryStrategy,
+    durability::Durability,
+    key::DatabaseKeyIndex,
+    revision::AtomicRevision,
+    table::Table,
+    zalsa_local::ZalsaLocal,
+    CancellationMode, Cancelled, Cycle, Database, Event, EventKind, Revision,
 };
 
-use self::dependency_graph::DependencyGraph;
+use self::{change_set::ChangeLog, deadlock::DeadlockWatchdog, dependency_graph::DependencyGraph};
 
+pub(crate) mod change_set;
+pub(crate) mod deadlock;
 mod dependency_graph;
 
 pub struct Runtime {
@@ -37,12 +44,35 @@ pub struct Runtime {
     /// with durability less than D may have changed too.
     revisions: [AtomicRevision; Durability::LEN],
 
+    /// Stores the "last change" revision of the inputs of each channel,
+    /// see [`Channel`].
+    channel_revisions: [AtomicRevision; Channel::LEN],
+
     /// The dependency graph tracks which runtimes are blocked on one
     /// another, waiting for queries to terminate.
     dependency_graph: Mutex<DependencyGraph>,
 
     /// Data for instances
     table: Table,
+
+    /// The inputs changed in the current revision, not yet delivered to listeners.
+    changes: ChangeLog,
+
+    /// See [`Database::set_deterministic`](`crate::Database::set_deterministic`).
+    deterministic: AtomicBool,
+
+    /// If true, memos are not reused across revisions, see [`check_incremental`](`crate::check_incremental`).
+    #[cfg(feature = "testing")]
+    from_scratch: AtomicBool,
+
+    /// Whether pending writes cancel running queries by unwinding.
+    cancellation_mode: CancellationMode,
+
+    /// See [`StorageBuilder::deadlock_timeout`](`crate::StorageBuilder::deadlock_timeout`).
+    deadlock_watchdog: Option<DeadlockWatchdog>,
+
+    /// See [`Database::set_default_intern_durability`](`crate::Database::set_default_intern_durability`).
+    default_intern_durability: AtomicCell<Durability>,
 }
 
 #[derive(Clone, Debug)]
@@ -50,6 +80,15 @@ pub(crate) enum WaitResult {
     Completed,
     Panicked,
     Cycle(Cycle),
+
+    /// The wait exceeded the deadlock timeout and was abandoned.
+    DeadlockDetected,
+
+    /// The wait was abandoned because another handle is waiting to write.
+    Cancelled,
+
+    /// The other thread abandoned the query because a query it was executing for timed out.
+    TimedOut,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -57,6 +96,7 @@ pub struct StampedValue<V> {
     pub value: V,
     pub durability: Durability,
     pub changed_at: Revision,
+    pub(crate) channels: Channels,
 }
 
 pub type Stamp = StampedValue<()>;
@@ -66,6 +106,7 @@ pub fn stamp(revision: Revision, durability: Durability) -> Stamp {
         value: (),
         durability,
         changed_at: revision,
+        channels: Channels::default(),
     }
 }
 
@@ -75,6 +116,7 @@ impl<V> StampedValue<V> {
     pub(crate) fn merge_revision_info<U>(&mut self, other: &StampedValue<U>) {
         self.durability = self.durability.min(other.durability);
         self.changed_at = self.changed_at.max(other.changed_at);
+        self.channels = self.channels.union(other.channels);
     }
 }
 
@@ -82,9 +124,17 @@ impl Default for Runtime {
     fn default() -> Self {
         Runtime {
             revisions: [const { AtomicRevision::start() }; Durability::LEN],
+            channel_revisions: [const { AtomicRevision::start() }; Channel::LEN],
             revision_canceled: Default::default(),
             dependency_graph: Default::default(),
             table: Default::default(),
+            changes: Default::default(),
+            deterministic: Default::default(),
+            #[cfg(feature = "testing")]
+            from_scratch: Default::default(),
+            cancellation_mode: Default::default(),
+            deadlock_watchdog: None,
+            default_intern_durability: AtomicCell::new(Durability::MAX),
         }
     }
 }
@@ -104,14 +154,37 @@ impl Runtime {
         self.revisions[0].load()
     }
 
+    pub(crate) fn changes(&self) -> &ChangeLog {
+        &self.changes
+    }
+
+    /// Delivers the inputs changed in the current revision to the listeners
+    /// registered with [`Storage::on_change`](`crate::Storage::on_change`),
+    /// unless the writes are part of a batch.
+    pub(crate) fn deliver_changes(&self) {
+        self.changes.deliver(self.current_revision());
+    }
+
     /// Reports that an input with durability `durability` changed.
     /// This will update the 'last changed at' values for every durability
     /// less than or equal to `durability` to the current revision.
-    pub(crate) fn report_tracked_write(&mut self, durability: Durability) {
+    /// As the channel of the input is not known, every channel is updated too.
+    ///
+    /// Only takes `&self` so that the writes of a [`WriteScope`](`crate::WriteScope`)
+    /// can report concurrently; otherwise this requires `&mut` access to the database.
+    pub(crate) fn report_tracked_write(&self, durability: Durability) {
+        self.report_tracked_write_in(durability, Channels::ALL);
+    }
+
+    /// Like [`Self::report_tracked_write`], for an input in the given `channels`.
+    pub(crate) fn report_tracked_write_in(&self, durability: Durability, channels: Channels) {
         let new_revision = self.current_revision();
         for rev in &self.revisions[1..=durability.index()] {
             rev.store(new_revision);
         }
+        for index in channels.indices() {
+            self.channel_revisions[index].store(new_revision);
+        }
     }
 
     /// The revision in which values with durability `d` may have last
@@ -126,18 +199,80 @@ impl Runtime {
         self.revisions[d.index()].load()
     }
 
+    /// The revision in which values read from inputs of the given `channels`
+    /// may have last changed; like [`Self::last_changed_revision`], this bounds
+    /// when a value that only read such inputs may have changed.
+    pub(crate) fn last_changed_revision_in(&self, channels: Channels) -> Revision {
+        // Values that read untracked state must be revalidated in every new revision,
+        // even if no input changed.
+        if channels == Channels::ALL {
+            return self.current_revision();
+        }
+        channels
+            .indices()
+            .map(|index| self.channel_revisions[index].load())
+            .max()
+            .unwrap_or(Revision::start())
+    }
+
     pub(crate) fn load_cancellation_flag(&self) -> bool {
         self.revision_canceled.load(Ordering::Acquire)
     }
 
     pub(crate) fn set_cancellation_flag(&self) {
         self.revision_canceled.store(true, Ordering::Release);
+
+        // Wake the threads blocked on queries executing in other threads, so that
+        // they unwind now rather than once those queries complete.
+        self.dependency_graph.lock().wake_blocked();
+    }
+
+    pub(crate) fn clear_cancellation_flag(&self) {
+        self.revision_canceled.store(false, Ordering::Release);
     }
 
     pub(crate) fn table(&self) -> &Table {
         &self.table
     }
 
+    pub(crate) fn set_cancellation_mode(&mut self, mode: CancellationMode) {
+        self.cancellation_mode = mode;
+    }
+
+    pub(crate) fn set_deadlock_watchdog(&mut self, watchdog: Option<DeadlockWatchdog>) {
+        self.deadlock_watchdog = watchdog;
+    }
+
+    pub(crate) fn cancellation_mode(&self) -> CancellationMode {
+        self.cancellation_mode
+    }
+
+    pub(crate) fn set_deterministic(&self, deterministic: bool) {
+        self.deterministic.store(deterministic, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_deterministic(&self) -> bool {
+        self.deterministic.load(Ordering::Relaxed)
+    }
+
+    #[cfg(feature = "testing")]
+    pub(crate) fn set_from_scratch(&self, from_scratch: bool) {
+        self.from_scratch.store(from_scratch, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "testing")]
+    pub(crate) fn is_from_scratch(&self) -> bool {
+        self.from_scratch.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_default_intern_durability(&self, durability: Durability) {
+        self.default_intern_durability.store(durability);
+    }
+
+    pub(crate) fn default_intern_durability(&self) -> Durability {
+        self.default_intern_durability.load()
+    }
+
     /// Increments the "current revision" counter and clears
     /// the cancellation flag.
     ///
@@ -162,6 +297,12 @@ impl Runtime {
     /// If the thread `other_id` panics, then our thread is considered
     /// cancelled, so this function will panic with a `Cancelled` value.
     ///
+    /// # Suspected deadlocks
+    ///
+    /// If the wait exceeds the deadlock timeout, it is reported with a
+    /// [`EventKind::DidDetectDeadlock`] event; with `panic_on_deadlock`, this function
+    /// then panics with [`Cancelled::DeadlockDetected`].
+    ///
     /// # Cycle handling
     ///
     /// If the thread `other_id` already depends on the current thread,
@@ -191,13 +332,14 @@ impl Runtime {
             assert!(!dg.depends_on(other_id, thread_id));
         }
 
-        db.salsa_event(&|| {
+        crate::event::emit(db, &|| {
             Event::new(EventKind::WillBlockOn {
                 other_thread_id: other_id,
                 database_key,
             })
         });
 
+        let start = Instant::now();
         let result = local_state.with_query_stack(|stack| {
             let (new_stack, result) = DependencyGraph::block_on(
                 dg,
@@ -206,20 +348,46 @@ impl Runtime {
                 other_id,
                 mem::take(stack),
                 query_mutex_guard,
+                self.deadlock_watchdog,
+                |report| {
+                    tracing::warn!("{report}");
+                    crate::event::emit(db, &|| {
+                        Event::new(EventKind::DidDetectDeadlock {
+                            report: report.clone(),
+                        })
+                    });
+                },
+                || {
+                    self.cancellation_mode == CancellationMode::Unwind
+                        && local_state.is_cancelled(db.zalsa())
+                },
             );
             *stack = new_stack;
             result
         });
+        local_state.report_blocked(start.elapsed());
 
         match result {
             WaitResult::Completed => (),
 
+            // A pinned revision must not be cancelled by a pending write: the other thread
+            // was most likely cancelled by it, so retry, executing the query ourselves.
+            WaitResult::Panicked
+                if local_state.is_revision_pinned() && self.load_cancellation_flag() => {}
+
             // If the other thread panicked, then we consider this thread
             // cancelled. The assumption is that the panic will be detected
             // by the other thread and responded to appropriately.
             WaitResult::Panicked => Cancelled::PropagatedPanic.throw(),
 
+            // The query itself did not fail, so retry, executing it ourselves.
+            WaitResult::TimedOut => (),
+
             WaitResult::Cycle(c) => c.throw(),
+
+            WaitResult::DeadlockDetected => Cancelled::DeadlockDetected.throw(),
+
+            WaitResult::Cancelled => local_state.unwind_cancelled(self.current_revision()),
         }
     }
 
@@ -250,7 +418,7 @@ impl Runtime {
             // Make a "dummy stack frame". As we iterate through the cycle, we will collect the
             // inputs from each participant. Then, if we are participating in cycle recovery, we
             // will propagate those results to all participants.
-            let mut cycle_query = ActiveQuery::new(database_key_index);
+            let mut cycle_query = ActiveQuery::new(database_key_index, None);
 
             // Identify the cycle participants:
             let cycle = {
@@ -271,18 +439,27 @@ impl Runtime {
                 // We want to give the participants in a deterministic order
                 // (at least for this execution, not necessarily across executions),
                 // no matter where it started on the stack. Find the minimum
-                // key and rotate it to the front.
-
-                if let Some((_, index)) = v
+                // key and rotate it to the front. In deterministic mode, ties are
+                // broken by the key rather than the position on the stack, so that the
+                // order does not depend on which thread detected the cycle.
+                let deterministic = self.is_deterministic();
+                if let Some((_, _, index)) = v
                     .iter()
                     .enumerate()
-                    .map(|(idx, key)| (key.ingredient_index.debug_name(db), idx))
+                    .map(|(idx, key)| {
+                        let tiebreak = if deterministic {
+                            (key.ingredient_index.as_u32(), key.key_index.as_u32())
+                        } else {
+                            (0, 0)
+                        };
+                        (key.ingredient_index.debug_name(db), tiebreak, idx)
+                    })
                     .min()
                 {
                     v.rotate_left(index);
                 }
 
-                Cycle::new(Arc::new(v.into_boxed_slice()))
+                Cycle::new(v.into_boxed_slice(), database_key_index)
             };
             tracing::debug!("cycle {cycle:?}, cycle_query {cycle_query:#?}");
 
@@ -0,0 +1,110 @@
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+use parking_lot::Mutex;
+
+use crate::{hash::FxIndexSet, key::DatabaseKeyIndex, Revision};
+
+/// The inputs changed in a revision, delivered to the listeners registered with
+/// [`Storage::on_change`](`crate::Storage::on_change`).
+///
+/// A single set produces a change set of its own, while all the sets made within a
+/// [`Database::transaction`](`crate::Database::transaction`) or
+/// [`Database::write_scope`](`crate::Database::write_scope`) are coalesced into one.
+#[derive(Clone, Debug)]
+pub struct ChangeSet {
+    revision: Revision,
+    changed: Arc<[DatabaseKeyIndex]>,
+}
+
+impl ChangeSet {
+    /// The revision in which the inputs were changed.
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// The input fields that were set, in the order they were first set.
+    /// Each field appears once even if it was set several times.
+    pub fn changed(&self) -> &[DatabaseKeyIndex] {
+        &self.changed
+    }
+}
+
+/// Identifies a listener registered with [`Storage::on_change`](`crate::Storage::on_change`),
+/// so that it can later be removed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChangeListenerId(u64);
+
+type ChangeListenerFn = dyn Fn(&ChangeSet) + Send + Sync;
+
+/// Collects the inputs changed in the current revision and delivers them to listeners.
+#[derive(Default)]
+pub(crate) struct ChangeLog {
+    pending: Mutex<FxIndexSet<DatabaseKeyIndex>>,
+
+    /// Number of open batches (transactions or write scopes); while non-zero,
+    /// delivery is deferred until the outermost batch ends.
+    batch_depth: AtomicUsize,
+
+    next_id: AtomicU64,
+    listeners: Mutex<Vec<(ChangeListenerId, Arc<ChangeListenerFn>)>>,
+}
+
+impl ChangeLog {
+    pub(crate) fn add_listener(
+        &self,
+        listener: impl Fn(&ChangeSet) + Send + Sync + 'static,
+    ) -> ChangeListenerId {
+        let id = ChangeListenerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.listeners.lock().push((id, Arc::new(listener)));
+        id
+    }
+
+    pub(crate) fn remove_listener(&self, id: ChangeListenerId) -> bool {
+        let mut listeners = self.listeners.lock();
+        let len = listeners.len();
+        listeners.retain(|&(other, _)| other != id);
+        listeners.len() != len
+    }
+
+    pub(crate) fn record(&self, key: DatabaseKeyIndex) {
+        self.pending.lock().insert(key);
+    }
+
+    pub(crate) fn begin_batch(&self) {
+        self.batch_depth.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn end_batch(&self, revision: Revision) {
+        self.batch_depth.fetch_sub(1, Ordering::Relaxed);
+        self.deliver(revision);
+    }
+
+    /// Delivers the changes recorded so far, unless a batch is open.
+    pub(crate) fn deliver(&self, revision: Revision) {
+        if self.batch_depth.load(Ordering::Relaxed) != 0 {
+            return;
+        }
+        let changed = std::mem::take(&mut *self.pending.lock());
+        if changed.is_empty() {
+            return;
+        }
+
+        // Call the listeners without holding the lock, so that they may (un)register listeners.
+        let listeners: Vec<_> = self
+            .listeners
+            .lock()
+            .iter()
+            .map(|(_, listener)| listener.clone())
+            .collect();
+        let change_set = ChangeSet {
+            revision,
+            changed: changed.into_iter().collect(),
+        };
+        for listener in listeners {
+            listener(&change_set);
+        }
+    }
+}
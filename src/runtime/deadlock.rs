@@ -0,0 +1,124 @@
+use std::{fmt, thread::ThreadId, time::Duration};
+
+use crate::{key::DatabaseKeyIndex, Backtrace};
+
+/// How long a thread may wait on a query executing in another thread before the wait
+/// is reported as a suspected deadlock, see
+/// [`StorageBuilder::deadlock_timeout`](`crate::StorageBuilder::deadlock_timeout`).
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct DeadlockWatchdog {
+    pub(crate) timeout: Duration,
+
+    /// If true, the waiting thread unwinds with
+    /// [`Cancelled::DeadlockDetected`](`crate::Cancelled::DeadlockDetected`)
+    /// once the wait has been reported.
+    pub(crate) panic: bool,
+}
+
+/// A wait on another thread that exceeded the
+/// [`deadlock_timeout`](`crate::StorageBuilder::deadlock_timeout`),
+/// delivered with [`EventKind::DidDetectDeadlock`](`crate::EventKind::DidDetectDeadlock`).
+///
+/// The report follows the chain of waits starting with the thread that timed out:
+/// each thread in [`Self::waits`] is blocked on a query executed by the next one,
+/// and the last one is blocked on a query executed by [`Self::running_thread`].
+#[derive(Clone, Debug)]
+pub struct DeadlockReport {
+    waited: Duration,
+    waits: Vec<BlockedThread>,
+    running_thread: ThreadId,
+}
+
+/// A thread in a [`DeadlockReport`] that is blocked on a query executing in another thread.
+#[derive(Clone, Debug)]
+pub struct BlockedThread {
+    thread_id: ThreadId,
+    blocked_on: DatabaseKeyIndex,
+    backtrace: Backtrace,
+}
+
+impl DeadlockReport {
+    pub(crate) fn new(
+        waited: Duration,
+        waits: Vec<BlockedThread>,
+        running_thread: ThreadId,
+    ) -> Self {
+        debug_assert!(!waits.is_empty());
+        Self {
+            waited,
+            waits,
+            running_thread,
+        }
+    }
+
+    /// How long the first thread had been waiting.
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+
+    /// The blocked threads, starting with the thread that timed out.
+    pub fn waits(&self) -> &[BlockedThread] {
+        &self.waits
+    }
+
+    /// The thread executing the query the last blocked thread waits on.
+    /// It is not blocked on another query, so it may be blocked outside of salsa
+    /// (e.g. on a lock held by one of the blocked threads) or just slow.
+    ///
+    /// Its query stack is not known, but its innermost query
+    /// is the one the last blocked thread waits on.
+    pub fn running_thread(&self) -> ThreadId {
+        self.running_thread
+    }
+}
+
+impl BlockedThread {
+    pub(crate) fn new(
+        thread_id: ThreadId,
+        blocked_on: DatabaseKeyIndex,
+        backtrace: Backtrace,
+    ) -> Self {
+        Self {
+            thread_id,
+            blocked_on,
+            backtrace,
+        }
+    }
+
+    pub fn thread_id(&self) -> ThreadId {
+        self.thread_id
+    }
+
+    /// The query the thread waits on.
+    pub fn blocked_on(&self) -> DatabaseKeyIndex {
+        self.blocked_on
+    }
+
+    /// The queries the thread was executing when it blocked.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.backtrace
+    }
+}
+
+impl fmt::Display for DeadlockReport {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            fmt,
+            "{:?} has been waiting for {:?}, a deadlock is suspected:",
+            self.waits[0].thread_id, self.waited
+        )?;
+        for wait in &self.waits {
+            writeln!(
+                fmt,
+                "{:?} is blocked on {:?}, with {}",
+                wait.thread_id, wait.blocked_on, wait.backtrace
+            )?;
+        }
+        let last = self.waits.last().unwrap();
+        writeln!(
+            fmt,
+            "{:?} is executing {:?} and is not blocked on another query",
+            self.running_thread, last.blocked_on
+        )
+    }
+}
@@ -1,8 +1,10 @@
 use std::sync::Arc;
 use std::thread::ThreadId;
+use std::time::{Duration, Instant};
 
-use crate::active_query::ActiveQuery;
+use crate::active_query::{ActiveQuery, Backtrace};
 use crate::key::DatabaseKeyIndex;
+use crate::runtime::deadlock::{BlockedThread, DeadlockReport, DeadlockWatchdog};
 use crate::runtime::WaitResult;
 use parking_lot::{Condvar, MutexGuard};
 use rustc_hash::FxHashMap;
@@ -177,6 +179,15 @@ impl DependencyGraph {
     /// * No path from `to_id` to `from_id`
     ///   (i.e., `me.depends_on(to_id, from_id)` is false)
     /// * `held_mutex` is a read lock (or stronger) on `database_key`
+    ///
+    /// If the wait exceeds the timeout of `watchdog`, `on_deadlock` is invoked with a
+    /// [`DeadlockReport`]; if the watchdog panics, the edge is then removed and
+    /// `WaitResult::DeadlockDetected` returned.
+    ///
+    /// `cancelled` is checked whenever the thread is woken, including by
+    /// [`Self::wake_blocked`]; if it returns true, the edge is removed and
+    /// `WaitResult::Cancelled` returned.
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn block_on<QueryMutexGuard>(
         mut me: MutexGuard<'_, Self>,
         from_id: ThreadId,
@@ -184,6 +195,9 @@ impl DependencyGraph {
         to_id: ThreadId,
         from_stack: QueryStack,
         query_mutex_guard: QueryMutexGuard,
+        watchdog: Option<DeadlockWatchdog>,
+        on_deadlock: impl FnOnce(&DeadlockReport),
+        cancelled: impl Fn() -> bool,
     ) -> (QueryStack, WaitResult) {
         let condvar = me.add_edge(from_id, database_key, to_id, from_stack);
 
@@ -191,13 +205,68 @@ impl DependencyGraph {
         // from completing, now that the edge has been added.
         drop(query_mutex_guard);
 
+        let start = Instant::now();
+        let mut deadline = watchdog.map(|watchdog| start + watchdog.timeout);
+        let mut on_deadlock = Some(on_deadlock);
         loop {
             if let Some(stack_and_result) = me.wait_results.remove(&from_id) {
                 debug_assert!(!me.edges.contains_key(&from_id));
                 return stack_and_result;
             }
-            condvar.wait(&mut me);
+            if cancelled() {
+                let edge = me.remove_edge(from_id);
+                return (edge.stack, WaitResult::Cancelled);
+            }
+            match deadline {
+                Some(instant) if Instant::now() >= instant => {
+                    let report = me.deadlock_report(from_id, start.elapsed());
+                    if let Some(on_deadlock) = on_deadlock.take() {
+                        on_deadlock(&report);
+                    }
+                    if watchdog.is_some_and(|watchdog| watchdog.panic) {
+                        let edge = me.remove_edge(from_id);
+                        return (edge.stack, WaitResult::DeadlockDetected);
+                    }
+                    // Report the wait once, then keep waiting.
+                    deadline = None;
+                }
+                Some(instant) => {
+                    condvar.wait_until(&mut me, instant);
+                }
+                None => condvar.wait(&mut me),
+            }
+        }
+    }
+
+    /// Describes the chain of waits starting with `from_id`, which has been blocked for `waited`.
+    fn deadlock_report(&self, from_id: ThreadId, waited: Duration) -> DeadlockReport {
+        let mut waits = vec![];
+        let mut id = from_id;
+        while let Some(edge) = self.edges.get(&id) {
+            waits.push(BlockedThread::new(
+                id,
+                edge.blocked_on_key,
+                Backtrace::from_stack(&edge.stack),
+            ));
+            id = edge.blocked_on_id;
+            if id == from_id {
+                // Cycles are resolved before blocking, so this is not expected.
+                break;
+            }
+        }
+        DeadlockReport::new(waited, waits, id)
+    }
+
+    /// Removes the edge of `from_id`, which gives up waiting.
+    fn remove_edge(&mut self, from_id: ThreadId) -> Edge {
+        let edge = self.edges.remove(&from_id).expect("not blocked");
+        if let Some(dependents) = self.query_dependents.get_mut(&edge.blocked_on_key) {
+            dependents.retain(|id| *id != from_id);
+            if dependents.is_empty() {
+                self.query_dependents.remove(&edge.blocked_on_key);
+            }
         }
+        edge
     }
 
     /// Helper for `block_on`: performs actual graph modification
@@ -231,6 +300,14 @@ impl DependencyGraph {
         condvar
     }
 
+    /// Wakes all blocked threads without a result, so that they check whether
+    /// they were cancelled.
+    pub(super) fn wake_blocked(&self) {
+        for edge in self.edges.values() {
+            edge.condvar.notify_one();
+        }
+    }
+
     /// Invoked when runtime `to_id` completes executing
     /// `database_key`.
     pub(super) fn unblock_runtimes_blocked_on(
@@ -1,5 +1,75 @@
-use crate::{plumbing::JarAux, IngredientIndex};
+use crate::{
+    id::{AsId, FromId},
+    plumbing::JarAux,
+    Database, Id, IngredientIndex,
+};
 
 pub trait SalsaStructInDb {
     fn lookup_ingredient_index(aux: &dyn JarAux) -> Option<IngredientIndex>;
+
+    /// The ingredients of all the salsa structs a value of this type can be.
+    ///
+    /// This is the ingredient of the struct itself for plain salsa structs,
+    /// and the ingredients of every variant for `#[derive(salsa::Supertype)]` enums.
+    fn lookup_ingredient_indices(aux: &dyn JarAux) -> Vec<IngredientIndex> {
+        Self::lookup_ingredient_index(aux).into_iter().collect()
+    }
+
+    /// Creates the ingredients of the salsa structs a value of this type can be,
+    /// if they were not created yet.
+    fn register_ingredients(db: &dyn Database) {
+        _ = db;
+    }
+}
+
+/// Converts an id back into a value of a salsa struct type
+/// whose variant depends on the struct that owns the id,
+/// like the enums of `#[derive(salsa::Supertype)]`.
+pub trait FromIdWithDb: AsId + Sized {
+    fn from_id(id: Id, db: &dyn Database) -> Self;
+}
+
+impl<T: FromId> FromIdWithDb for T {
+    fn from_id(id: Id, _db: &dyn Database) -> Self {
+        FromId::from_id(id)
+    }
+}
+
+/// The ingredients of the salsa structs `S` can be, creating them if needed.
+/// Used by `#[derive(salsa::Supertype)]` to find the variant of an id.
+pub fn ingredient_indices<S: SalsaStructInDb>(db: &dyn Database) -> Vec<IngredientIndex> {
+    S::register_ingredients(db);
+    db.zalsa().lookup_struct_ingredients::<S>()
+}
+
+/// The ingredient of the salsa struct that owns `id`.
+pub fn owner_ingredient(db: &dyn Database, id: Id) -> IngredientIndex {
+    db.zalsa()
+        .table()
+        .owner(id)
+        .expect("id does not belong to a salsa struct")
+}
+
+/// Returns true if `handle` refers to a struct that no longer exists in the database,
+/// e.g. a tracked struct that was deleted because the query that created it
+/// re-executed without creating it again.
+///
+/// Inputs and interned structs are never deleted individually, so their handles are never stale.
+///
+/// Ids do not record the generation of their slot: once the slot of a deleted tracked struct
+/// has been reused for a new one, an old handle is indistinguishable from a handle to the
+/// new struct, and this returns false.
+pub fn is_stale<S>(db: &dyn Database, handle: S) -> bool
+where
+    S: SalsaStructInDb + AsId,
+{
+    let zalsa = db.zalsa();
+    let id = handle.as_id();
+    match zalsa.table().owner(id) {
+        Some(owner) => zalsa
+            .lookup_ingredient(owner)
+            .id_generation(db, id)
+            .is_none(),
+        None => true,
+    }
 }
@@ -0,0 +1,97 @@
+use std::fmt;
+
+use crate::{channel::Channels, nonce::Nonce, zalsa::StorageNonce, Database, Durability, Revision};
+
+/// A value exported from a database, e.g. an index serialized for another subsystem,
+/// tagged with the revision it was computed in, so that it can later be checked for
+/// staleness cheaply, without holding a reference to the database.
+///
+/// Like a memoized value that is validated without walking its dependencies, a snapshot
+/// is current as long as no input of the durability (and channels) it was computed
+/// from changed; it is conservative, as such changes may not have affected it.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Snapshot<T> {
+    value: T,
+    revision: Revision,
+    durability: Durability,
+    channels: Channels,
+    nonce: Nonce<StorageNonce>,
+}
+
+impl<T> Snapshot<T> {
+    /// Captures `value`, computed from the current state of `db`.
+    ///
+    /// Within a query, the snapshot depends on the inputs read by the query so far
+    /// (so it should be taken once the value is computed): it becomes stale once an
+    /// input of their durability or channels changes.
+    /// Outside of queries, it becomes stale once any input changes.
+    pub fn new(db: &dyn Database, value: T) -> Self {
+        let (durability, channels) = match db.zalsa_local().active_query() {
+            Some((_, stamp)) => (stamp.durability, stamp.channels),
+            None => (Durability::LOW, Channels::ALL),
+        };
+        Self::with_channels(db, value, durability, channels)
+    }
+
+    /// Captures `value`, computed from inputs of at least the given durability, so that
+    /// the snapshot only becomes stale once such an input changes.
+    /// The durability of a memoized value is given by
+    /// [`Database::max_durability_of`](`crate::Database::max_durability_of`).
+    pub fn with_durability(db: &dyn Database, value: T, durability: Durability) -> Self {
+        Self::with_channels(db, value, durability, Channels::ALL)
+    }
+
+    fn with_channels(
+        db: &dyn Database,
+        value: T,
+        durability: Durability,
+        channels: Channels,
+    ) -> Self {
+        let zalsa = db.zalsa();
+        Self {
+            value,
+            revision: zalsa.current_revision(),
+            durability,
+            channels,
+            nonce: zalsa.nonce(),
+        }
+    }
+
+    /// True if no input the value may depend on has changed since it was captured.
+    /// Always false for a database other than the one the snapshot was taken from.
+    pub fn is_current(&self, db: &dyn Database) -> bool {
+        let zalsa = db.zalsa();
+        zalsa.nonce() == self.nonce
+            && (zalsa.last_changed_revision(self.durability) <= self.revision
+                || zalsa.last_changed_revision_in(self.channels) <= self.revision)
+    }
+
+    /// The revision the value was captured in.
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// The durability of the inputs the value may depend on.
+    pub fn durability(&self) -> Durability {
+        self.durability
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("value", &self.value)
+            .field("revision", &self.revision)
+            .field("durability", &self.durability)
+            .field("channels", &self.channels)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,14 +1,22 @@
-use std::{marker::PhantomData, panic::RefUnwindSafe, sync::Arc};
+use std::{marker::PhantomData, panic::RefUnwindSafe, sync::Arc, time::Duration};
 
 use parking_lot::{Condvar, Mutex};
 
 use crate::{
+    memo_stats::MemoStats,
     plumbing::{input, interned, tracked_struct},
+    runtime::{
+        change_set::{ChangeListenerId, ChangeSet},
+        deadlock::DeadlockWatchdog,
+    },
     zalsa::{Zalsa, ZalsaDatabase},
     zalsa_local::{self, ZalsaLocal},
-    Database, Event, EventKind,
+    CancellationMode, Database, Event, EventFilter, EventKind, SubscriberId,
 };
 
+#[cfg(feature = "testing")]
+use crate::event::Subscribers;
+
 /// Access the "storage" of a Salsa database: this is an internal plumbing trait
 /// automatically implemented by `#[salsa::db]` applied to a struct.
 ///
@@ -60,7 +68,179 @@ impl<Db: Database> Default for Storage<Db> {
     }
 }
 
+/// Options set with a [`StorageBuilder`] that apply to the ingredients of a database.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StorageOptions {
+    /// See [`StorageBuilder::parallelism`].
+    pub(crate) parallelism: Option<usize>,
+
+    /// See [`StorageBuilder::interned_shards`].
+    pub(crate) interned_shards: Option<usize>,
+
+    /// See [`StorageBuilder::default_lru`].
+    pub(crate) default_lru: usize,
+
+    /// See [`StorageBuilder::memo_layout_hint`].
+    pub(crate) memo_layout_hint: Option<MemoStats>,
+}
+
+impl StorageOptions {
+    /// The number of shards of concurrent maps, if derived from [`StorageBuilder::parallelism`]
+    /// rather than from the available parallelism of the process (as `dashmap` does).
+    pub(crate) fn shards(&self) -> Option<usize> {
+        self.parallelism
+            .map(|threads| (threads * 4).next_power_of_two())
+    }
+}
+
+/// Configures the storage of a new database, see [`Storage::builder`].
+pub struct StorageBuilder<Db: Database> {
+    options: StorageOptions,
+    cancellation_mode: CancellationMode,
+    deadlock_watchdog: Option<DeadlockWatchdog>,
+    #[allow(clippy::type_complexity)]
+    event_handlers: Vec<(
+        EventFilter,
+        Box<dyn Fn(&dyn Database, &Event) + Send + Sync>,
+    )>,
+    phantom: PhantomData<fn() -> Db>,
+}
+
+impl<Db: Database> StorageBuilder<Db> {
+    /// Sets the number of threads expected to use the database at once, from which the
+    /// number of shards of its concurrent maps (e.g. those used to look up interned values)
+    /// is derived.
+    ///
+    /// Defaults to the available parallelism of the process, which is read once for all
+    /// databases and may not reflect the CPUs actually available (e.g. in a container
+    /// limited by cgroups) or the workload of each database.
+    ///
+    /// # Panics
+    ///
+    /// If `threads` is zero.
+    pub fn parallelism(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "the parallelism must be at least one");
+        self.options.parallelism = Some(threads);
+        self
+    }
+
+    /// Sets the number of shards of the maps used to look up interned values.
+    /// More shards reduce contention when many threads intern at once.
+    /// Defaults to a multiple of the [parallelism](`Self::parallelism`).
+    ///
+    /// # Panics
+    ///
+    /// If `shards` is not a power of two greater than one.
+    pub fn interned_shards(mut self, shards: usize) -> Self {
+        assert!(
+            shards > 1 && shards.is_power_of_two(),
+            "the number of interned shards must be a power of two greater than one, not {shards}"
+        );
+        self.options.interned_shards = Some(shards);
+        self
+    }
+
+    /// Sets the LRU capacity of tracked functions that do not set one with
+    /// `#[salsa::tracked(lru = N)]`. Defaults to `0`, which keeps all memoized values.
+    pub fn default_lru(mut self, capacity: usize) -> Self {
+        self.options.default_lru = capacity;
+        self
+    }
+
+    /// Stores the memos of the tracked functions taking each salsa struct in the order
+    /// given by `stats`, most memoized first, instead of the order in which the functions
+    /// are first used; see [`crate::memo_stats`].
+    pub fn memo_layout_hint(mut self, stats: MemoStats) -> Self {
+        self.options.memo_layout_hint = Some(stats);
+        self
+    }
+
+    /// Registers `handler` to be invoked with every event,
+    /// like a subscriber registered with [`Storage::subscribe`].
+    pub fn event_handler(self, handler: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        self.event_handler_with_db(move |_, event| handler(event))
+    }
+
+    /// Registers `handler` to be invoked with every event and the database emitting it,
+    /// like a subscriber registered with [`Storage::subscribe_with_db`].
+    pub fn event_handler_with_db(
+        mut self,
+        handler: impl Fn(&dyn Database, &Event) + Send + Sync + 'static,
+    ) -> Self {
+        self.event_handlers
+            .push((EventFilter::ALL, Box::new(handler)));
+        self
+    }
+
+    /// Sets how running queries are cancelled, see [`CancellationMode`].
+    pub fn cancellation_mode(mut self, mode: CancellationMode) -> Self {
+        self.cancellation_mode = mode;
+        self
+    }
+
+    /// Reports a thread that has been blocked on a query executing in another thread
+    /// for longer than `timeout`, which usually means the threads are deadlocked:
+    /// an [`EventKind::DidDetectDeadlock`](`crate::EventKind::DidDetectDeadlock`) event
+    /// describes the chain of waits, with the query stack of each blocked thread.
+    ///
+    /// By default, waits are never reported.
+    pub fn deadlock_timeout(mut self, timeout: Duration) -> Self {
+        let panic = self
+            .deadlock_watchdog
+            .is_some_and(|watchdog| watchdog.panic);
+        self.deadlock_watchdog = Some(DeadlockWatchdog { timeout, panic });
+        self
+    }
+
+    /// Once a wait has been reported with [`Self::deadlock_timeout`], gives up waiting
+    /// and unwinds with [`Cancelled::DeadlockDetected`](`crate::Cancelled::DeadlockDetected`),
+    /// rather than waiting on.
+    ///
+    /// # Panics
+    ///
+    /// If no deadlock timeout was set.
+    pub fn panic_on_deadlock(mut self) -> Self {
+        let watchdog = self
+            .deadlock_watchdog
+            .as_mut()
+            .expect("`panic_on_deadlock` requires a `deadlock_timeout`");
+        watchdog.panic = true;
+        self
+    }
+
+    /// Creates the storage.
+    pub fn build(self) -> Storage<Db> {
+        let mut storage = Storage::default();
+        let zalsa = Arc::get_mut(&mut storage.zalsa_impl).unwrap();
+        zalsa.set_cancellation_mode(self.cancellation_mode);
+        zalsa.set_deadlock_watchdog(self.deadlock_watchdog);
+        zalsa.set_options(self.options);
+        for (filter, handler) in self.event_handlers {
+            storage.subscribe_with_db(filter, handler);
+        }
+        storage
+    }
+}
+
 impl<Db: Database> Storage<Db> {
+    /// Configures the storage for a new database;
+    /// [`Self::default`] is the same as `Self::builder().build()`.
+    pub fn builder() -> StorageBuilder<Db> {
+        StorageBuilder {
+            options: Default::default(),
+            cancellation_mode: Default::default(),
+            deadlock_watchdog: None,
+            event_handlers: Default::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates storage for a new database that handles cancellation as given by `mode`.
+    /// [`Self::default`] uses [`CancellationMode::Unwind`].
+    pub fn with_cancellation_mode(mode: CancellationMode) -> Self {
+        Self::builder().cancellation_mode(mode).build()
+    }
+
     pub fn debug_input_entries<T>(&self) -> impl Iterator<Item = &input::Value<T>>
     where
         T: input::Configuration,
@@ -100,6 +280,61 @@ impl<Db: Database> Storage<Db> {
             .flat_map(|pages| pages.slots())
     }
 
+    /// Registers `subscriber` to be invoked with every event whose kind is in `filter`,
+    /// in addition to [`Database::salsa_event`](`crate::Database::salsa_event`).
+    /// Subscribers are shared by all handles (clones) of the database.
+    pub fn subscribe(
+        &self,
+        filter: EventFilter,
+        subscriber: impl Fn(&Event) + Send + Sync + 'static,
+    ) -> SubscriberId {
+        self.subscribe_with_db(filter, move |_, event| subscriber(event))
+    }
+
+    /// Like [`Self::subscribe`], but `subscriber` is also given the database emitting the event,
+    /// which it can use to describe the event, e.g. with
+    /// [`DatabaseKeyIndex::debug`](`crate::DatabaseKeyIndex::debug`) or
+    /// [`Database::ingredient_debug_name`](`crate::Database::ingredient_debug_name`).
+    ///
+    /// The database is only borrowed for the duration of the call. Events are emitted
+    /// in the middle of salsa operations, so the subscriber must not execute queries,
+    /// create tracked structs, or read tracked fields with it.
+    pub fn subscribe_with_db(
+        &self,
+        filter: EventFilter,
+        subscriber: impl Fn(&dyn Database, &Event) + Send + Sync + 'static,
+    ) -> SubscriberId {
+        self.zalsa_impl.subscribers().subscribe(filter, subscriber)
+    }
+
+    /// Removes a subscriber registered with [`Self::subscribe`] or [`Self::subscribe_with_db`].
+    /// Returns false if it had already been removed.
+    pub fn unsubscribe(&self, id: SubscriberId) -> bool {
+        self.zalsa_impl.subscribers().unsubscribe(id)
+    }
+
+    /// The subscribers of the database, for helpers that must unsubscribe without
+    /// access to the storage, e.g. when dropped.
+    #[cfg(feature = "testing")]
+    pub(crate) fn subscribers(&self) -> &Arc<Subscribers> {
+        self.zalsa_impl.subscribers()
+    }
+
+    /// Registers `listener` to be invoked once per revision with the inputs set in it.
+    /// See [`ChangeSet`] for how sets are coalesced.
+    pub fn on_change(
+        &self,
+        listener: impl Fn(&ChangeSet) + Send + Sync + 'static,
+    ) -> ChangeListenerId {
+        self.zalsa_impl.runtime().changes().add_listener(listener)
+    }
+
+    /// Removes a listener registered with [`Self::on_change`].
+    /// Returns false if it had already been removed.
+    pub fn remove_change_listener(&self, id: ChangeListenerId) -> bool {
+        self.zalsa_impl.runtime().changes().remove_listener(id)
+    }
+
     /// Access the `Arc<Zalsa>`. This should always be
     /// possible as `zalsa_impl` only becomes
     /// `None` once we are in the `Drop` impl.
@@ -116,7 +351,9 @@ impl<Db: Database> Storage<Db> {
     fn cancel_others(&self, db: &Db) {
         self.zalsa_impl.set_cancellation_flag();
 
-        db.salsa_event(&|| Event::new(EventKind::DidSetCancellationFlag));
+        crate::event::emit(db.as_dyn_database(), &|| {
+            Event::new(EventKind::DidSetCancellationFlag)
+        });
 
         let mut clones = self.coordinate.clones.lock();
         while *clones != 1 {
@@ -132,15 +369,28 @@ unsafe impl<T: HasStorage> ZalsaDatabase for T {
     }
 
     fn zalsa_mut(&mut self) -> &mut Zalsa {
-        self.storage().cancel_others(self);
+        let zalsa = &self.storage().zalsa_impl;
+        let in_transaction = zalsa.in_transaction();
+
+        // Within a transaction, other handles were cancelled when it began,
+        // so we only need to do it again if new ones were created since.
+        if !in_transaction || Arc::strong_count(zalsa) > 1 {
+            self.storage().cancel_others(self);
+        }
 
         let storage = self.storage_mut();
         // The ref count on the `Arc` should now be 1
         let zalsa_mut = Arc::get_mut(&mut storage.zalsa_impl).unwrap();
-        zalsa_mut.new_revision();
+        if !in_transaction {
+            zalsa_mut.new_revision();
+        }
         zalsa_mut
     }
 
+    fn zalsa_mut_if_unshared(&mut self) -> Option<&mut Zalsa> {
+        Arc::get_mut(&mut self.storage_mut().zalsa_impl)
+    }
+
     fn zalsa_local(&self) -> &ZalsaLocal {
         &self.storage().zalsa_local
     }
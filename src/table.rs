@@ -8,11 +8,14 @@ use std::{
 };
 
 use append_only_vec::AppendOnlyVec;
-use memo::MemoTable;
+use memo::{Memo, MemoTable};
 use parking_lot::Mutex;
 use sync::SyncTable;
 
-use crate::{zalsa::transmute_data_ptr, Id, IngredientIndex, Revision};
+use crate::{
+    zalsa::{transmute_data_ptr, MemoIngredientIndex},
+    Id, IngredientIndex, Revision,
+};
 
 pub(crate) mod memo;
 pub(crate) mod sync;
@@ -37,17 +40,58 @@ pub(crate) trait TablePage: Any + Send + Sync {
     /// The `current_revision` MUST be the current revision of the database owning this table page.
     unsafe fn memos(&self, slot: SlotIndex, current_revision: Revision) -> &MemoTable;
 
+    /// Like [`Self::memos`], but returns `None` if `slot` is not in use, see [`Slot::memos_if_in_use`].
+    ///
+    /// # Safety condition
+    ///
+    /// The `current_revision` MUST be the current revision of the database owning this table page.
+    unsafe fn memos_if_in_use(
+        &self,
+        slot: SlotIndex,
+        current_revision: Revision,
+    ) -> Option<&MemoTable>;
+
+    /// True if `slot` is in use, see [`Slot::is_in_use`].
+    fn is_in_use(&self, slot: SlotIndex) -> bool;
+
     /// Access the syncs attached to `slot`.
     ///
     /// # Safety condition
     ///
     /// The `current_revision` MUST be the current revision of the database owning this table page.
     unsafe fn syncs(&self, slot: SlotIndex, current_revision: Revision) -> &SyncTable;
+
+    /// The ingredient for elements on this page.
+    fn ingredient(&self) -> IngredientIndex;
+
+    /// Number of slots that have been allocated on this page.
+    fn allocated(&self) -> usize;
+
+    /// Size of each slot on this page, in bytes.
+    fn slot_size(&self) -> usize;
+
+    /// Calls `f` with each memo attached to the slots on this page that are in use.
+    ///
+    /// # Safety condition
+    ///
+    /// The `current_revision` MUST be the current revision of the database owning this table page.
+    unsafe fn for_each_memo(
+        &self,
+        current_revision: Revision,
+        f: &mut dyn FnMut(SlotIndex, MemoIngredientIndex, &dyn Memo),
+    );
+
+    /// Shrinks the memo tables attached to the slots on this page that are in use to fit,
+    /// returning the number of bytes reclaimed.
+    ///
+    /// # Safety condition
+    ///
+    /// The `current_revision` MUST be the current revision of the database owning this table page.
+    unsafe fn compact_memos(&self, current_revision: Revision) -> usize;
 }
 
 pub(crate) struct Page<T: Slot> {
     /// The ingredient for elements on this page.
-    #[allow(dead_code)] // pretty sure we'll need this
     ingredient: IngredientIndex,
 
     /// Number of elements of `data` that are initialized.
@@ -81,6 +125,26 @@ pub(crate) trait Slot: Any + Send + Sync {
     ///
     /// The current revision MUST be the current revision of the database containing this slot.
     unsafe fn syncs(&self, current_revision: Revision) -> &SyncTable;
+
+    /// Like [`Self::memos`], but returns `None` if the slot is not in use,
+    /// e.g. because it holds a tracked struct that is being created or was deleted.
+    ///
+    /// Unlike [`Self::memos`], this is a passive check that does not lock the slot,
+    /// so walking the table does not affect the values it visits.
+    ///
+    /// # Safety condition
+    ///
+    /// The current revision MUST be the current revision of the database containing this slot.
+    unsafe fn memos_if_in_use(&self, current_revision: Revision) -> Option<&MemoTable> {
+        Some(self.memos(current_revision))
+    }
+
+    /// True if the slot is in use, see [`Self::memos_if_in_use`].
+    ///
+    /// This is a passive check that does not lock the slot.
+    fn is_in_use(&self) -> bool {
+        true
+    }
 }
 
 unsafe impl<T: Slot> Send for Page<T> {}
@@ -153,12 +217,86 @@ impl Table {
         self.pages[page.0].assert_type::<Page<T>>()
     }
 
+    /// Number of slots on each page.
+    pub(crate) const PAGE_LEN: usize = PAGE_LEN;
+
     /// Allocate a new page for the given ingredient and with slots of type `T`
     pub fn push_page<T: Slot>(&self, ingredient: IngredientIndex) -> PageIndex {
         let page = Box::new(<Page<T>>::new(ingredient));
         PageIndex::new(self.pages.push(page))
     }
 
+    /// The ingredient that allocated `id`, or `None` if `id` has not been allocated
+    /// from this table.
+    pub(crate) fn owner(&self, id: Id) -> Option<IngredientIndex> {
+        let (page, slot) = split_id(id);
+        if page.0 >= self.pages.len() {
+            return None;
+        }
+        let page = &self.pages[page.0];
+        (slot.0 < page.allocated()).then(|| page.ingredient())
+    }
+
+    /// Calls `f` with each memo attached to a value in use, along with the ingredient
+    /// and id of that value.
+    ///
+    /// # Safety condition
+    ///
+    /// The parameter `current_revision` MUST be the current revision
+    /// of the owner of database owning this table.
+    pub(crate) unsafe fn for_each_memo(
+        &self,
+        current_revision: Revision,
+        f: &mut dyn FnMut(IngredientIndex, Id, MemoIngredientIndex, &dyn Memo),
+    ) {
+        for (page_index, page) in self.pages.iter().enumerate() {
+            let ingredient = page.ingredient();
+            page.for_each_memo(
+                current_revision,
+                &mut |slot, memo_ingredient_index, memo| {
+                    let id = make_id(PageIndex::new(page_index), slot);
+                    f(ingredient, id, memo_ingredient_index, memo)
+                },
+            );
+        }
+    }
+
+    /// The indices of the pages holding the values of `ingredient`.
+    pub(crate) fn pages_of(&self, ingredient: IngredientIndex) -> Vec<usize> {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| page.ingredient() == ingredient)
+            .map(|(page_index, _)| page_index)
+            .collect()
+    }
+
+    /// The ids of the values in use on the page `page_index`, see [`Slot::is_in_use`].
+    pub(crate) fn ids_in_use(&self, page_index: usize) -> Vec<Id> {
+        let page = &self.pages[page_index];
+        (0..page.allocated())
+            .map(SlotIndex::new)
+            .filter(|&slot| page.is_in_use(slot))
+            .map(|slot| make_id(PageIndex::new(page_index), slot))
+            .collect()
+    }
+
+    /// Like [`Self::memos`], but returns `None` if the value of `id` is not in use,
+    /// e.g. because it is a tracked struct that was deleted.
+    ///
+    /// # Safety condition
+    ///
+    /// The parameter `current_revision` MUST be the current revision
+    /// of the owner of database owning this table.
+    pub(crate) unsafe fn memos_if_in_use(
+        &self,
+        id: Id,
+        current_revision: Revision,
+    ) -> Option<&MemoTable> {
+        let (page, slot) = split_id(id);
+        self.pages[page.0].memos_if_in_use(slot, current_revision)
+    }
+
     /// Get the memo table associated with `id`
     ///
     /// # Safety condition
@@ -263,6 +401,41 @@ impl<T: Slot> Page<T> {
 
         Ok(id)
     }
+
+    /// Like [`Self::allocate`], but takes values from `values` until the page is full,
+    /// pushing the id of each into `ids`.
+    pub(crate) fn allocate_many<V>(
+        &self,
+        page: PageIndex,
+        values: &mut impl Iterator<Item = V>,
+        ids: &mut Vec<Id>,
+    ) where
+        V: FnOnce(Id) -> T,
+    {
+        let guard = self.allocation_lock.lock();
+        let mut index = self.allocated.load(Ordering::Acquire);
+        while index < PAGE_LEN {
+            let Some(value) = values.next() else {
+                break;
+            };
+
+            // Initialize entry `index`
+            let id = make_id(page, SlotIndex::new(index));
+            let data = &self.data[index];
+            unsafe { (*data.get()).write(value(id)) };
+            ids.push(id);
+
+            // Update the length (this must be done after initialization!)
+            index += 1;
+            self.allocated.store(index, Ordering::Release);
+        }
+        drop(guard);
+    }
+
+    /// Number of slots that can still be allocated on this page.
+    pub(crate) fn remaining(&self) -> usize {
+        PAGE_LEN - self.allocated.load(Ordering::Acquire)
+    }
 }
 
 impl<T: Slot> TablePage for Page<T> {
@@ -274,9 +447,54 @@ impl<T: Slot> TablePage for Page<T> {
         self.get(slot).memos(current_revision)
     }
 
+    unsafe fn memos_if_in_use(
+        &self,
+        slot: SlotIndex,
+        current_revision: Revision,
+    ) -> Option<&MemoTable> {
+        self.get(slot).memos_if_in_use(current_revision)
+    }
+
+    fn is_in_use(&self, slot: SlotIndex) -> bool {
+        self.get(slot).is_in_use()
+    }
+
     unsafe fn syncs(&self, slot: SlotIndex, current_revision: Revision) -> &SyncTable {
         self.get(slot).syncs(current_revision)
     }
+
+    fn ingredient(&self) -> IngredientIndex {
+        self.ingredient
+    }
+
+    fn allocated(&self) -> usize {
+        self.allocated.load(Ordering::Acquire)
+    }
+
+    fn slot_size(&self) -> usize {
+        std::mem::size_of::<T>()
+    }
+
+    unsafe fn for_each_memo(
+        &self,
+        current_revision: Revision,
+        f: &mut dyn FnMut(SlotIndex, MemoIngredientIndex, &dyn Memo),
+    ) {
+        for (index, slot) in self.slots().enumerate() {
+            if let Some(memos) = slot.memos_if_in_use(current_revision) {
+                memos.for_each_memo(&mut |memo_ingredient_index, memo| {
+                    f(SlotIndex::new(index), memo_ingredient_index, memo)
+                });
+            }
+        }
+    }
+
+    unsafe fn compact_memos(&self, current_revision: Revision) -> usize {
+        self.slots()
+            .filter_map(|slot| slot.memos_if_in_use(current_revision))
+            .map(MemoTable::shrink_to_fit)
+            .sum()
+    }
 }
 
 impl<T: Slot> Drop for Page<T> {
@@ -7,7 +7,7 @@ use std::{
 use arc_swap::ArcSwap;
 use parking_lot::RwLock;
 
-use crate::{zalsa::MemoIngredientIndex, zalsa_local::QueryOrigin};
+use crate::{zalsa::MemoIngredientIndex, zalsa_local::QueryOrigin, Revision};
 
 /// The "memo table" stores the memoized results of tracked function calls.
 /// Every tracked function must take a salsa struct as its first argument
@@ -20,6 +20,17 @@ pub(crate) struct MemoTable {
 pub(crate) trait Memo: Any + Send + Sync + Debug {
     /// Returns the `origin` of this memo
     fn origin(&self) -> &QueryOrigin;
+
+    /// The number of bytes used by this memo, including its recorded dependencies
+    /// but not the heap allocations owned by its value.
+    fn memory_usage(&self) -> usize;
+
+    /// The last revision in which this memo's value changed.
+    fn changed_at(&self) -> Revision;
+
+    /// The time spent computing this memo's value, if it was computed by executing a query.
+    #[cfg(feature = "query_timing")]
+    fn execution_time(&self) -> Option<crate::ExecutionTime>;
 }
 
 /// Wraps the data stored for a memoized entry.
@@ -194,6 +205,33 @@ impl MemoTable {
         unsafe { Self::from_dummy::<M>(arc_swap.swap(Self::to_dummy(memo))) };
     }
 
+    /// Removes the trailing empty entries of this table and shrinks it to fit,
+    /// returning the number of bytes reclaimed.
+    pub(crate) fn shrink_to_fit(&self) -> usize {
+        let mut memos = self.memos.write();
+        let capacity = memos.capacity();
+        while memos.last().is_some_and(|entry| entry.data.is_none()) {
+            memos.pop();
+        }
+        memos.shrink_to_fit();
+        (capacity - memos.capacity()) * std::mem::size_of::<MemoEntry>()
+    }
+
+    /// Calls `f` with each memo in this table.
+    pub(crate) fn for_each_memo(&self, f: &mut dyn FnMut(MemoIngredientIndex, &dyn Memo)) {
+        for (index, entry) in self.memos.read().iter().enumerate() {
+            if let Some(MemoEntryData {
+                type_id: _,
+                to_dyn_fn,
+                arc_swap,
+            }) = &entry.data
+            {
+                let memo = to_dyn_fn(arc_swap.load_full());
+                f(MemoIngredientIndex::from_usize(index), &*memo);
+            }
+        }
+    }
+
     pub(crate) fn into_memos(self) -> impl Iterator<Item = (MemoIngredientIndex, Arc<dyn Memo>)> {
         self.memos
             .into_inner()
@@ -6,6 +6,7 @@ use std::{
 use parking_lot::RwLock;
 
 use crate::{
+    cancelled::TimedOut,
     key::DatabaseKeyIndex,
     runtime::WaitResult,
     zalsa::{MemoIngredientIndex, Zalsa},
@@ -104,7 +105,11 @@ impl ClaimGuard<'_> {
 impl Drop for ClaimGuard<'_> {
     fn drop(&mut self) {
         let wait_result = if std::thread::panicking() {
-            WaitResult::Panicked
+            if TimedOut::is_unwinding() {
+                WaitResult::TimedOut
+            } else {
+                WaitResult::Panicked
+            }
         } else {
             WaitResult::Completed
         };
@@ -0,0 +1,161 @@
+//! Helpers for testing incremental behavior, with the `testing` feature.
+//!
+//! An [`ExecutionRecorder`] records which queries were executed or validated,
+//! so that tests need not log from their tracked functions:
+//!
+//! ```rust,ignore
+//! let recorder = ExecutionRecorder::attach(db.storage());
+//! assert_eq!(compile(&db, file), 3);
+//! recorder.assert_executed(["compile", "parse"]);
+//!
+//! file.set_text(&mut db).to(" 1 + 2".to_string());
+//! assert_eq!(compile(&db, file), 3);
+//! recorder.assert_no_reexecution("compile");
+//! ```
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::{
+    event::Subscribers, Database, EventFilter, EventKind, Storage, SubscriberId, ValidationKind,
+};
+
+/// Something an [`ExecutionRecorder`] recorded, naming the query by the `Debug`
+/// output of its key, e.g. `parse(Id(0))`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Record {
+    /// The function of the query was executed.
+    Executed { key: String },
+
+    /// The memoized value of the query was validated without executing its function.
+    Validated { key: String, kind: ValidationKind },
+}
+
+impl Record {
+    /// The `Debug` output of the key of the query.
+    pub fn key(&self) -> &str {
+        match self {
+            Record::Executed { key } | Record::Validated { key, .. } => key,
+        }
+    }
+}
+
+/// Records the queries executed and validated by all handles to a database,
+/// in the order in which they were, until it is [detached](`Self::detach`) or dropped.
+///
+/// The assertions take queries either by the `Debug` output of their key, e.g.
+/// `parse(Id(0))`, or by the name of their function, e.g. `parse`, which matches
+/// the query for any arguments.
+pub struct ExecutionRecorder {
+    records: Arc<Mutex<Vec<Record>>>,
+    subscribers: Arc<Subscribers>,
+    subscriber: SubscriberId,
+}
+
+impl ExecutionRecorder {
+    /// Starts recording the queries of the database owning `storage`,
+    /// e.g. `ExecutionRecorder::attach(db.storage())` for a [`DatabaseImpl`](`crate::DatabaseImpl`).
+    pub fn attach<Db: Database>(storage: &Storage<Db>) -> Self {
+        let records: Arc<Mutex<Vec<Record>>> = Default::default();
+        let subscribers = storage.subscribers().clone();
+        let subscriber = subscribers.subscribe(
+            EventFilter::WILL_EXECUTE | EventFilter::DID_VALIDATE_MEMOIZED_VALUE,
+            {
+                let records = records.clone();
+                move |db, event| {
+                    let record = match event.kind {
+                        EventKind::WillExecute { database_key } => Record::Executed {
+                            key: format!("{:?}", database_key.debug(db)),
+                        },
+                        EventKind::DidValidateMemoizedValue {
+                            database_key, kind, ..
+                        } => Record::Validated {
+                            key: format!("{:?}", database_key.debug(db)),
+                            kind,
+                        },
+                        _ => return,
+                    };
+                    records.lock().push(record);
+                }
+            },
+        );
+        Self {
+            records,
+            subscribers,
+            subscriber,
+        }
+    }
+
+    /// Stops recording, like dropping the recorder.
+    pub fn detach(self) {}
+
+    /// The records since the recorder was attached or last cleared.
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().clone()
+    }
+
+    /// Returns the records since the recorder was attached or last cleared, and clears them.
+    pub fn take(&self) -> Vec<Record> {
+        std::mem::take(&mut *self.records.lock())
+    }
+
+    /// The keys of the queries executed since the recorder was attached or last cleared,
+    /// in the order in which they were executed.
+    pub fn executed(&self) -> Vec<String> {
+        self.records
+            .lock()
+            .iter()
+            .filter(|record| matches!(record, Record::Executed { .. }))
+            .map(|record| record.key().to_string())
+            .collect()
+    }
+
+    /// Asserts that exactly the `expected` queries were executed, in this order,
+    /// since the recorder was attached or last cleared, and clears the records.
+    #[track_caller]
+    pub fn assert_executed<'q>(&self, expected: impl IntoIterator<Item = &'q str>) {
+        let executed = self.executed();
+        let expected: Vec<&str> = expected.into_iter().collect();
+        let matches = executed.len() == expected.len()
+            && executed
+                .iter()
+                .zip(&expected)
+                .all(|(key, query)| is_query(key, query));
+        assert!(
+            matches,
+            "expected the queries {expected:?} to be executed, but {executed:?} were"
+        );
+        self.take();
+    }
+
+    /// Asserts that `query` was not executed since the recorder was attached
+    /// or last cleared, e.g. because its memoized value was validated instead.
+    #[track_caller]
+    pub fn assert_no_reexecution(&self, query: &str) {
+        let executed = self.executed();
+        assert!(
+            !executed.iter().any(|key| is_query(key, query)),
+            "expected `{query}` not to be executed, but {executed:?} were"
+        );
+    }
+
+    /// Asserts that the records since the recorder was attached or last cleared
+    /// match `expected`, and clears them.
+    #[track_caller]
+    pub fn assert_records(&self, expected: expect_test::Expect) {
+        expected.assert_debug_eq(&self.take());
+    }
+}
+
+impl Drop for ExecutionRecorder {
+    fn drop(&mut self) {
+        self.subscribers.unsubscribe(self.subscriber);
+    }
+}
+
+/// True if `query` is `key` or the name of its function.
+fn is_query(key: &str, query: &str) -> bool {
+    key == query || key.split_once('(').is_some_and(|(name, _)| name == query)
+}
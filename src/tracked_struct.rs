@@ -1,13 +1,19 @@
 use std::{any::TypeId, fmt, hash::Hash, marker::PhantomData, ops::DerefMut};
 
 use crossbeam::{atomic::AtomicCell, queue::SegQueue};
+use lazy::LazyUpdate;
+use parking_lot::Mutex;
+use smallvec::SmallVec;
 use tracked_field::FieldIngredientImpl;
 
 use crate::{
     accumulator::accumulated_map::InputAccumulatedValues,
+    channel::Channels,
     cycle::CycleRecoveryStrategy,
-    ingredient::{fmt_index, Ingredient, Jar, JarAux, MaybeChangedAfter},
-    key::{DatabaseKeyIndex, InputDependencyIndex},
+    id::AsId,
+    ingredient::{fmt_index, Ingredient, IngredientKind, Jar, JarAux, MaybeChangedAfter},
+    input::edit::EditRange,
+    key::{DatabaseKeyIndex, InputDependencyIndex, OutputDependencyIndex},
     plumbing::ZalsaLocal,
     runtime::StampedValue,
     salsa_struct::SalsaStructInDb,
@@ -17,6 +23,8 @@ use crate::{
     Database, Durability, Event, EventKind, Id, Revision,
 };
 
+pub mod elements;
+pub mod lazy;
 pub mod tracked_field;
 
 // ANCHOR: Configuration
@@ -54,8 +62,17 @@ pub trait Configuration: Sized + 'static {
     /// Create a new value revision array where each element is set to `current_revision`.
     fn new_revisions(current_revision: Revision) -> Self::Revisions;
 
+    /// Create the per-element revisions of the `#[elements]` fields of `fields`,
+    /// each set to `current_revision`. They are indexed by field and
+    /// empty (without allocating) if the struct has no `#[elements]` fields.
+    fn new_element_revisions(
+        fields: &Self::Fields<'_>,
+        current_revision: Revision,
+    ) -> Box<[Vec<Revision>]>;
+
     /// Update the field data and, if the value has changed,
-    /// the appropriate entry in the `revisions` array.
+    /// the appropriate entry in the `revisions` array
+    /// (and, for `#[elements]` fields, in `element_revisions`).
     ///
     /// # Safety
     ///
@@ -78,9 +95,33 @@ pub trait Configuration: Sized + 'static {
     unsafe fn update_fields<'db>(
         current_revision: Revision,
         revisions: &mut Self::Revisions,
+        element_revisions: &mut [Vec<Revision>],
         old_fields: *mut Self::Fields<'db>,
         new_fields: Self::Fields<'db>,
     );
+
+    /// If true (the `lazy_update` option), re-created structs replace their fields
+    /// without comparing them; each field is instead compared with its old value
+    /// when it is first read, and `update_fields` is not used.
+    const LAZY_UPDATE: bool;
+
+    /// True if the field `field_index` of `new_fields` differs from that of `old_fields`
+    /// (always true for `#[no_eq]` fields). Used by structs with `LAZY_UPDATE`.
+    fn field_changed<'db>(
+        old_fields: &Self::Fields<'db>,
+        new_fields: &Self::Fields<'db>,
+        field_index: usize,
+    ) -> bool;
+
+    /// If true (the `singleton` option), at most one struct exists at a time,
+    /// see [`IngredientImpl::get_singleton`].
+    const SINGLETON: bool;
+
+    /// The field `field_index` of `fields`, to format it; used by `Database::checksum`.
+    fn field_debug<'a, 'db>(
+        fields: &'a Self::Fields<'db>,
+        field_index: usize,
+    ) -> &'a dyn std::fmt::Debug;
 }
 // ANCHOR_END: Configuration
 
@@ -122,6 +163,30 @@ impl<C: Configuration> Jar for JarImpl<C> {
 pub trait TrackedStructInDb: SalsaStructInDb {
     /// Converts the identifier for this tracked struct into a `DatabaseKeyIndex`.
     fn database_key_index(db: &dyn Database, id: Id) -> DatabaseKeyIndex;
+
+    /// Transfers ownership of the tracked struct with the given id to the active query.
+    /// See [`adopt`].
+    fn adopt(db: &dyn Database, id: Id);
+}
+
+/// Transfers ownership of the tracked struct `s` to the currently executing query.
+///
+/// Tracked structs are normally owned by the query that created them and are deleted
+/// once that query re-executes without creating them again. After `adopt`, the struct
+/// is owned by the *adopting* query instead: it survives as long as the adopter keeps
+/// adopting it on each execution, even if the creator (e.g., a helper query extracted
+/// during a refactoring) no longer creates it. Several queries may adopt the same struct;
+/// it then survives as long as any of them keeps adopting it. When the last adopter
+/// stops adopting it, ownership returns to the creator.
+///
+/// # Panics
+///
+/// If called outside of a tracked function.
+pub fn adopt<S>(db: &dyn Database, s: S)
+where
+    S: TrackedStructInDb + AsId,
+{
+    S::adopt(db, s.as_id())
 }
 
 /// Created for each tracked struct.
@@ -145,6 +210,16 @@ where
 
     /// Store freed ids
     free_list: SegQueue<Id>,
+
+    /// For singleton structs, the struct that currently exists, if any.
+    singleton: AtomicCell<Option<Id>>,
+
+    /// For singleton structs, the last revision in which the singleton
+    /// was created or deleted.
+    singleton_changed_at: AtomicCell<Revision>,
+
+    /// For singleton structs, the query that last created the singleton, if any.
+    singleton_creator: AtomicCell<Option<DatabaseKeyIndex>>,
 }
 
 /// Defines the identity of a tracked struct.
@@ -253,6 +328,10 @@ where
     /// create this struct with different values.
     durability: Durability,
 
+    /// The channels of all inputs consumed by the creator query
+    /// prior to creating this tracked struct, like `durability`.
+    channels: Channels,
+
     /// The revision when this tracked struct was last updated.
     /// This field also acts as a kind of "lock". Once it is equal
     /// to `Some(current_revision)`, the fields are locked and
@@ -274,6 +353,25 @@ where
     /// leaked a reference across threads somehow.
     updated_at: AtomicCell<Option<Revision>>,
 
+    /// The query that created this tracked struct.
+    created_by: DatabaseKeyIndex,
+
+    /// How many times this slot has been reused, see [`Ingredient::id_generation`].
+    generation: u16,
+
+    /// The revision in which this struct was first created.
+    #[cfg(feature = "strict_tracked_structs")]
+    created_at: Revision,
+
+    /// The last revision in which `created_by` (or the adopter) created this struct again
+    /// or was validated, see [`IngredientImpl::assert_not_stale`].
+    #[cfg(feature = "strict_tracked_structs")]
+    validated_at: AtomicCell<Revision>,
+
+    /// The queries that adopted this tracked struct (see [`adopt`]), if any.
+    /// While there are some, the struct is not deleted when `created_by` stops creating it.
+    adopted_by: Mutex<SmallVec<[DatabaseKeyIndex; 1]>>,
+
     /// Fields of this tracked struct. They can change across revisions,
     /// but they do not change within a particular revision.
     fields: C::Fields<'static>,
@@ -283,6 +381,14 @@ where
     /// current revision if the value is different.
     revisions: C::Revisions,
 
+    /// For `#[elements]` fields, when did each element last change.
+    /// Indexed by field; empty if the struct has no `#[elements]` fields.
+    element_revisions: Box<[Vec<Revision>]>,
+
+    /// For structs with `lazy_update`, the fields before the struct was last re-created,
+    /// if some of them may not have been compared with the new ones yet.
+    lazy: Option<Box<LazyUpdate<C>>>,
+
     /// Memo table storing the results of query functions etc.
     memos: MemoTable,
 
@@ -348,6 +454,9 @@ where
             ingredient_index: index,
             phantom: PhantomData,
             free_list: Default::default(),
+            singleton: AtomicCell::new(None),
+            singleton_changed_at: AtomicCell::new(Revision::start()),
+            singleton_creator: AtomicCell::new(None),
         }
     }
 
@@ -363,15 +472,38 @@ where
         &'db self,
         db: &'db dyn Database,
         fields: C::Fields<'db>,
+    ) -> C::Struct<'db> {
+        let hash = crate::hash::hash(&C::id_fields(&fields));
+        self.new_struct_with_hash(db, hash, fields)
+    }
+
+    /// Like [`Self::new_struct`], but the struct is identified by `identity_key` rather than
+    /// by its `#[id]` fields, for structs declared with `#[salsa::tracked(identity_key)]`.
+    /// Structs created with distinct keys keep their identity however the creations are reordered;
+    /// only those created with equal keys within a query are told apart by their order.
+    pub fn new_struct_with_identity_key<'db>(
+        &'db self,
+        db: &'db dyn Database,
+        identity_key: &impl Hash,
+        fields: C::Fields<'db>,
+    ) -> C::Struct<'db> {
+        self.new_struct_with_hash(db, crate::hash::hash(identity_key), fields)
+    }
+
+    fn new_struct_with_hash<'db>(
+        &'db self,
+        db: &'db dyn Database,
+        hash: u64,
+        fields: C::Fields<'db>,
     ) -> C::Struct<'db> {
         let (zalsa, zalsa_local) = db.zalsas();
 
         let identity_hash = IdentityHash {
             ingredient_index: self.ingredient_index,
-            hash: crate::hash::hash(&C::id_fields(&fields)),
+            hash,
         };
 
-        let (current_deps, disambiguator) = zalsa_local.disambiguate(identity_hash);
+        let (current_key, current_deps, disambiguator) = zalsa_local.disambiguate(identity_hash);
 
         let identity = Identity {
             hash: identity_hash.hash,
@@ -385,12 +517,34 @@ where
                 // The struct already exists in the intern map.
                 zalsa_local.add_output(self.database_key_index(id).into());
                 self.update(zalsa, current_revision, id, &current_deps, fields);
+                #[cfg(feature = "strict_tracked_structs")]
+                Self::data(zalsa.table(), id)
+                    .validated_at
+                    .store(current_revision);
                 C::struct_from_id(id)
             }
 
             None => {
+                if C::SINGLETON && self.singleton.load().is_some() {
+                    panic!("singleton struct may not be duplicated");
+                }
+
                 // This is a new tracked struct, so create an entry in the struct map.
-                let id = self.allocate(zalsa, zalsa_local, current_revision, &current_deps, fields);
+                let id = self.allocate(
+                    zalsa,
+                    zalsa_local,
+                    current_revision,
+                    current_key,
+                    &current_deps,
+                    fields,
+                );
+                if C::SINGLETON {
+                    if self.singleton.compare_exchange(None, Some(id)).is_err() {
+                        panic!("singleton struct may not be duplicated");
+                    }
+                    self.singleton_changed_at.store(current_revision);
+                    self.singleton_creator.store(Some(current_key));
+                }
                 let key = self.database_key_index(id);
                 zalsa_local.add_output(key.into());
                 zalsa_local.store_tracked_struct_id(identity, id);
@@ -404,14 +558,26 @@ where
         zalsa: &'db Zalsa,
         zalsa_local: &'db ZalsaLocal,
         current_revision: Revision,
+        current_key: DatabaseKeyIndex,
         current_deps: &StampedValue<()>,
         fields: C::Fields<'db>,
     ) -> Id {
-        let value = |_| Value {
+        let element_revisions = C::new_element_revisions(&fields, current_deps.changed_at);
+        let value = |generation| Value {
             updated_at: AtomicCell::new(Some(current_revision)),
             durability: current_deps.durability,
+            channels: current_deps.channels,
+            created_by: current_key,
+            generation,
+            #[cfg(feature = "strict_tracked_structs")]
+            created_at: current_revision,
+            #[cfg(feature = "strict_tracked_structs")]
+            validated_at: AtomicCell::new(current_revision),
+            adopted_by: Default::default(),
             fields: unsafe { self.to_static(fields) },
             revisions: C::new_revisions(current_deps.changed_at),
+            element_revisions,
+            lazy: None,
             memos: Default::default(),
             syncs: Default::default(),
         };
@@ -426,12 +592,13 @@ where
             // Overwrite the free-list entry. Use `*foo = ` because the entry
             // has been previously initialized and we want to free the old contents.
             unsafe {
-                *data_raw = value(id);
+                let generation = (*data_raw).generation.wrapping_add(1);
+                *data_raw = value(generation);
             }
 
             id
         } else {
-            zalsa_local.allocate::<Value<C>>(zalsa.table(), self.ingredient_index, value)
+            zalsa_local.allocate::<Value<C>>(zalsa.table(), self.ingredient_index, |_| value(0))
         }
     }
 
@@ -514,23 +681,39 @@ where
         // any attempt to read concurrently will panic.
         let data = unsafe { &mut *data_raw };
 
-        // SAFETY: We assert that the pointer to `data.revisions`
-        // is a pointer into the database referencing a value
-        // from a previous revision. As such, it continues to meet
-        // its validity invariant and any owned content also continues
-        // to meet its safety invariant.
-        unsafe {
-            C::update_fields(
-                current_revision,
-                &mut data.revisions,
-                self.to_self_ptr(std::ptr::addr_of_mut!(data.fields)),
-                fields,
-            );
+        if C::LAZY_UPDATE {
+            if let Some(lazy) = data.lazy.take() {
+                lazy.finish(&mut data.revisions);
+            }
+            let old_fields = std::mem::replace(&mut data.fields, unsafe { self.to_static(fields) });
+            data.lazy = Some(Box::new(LazyUpdate::new(old_fields, current_revision)));
+        } else {
+            // SAFETY: We assert that the pointer to `data.revisions`
+            // is a pointer into the database referencing a value
+            // from a previous revision. As such, it continues to meet
+            // its validity invariant and any owned content also continues
+            // to meet its safety invariant.
+            unsafe {
+                C::update_fields(
+                    current_revision,
+                    &mut data.revisions,
+                    &mut data.element_revisions,
+                    self.to_self_ptr(std::ptr::addr_of_mut!(data.fields)),
+                    fields,
+                );
+            }
         }
-        if current_deps.durability < data.durability {
+        if current_deps.durability < data.durability
+            || !current_deps.channels.is_subset(data.channels)
+        {
+            data.lazy = None;
             data.revisions = C::new_revisions(current_revision);
+            for element_revisions in &mut data.element_revisions {
+                element_revisions.fill(current_revision);
+            }
         }
         data.durability = current_deps.durability;
+        data.channels = current_deps.channels;
         let swapped_out = data.updated_at.swap(Some(current_revision));
         assert!(swapped_out.is_none());
     }
@@ -556,12 +739,6 @@ where
     /// unspecified results (but not UB). See [`InternedIngredient::delete_index`] for more
     /// discussion and important considerations.
     pub(crate) fn delete_entity(&self, db: &dyn crate::Database, id: Id) {
-        db.salsa_event(&|| {
-            Event::new(crate::EventKind::DidDiscard {
-                key: self.database_key_index(id),
-            })
-        });
-
         let zalsa = db.zalsa();
         let current_revision = zalsa.current_revision();
         let data = Self::data_raw(zalsa.table(), id);
@@ -600,17 +777,128 @@ where
                 key_index: id,
             };
 
-            db.salsa_event(&|| Event::new(EventKind::DidDiscard { key: executor }));
+            crate::event::emit(db, &|| Event::new(EventKind::DidDiscard { key: executor }));
 
             for stale_output in memo.origin().outputs() {
                 stale_output.remove_stale_output(db, executor);
             }
         }
 
+        if C::SINGLETON && self.singleton.compare_exchange(Some(id), None).is_ok() {
+            self.singleton_changed_at.store(current_revision);
+        }
+
+        // Report the struct as discarded once it and its memos are gone,
+        // but before its id can be reused by a new struct.
+        crate::event::emit(db, &|| {
+            Event::new(crate::EventKind::DidDiscard {
+                key: self.database_key_index(id),
+            })
+        });
+
         // now that all cleanup has occurred, make available for re-use
         self.free_list.push(id);
     }
 
+    /// Transfers ownership of `id` to the active query, see [`adopt`].
+    pub fn adopt(&self, db: &dyn Database, id: Id) {
+        let (zalsa, zalsa_local) = db.zalsas();
+        let (adopter, _) = zalsa_local
+            .active_query()
+            .expect("cannot adopt a tracked struct outside of a tracked function");
+
+        let data = Self::data(zalsa.table(), id);
+        data.read_lock(zalsa.current_revision());
+
+        if adopter != data.created_by {
+            let mut adopted_by = data.adopted_by.lock();
+            if !adopted_by.contains(&adopter) {
+                adopted_by.push(adopter);
+            }
+        }
+        #[cfg(feature = "strict_tracked_structs")]
+        data.validated_at.store(zalsa.current_revision());
+        zalsa_local.add_output(self.database_key_index(id).into());
+    }
+
+    /// Returns the singleton struct, if it was created and not deleted since.
+    ///
+    /// The active query depends on whether the singleton exists,
+    /// but not on its fields until they are read.
+    /// It also depends on the query that last created the singleton, so that query is
+    /// validated first, creating or deleting the singleton as in the current revision.
+    pub fn get_singleton<'db>(&'db self, db: &'db dyn Database) -> Option<C::Struct<'db>> {
+        let zalsa_local = db.zalsa_local();
+        let id = self.singleton.load();
+        let (durability, channels) = match id {
+            Some(id) => {
+                let data = Self::data(db.zalsa().table(), id);
+                (data.durability, data.channels)
+            }
+            None => (Durability::LOW, Channels::ALL),
+        };
+        if let Some(creator) = self.singleton_creator.load() {
+            // The creator itself, or a query it executes, reads what it is creating.
+            if !zalsa_local.is_active(creator) {
+                zalsa_local.report_tracked_read(
+                    creator.into(),
+                    durability,
+                    channels,
+                    self.singleton_changed_at.load(),
+                    InputAccumulatedValues::Empty,
+                );
+            }
+        }
+        zalsa_local.report_tracked_read(
+            InputDependencyIndex::for_table(self.ingredient_index),
+            durability,
+            channels,
+            self.singleton_changed_at.load(),
+            InputAccumulatedValues::Empty,
+        );
+        id.map(C::struct_from_id)
+    }
+
+    /// Panics if `id` is a stale handle: the struct was deleted, or an input of the query
+    /// that created it has changed since that query last created it or was validated,
+    /// so the struct may be about to be deleted or its fields may be out of date.
+    ///
+    /// Like a memo that passes shallow verification, a struct whose creator only read inputs
+    /// of a durability or of channels that have not changed since is still valid.
+    #[cfg(feature = "strict_tracked_structs")]
+    fn assert_not_stale(&self, db: &dyn Database, id: Id, data: &Value<C>) {
+        let zalsa = db.zalsa();
+        let current_revision = zalsa.current_revision();
+        let problem = if data.updated_at.load().is_none() {
+            "it has been deleted"
+        } else if zalsa.last_changed_revision(data.durability) > data.validated_at.load()
+            && zalsa.last_changed_revision_in(data.channels) > data.validated_at.load()
+        {
+            "the query that created it has not been validated"
+        } else {
+            return;
+        };
+        panic!(
+            "stale handle to tracked struct `{}` {id:?} (generation {}) \
+            created in revision {:?} by {:?}: {problem} in the current revision {current_revision:?}",
+            C::DEBUG_NAME,
+            data.generation,
+            data.created_at,
+            data.created_by.debug(db),
+        );
+    }
+
+    /// Returns true if `id` is still listed among the outputs of the query that created it.
+    fn is_output_of_creator(&self, db: &dyn Database, id: Id) -> bool {
+        let zalsa = db.zalsa();
+        let creator = Self::data(zalsa.table(), id).created_by;
+        let key = OutputDependencyIndex::from(self.database_key_index(id));
+        zalsa
+            .lookup_ingredient(creator.ingredient_index)
+            .origin(db, creator.key_index)
+            .is_some_and(|origin| origin.outputs().any(|output| output == key))
+    }
+
     /// Return reference to the field data ignoring dependency tracking.
     /// Used for debugging.
     pub fn leak_fields<'db>(
@@ -637,19 +925,53 @@ where
         let field_ingredient_index = self.ingredient_index.successor(field_index);
         let data = Self::data(zalsa.table(), id);
 
+        #[cfg(feature = "strict_tracked_structs")]
+        self.assert_not_stale(db, id, data);
+
         data.read_lock(zalsa.current_revision());
 
-        let field_changed_at = data.revisions[field_index];
+        let field_changed_at = data.field_changed_at(field_index);
 
         zalsa_local.report_tracked_read(
             InputDependencyIndex::new(field_ingredient_index, id),
             data.durability,
+            data.channels,
             field_changed_at,
             InputAccumulatedValues::Empty,
         );
 
         unsafe { self.to_self_ref(&data.fields) }
     }
+
+    /// Access to the value fields, like [`Self::field`], but only records a dependency
+    /// on the element `element` of the `#[elements]` field `field_index`.
+    pub fn field_element<'db>(
+        &'db self,
+        db: &'db dyn crate::Database,
+        s: C::Struct<'db>,
+        field_index: usize,
+        element: usize,
+    ) -> &'db C::Fields<'db> {
+        let (zalsa, zalsa_local) = db.zalsas();
+        let id = C::deref_struct(s);
+        let field_ingredient_index = self.ingredient_index.successor(field_index);
+        let data = Self::data(zalsa.table(), id);
+
+        #[cfg(feature = "strict_tracked_structs")]
+        self.assert_not_stale(db, id, data);
+
+        data.read_lock(zalsa.current_revision());
+
+        zalsa_local.report_tracked_range_read(
+            InputDependencyIndex::new(field_ingredient_index, id),
+            EditRange::from(element..element + 1),
+            data.durability,
+            data.channels,
+            data.element_changed_at(field_index, element),
+        );
+
+        unsafe { self.to_self_ref(&data.fields) }
+    }
 }
 
 impl<C> Ingredient for IngredientImpl<C>
@@ -669,35 +991,73 @@ where
         MaybeChangedAfter::No(InputAccumulatedValues::Empty)
     }
 
+    fn table_maybe_changed_after(&self, revision: Revision) -> MaybeChangedAfter {
+        MaybeChangedAfter::from(revision < self.singleton_changed_at.load())
+    }
+
     fn cycle_recovery_strategy(&self) -> CycleRecoveryStrategy {
         crate::cycle::CycleRecoveryStrategy::Panic
     }
 
+    fn id_generation(&self, db: &dyn Database, id: Id) -> Option<u16> {
+        let table = db.zalsa().table();
+        if table.owner(id) != Some(self.ingredient_index) {
+            return None;
+        }
+        let data = Self::data(table, id);
+        data.updated_at.load()?;
+        Some(data.generation)
+    }
+
     fn origin(&self, _db: &dyn Database, _key_index: crate::Id) -> Option<QueryOrigin> {
         None
     }
 
     fn mark_validated_output<'db>(
         &'db self,
-        _db: &'db dyn Database,
+        db: &'db dyn Database,
         _executor: DatabaseKeyIndex,
-        _output_key: crate::Id,
+        output_key: crate::Id,
     ) {
-        // we used to update `update_at` field but now we do it lazilly when data is accessed
-        //
-        // FIXME: delete this method
+        // we used to update `update_at` field but now we do it lazilly when data is accessed;
+        // only the revision checked by `assert_not_stale` is recorded.
+        #[cfg(feature = "strict_tracked_structs")]
+        Self::data(db.zalsa().table(), output_key)
+            .validated_at
+            .store(db.zalsa().current_revision());
+        #[cfg(not(feature = "strict_tracked_structs"))]
+        let _ = (db, output_key);
     }
 
     fn remove_stale_output(
         &self,
         db: &dyn Database,
-        _executor: DatabaseKeyIndex,
+        executor: DatabaseKeyIndex,
         stale_output_key: crate::Id,
     ) {
         // This method is called when, in prior revisions,
-        // `executor` creates a tracked struct `salsa_output_key`,
+        // `executor` creates (or adopts) a tracked struct `salsa_output_key`,
         // but it did not in the current revision.
-        // In that case, we can delete `stale_output_key` and any data associated with it.
+        // In that case, we can delete `stale_output_key` and any data associated with it,
+        // unless some other query still owns it.
+        let data = Self::data(db.zalsa().table(), stale_output_key);
+        let (was_adopter, still_adopted) = {
+            let mut adopted_by = data.adopted_by.lock();
+            let was_adopter = adopted_by.contains(&executor);
+            adopted_by.retain(|adopter| *adopter != executor);
+            (was_adopter, !adopted_by.is_empty())
+        };
+
+        // The creator no longer creates it, or one adopter gave it up,
+        // but the remaining adopters own it.
+        if still_adopted {
+            return;
+        }
+
+        // The last adopter gave it up: ownership returns to the creator.
+        if was_adopter && self.is_output_of_creator(db, stale_output_key) {
+            return;
+        }
         self.delete_entity(db.as_dyn_database(), stale_output_key);
     }
 
@@ -709,6 +1069,10 @@ where
         C::DEBUG_NAME
     }
 
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::TrackedStruct
+    }
+
     fn requires_reset_for_new_revision(&self) -> bool {
         false
     }
@@ -749,6 +1113,27 @@ where
         std::mem::take(&mut self.memos)
     }
 
+    /// The revision in which the element `element` of the `#[elements]` field
+    /// `field_index` last changed. Elements that never existed are only recorded
+    /// as part of the whole field.
+    fn element_changed_at(&self, field_index: usize, element: usize) -> Revision {
+        self.element_revisions
+            .get(field_index)
+            .and_then(|element_revisions| element_revisions.get(element))
+            .copied()
+            .unwrap_or_else(|| self.field_changed_at(field_index))
+    }
+
+    /// The revision in which the field `field_index` last changed.
+    fn field_changed_at(&self, field_index: usize) -> Revision {
+        match &self.lazy {
+            Some(lazy) => {
+                lazy.field_changed_at(&self.fields, field_index, self.revisions[field_index])
+            }
+            None => self.revisions[field_index],
+        }
+    }
+
     fn read_lock(&self, current_revision: Revision) {
         loop {
             match self.updated_at.load() {
@@ -792,4 +1177,15 @@ where
         self.read_lock(current_revision);
         &self.syncs
     }
+
+    unsafe fn memos_if_in_use(&self, _current_revision: Revision) -> Option<&MemoTable> {
+        self.is_in_use().then_some(&self.memos)
+    }
+
+    fn is_in_use(&self) -> bool {
+        // Skips structs that are being initialized or were deleted. Unlike `read_lock`,
+        // this does not mark the struct as read in the current revision: that would keep
+        // it from being updated, or deleted by the query that created it.
+        self.updated_at.load().is_some()
+    }
 }
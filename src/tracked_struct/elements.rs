@@ -0,0 +1,59 @@
+use crate::Revision;
+
+/// Types that can be used as `#[elements]` fields of tracked structs.
+pub trait Elements {
+    /// The type of a single element.
+    type Element;
+}
+
+impl<T> Elements for Vec<T> {
+    type Element = T;
+}
+
+/// Updates an `#[elements]` field element by element, recording in `element_revisions`
+/// the revision in which each index last changed (including appearing or disappearing).
+/// `field_revision` is updated if any element changed.
+/// Invoked by the generated `update_fields`, with the element type's `maybe_update`.
+///
+/// `element_revisions` never shrinks, so that reads of elements that were removed
+/// are invalidated.
+///
+/// # Safety
+///
+/// Requires the same conditions as the `maybe_update`
+/// method on [the `Update` trait](`crate::update::Update`).
+pub unsafe fn update_elements<T>(
+    field_revision: &mut Revision,
+    element_revisions: &mut Vec<Revision>,
+    current_revision: Revision,
+    old_pointer: *mut Vec<T>,
+    new_value: Vec<T>,
+    maybe_update: unsafe fn(*mut T, T) -> bool,
+) {
+    let old_vec: &mut Vec<T> = unsafe { &mut *old_pointer };
+    let (old_len, new_len) = (old_vec.len(), new_value.len());
+    if element_revisions.len() < new_len {
+        element_revisions.resize(new_len, current_revision);
+    }
+
+    let mut changed = old_len != new_len;
+    for (index, new_element) in new_value.into_iter().enumerate() {
+        if index < old_len {
+            if unsafe { maybe_update(&mut old_vec[index], new_element) } {
+                element_revisions[index] = current_revision;
+                changed = true;
+            }
+        } else {
+            old_vec.push(new_element);
+            element_revisions[index] = current_revision;
+        }
+    }
+    if new_len < old_len {
+        old_vec.truncate(new_len);
+        element_revisions[new_len..old_len].fill(current_revision);
+    }
+
+    if changed {
+        *field_revision = current_revision;
+    }
+}
@@ -0,0 +1,106 @@
+use crossbeam::atomic::AtomicCell;
+
+use crate::Revision;
+
+use super::Configuration;
+
+/// Used by the generated code of `lazy_update` tracked structs to compare a field
+/// with its old value by reference, using `PartialEq` if the field's type implements it.
+///
+/// To use:
+///
+/// ```rust,ignore
+/// use crate::tracked_struct::lazy::helper::Fallback;
+/// tracked_struct::lazy::helper::Dispatch::<$ty>::changed(&old, &new);
+/// ```
+///
+/// Like [`crate::update::helper`], this uses the "method dispatch hack":
+/// fields whose type does not implement `PartialEq` are always considered changed.
+pub mod helper {
+    use std::marker::PhantomData;
+
+    pub struct Dispatch<D>(PhantomData<D>);
+
+    impl<D: PartialEq> Dispatch<D> {
+        pub fn changed(old_value: &D, new_value: &D) -> bool {
+            old_value != new_value
+        }
+    }
+
+    pub trait Fallback<T> {
+        fn changed(old_value: &T, new_value: &T) -> bool;
+    }
+
+    impl<T> Fallback<T> for Dispatch<T> {
+        fn changed(_old_value: &T, _new_value: &T) -> bool {
+            true
+        }
+    }
+}
+
+/// The fields a `lazy_update` tracked struct had before it was last re-created.
+/// Each field is compared with its new value when it is first read.
+#[derive(Debug)]
+pub(super) struct LazyUpdate<C>
+where
+    C: Configuration,
+{
+    /// The fields before the struct was re-created.
+    old_fields: C::Fields<'static>,
+
+    /// The revision in which the struct was re-created.
+    updated_at: Revision,
+
+    /// For each field, whether it differs from its old value, once compared.
+    changed: Box<[AtomicCell<Option<bool>>]>,
+}
+
+impl<C> LazyUpdate<C>
+where
+    C: Configuration,
+{
+    pub(super) fn new(old_fields: C::Fields<'static>, updated_at: Revision) -> Self {
+        Self {
+            old_fields,
+            updated_at,
+            changed: (0..C::FIELD_DEBUG_NAMES.len())
+                .map(|_| AtomicCell::new(None))
+                .collect(),
+        }
+    }
+
+    /// The revision in which the field `field_index` of `fields` last changed, given
+    /// that `revision` is when it last changed before the struct was re-created.
+    /// Compares the field with its old value, unless that was done already.
+    pub(super) fn field_changed_at(
+        &self,
+        fields: &C::Fields<'static>,
+        field_index: usize,
+        revision: Revision,
+    ) -> Revision {
+        let changed = match self.changed[field_index].load() {
+            Some(changed) => changed,
+            None => {
+                let changed = C::field_changed(&self.old_fields, fields, field_index);
+                self.changed[field_index].store(Some(changed));
+                changed
+            }
+        };
+        if changed {
+            self.updated_at
+        } else {
+            revision
+        }
+    }
+
+    /// Records the outcome of every comparison in `revisions`, before the struct is
+    /// re-created again. Fields that were not compared are treated as changed,
+    /// which is conservative but avoids comparing fields that are never read.
+    pub(super) fn finish(self, revisions: &mut [Revision]) {
+        for (revision, changed) in revisions.iter_mut().zip(self.changed.iter()) {
+            if changed.load() != Some(false) {
+                *revision = self.updated_at;
+            }
+        }
+    }
+}
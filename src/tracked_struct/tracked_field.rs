@@ -1,9 +1,10 @@
 use std::marker::PhantomData;
 
 use crate::{
-    ingredient::{Ingredient, MaybeChangedAfter},
+    ingredient::{Ingredient, IngredientKind, MaybeChangedAfter},
+    input::edit::EditRange,
     zalsa::IngredientIndex,
-    Database, Id,
+    Database, Durability, Id,
 };
 
 use super::{Configuration, Value};
@@ -23,6 +24,9 @@ where
 {
     /// Index of this ingredient in the database (used to construct database-ids, etc).
     ingredient_index: IngredientIndex,
+
+    /// Index of the tracked struct ingredient whose field this is.
+    struct_index: IngredientIndex,
     field_index: usize,
     phantom: PhantomData<fn() -> Value<C>>,
 }
@@ -34,6 +38,7 @@ where
     pub(super) fn new(struct_index: IngredientIndex, field_index: usize) -> Self {
         Self {
             ingredient_index: struct_index.successor(field_index),
+            struct_index,
             field_index,
             phantom: PhantomData,
         }
@@ -60,10 +65,43 @@ where
     ) -> MaybeChangedAfter {
         let zalsa = db.zalsa();
         let data = <super::IngredientImpl<C>>::data(zalsa.table(), input);
-        let field_changed_at = data.revisions[self.field_index];
+        let field_changed_at = data.field_changed_at(self.field_index);
         MaybeChangedAfter::from(field_changed_at > revision)
     }
 
+    fn maybe_changed_after_range<'db>(
+        &'db self,
+        db: &'db dyn Database,
+        input: Id,
+        range: EditRange,
+        revision: crate::Revision,
+    ) -> MaybeChangedAfter {
+        // Ranges are only recorded by `field_element`, for a single element.
+        let zalsa = db.zalsa();
+        let data = <super::IngredientImpl<C>>::data(zalsa.table(), input);
+        let element_changed_at = data.element_changed_at(self.field_index, range.start());
+        MaybeChangedAfter::from(element_changed_at > revision)
+    }
+
+    fn durability(&self, db: &dyn Database, key_index: Id) -> Option<Durability> {
+        let data = <super::IngredientImpl<C>>::data(db.zalsa().table(), key_index);
+        Some(data.durability)
+    }
+
+    fn id_generation(&self, db: &dyn Database, id: Id) -> Option<u16> {
+        db.zalsa()
+            .lookup_ingredient(self.struct_index)
+            .id_generation(db, id)
+    }
+
+    fn value_hash(&self, db: &dyn Database, key_index: Id) -> Option<u64> {
+        let data = <super::IngredientImpl<C>>::data(db.zalsa().table(), key_index);
+        Some(crate::checksum::debug_hash(C::field_debug(
+            &data.fields,
+            self.field_index,
+        )))
+    }
+
     fn origin(
         &self,
         _db: &dyn Database,
@@ -115,6 +153,10 @@ where
     fn debug_name(&self) -> &'static str {
         C::FIELD_DEBUG_NAMES[self.field_index]
     }
+
+    fn kind(&self) -> IngredientKind {
+        IngredientKind::TrackedField
+    }
 }
 
 impl<C> std::fmt::Debug for FieldIngredientImpl<C>
@@ -110,6 +110,60 @@ pub fn always_update<T>(
     *old_pointer = new_value;
 }
 
+/// A boxed trait object, possibly optional, that can be compared with a function
+/// on the trait objects. See [`update_dyn_eq`].
+pub trait DynBox {
+    /// The trait object, e.g. `dyn Plugin`.
+    type Target: ?Sized;
+
+    /// Compares `self` with `other`, comparing the trait objects with `eq`.
+    fn dyn_eq(&self, other: &Self, eq: &dyn Fn(&Self::Target, &Self::Target) -> bool) -> bool;
+}
+
+impl<T: ?Sized> DynBox for Box<T> {
+    type Target = T;
+
+    fn dyn_eq(&self, other: &Self, eq: &dyn Fn(&T, &T) -> bool) -> bool {
+        eq(self, other)
+    }
+}
+
+impl<B: DynBox> DynBox for Option<B> {
+    type Target = B::Target;
+
+    fn dyn_eq(&self, other: &Self, eq: &dyn Fn(&B::Target, &B::Target) -> bool) -> bool {
+        match (self, other) {
+            (Some(this), Some(other)) => this.dyn_eq(other, eq),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Helper for generated code. Like [`update_fallback`], but compares the trait objects
+/// in a `Box<dyn Trait>` or `Option<Box<dyn Trait>>` with `eq`. Used for fields tagged
+/// with `#[update(dyn_eq = eq)]` in `#[derive(Update)]`, which makes structs holding
+/// trait objects backdate rather than be marked `#[no_eq]`.
+///
+/// # Safety
+///
+/// See `Update::maybe_update`
+pub unsafe fn update_dyn_eq<T, E>(old_pointer: *mut T, new_value: T, eq: E) -> bool
+where
+    T: 'static + DynBox,
+    E: Fn(&T::Target, &T::Target) -> bool,
+{
+    // Because everything is owned, this ref is simply a valid `&mut`
+    let old_ref: &mut T = unsafe { &mut *old_pointer };
+
+    if !old_ref.dyn_eq(&new_value, &eq) {
+        *old_ref = new_value;
+        true
+    } else {
+        false
+    }
+}
+
 /// # Safety
 ///
 /// Implementing this trait requires the implementor to verify:
@@ -170,16 +224,16 @@ where
     unsafe fn maybe_update(old_pointer: *mut Self, new_vec: Self) -> bool {
         let old_vec: &mut Vec<T> = unsafe { &mut *old_pointer };
 
-        if old_vec.len() != new_vec.len() {
-            old_vec.clear();
-            old_vec.extend(new_vec);
-            return true;
-        }
+        // Update the elements in place, so that the old vector's allocation
+        // (and those of its elements) are reused, even if the length changed.
+        let mut changed = old_vec.len() != new_vec.len();
+        old_vec.truncate(new_vec.len());
 
-        let mut changed = false;
-        for (old_element, new_element) in old_vec.iter_mut().zip(new_vec) {
+        let mut new_elements = new_vec.into_iter();
+        for (old_element, new_element) in old_vec.iter_mut().zip(&mut new_elements) {
             changed |= T::maybe_update(old_element, new_element);
         }
+        old_vec.extend(new_elements);
 
         changed
     }
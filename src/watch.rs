@@ -0,0 +1,127 @@
+//! Inputs that mirror files on disk, updated when the files change,
+//! with the `watch` feature.
+//!
+//! An [`InputWatcher`] creates the input for a file the first time it is requested
+//! (typically from within a query, so that the files read are discovered lazily)
+//! and watches the file from then on. [`InputWatcher::recv_changes`] waits for files
+//! to change and applies all the changes gathered so far in a single new revision.
+//!
+//! ```rust,ignore
+//! let watcher = db.watcher.clone();
+//! loop {
+//!     println!("{}", compile(&db, root));
+//!     watcher.recv_changes(&mut db, |db, file: File, contents| {
+//!         file.set_contents(db).to(contents.unwrap_or_default());
+//!     })?;
+//! }
+//! ```
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+    time::Duration,
+};
+
+use notify_debouncer_mini::{
+    new_debouncer,
+    notify::{RecommendedWatcher, RecursiveMode},
+    DebounceEventResult, DebouncedEvent, Debouncer,
+};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+use crate::Database;
+
+pub use notify_debouncer_mini::notify;
+
+/// Owns the inputs created for files and the watches on those files.
+/// `I` is the input struct holding the contents of a file.
+///
+/// Changes are debounced: the changes to a file are reported once it has not
+/// changed for the timeout given to [`Self::new`].
+pub struct InputWatcher<I> {
+    /// The input of each file, by canonical path.
+    inputs: Mutex<FxHashMap<PathBuf, I>>,
+    debouncer: Mutex<Debouncer<RecommendedWatcher>>,
+    events: Mutex<Receiver<DebounceEventResult>>,
+}
+
+impl<I> InputWatcher<I>
+where
+    I: Copy,
+{
+    /// Creates a watcher that reports the changes to a file once it has not changed for `timeout`.
+    pub fn new(timeout: Duration) -> notify::Result<Self> {
+        let (sender, events) = channel();
+        Ok(Self {
+            inputs: Default::default(),
+            debouncer: Mutex::new(new_debouncer(timeout, sender)?),
+            events: Mutex::new(events),
+        })
+    }
+
+    /// Returns the input for the file at `path`. The first time a file is requested,
+    /// starts watching it, reads it, and creates its input with `create`,
+    /// which is given the canonical path and the contents of the file.
+    pub fn input(&self, path: &Path, create: impl FnOnce(PathBuf, String) -> I) -> io::Result<I> {
+        let path = path.canonicalize()?;
+        let mut inputs = self.inputs.lock();
+        if let Some(&input) = inputs.get(&path) {
+            return Ok(input);
+        }
+
+        // Watch the file before reading it, so that a change in between is not missed.
+        self.debouncer
+            .lock()
+            .watcher()
+            .watch(&path, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+        let contents = std::fs::read_to_string(&path)?;
+        let input = create(path.clone(), contents);
+        inputs.insert(path, input);
+        Ok(input)
+    }
+
+    /// Blocks until some watched files have changed, then reads them and applies their
+    /// contents with `apply`, all in a single new revision (see [`Database::transaction`]).
+    /// Changes that were reported while waiting are applied along with the first one.
+    ///
+    /// `apply` is given the contents of each changed file once, or the error reading it,
+    /// e.g. if it was deleted. Returns the inputs of the changed files.
+    pub fn recv_changes<Db: Database>(
+        &self,
+        db: &mut Db,
+        mut apply: impl FnMut(&mut Db, I, io::Result<String>),
+    ) -> notify::Result<Vec<I>> {
+        let mut changed_paths = vec![];
+        {
+            let events = self.events.lock();
+            let first = events
+                .recv()
+                .expect("the debouncer is owned by the watcher, so it cannot disconnect");
+            for result in std::iter::once(first).chain(events.try_iter()) {
+                changed_paths.extend(result?.into_iter().map(|event: DebouncedEvent| event.path));
+            }
+        }
+
+        let changes: Vec<(I, PathBuf)> = {
+            let inputs = self.inputs.lock();
+            changed_paths.sort();
+            changed_paths.dedup();
+            changed_paths
+                .into_iter()
+                .filter_map(|path| Some((*inputs.get(&path)?, path)))
+                .collect()
+        };
+
+        if !changes.is_empty() {
+            db.transaction(|db| {
+                for &(input, ref path) in &changes {
+                    apply(db, input, std::fs::read_to_string(path));
+                }
+            });
+        }
+        Ok(changes.into_iter().map(|(input, _)| input).collect())
+    }
+}
@@ -0,0 +1,57 @@
+use append_only_vec::AppendOnlyVec;
+use parking_lot::{Mutex, MutexGuard};
+
+use crate::{
+    ingredient::{Ingredient, Jar},
+    zalsa::{IngredientIndex, Zalsa},
+};
+
+/// Allows inputs to be set from multiple threads at once, as part of a single new revision.
+/// Created with [`Database::write_scope`](`crate::Database::write_scope`).
+///
+/// Each input ingredient (i.e., each `#[salsa::input]` struct) has its own lock,
+/// so setting fields of *different* input structs proceeds in parallel,
+/// while sets on the same input struct are serialized, even if they are sets of
+/// different inputs of that struct: all of them take the lock of its ingredient.
+/// Fields are set with the `set_<field>_in` setters generated for each input.
+pub struct WriteScope<'w> {
+    zalsa: &'w Zalsa,
+
+    /// The lock of each ingredient, indexed by its ingredient index. Grown on demand,
+    /// as jars are registered lazily and may be registered within the scope.
+    ingredient_locks: AppendOnlyVec<Mutex<()>>,
+}
+
+impl<'w> WriteScope<'w> {
+    /// Create a scope for writing into `zalsa`.
+    /// The caller must have obtained `zalsa` through `zalsa_mut`, so that
+    /// other handles are cancelled and no queries can run while the scope exists.
+    pub(crate) fn new(zalsa: &'w Zalsa) -> Self {
+        Self {
+            zalsa,
+            ingredient_locks: AppendOnlyVec::new(),
+        }
+    }
+
+    /// **NOT SEMVER STABLE**
+    pub fn zalsa(&self) -> &'w Zalsa {
+        self.zalsa
+    }
+
+    /// **NOT SEMVER STABLE**
+    ///
+    /// The first ingredient of `jar`, registering the jar if needed; it must be of type `I`.
+    pub fn ingredient<I: Ingredient>(&self, jar: &dyn Jar) -> &'w I {
+        let index = self.zalsa.add_or_lookup_jar_by_type(jar);
+        self.zalsa.lookup_ingredient(index).assert_type::<I>()
+    }
+
+    /// Acquire the lock for writing to `index`.
+    pub(crate) fn lock_ingredient(&self, index: IngredientIndex) -> MutexGuard<'_, ()> {
+        // Every lock is the same, so it does not matter which thread pushes the one of `index`.
+        while self.ingredient_locks.len() <= index.as_usize() {
+            self.ingredient_locks.push(Mutex::new(()));
+        }
+        self.ingredient_locks[index.as_usize()].lock()
+    }
+}
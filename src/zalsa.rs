@@ -2,18 +2,25 @@ use append_only_vec::AppendOnlyVec;
 use parking_lot::{Mutex, RwLock};
 use rustc_hash::FxHashMap;
 use std::any::{Any, TypeId};
+use std::borrow::Cow;
 use std::marker::PhantomData;
+use std::sync::Arc;
 use std::thread::ThreadId;
 
+use crate::channel::Channels;
 use crate::cycle::CycleRecoveryStrategy;
+use crate::event::Subscribers;
 use crate::ingredient::{Ingredient, Jar, JarAux};
 use crate::nonce::{Nonce, NonceGenerator};
-use crate::runtime::{Runtime, WaitResult};
+use crate::runtime::{deadlock::DeadlockWatchdog, Runtime, WaitResult};
+use crate::salsa_struct::SalsaStructInDb;
+use crate::storage::StorageOptions;
 use crate::table::memo::MemoTable;
 use crate::table::sync::SyncTable;
 use crate::table::Table;
 use crate::views::Views;
 use crate::zalsa_local::ZalsaLocal;
+use crate::CancellationMode;
 use crate::{Database, DatabaseKeyIndex, Durability, Id, Revision};
 
 /// Internal plumbing trait.
@@ -47,6 +54,11 @@ pub unsafe trait ZalsaDatabase: Any {
     #[doc(hidden)]
     fn zalsa_mut(&mut self) -> &mut Zalsa;
 
+    /// Plumbing method: Access the internal salsa methods for mutating the database
+    /// without starting a new revision, if there are no other handles to the database.
+    #[doc(hidden)]
+    fn zalsa_mut_if_unshared(&mut self) -> Option<&mut Zalsa>;
+
     /// Access the thread-local state associated with this database
     #[doc(hidden)]
     fn zalsa_local(&self) -> &ZalsaLocal;
@@ -81,6 +93,10 @@ impl IngredientIndex {
         Self(v as u32)
     }
 
+    pub(crate) fn as_u32(self) -> u32 {
+        self.0
+    }
+
     /// Convert the ingredient index back into a usize.
     pub(crate) fn as_usize(self) -> usize {
         self.0 as usize
@@ -125,8 +141,9 @@ pub struct Zalsa {
 
     /// Map from the [`IngredientIndex::as_usize`][] of a salsa struct to a list of
     /// [ingredient-indices](`IngredientIndex`) for tracked functions that have this salsa struct
-    /// as input.
-    memo_ingredient_indices: RwLock<Vec<Vec<IngredientIndex>>>,
+    /// as input. Indices reserved by [`StorageBuilder::memo_layout_hint`](`crate::StorageBuilder::memo_layout_hint`)
+    /// for functions that were not used yet are `None`.
+    memo_ingredient_indices: RwLock<Vec<Vec<Option<IngredientIndex>>>>,
 
     /// Map from the type-id of an `impl Jar` to the index of its first ingredient.
     /// This is using a `Mutex<FxHashMap>` (versus, say, a `FxDashMap`)
@@ -147,6 +164,17 @@ pub struct Zalsa {
     /// The runtime for this particular salsa database handle.
     /// Each handle gets its own runtime, but the runtimes have shared state between them.
     runtime: Runtime,
+
+    /// True while a [`Database::transaction`] is running: writes then reuse the
+    /// revision started by the transaction instead of starting a new one each.
+    in_transaction: bool,
+
+    /// Subscribers registered with [`Storage::subscribe`](`crate::Storage::subscribe`).
+    /// Shared with the helpers that unsubscribe when dropped, e.g. `ExecutionRecorder`.
+    subscribers: Arc<Subscribers>,
+
+    /// Options for the ingredients, set when the storage was built.
+    options: StorageOptions,
 }
 
 impl Zalsa {
@@ -159,6 +187,9 @@ impl Zalsa {
             ingredients_requiring_reset: AppendOnlyVec::new(),
             runtime: Runtime::default(),
             memo_ingredient_indices: Default::default(),
+            in_transaction: false,
+            subscribers: Default::default(),
+            options: Default::default(),
         }
     }
 
@@ -233,6 +264,23 @@ impl Zalsa {
         &*self.ingredients_vec[index.as_usize()]
     }
 
+    /// The number of ingredients created so far.
+    pub(crate) fn ingredients_len(&self) -> usize {
+        self.ingredients_vec.len()
+    }
+
+    /// The index of the ingredient of the salsa struct `S`, if it was created already.
+    pub(crate) fn lookup_struct_ingredient<S: SalsaStructInDb>(&self) -> Option<IngredientIndex> {
+        let jar_map = self.jar_map.lock();
+        S::lookup_ingredient_index(&JarAuxImpl(self, &jar_map))
+    }
+
+    /// The indices of the ingredients created so far for the salsa structs `S` can be.
+    pub(crate) fn lookup_struct_ingredients<S: SalsaStructInDb>(&self) -> Vec<IngredientIndex> {
+        let jar_map = self.jar_map.lock();
+        S::lookup_ingredient_indices(&JarAuxImpl(self, &jar_map))
+    }
+
     /// **NOT SEMVER STABLE**
     pub fn lookup_ingredient_mut(
         &mut self,
@@ -249,10 +297,26 @@ impl Zalsa {
         self.runtime.current_revision()
     }
 
+    pub(crate) fn set_options(&mut self, options: StorageOptions) {
+        self.options = options;
+    }
+
+    pub(crate) fn set_cancellation_mode(&mut self, mode: CancellationMode) {
+        self.runtime.set_cancellation_mode(mode)
+    }
+
+    pub(crate) fn set_deadlock_watchdog(&mut self, watchdog: Option<DeadlockWatchdog>) {
+        self.runtime.set_deadlock_watchdog(watchdog)
+    }
+
     pub(crate) fn load_cancellation_flag(&self) -> bool {
         self.runtime.load_cancellation_flag()
     }
 
+    pub(crate) fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+
     pub(crate) fn report_tracked_write(&mut self, durability: Durability) {
         self.runtime.report_tracked_write(durability)
     }
@@ -262,10 +326,41 @@ impl Zalsa {
         self.runtime.last_changed_revision(durability)
     }
 
+    pub(crate) fn last_changed_revision_in(&self, channels: Channels) -> Revision {
+        self.runtime.last_changed_revision_in(channels)
+    }
+
     pub(crate) fn set_cancellation_flag(&self) {
         self.runtime.set_cancellation_flag()
     }
 
+    pub(crate) fn subscribers(&self) -> &Arc<Subscribers> {
+        &self.subscribers
+    }
+
+    pub(crate) fn in_transaction(&self) -> bool {
+        self.in_transaction
+    }
+
+    /// Invoked (through `zalsa_mut`, so in a fresh revision) when a transaction starts.
+    /// The cancellation flag stays set until [`Self::end_transaction`],
+    /// so that any query attempted during the transaction is cancelled
+    /// rather than observing a partially written revision.
+    pub(crate) fn begin_transaction(&mut self) {
+        assert!(!self.in_transaction, "transactions cannot be nested");
+        self.in_transaction = true;
+        self.runtime.set_cancellation_flag();
+        self.runtime.changes().begin_batch();
+    }
+
+    pub(crate) fn end_transaction(&mut self) {
+        self.in_transaction = false;
+        self.runtime.clear_cancellation_flag();
+        self.runtime
+            .changes()
+            .end_batch(self.runtime.current_revision());
+    }
+
     /// Triggers a new revision. Invoked automatically when you call `zalsa_mut`
     /// and so doesn't need to be called otherwise.
     pub(crate) fn new_revision(&mut self) -> Revision {
@@ -278,6 +373,19 @@ impl Zalsa {
         new_revision
     }
 
+    /// Frees the memos that ingredients replaced while other handles could still read them,
+    /// see [`Ingredient::free_deleted_entries`].
+    /// Returns the number of bytes freed by each ingredient that may replace memos.
+    pub(crate) fn free_deleted_entries(&mut self) -> Vec<(IngredientIndex, usize)> {
+        self.ingredients_requiring_reset
+            .iter()
+            .map(|index| {
+                let bytes = self.ingredients_vec[index.as_usize()].free_deleted_entries();
+                (*index, bytes)
+            })
+            .collect()
+    }
+
     /// See [`Runtime::block_on_or_unwind`][]
     pub(crate) fn block_on_or_unwind<QueryMutexGuard>(
         &self,
@@ -308,6 +416,27 @@ impl Zalsa {
     ) -> IngredientIndex {
         self.memo_ingredient_indices.read()[struct_ingredient_index.as_usize()]
             [memo_ingredient_index.as_usize()]
+        .expect("memos are only stored for the functions that were used")
+    }
+
+    /// The tracked functions taking each salsa struct, for the structs taken by any.
+    pub(crate) fn memo_ingredients(&self) -> Vec<(IngredientIndex, Vec<IngredientIndex>)> {
+        self.memo_ingredient_indices
+            .read()
+            .iter()
+            .enumerate()
+            .map(|(struct_index, function_indices)| {
+                (
+                    IngredientIndex::from(struct_index),
+                    function_indices
+                        .iter()
+                        .flatten()
+                        .copied()
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .filter(|(_, function_indices)| !function_indices.is_empty())
+            .collect()
     }
 }
 
@@ -322,6 +451,7 @@ impl JarAux for JarAuxImpl<'_> {
         &self,
         struct_ingredient_index: IngredientIndex,
         ingredient_index: IngredientIndex,
+        debug_name: &'static str,
     ) -> MemoIngredientIndex {
         let mut memo_ingredients = self.0.memo_ingredient_indices.write();
         let idx = struct_ingredient_index.as_usize();
@@ -331,10 +461,56 @@ impl JarAux for JarAuxImpl<'_> {
             memo_ingredients.resize_with(idx + 1, Vec::new);
             &mut memo_ingredients[idx]
         };
+
+        // The struct is not created yet if it is the one interning the arguments of
+        // this very function, which is the only function taking it.
+        let layout = self
+            .0
+            .options
+            .memo_layout_hint
+            .as_ref()
+            .filter(|_| idx < self.0.ingredients_len())
+            .and_then(|hint| {
+                hint.layout_of(
+                    self.0
+                        .lookup_ingredient(struct_ingredient_index)
+                        .debug_name(),
+                )
+            });
+        if let Some(layout) = layout {
+            // Reserve the indices of the hinted functions when the first one is used.
+            if memo_ingredients.is_empty() {
+                memo_ingredients.resize(layout.len(), None);
+            }
+            let reserved = layout
+                .iter()
+                .position(|&name| name == debug_name)
+                .filter(|&position| memo_ingredients[position].is_none());
+            if let Some(position) = reserved {
+                memo_ingredients[position] = Some(ingredient_index);
+                return MemoIngredientIndex::from_usize(position);
+            }
+        }
+
         let mi = MemoIngredientIndex(u32::try_from(memo_ingredients.len()).unwrap());
-        memo_ingredients.push(ingredient_index);
+        memo_ingredients.push(Some(ingredient_index));
         mi
     }
+
+    fn interned_shards(&self) -> Option<usize> {
+        self.0
+            .options
+            .interned_shards
+            .or_else(|| self.0.options.shards())
+    }
+
+    fn shards(&self) -> Option<usize> {
+        self.0.options.shards()
+    }
+
+    fn default_lru_capacity(&self) -> usize {
+        self.0.options.default_lru
+    }
 }
 
 /// Caches a pointer to an ingredient in a database.
@@ -399,6 +575,46 @@ where
     }
 }
 
+/// Caches the indices of several ingredients, like [`IngredientCache`] caches one;
+/// used for the struct ingredients of the variants of a `#[derive(salsa::Supertype)]` enum.
+pub struct IngredientIndicesCache {
+    cached_data: std::sync::OnceLock<(Nonce<StorageNonce>, Vec<IngredientIndex>)>,
+}
+
+impl Default for IngredientIndicesCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IngredientIndicesCache {
+    /// Create a new cache
+    pub const fn new() -> Self {
+        Self {
+            cached_data: std::sync::OnceLock::new(),
+        }
+    }
+
+    /// Get the indices of the ingredients in the database.
+    /// If they are not already in the cache, they will be created.
+    pub fn get_or_create(
+        &self,
+        db: &dyn Database,
+        create_indices: impl Fn() -> Vec<IngredientIndex>,
+    ) -> Cow<'_, [IngredientIndex]> {
+        let (nonce, indices) = self
+            .cached_data
+            .get_or_init(|| (db.zalsa().nonce(), create_indices()));
+
+        // The cache is shared by all databases, but only filled for the first one.
+        if db.zalsa().nonce() == *nonce {
+            Cow::Borrowed(indices)
+        } else {
+            Cow::Owned(create_indices())
+        }
+    }
+}
+
 /// Given a wide pointer `T`, extracts the data pointer (typed as `U`).
 ///
 /// # Safety requirement
@@ -4,15 +4,19 @@ use tracing::debug;
 
 use crate::accumulator::accumulated_map::{AccumulatedMap, InputAccumulatedValues};
 use crate::active_query::ActiveQuery;
+use crate::cancelled::TimedOut;
+use crate::channel::Channels;
 use crate::durability::Durability;
+use crate::input::edit::EditRange;
 use crate::key::{DatabaseKeyIndex, InputDependencyIndex, OutputDependencyIndex};
 use crate::runtime::StampedValue;
 use crate::table::PageIndex;
 use crate::table::Slot;
 use crate::table::Table;
 use crate::tracked_struct::{Disambiguator, Identity, IdentityHash, IdentityMap};
-use crate::zalsa::IngredientIndex;
+use crate::zalsa::{IngredientIndex, Zalsa};
 use crate::Accumulator;
+use crate::CancellationMode;
 use crate::Cancelled;
 use crate::Cycle;
 use crate::Database;
@@ -20,7 +24,8 @@ use crate::Event;
 use crate::EventKind;
 use crate::Id;
 use crate::Revision;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
 
 /// State that is specific to a single execution thread.
 ///
@@ -41,6 +46,29 @@ pub struct ZalsaLocal {
     /// Stores the most recent page for a given ingredient.
     /// This is thread-local to avoid contention.
     most_recent_pages: RefCell<FxHashMap<IngredientIndex, PageIndex>>,
+
+    /// See [`Database::thread_stats`](`crate::Database::thread_stats`).
+    thread_stats: Cell<ThreadStats>,
+
+    /// While [`check_incremental`](`crate::check_incremental`) runs a test,
+    /// the key and the debug output of the value of each query fetched outside of any query.
+    #[cfg(feature = "testing")]
+    fetches: RefCell<Option<Vec<(DatabaseKeyIndex, String)>>>,
+
+    /// The number of live [`PinnedRevision`](`crate::PinnedRevision`) guards for this handle.
+    pinned: Cell<usize>,
+}
+
+/// Statistics about the time a database handle spent in salsa,
+/// see [`Database::thread_stats`](`crate::Database::thread_stats`).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ThreadStats {
+    /// The total time spent waiting for queries executing on other threads.
+    pub blocked: Duration,
+
+    /// The number of times the handle waited for a query executing on another thread.
+    pub blocked_count: u64,
 }
 
 impl ZalsaLocal {
@@ -48,9 +76,77 @@ impl ZalsaLocal {
         ZalsaLocal {
             query_stack: RefCell::new(vec![]),
             most_recent_pages: RefCell::new(FxHashMap::default()),
+            thread_stats: Cell::new(ThreadStats::default()),
+            #[cfg(feature = "testing")]
+            fetches: RefCell::new(None),
+            pinned: Cell::new(0),
+        }
+    }
+
+    pub(crate) fn thread_stats(&self) -> ThreadStats {
+        self.thread_stats.get()
+    }
+
+    pub(crate) fn reset_thread_stats(&self) -> ThreadStats {
+        self.thread_stats.take()
+    }
+
+    #[cfg(feature = "testing")]
+    /// Starts recording the queries fetched outside of any query, see [`Self::record_fetch`].
+    pub(crate) fn start_recording_fetches(&self) {
+        *self.fetches.borrow_mut() = Some(vec![]);
+    }
+
+    #[cfg(feature = "testing")]
+    /// Stops recording and returns the queries fetched since [`Self::start_recording_fetches`].
+    pub(crate) fn take_recorded_fetches(&self) -> Vec<(DatabaseKeyIndex, String)> {
+        self.fetches.take().unwrap_or_default()
+    }
+
+    #[cfg(feature = "testing")]
+    /// Records that `database_key_index` was fetched, if recording and not in a query.
+    /// `value` produces the debug output of its value.
+    pub(crate) fn record_fetch(
+        &self,
+        database_key_index: DatabaseKeyIndex,
+        value: impl FnOnce() -> String,
+    ) {
+        if self.fetches.borrow().is_none() || !self.query_stack.borrow().is_empty() {
+            return;
+        }
+        let value = value();
+        if let Some(fetches) = self.fetches.borrow_mut().as_mut() {
+            fetches.push((database_key_index, value));
         }
     }
 
+    pub(crate) fn pin_revision(&self) {
+        self.pinned.set(self.pinned.get() + 1);
+    }
+
+    pub(crate) fn unpin_revision(&self) {
+        self.pinned.set(self.pinned.get() - 1);
+    }
+
+    pub(crate) fn is_revision_pinned(&self) -> bool {
+        self.pinned.get() > 0
+    }
+
+    /// True if the queries of this handle should stop because another handle is waiting
+    /// to write. A pinned revision ignores pending writes, but not transactions, during
+    /// which the revision is only partially written.
+    pub(crate) fn is_cancelled(&self, zalsa: &Zalsa) -> bool {
+        zalsa.load_cancellation_flag() && (!self.is_revision_pinned() || zalsa.in_transaction())
+    }
+
+    /// Records that this thread was blocked on another thread for `duration`.
+    pub(crate) fn report_blocked(&self, duration: Duration) {
+        let mut stats = self.thread_stats.get();
+        stats.blocked += duration;
+        stats.blocked_count += 1;
+        self.thread_stats.set(stats);
+    }
+
     /// Allocate a new id in `table` for the given ingredient
     /// storing `value`. Remembers the most recent page from this
     /// thread and attempts to reuse it.
@@ -84,10 +180,47 @@ impl ZalsaLocal {
         }
     }
 
+    /// Like [`Self::allocate`], but for many values at once, returning their ids in order.
+    /// The pages needed are pushed up-front, and each page is locked once.
+    pub(crate) fn allocate_many<T: Slot, V: FnOnce(Id) -> T>(
+        &self,
+        table: &Table,
+        ingredient: IngredientIndex,
+        mut values: impl ExactSizeIterator<Item = V>,
+    ) -> Vec<Id> {
+        let mut ids = Vec::with_capacity(values.len());
+        let mut pages = Vec::new();
+        let mut remaining = 0;
+        if let Some(&page) = self.most_recent_pages.borrow().get(&ingredient) {
+            pages.push(page);
+            remaining = table.page::<T>(page).remaining();
+        }
+        while remaining < values.len() {
+            pages.push(table.push_page::<T>(ingredient));
+            remaining += Table::PAGE_LEN;
+        }
+        if let Some(&page) = pages.last() {
+            self.most_recent_pages.borrow_mut().insert(ingredient, page);
+        }
+
+        for page in pages {
+            table
+                .page::<T>(page)
+                .allocate_many(page, &mut values, &mut ids);
+        }
+
+        // Only reached if another thread allocated on the most recent page meanwhile.
+        for value in values {
+            ids.push(self.allocate(table, ingredient, value));
+        }
+        ids
+    }
+
     #[inline]
     pub(crate) fn push_query(&self, database_key_index: DatabaseKeyIndex) -> ActiveQueryGuard<'_> {
         let mut query_stack = self.query_stack.borrow_mut();
-        query_stack.push(ActiveQuery::new(database_key_index));
+        let deadline = query_stack.last().and_then(|query| query.deadline);
+        query_stack.push(ActiveQuery::new(database_key_index, deadline));
         ActiveQueryGuard {
             local_state: self,
             database_key_index,
@@ -100,6 +233,15 @@ impl ZalsaLocal {
         c(self.query_stack.borrow_mut().as_mut())
     }
 
+    /// True if `database_key_index` is executing on this thread, i.e. is on the query stack.
+    pub(crate) fn is_active(&self, database_key_index: DatabaseKeyIndex) -> bool {
+        self.with_query_stack(|stack| {
+            stack
+                .iter()
+                .any(|query| query.database_key_index == database_key_index)
+        })
+    }
+
     /// Returns the index of the active query along with its *current* durability/changed-at
     /// information. As the query continues to execute, naturally, that information may change.
     pub(crate) fn active_query(&self) -> Option<(DatabaseKeyIndex, StampedValue<()>)> {
@@ -111,6 +253,7 @@ impl ZalsaLocal {
                         value: (),
                         durability: active_query.durability,
                         changed_at: active_query.changed_at,
+                        channels: active_query.channels,
                     },
                 )
             })
@@ -135,6 +278,22 @@ impl ZalsaLocal {
         })
     }
 
+    /// Extends `output` with the values accumulated to `index` so far by the active query, and
+    /// returns the queries it has read so far, unless none of them has accumulated values.
+    ///
+    /// Returns `Err` if not in a query.
+    pub(crate) fn accumulated_so_far<A: Accumulator>(
+        &self,
+        index: IngredientIndex,
+        output: &mut Vec<A>,
+    ) -> Result<Vec<DatabaseKeyIndex>, ()> {
+        self.with_query_stack(|stack| {
+            let top_query = stack.last().ok_or(())?;
+            top_query.accumulated.extend_with_accumulated(index, output);
+            Ok(top_query.inputs_with_accumulated())
+        })
+    }
+
     /// Add an output to the current query's list of dependencies
     pub(crate) fn add_output(&self, entity: OutputDependencyIndex) {
         self.with_query_stack(|stack| {
@@ -160,16 +319,57 @@ impl ZalsaLocal {
         &self,
         input: InputDependencyIndex,
         durability: Durability,
+        channels: Channels,
         changed_at: Revision,
         accumulated: InputAccumulatedValues,
     ) {
         debug!(
-            "report_tracked_read(input={:?}, durability={:?}, changed_at={:?})",
-            input, durability, changed_at
+            "report_tracked_read(input={:?}, durability={:?}, channels={:?}, changed_at={:?})",
+            input, durability, channels, changed_at
         );
+        self.report_read(
+            QueryEdge::Input(input),
+            durability,
+            channels,
+            changed_at,
+            accumulated,
+        )
+    }
+
+    /// Register that currently active query reads the bytes `range` of the given input field
+    /// (or the elements `range` of a tracked struct field)
+    pub(crate) fn report_tracked_range_read(
+        &self,
+        input: InputDependencyIndex,
+        range: EditRange,
+        durability: Durability,
+        channels: Channels,
+        changed_at: Revision,
+    ) {
+        debug!(
+            "report_tracked_range_read(input={:?}, range={:?}, durability={:?}, channels={:?}, changed_at={:?})",
+            input, range, durability, channels, changed_at
+        );
+        self.report_read(
+            QueryEdge::InputRange(input, range),
+            durability,
+            channels,
+            changed_at,
+            InputAccumulatedValues::Empty,
+        )
+    }
+
+    fn report_read(
+        &self,
+        edge: QueryEdge,
+        durability: Durability,
+        channels: Channels,
+        changed_at: Revision,
+        accumulated: InputAccumulatedValues,
+    ) {
         self.with_query_stack(|stack| {
             if let Some(top_query) = stack.last_mut() {
-                top_query.add_read(input, durability, changed_at, accumulated);
+                top_query.add_read(edge, durability, channels, changed_at, accumulated);
 
                 // We are a cycle participant:
                 //
@@ -235,17 +435,22 @@ impl ZalsaLocal {
     ///   * the current dependencies (durability, changed_at) of current query
     ///   * the disambiguator index
     #[track_caller]
-    pub(crate) fn disambiguate(&self, key: IdentityHash) -> (StampedValue<()>, Disambiguator) {
+    pub(crate) fn disambiguate(
+        &self,
+        key: IdentityHash,
+    ) -> (DatabaseKeyIndex, StampedValue<()>, Disambiguator) {
         self.with_query_stack(|stack| {
             let top_query = stack.last_mut().expect(
                 "cannot create a tracked struct disambiguator outside of a tracked function",
             );
             let disambiguator = top_query.disambiguate(key);
             (
+                top_query.database_key_index,
                 StampedValue {
                     value: (),
                     durability: top_query.durability,
                     changed_at: top_query.changed_at,
+                    channels: top_query.channels,
                 },
                 disambiguator,
             )
@@ -289,11 +494,38 @@ impl ZalsaLocal {
     /// `salsa_event` is emitted when this method is called, so that should be
     /// used instead.
     pub(crate) fn unwind_if_revision_cancelled(&self, db: &dyn Database) {
-        db.salsa_event(&|| Event::new(EventKind::WillCheckCancellation));
+        crate::event::emit(db, &|| Event::new(EventKind::WillCheckCancellation));
         let zalsa = db.zalsa();
-        if zalsa.load_cancellation_flag() {
+        let unwind = zalsa.runtime().cancellation_mode() == CancellationMode::Unwind;
+        // Even in cooperative mode, queries must not observe a partially written revision.
+        if (unwind || zalsa.in_transaction()) && self.is_cancelled(zalsa) {
             self.unwind_cancelled(zalsa.current_revision());
         }
+        if unwind {
+            self.unwind_if_timed_out();
+        }
+    }
+
+    /// Starts unwinding the stack if a query on it has run past its deadline.
+    ///
+    /// A query's deadline is inherited by the queries it executes, so the top of the stack
+    /// has the earliest one. Unwinding stops at the outermost query that timed out,
+    /// which recovers with the result of its `timeout_result` function.
+    pub(crate) fn unwind_if_timed_out(&self) {
+        let timed_out = self.with_query_stack(|stack| {
+            let deadline = stack.last()?.deadline?;
+            let now = Instant::now();
+            if deadline > now {
+                return None;
+            }
+            stack
+                .iter()
+                .find(|query| query.deadline.is_some_and(|deadline| deadline <= now))
+                .map(|query| query.database_key_index)
+        });
+        if let Some(database_key_index) = timed_out {
+            TimedOut::new(database_key_index).throw();
+        }
     }
 
     #[cold]
@@ -316,6 +548,9 @@ pub(crate) struct QueryRevisions {
     /// Minimum durability of the inputs to this query.
     pub(crate) durability: Durability,
 
+    /// Channels of the inputs to this query.
+    pub(crate) channels: Channels,
+
     /// How was this query computed?
     pub(crate) origin: QueryOrigin,
 
@@ -353,6 +588,7 @@ impl QueryRevisions {
         StampTemplate {
             durability: self.durability,
             changed_at: self.changed_at,
+            channels: self.channels,
         }
     }
 }
@@ -361,6 +597,7 @@ impl QueryRevisions {
 pub(crate) struct StampTemplate {
     durability: Durability,
     changed_at: Revision,
+    channels: Channels,
 }
 
 impl StampTemplate {
@@ -369,6 +606,7 @@ impl StampTemplate {
             value,
             durability: self.durability,
             changed_at: self.changed_at,
+            channels: self.channels,
         }
     }
 }
@@ -441,6 +679,8 @@ pub struct QueryEdges {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum QueryEdge {
     Input(InputDependencyIndex),
+    /// A read of only some bytes of a `#[delta]` input field.
+    InputRange(InputDependencyIndex, EditRange),
     Output(OutputDependencyIndex),
 }
 
@@ -450,7 +690,9 @@ impl QueryEdges {
     /// These will always be in execution order.
     pub(crate) fn inputs(&self) -> impl DoubleEndedIterator<Item = InputDependencyIndex> + '_ {
         self.input_outputs.iter().filter_map(|&edge| match edge {
-            QueryEdge::Input(dependency_index) => Some(dependency_index),
+            QueryEdge::Input(dependency_index) | QueryEdge::InputRange(dependency_index, _) => {
+                Some(dependency_index)
+            }
             QueryEdge::Output(_) => None,
         })
     }
@@ -461,7 +703,7 @@ impl QueryEdges {
     pub(crate) fn outputs(&self) -> impl DoubleEndedIterator<Item = OutputDependencyIndex> + '_ {
         self.input_outputs.iter().filter_map(|&edge| match edge {
             QueryEdge::Output(dependency_index) => Some(dependency_index),
-            QueryEdge::Input(_) => None,
+            QueryEdge::Input(_) | QueryEdge::InputRange(..) => None,
         })
     }
 
@@ -527,6 +769,56 @@ impl ActiveQueryGuard<'_> {
         popped_query.into_revisions()
     }
 
+    /// Discards the values accumulated so far by the active query.
+    ///
+    /// Used when a cycle participant recovers: its execution was aborted,
+    /// so the values it accumulated do not correspond to the recovered value.
+    pub(crate) fn discard_accumulated(&self) {
+        self.local_state.with_query_stack(|stack| {
+            assert_eq!(stack.len(), self.push_len);
+            let frame = stack.last_mut().unwrap();
+            frame.accumulated = Default::default();
+        })
+    }
+
+    /// Sets the deadline of the active query to `timeout` from now,
+    /// unless it inherited an earlier one.
+    pub(crate) fn set_timeout(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        self.local_state.with_query_stack(|stack| {
+            assert_eq!(stack.len(), self.push_len);
+            let frame = stack.last_mut().unwrap();
+            frame.deadline = Some(frame.deadline.map_or(deadline, |d| d.min(deadline)));
+        })
+    }
+
+    /// Invoked when the active query timed out: lifts its deadline, so that its
+    /// `timeout_result` function can execute queries, discards the values it accumulated
+    /// and marks its value as only valid in the current revision,
+    /// so that it is re-executed in the next one.
+    pub(crate) fn report_timed_out(&self, current_revision: Revision) {
+        self.local_state.with_query_stack(|stack| {
+            assert_eq!(stack.len(), self.push_len);
+            let inherited_deadline = stack.iter().rev().nth(1).and_then(|query| query.deadline);
+            let frame = stack.last_mut().unwrap();
+            frame.deadline = inherited_deadline;
+            frame.accumulated = Default::default();
+            frame.add_untracked_read(current_revision);
+        })
+    }
+
+    /// Marks the value of the active query as only valid in the current revision,
+    /// so that it is re-executed in the next one; used for errors of `result(retry)` functions.
+    pub(crate) fn report_retry(&self, current_revision: Revision) {
+        self.local_state.with_query_stack(|stack| {
+            assert_eq!(stack.len(), self.push_len);
+            stack
+                .last_mut()
+                .unwrap()
+                .add_untracked_read(current_revision);
+        })
+    }
+
     /// If the active query is registered as a cycle participant, remove and
     /// return that cycle.
     pub(crate) fn take_cycle(&self) -> Option<Cycle> {
@@ -0,0 +1,51 @@
+//! Test that documented fields, and fields with `#[get_doc]` and `#[set_doc]`,
+//! generate working accessors for each kind of struct.
+
+use salsa::{Database, DatabaseImpl, Setter};
+
+/// A source file.
+#[salsa::input]
+struct File {
+    /// The path of the file.
+    path: String,
+
+    /// The contents of the file.
+    #[get_doc = "Returns the contents of the file."]
+    #[set_doc = "Replaces the contents of the file."]
+    contents: String,
+}
+
+#[salsa::interned]
+struct Name<'db> {
+    /// The text of the name.
+    #[get_doc = "Returns the text of the name."]
+    text: String,
+}
+
+#[salsa::tracked]
+struct Item<'db> {
+    /// The name of the item.
+    name: Name<'db>,
+
+    /// The number of lines of the item.
+    #[get_doc = "Returns the number of lines of the item."]
+    lines: usize,
+}
+
+#[salsa::tracked]
+fn file_item<'db>(db: &'db dyn Database, file: File) -> Item<'db> {
+    let name = Name::new(db, file.path(db));
+    Item::new(db, name, file.contents(db).lines().count())
+}
+
+#[test]
+fn accessors() {
+    let mut db = DatabaseImpl::new();
+    let file = File::new(&db, "a.rs".to_string(), "fn main() {}".to_string());
+    let item = file_item(&db, file);
+    assert_eq!(item.name(&db).text(&db), "a.rs");
+    assert_eq!(item.lines(&db), 1);
+
+    file.set_contents(&mut db).to("fn main() {\n}".to_string());
+    assert_eq!(file_item(&db, file).lines(&db), 2);
+}
@@ -0,0 +1,65 @@
+//! Test that a tracked function can read the values accumulated so far
+//! by itself and the queries it has called.
+
+use salsa::{Accumulator, Database, DatabaseImpl};
+use test_log::test;
+
+#[salsa::accumulator]
+#[derive(PartialEq)]
+struct Error(u32);
+
+#[salsa::input]
+struct Item {
+    value: u32,
+}
+
+#[salsa::input]
+struct File {
+    items: Vec<Item>,
+}
+
+#[salsa::tracked]
+fn check_item(db: &dyn Database, item: Item) {
+    let value = item.value(db);
+    if value % 2 == 1 {
+        Error(value).accumulate(db);
+    }
+}
+
+/// Checks the items of `file` until two errors were found, and returns the values checked.
+#[salsa::tracked]
+fn check_file(db: &dyn Database, file: File) -> Vec<u32> {
+    Error(0).accumulate(db);
+    let mut checked = vec![];
+    for item in file.items(db) {
+        if Error::accumulated_so_far(db).len() > 2 {
+            break;
+        }
+        check_item(db, item);
+        checked.push(item.value(db));
+    }
+    checked
+}
+
+#[test]
+fn bail_out_early() {
+    let db = DatabaseImpl::new();
+    let items = [2, 3, 4, 5, 7, 9]
+        .into_iter()
+        .map(|value| Item::new(&db, value))
+        .collect();
+    let file = File::new(&db, items);
+
+    assert_eq!(check_file(&db, file), vec![2, 3, 4, 5]);
+    assert_eq!(
+        check_file::accumulated::<Error>(&db, file),
+        vec![Error(0), Error(3), Error(5)]
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot read accumulated values outside of an active tracked function")]
+fn outside_of_query() {
+    let db = DatabaseImpl::new();
+    Error::accumulated_so_far(&db);
+}
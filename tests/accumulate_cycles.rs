@@ -0,0 +1,268 @@
+//! Test the values accumulated by the participants of a cycle
+//! that is recovered with a `recovery_fn`: a participant that recovers reports
+//! only the values accumulated by its recovery function, and every value is
+//! collected exactly once.
+
+use expect_test::expect;
+use salsa::{Accumulator, Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::accumulator]
+struct Log(#[allow(dead_code)] String);
+
+#[salsa::input]
+struct MyInput {
+    /// Returned by `b_recover` and `d_recover` when there is no cycle.
+    value: u32,
+
+    /// If false, `b_recover` and `d_recover` do not call back into
+    /// `a_recover` and `c_no_recovery`, so there is no cycle.
+    cyclic: bool,
+}
+
+#[salsa::tracked]
+fn root(db: &dyn Database, input: MyInput) -> u32 {
+    Log("root".to_string()).accumulate(db);
+    a_recover(db, input) + c_no_recovery(db, input)
+}
+
+#[salsa::tracked(recovery_fn = recover_a)]
+fn a_recover(db: &dyn Database, input: MyInput) -> u32 {
+    Log("a_recover before".to_string()).accumulate(db);
+    let value = b_recover(db, input);
+    Log("a_recover after".to_string()).accumulate(db);
+    value + 1
+}
+
+fn recover_a(db: &dyn Database, _cycle: &salsa::Cycle, _input: MyInput) -> u32 {
+    Log("a_recover recovered".to_string()).accumulate(db);
+    10
+}
+
+#[salsa::tracked(recovery_fn = recover_b)]
+fn b_recover(db: &dyn Database, input: MyInput) -> u32 {
+    Log("b_recover before".to_string()).accumulate(db);
+    let value = if input.cyclic(db) {
+        a_recover(db, input)
+    } else {
+        input.value(db)
+    };
+    Log("b_recover after".to_string()).accumulate(db);
+    value + 1
+}
+
+fn recover_b(db: &dyn Database, _cycle: &salsa::Cycle, _input: MyInput) -> u32 {
+    Log("b_recover recovered".to_string()).accumulate(db);
+    20
+}
+
+/// Like `a_recover`, but without recovery: it continues with the value recovered by `d_recover`.
+#[salsa::tracked]
+fn c_no_recovery(db: &dyn Database, input: MyInput) -> u32 {
+    Log("c_no_recovery before".to_string()).accumulate(db);
+    let value = d_recover(db, input);
+    Log("c_no_recovery after".to_string()).accumulate(db);
+    value + 1
+}
+
+#[salsa::tracked(recovery_fn = recover_d)]
+fn d_recover(db: &dyn Database, input: MyInput) -> u32 {
+    Log("d_recover before".to_string()).accumulate(db);
+    let value = if input.cyclic(db) {
+        c_no_recovery(db, input)
+    } else {
+        input.value(db)
+    };
+    Log("d_recover after".to_string()).accumulate(db);
+    value + 1
+}
+
+fn recover_d(db: &dyn Database, _cycle: &salsa::Cycle, _input: MyInput) -> u32 {
+    Log("d_recover recovered".to_string()).accumulate(db);
+    30
+}
+
+#[test]
+fn all_participants_recover() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0, true);
+    assert_eq!(a_recover(&db, input), 10);
+    let logs = a_recover::accumulated::<Log>(&db, input);
+    expect![[r#"
+        [
+            Log(
+                "a_recover recovered",
+            ),
+            Log(
+                "b_recover recovered",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&logs);
+
+    let logs = b_recover::accumulated::<Log>(&db, input);
+    expect![[r#"
+        [
+            Log(
+                "b_recover recovered",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&logs);
+}
+
+#[test]
+fn some_participants_recover() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0, true);
+    assert_eq!(c_no_recovery(&db, input), 31);
+    let logs = c_no_recovery::accumulated::<Log>(&db, input);
+    expect![[r#"
+        [
+            Log(
+                "c_no_recovery before",
+            ),
+            Log(
+                "c_no_recovery after",
+            ),
+            Log(
+                "d_recover recovered",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&logs);
+}
+
+#[test]
+fn collected_once_through_several_paths() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0, true);
+    root(&db, input);
+    let logs = root::accumulated::<Log>(&db, input);
+    expect![[r#"
+        [
+            Log(
+                "root",
+            ),
+            Log(
+                "a_recover recovered",
+            ),
+            Log(
+                "b_recover recovered",
+            ),
+            Log(
+                "c_no_recovery before",
+            ),
+            Log(
+                "c_no_recovery after",
+            ),
+            Log(
+                "d_recover recovered",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&logs);
+}
+
+#[test]
+fn cycle_appears_and_disappears() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0, false);
+    assert_eq!(root(&db, input), 4);
+    let logs = root::accumulated::<Log>(&db, input);
+    expect![[r#"
+        [
+            Log(
+                "root",
+            ),
+            Log(
+                "a_recover before",
+            ),
+            Log(
+                "a_recover after",
+            ),
+            Log(
+                "b_recover before",
+            ),
+            Log(
+                "b_recover after",
+            ),
+            Log(
+                "c_no_recovery before",
+            ),
+            Log(
+                "c_no_recovery after",
+            ),
+            Log(
+                "d_recover before",
+            ),
+            Log(
+                "d_recover after",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&logs);
+
+    input.set_cyclic(&mut db).to(true);
+    root(&db, input);
+    let logs = root::accumulated::<Log>(&db, input);
+    expect![[r#"
+        [
+            Log(
+                "root",
+            ),
+            Log(
+                "a_recover recovered",
+            ),
+            Log(
+                "b_recover recovered",
+            ),
+            Log(
+                "c_no_recovery before",
+            ),
+            Log(
+                "c_no_recovery after",
+            ),
+            Log(
+                "d_recover recovered",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&logs);
+
+    input.set_cyclic(&mut db).to(false);
+    assert_eq!(root(&db, input), 4);
+    let logs = root::accumulated::<Log>(&db, input);
+    expect![[r#"
+        [
+            Log(
+                "root",
+            ),
+            Log(
+                "a_recover before",
+            ),
+            Log(
+                "a_recover after",
+            ),
+            Log(
+                "b_recover before",
+            ),
+            Log(
+                "b_recover after",
+            ),
+            Log(
+                "c_no_recovery before",
+            ),
+            Log(
+                "c_no_recovery after",
+            ),
+            Log(
+                "d_recover before",
+            ),
+            Log(
+                "d_recover after",
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&logs);
+}
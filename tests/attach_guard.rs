@@ -0,0 +1,66 @@
+//! Test that `salsa::attach_guard` attaches the database
+//! for `Debug` impls until the guard is dropped.
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+fn describe(db: &salsa::DatabaseImpl, input: MyInput) -> Result<String, std::num::ParseIntError> {
+    // SAFETY: The guard is dropped at the end of this function.
+    let _guard = unsafe { salsa::attach_guard(db) };
+    let parsed: u32 = "22".parse()?;
+    Ok(format!("{input:?} {parsed}"))
+}
+
+#[test]
+fn guard_attaches_until_dropped() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 44);
+
+    expect_test::expect![[r#"MyInput { [salsa id]: Id(0), field: 44 } 22"#]]
+        .assert_eq(&describe(&db, input).unwrap());
+
+    // Detached again once the guard is gone.
+    expect_test::expect![[r#"MyInput { [salsa id]: Id(0) }"#]].assert_eq(&format!("{input:?}"));
+}
+
+#[test]
+fn nested_guards() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 44);
+
+    // SAFETY: Both guards are dropped before `db`.
+    let outer = unsafe { salsa::attach_guard(&db) };
+    let inner = unsafe { salsa::attach_guard(&db) };
+    salsa::plumbing::attach(&db, || {
+        assert!(format!("{input:?}").contains("field: 44"));
+    });
+    drop(inner);
+    assert!(format!("{input:?}").contains("field: 44"));
+    drop(outer);
+    assert!(!format!("{input:?}").contains("field"));
+}
+
+#[test]
+#[should_panic(expected = "Cannot change database mid-query")]
+fn different_database() {
+    let db1 = salsa::DatabaseImpl::new();
+    let db2 = salsa::DatabaseImpl::new();
+
+    // SAFETY: The guards are dropped before the databases.
+    let _guard1 = unsafe { salsa::attach_guard(&db1) };
+    let _guard2 = unsafe { salsa::attach_guard(&db2) };
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "reverse order")]
+fn out_of_order_drop() {
+    let db = salsa::DatabaseImpl::new();
+
+    // SAFETY: Both guards are dropped before `db`.
+    let outer = unsafe { salsa::attach_guard(&db) };
+    let _inner = unsafe { salsa::attach_guard(&db) };
+    drop(outer);
+}
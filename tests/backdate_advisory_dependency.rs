@@ -0,0 +1,69 @@
+//! Test a dependency whose changes rarely change the result of the query reading it,
+//! e.g. formatting settings read by a parser: a change makes salsa re-execute the
+//! query and compare its result, and dependents are only re-executed if it differs.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct Settings {
+    tab_width: usize,
+}
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+/// The number of words of `file`, which only depends on `settings` when
+/// the text contains tabs.
+#[salsa::tracked]
+fn parse(db: &dyn LogDatabase, settings: Settings, file: File) -> usize {
+    db.push_log("parse".to_string());
+    let spaces = " ".repeat(settings.tab_width(db));
+    file.text(db)
+        .replace('\t', &spaces)
+        .split_whitespace()
+        .count()
+}
+
+#[salsa::tracked]
+fn check(db: &dyn LogDatabase, settings: Settings, file: File) -> bool {
+    db.push_log("check".to_string());
+    parse(db, settings, file) > 1
+}
+
+#[test]
+fn advisory_change_backdates() {
+    let mut db = LoggerDatabase::default();
+    let settings = Settings::new(&db, 4);
+    let file = File::new(&db, "fn main".to_string());
+
+    assert!(check(&db, settings, file));
+    db.assert_logs(expect![[r#"
+        [
+            "check",
+            "parse",
+        ]"#]]);
+
+    // `parse` is re-executed, but its result is unchanged, so `check` is not.
+    settings.set_tab_width(&mut db).to(2);
+    assert!(check(&db, settings, file));
+    db.assert_logs(expect![[r#"
+        [
+            "parse",
+        ]"#]]);
+
+    // A change to the result is still seen by dependents.
+    file.set_text(&mut db).to("main".to_string());
+    assert!(!check(&db, settings, file));
+    db.assert_logs(expect![[r#"
+        [
+            "parse",
+            "check",
+        ]"#]]);
+}
@@ -0,0 +1,57 @@
+//! Test that `salsa::Backtrace` captures the active query stack.
+
+use expect_test::expect;
+use salsa::{Backtrace, Database, DatabaseImpl, Durability};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn outer(db: &dyn Database, input: MyInput) -> String {
+    inner(db, input)
+}
+
+#[salsa::tracked]
+fn inner(db: &dyn Database, input: MyInput) -> String {
+    let _ = input.field(db);
+    Backtrace::capture().unwrap().to_string()
+}
+
+#[test]
+fn backtrace() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::builder(22).durability(Durability::HIGH).new(&db);
+
+    expect![[r#"
+        query stacktrace:
+           0: inner(Id(0)) (durability: Durability(2), changed_at: R1)
+           1: outer(Id(0)) (durability: Durability(2), changed_at: R1)
+    "#]]
+    .assert_eq(&outer(&db, input));
+}
+
+#[test]
+fn frames() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 22);
+
+    #[salsa::tracked]
+    fn capture(db: &dyn Database, input: MyInput) -> usize {
+        let before = Backtrace::capture().unwrap();
+        let _ = input.field(db);
+        let after = Backtrace::capture().unwrap();
+
+        let [before] = before.frames() else { panic!() };
+        let [after] = after.frames() else { panic!() };
+        assert_eq!(before.database_key_index(), after.database_key_index());
+        assert_eq!(before.durability(), Durability::HIGH);
+        assert_eq!(after.durability(), Durability::LOW);
+        assert!(!after.in_cycle());
+        1
+    }
+
+    assert_eq!(capture(&db, input), 1);
+    assert!(Backtrace::capture().is_none());
+}
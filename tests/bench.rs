@@ -0,0 +1,16 @@
+//! Test that the loops of `salsa::bench` exercise memoized values without re-executing them.
+
+use salsa::bench::{fetch_hot_loop, input_read_loop, interned_lookup_loop, Fixture};
+use salsa::Database;
+
+#[test]
+fn loops() {
+    let fixture = Fixture::new();
+    let memory = fixture.db().memory_report();
+
+    assert_eq!(fetch_hot_loop(&fixture, 10), 130);
+    assert_eq!(interned_lookup_loop(&fixture, 10), 1);
+    assert_eq!(input_read_loop(&fixture, 10), 130);
+
+    assert_eq!(fixture.db().memory_report().ingredients, memory.ingredients);
+}
@@ -0,0 +1,167 @@
+//! Test that queries which only read inputs of some channels are validated
+//! without walking their dependencies when inputs of other channels change.
+
+mod common;
+use common::{ExecuteValidateLoggerDatabase, LogDatabase};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use expect_test::expect;
+use salsa::{Channel, Database, Setter};
+use test_log::test;
+
+const OPEN_FILES: Channel = Channel::new(1);
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+struct Parsed<'db> {
+    #[tracked]
+    len: usize,
+}
+
+#[salsa::tracked]
+fn len(db: &dyn Database, file: File) -> usize {
+    file.text(db).len()
+}
+
+#[salsa::tracked]
+fn parse(db: &dyn Database, file: File) -> Parsed<'_> {
+    Parsed::new(db, file.text(db).len())
+}
+
+static CONFIG: AtomicU64 = AtomicU64::new(0);
+
+#[salsa::tracked]
+fn config(db: &dyn Database, _file: File) -> u64 {
+    db.report_external_dependency("config", |_| CONFIG.load(Ordering::SeqCst));
+    CONFIG.load(Ordering::SeqCst)
+}
+
+#[salsa::tracked]
+fn parsed_len(db: &dyn Database, file: File) -> usize {
+    parse(db, file).len(db)
+}
+
+#[test]
+fn change_in_other_channel() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let open = File::new(&db, "a".to_string());
+    let closed = File::new(&db, "bb".to_string());
+    db.set_channel(open, OPEN_FILES);
+    assert_eq!(len(&db, open), 1);
+    assert_eq!(len(&db, closed), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: len(Id(0)) })",
+            "salsa_event(WillExecute { database_key: len(Id(1)) })",
+        ]"#]]);
+
+    // Only inputs of `OPEN_FILES` changed, so `len(closed)` is validated shallowly.
+    open.set_text(&mut db).to("aaa".to_string());
+    assert_eq!(len(&db, open), 3);
+    assert_eq!(len(&db, closed), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: len(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: len(Id(1)), kind: Shallow, edges_traversed: 0 })",
+        ]"#]]);
+
+    closed.set_text(&mut db).to("bbbb".to_string());
+    assert_eq!(len(&db, open), 3);
+    assert_eq!(len(&db, closed), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: len(Id(0)), kind: Shallow, edges_traversed: 0 })",
+            "salsa_event(WillExecute { database_key: len(Id(1)) })",
+        ]"#]]);
+}
+
+#[test]
+fn set_channel() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let file = File::new(&db, "a".to_string());
+    let other = File::new(&db, "b".to_string());
+    assert_eq!(len(&db, file), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: len(Id(0)) })",
+        ]"#]]);
+
+    // The query is re-executed to record the new channel of `file`.
+    db.set_channel(file, OPEN_FILES);
+    assert_eq!(len(&db, file), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(WillExecute { database_key: len(Id(0)) })",
+        ]"#]]);
+
+    other.set_text(&mut db).to("c".to_string());
+    assert_eq!(len(&db, file), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: len(Id(0)), kind: Shallow, edges_traversed: 0 })",
+        ]"#]]);
+
+    // Assigning the channel an input is already in changes nothing.
+    db.set_channel(file, OPEN_FILES);
+    assert_eq!(len(&db, file), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: len(Id(0)), kind: Shallow, edges_traversed: 0 })",
+        ]"#]]);
+}
+
+#[test]
+fn tracked_struct_fields() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let open = File::new(&db, "a".to_string());
+    let closed = File::new(&db, "bb".to_string());
+    db.set_channel(open, OPEN_FILES);
+    assert_eq!(parsed_len(&db, open), 1);
+    assert_eq!(parsed_len(&db, closed), 2);
+    db.assert_logs_len(4);
+
+    // The fields of a tracked struct have the channels read by the query creating it.
+    closed.set_text(&mut db).to("bbb".to_string());
+    assert_eq!(parsed_len(&db, open), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: parsed_len(Id(0)), kind: Shallow, edges_traversed: 0 })",
+        ]"#]]);
+}
+
+#[test]
+fn synthetic_write() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let open = File::new(&db, "a".to_string());
+    db.set_channel(open, OPEN_FILES);
+    assert_eq!(len(&db, open), 1);
+    db.assert_logs_len(1);
+
+    // Writes that are not of a particular input affect every channel.
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(len(&db, open), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "salsa_event(DidValidateMemoizedValue { database_key: len(Id(0)), kind: Deep, edges_traversed: 1 })",
+        ]"#]]);
+}
+
+#[test]
+fn external_dependencies() {
+    let mut db = ExecuteValidateLoggerDatabase::default();
+    let file = File::new(&db, "a".to_string());
+    let open = File::new(&db, "b".to_string());
+    db.set_channel(open, OPEN_FILES);
+    assert_eq!(config(&db, file), 0);
+    db.assert_logs_len(1);
+
+    // External resources may change in any revision, whatever the channel of the write.
+    CONFIG.store(1, Ordering::SeqCst);
+    open.set_text(&mut db).to("bb".to_string());
+    assert_eq!(config(&db, file), 1);
+}
@@ -0,0 +1,66 @@
+//! Test that `salsa::check_incremental` compares incremental results with
+//! results recomputed from scratch.
+#![cfg(feature = "testing")]
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use salsa::{Database, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+    other: u32,
+}
+
+#[salsa::interned]
+struct Name<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn name<'db>(db: &'db dyn Database, input: MyInput) -> Name<'db> {
+    Name::new(db, format!("n{}", input.field(db)))
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+static UNTRACKED: AtomicU32 = AtomicU32::new(0);
+
+/// Reads state that salsa does not know about.
+#[salsa::tracked]
+fn with_untracked(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) + UNTRACKED.load(Ordering::Relaxed)
+}
+
+#[test]
+fn consistent() {
+    salsa::check_incremental(|db: &mut DatabaseImpl| {
+        let input = MyInput::new(db, 1, 0);
+        assert_eq!(double(db, input), 2);
+        name(db, input);
+
+        input.set_other(db).to(1);
+        assert_eq!(double(db, input), 2);
+
+        input.set_field(db).to(2);
+        assert_eq!(double(db, input), 4);
+        name(db, input);
+    });
+}
+
+#[test]
+#[should_panic(expected = "observed a different value than recomputing from scratch")]
+fn untracked_read() {
+    salsa::check_incremental(|db: &mut DatabaseImpl| {
+        UNTRACKED.store(0, Ordering::Relaxed);
+        let input = MyInput::new(db, 1, 0);
+        with_untracked(db, input);
+
+        UNTRACKED.store(1, Ordering::Relaxed);
+        input.set_other(db).to(1);
+        with_untracked(db, input);
+    });
+}
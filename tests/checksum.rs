@@ -0,0 +1,142 @@
+//! Test that `Database::checksum` agrees between incremental
+//! and from-scratch runs.
+
+use salsa::{Database, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn is_even(db: &dyn Database, input: MyInput) -> bool {
+    double(db, input) % 4 == 0
+}
+
+fn checksums(db: &DatabaseImpl, input: MyInput) -> (u64, u64) {
+    is_even(db, input);
+    let root = is_even::database_key_index(db, input).unwrap();
+    (db.checksum(&[root]), db.checksum_with_dependencies(&[root]))
+}
+
+#[test]
+fn incremental_matches_from_scratch() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 2);
+    let before = checksums(&db, input);
+
+    input.set_field(&mut db).to(4);
+    let incremental = checksums(&db, input);
+
+    let scratch_db = DatabaseImpl::new();
+    let scratch_input = MyInput::new(&scratch_db, 4);
+    let from_scratch = checksums(&scratch_db, scratch_input);
+
+    assert_eq!(incremental, from_scratch);
+
+    // `is_even` is `true` in both revisions, but `double` changed.
+    assert_eq!(before.0, incremental.0);
+    assert_ne!(before.1, incremental.1);
+}
+
+#[test]
+fn unexecuted_roots() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 2);
+    let root = double::database_key_index(&db, input).unwrap();
+    let empty = db.checksum(&[root]);
+
+    double(&db, input);
+    assert_ne!(db.checksum(&[root]), empty);
+}
+
+#[salsa::input]
+struct Names {
+    names: Vec<String>,
+}
+
+#[salsa::tracked]
+struct Item<'db> {
+    #[id]
+    name: String,
+}
+
+/// Not `Hash`, and holds salsa structs whose ids depend on the order of creation.
+#[derive(Clone, Debug, PartialEq, Eq, salsa::Update)]
+struct Items<'db> {
+    items: Vec<Item<'db>>,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn Database, names: Names) -> Items<'_> {
+    Items {
+        items: names
+            .names(db)
+            .into_iter()
+            .map(|name| Item::new(db, name))
+            .collect(),
+    }
+}
+
+fn items_checksum(db: &DatabaseImpl, names: Names) -> u64 {
+    items(db, names);
+    let root = items::database_key_index(db, names).unwrap();
+    db.checksum_with_dependencies(&[root])
+}
+
+#[test]
+fn ids_do_not_contribute() {
+    let mut db = DatabaseImpl::new();
+    let names = Names::new(&db, vec!["a".to_string()]);
+    let before = items_checksum(&db, names);
+
+    // `a` keeps its id, so `b` gets a different id than in a from-scratch run.
+    names
+        .set_names(&mut db)
+        .to(vec!["b".to_string(), "a".to_string()]);
+    let incremental = items_checksum(&db, names);
+
+    let scratch_db = DatabaseImpl::new();
+    MyInput::new(&scratch_db, 0);
+    let scratch_names = Names::new(&scratch_db, vec!["b".to_string(), "a".to_string()]);
+    assert_eq!(incremental, items_checksum(&scratch_db, scratch_names));
+
+    // Values that are not `Hash` still contribute their contents.
+    assert_ne!(before, incremental);
+}
+
+#[test]
+fn input_fields_contribute() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 2);
+    double(&db, input);
+    let root = double::database_key_index(&db, input).unwrap();
+    let before = (db.checksum(&[root]), db.checksum_with_dependencies(&[root]));
+
+    // The memoized value of `double` is not recomputed, but the field it read changed.
+    input.set_field(&mut db).to(3);
+    assert_eq!(db.checksum(&[root]), before.0);
+    assert_ne!(db.checksum_with_dependencies(&[root]), before.1);
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn Database, a: MyInput, b: MyInput) -> u32 {
+    a.field(db) + b.field(db)
+}
+
+#[test]
+fn keys_are_not_interned() {
+    let db = DatabaseImpl::new();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    assert_eq!(sum::database_key_index(&db, a, b), None);
+    assert_eq!(sum::database_key_index(&db, a, b), None);
+
+    sum(&db, a, b);
+    assert!(sum::database_key_index(&db, a, b).is_some());
+}
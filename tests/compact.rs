@@ -0,0 +1,106 @@
+//! Test that `Database::compact` reclaims memory without dropping memoized values.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{CompactionReport, Database, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn create_tracked_structs(db: &dyn LogDatabase, input: MyInput) -> Vec<MyTracked<'_>> {
+    (0..input.field(db))
+        .map(|i| MyTracked::new(db, i))
+        .collect()
+}
+
+#[salsa::tracked(lru = 1000)]
+fn contribution_from_struct<'db>(db: &'db dyn LogDatabase, tracked: MyTracked<'db>) -> u32 {
+    db.push_log(format!("contribution_from_struct({})", tracked.field(db)));
+    tracked.field(db) * 2
+}
+
+#[salsa::tracked]
+fn final_result(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    create_tracked_structs(db, input)
+        .into_iter()
+        .map(|tracked| contribution_from_struct(db, tracked))
+        .sum()
+}
+
+fn bytes_reclaimed(report: &CompactionReport, name: &str) -> usize {
+    report
+        .ingredients
+        .iter()
+        .find(|compaction| compaction.debug_name == name)
+        .unwrap()
+        .bytes_reclaimed
+}
+
+#[test]
+fn keeps_memoized_values() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 2);
+    assert_eq!(final_result(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "contribution_from_struct(0)",
+            "contribution_from_struct(1)",
+        ]"#]]);
+
+    let memory = db.memory_report();
+    let report = db.compact();
+    assert_eq!(report.ingredients.len(), memory.ingredients.len());
+    assert_eq!(db.memory_report().ingredients, memory.ingredients);
+
+    assert_eq!(final_result(&db, input), 2);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn prunes_deleted_keys() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 100);
+    assert_eq!(final_result(&db, input), 9900);
+
+    // Deletes all but one of the tracked structs, along with their memos;
+    // the LRU list still holds their keys.
+    input.set_field(&mut db).to(1);
+    assert_eq!(final_result(&db, input), 0);
+    db.assert_logs_len(100);
+
+    let report = db.compact();
+    assert!(bytes_reclaimed(&report, "contribution_from_struct") > 0);
+    assert!(report.total_bytes() >= bytes_reclaimed(&report, "contribution_from_struct"));
+
+    // Nothing is left to reclaim.
+    let report = db.compact();
+    assert_eq!(bytes_reclaimed(&report, "contribution_from_struct"), 0);
+
+    assert_eq!(final_result(&db, input), 0);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn shrinks_memo_tables() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 0);
+    assert_eq!(final_result(&db, input), 0);
+
+    // The memo table of `input` has room for more memos than the two it holds.
+    let report = db.compact();
+    assert!(bytes_reclaimed(&report, "MyInput") > 0);
+
+    let report = db.compact();
+    assert_eq!(bytes_reclaimed(&report, "MyInput"), 0);
+}
@@ -0,0 +1,18 @@
+#[salsa::tracked(identity_key)]
+struct TrackedWithIdentityKeyAndId {
+    #[id]
+    name: String,
+    value: u32,
+}
+
+#[salsa::tracked(identity_key, singleton)]
+struct TrackedWithIdentityKeyAndSingleton {
+    value: u32,
+}
+
+#[salsa::input(identity_key)]
+struct InputWithIdentityKey {
+    value: u32,
+}
+
+fn main() {}
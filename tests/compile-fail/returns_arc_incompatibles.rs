@@ -0,0 +1,23 @@
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(returns(arc), return_ref)]
+fn tracked_fn_with_returns_arc_and_return_ref(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked(returns(rc))]
+fn tracked_fn_with_unknown_return_mode(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::input]
+struct InputWithReturnsArcAndReturnRef {
+    #[returns(arc)]
+    #[return_ref]
+    field: String,
+}
+
+fn main() {}
@@ -1,6 +1,6 @@
 //! Compile Singleton struct test:
 //!
-//! Singleton flags are only allowed for input structs. If applied on any other Salsa struct compilation must fail
+//! Singleton flags are only allowed for input and tracked structs. If applied on any other Salsa item compilation must fail
 
 #[salsa::input(singleton)]
 struct MyInput {
@@ -13,10 +13,8 @@ struct MyTracked<'db> {
 }
 
 #[salsa::tracked(singleton)]
-fn create_tracked_structs(db: &dyn salsa::Database, input: MyInput) -> Vec<MyTracked> {
-    (0..input.field(db))
-        .map(|i| MyTracked::new(db, i))
-        .collect()
+fn create_tracked_struct(db: &dyn salsa::Database, input: MyInput) -> MyTracked {
+    MyTracked::new(db, input.field(db))
 }
 
 #[salsa::accumulator(singleton)]
@@ -0,0 +1,46 @@
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+struct MyCodec;
+
+#[salsa::tracked(store_with = MyCodec, return_ref)]
+fn tracked_fn_with_store_with_and_return_ref(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked(store_with = MyCodec, dedupe)]
+fn tracked_fn_with_store_with_and_dedupe(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked(store_with = MyCodec, fingerprint)]
+fn tracked_fn_with_store_with_and_fingerprint(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked(store_with = MyCodec, returns(arc))]
+fn tracked_fn_with_store_with_and_returns(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked(store_with = MyCodec, result)]
+fn tracked_fn_with_store_with_and_result(
+    db: &dyn salsa::Database,
+    input: MyInput,
+) -> Result<u32, ()> {
+    Ok(input.field(db))
+}
+
+#[salsa::tracked(store_with = MyCodec, compare_with = same)]
+fn tracked_fn_with_store_with_and_compare_with(db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::input(store_with = MyCodec)]
+struct InputWithStoreWith {
+    field: u32,
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn not_key_before_key(
+    db: &dyn salsa::Database,
+    #[salsa::not_key] context: &String,
+    input: MyInput,
+) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn not_key_database(#[salsa::not_key] db: &dyn salsa::Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn not_key_by_value(db: &dyn salsa::Database, input: MyInput, #[salsa::not_key] context: String) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn not_key_mutable(db: &dyn salsa::Database, input: MyInput, #[salsa::not_key] context: &mut String) -> u32 {
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn not_key_returned<'db>(
+    db: &'db dyn salsa::Database,
+    input: MyInput,
+    #[salsa::not_key] context: &'db String,
+) -> &'db str {
+    context.as_str()
+}
+
+struct Wrapper<'a> {
+    text: &'a str,
+}
+
+#[salsa::tracked]
+fn not_key_returned_from_struct<'db>(
+    db: &'db dyn salsa::Database,
+    input: MyInput,
+    #[salsa::not_key] context: &Wrapper<'db>,
+) -> &'db str {
+    context.text
+}
+
+fn main() {}
@@ -0,0 +1,89 @@
+//! Test configuration values set on the database and read from tracked functions.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{AsDynDatabase, Database, Durability, Setter};
+use test_log::test;
+
+#[derive(Debug, PartialEq)]
+struct Target(&'static str);
+
+#[derive(Debug, PartialEq)]
+struct Verbose(bool);
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn target(db: &dyn LogDatabase) -> String {
+    let target = db.as_dyn_database().config::<Target>();
+    db.push_log(format!("target({target:?})"));
+    target.map_or("host", |target| target.0).to_string()
+}
+
+#[salsa::tracked]
+fn describe(db: &dyn LogDatabase, input: MyInput) -> String {
+    db.push_log("describe".to_string());
+    format!("{} on {}", input.field(db), target(db))
+}
+
+#[test]
+fn set_and_read() {
+    let mut db = LoggerDatabase::default();
+    assert_eq!(db.as_dyn_database().config::<Target>(), None);
+    assert_eq!(target(&db), "host");
+
+    db.set_config(Target("wasm32"));
+    assert_eq!(
+        db.as_dyn_database().config::<Target>(),
+        Some(&Target("wasm32"))
+    );
+    assert_eq!(target(&db), "wasm32");
+    db.assert_logs(expect![[r#"
+        [
+            "target(None)",
+            "target(Some(Target(\"wasm32\")))",
+        ]"#]]);
+
+    // Other configuration types are held by other inputs.
+    db.set_config(Verbose(true));
+    assert_eq!(target(&db), "wasm32");
+    assert_eq!(
+        db.as_dyn_database().config::<Verbose>(),
+        Some(&Verbose(true))
+    );
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn durability() {
+    let mut db = LoggerDatabase::default();
+    db.set_config(Target("wasm32"));
+    let input = MyInput::new(&db, 1);
+    assert_eq!(describe(&db, input), "1 on wasm32");
+    db.assert_logs(expect![[r#"
+        [
+            "describe",
+            "target(Some(Target(\"wasm32\")))",
+        ]"#]]);
+
+    // `target` only depends on high-durability configuration, so it is not re-executed.
+    input.set_field(&mut db).to(2);
+    assert_eq!(describe(&db, input), "2 on wasm32");
+    db.assert_logs(expect![[r#"
+        [
+            "describe",
+        ]"#]]);
+
+    db.set_config_with_durability(Target("x86_64"), Durability::LOW);
+    assert_eq!(describe(&db, input), "2 on x86_64");
+    db.assert_logs(expect![[r#"
+        [
+            "target(Some(Target(\"x86_64\")))",
+            "describe",
+        ]"#]]);
+}
@@ -0,0 +1,57 @@
+//! Test cycles recovered with `recovery_fn = default`,
+//! which returns the `Default` value of the output.
+
+use salsa::{Database, DatabaseImpl, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    /// If false, `a` and `b` do not call each other, so there is no cycle.
+    cyclic: bool,
+}
+
+#[salsa::tracked(recovery_fn = default)]
+fn a(db: &dyn Database, input: MyInput) -> u32 {
+    if input.cyclic(db) {
+        b(db, input, 10).len() as u32 + 1
+    } else {
+        1
+    }
+}
+
+#[salsa::tracked(recovery_fn = default)]
+fn b(db: &dyn Database, input: MyInput, offset: u32) -> Vec<u32> {
+    if input.cyclic(db) {
+        vec![a(db, input) + offset]
+    } else {
+        vec![offset]
+    }
+}
+
+#[test]
+fn no_cycle() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, false);
+    assert_eq!(a(&db, input), 1);
+    assert_eq!(b(&db, input, 10), vec![10]);
+}
+
+#[test]
+fn recover_with_default() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, true);
+    // Both participants recover: `a` returns `0` and `b` returns an empty `Vec`.
+    assert_eq!(a(&db, input), 0);
+    assert_eq!(b(&db, input, 10), Vec::<u32>::new());
+}
+
+#[test]
+fn cycle_disappears() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, true);
+    assert_eq!(a(&db, input), 0);
+
+    input.set_cyclic(&mut db).to(false);
+    assert_eq!(a(&db, input), 1);
+    assert_eq!(b(&db, input, 10), vec![10]);
+}
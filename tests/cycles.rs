@@ -169,6 +169,30 @@ fn cycle_memoized() {
     })
 }
 
+#[test]
+fn cycle_trigger_and_names() {
+    salsa::DatabaseImpl::new().attach(|db| {
+        let input = MyInput::new(db);
+        let cycle = extract_cycle(|| memoized_b(db, input));
+        let expected = expect![[r#"
+            (
+                memoized_b(Id(0)),
+                [
+                    (
+                        memoized_a(Id(0)),
+                        "memoized_a",
+                    ),
+                    (
+                        memoized_b(Id(0)),
+                        "memoized_b",
+                    ),
+                ],
+            )
+        "#]];
+        expected.assert_debug_eq(&(cycle.trigger(), cycle.participants_with_names(db)));
+    })
+}
+
 #[test]
 fn cycle_volatile() {
     salsa::DatabaseImpl::new().attach(|db| {
@@ -0,0 +1,118 @@
+//! Test that the options set with `DatabaseImpl::builder`
+//! are applied to the new database.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use salsa::{Database, DatabaseImpl, EventKind};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::input]
+struct File {
+    #[key]
+    path: String,
+}
+
+#[salsa::interned]
+struct Name<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked(lru = 8)]
+fn triple(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 3
+}
+
+fn count_executions() -> (Arc<AtomicUsize>, impl Fn(&salsa::Event) + Send + Sync) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let count = count.clone();
+        move |event: &salsa::Event| {
+            if let EventKind::WillExecute { .. } = event.kind {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    };
+    (count, handler)
+}
+
+#[test]
+fn event_handler() {
+    let (executed, handler) = count_executions();
+    let db = DatabaseImpl::builder().event_handler(handler).build();
+
+    let input = MyInput::new(&db, 1);
+    assert_eq!(double(&db, input), 2);
+    assert_eq!(double(&db, input), 2);
+    assert_eq!(executed.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn default_lru() {
+    let (executed, handler) = count_executions();
+    let mut db = DatabaseImpl::builder()
+        .default_lru(2)
+        .event_handler(handler)
+        .build();
+
+    let inputs: Vec<_> = (0..4).map(|i| MyInput::new(&db, i)).collect();
+    for &input in &inputs {
+        double(&db, input);
+        triple(&db, input);
+    }
+    assert_eq!(executed.load(Ordering::Relaxed), 8);
+
+    db.synthetic_write(salsa::Durability::LOW);
+    for &input in &inputs {
+        double(&db, input);
+        triple(&db, input);
+    }
+    // `double` uses the default capacity of 2, so cycling through 4 inputs
+    // recomputes all of them, while `triple` keeps its own capacity and recomputes nothing.
+    assert_eq!(executed.load(Ordering::Relaxed), 12);
+}
+
+#[test]
+fn interned_shards() {
+    let db = DatabaseImpl::builder().interned_shards(8).build();
+    let a = Name::new(&db, "a".to_string());
+    let b = Name::new(&db, "b".to_string());
+    assert_eq!(a, Name::new(&db, "a".to_string()));
+    assert_ne!(a, b);
+    assert_eq!(b.text(&db), "b");
+}
+
+#[test]
+#[should_panic(expected = "power of two")]
+fn interned_shards_must_be_a_power_of_two() {
+    DatabaseImpl::builder().interned_shards(6);
+}
+
+#[test]
+fn parallelism() {
+    let db = DatabaseImpl::builder().parallelism(3).build();
+    let a = Name::new(&db, "a".to_string());
+    assert_eq!(a, Name::new(&db, "a".to_string()));
+    assert_ne!(a, Name::new(&db, "b".to_string()));
+
+    let file = File::get_or_create(&db, "a.rs".to_string());
+    assert_eq!(File::get_by_key(&db, &"a.rs".to_string()), Some(file));
+    assert_eq!(File::get_or_create(&db, "a.rs".to_string()), file);
+}
+
+#[test]
+#[should_panic(expected = "at least one")]
+fn parallelism_must_be_positive() {
+    DatabaseImpl::builder().parallelism(0);
+}
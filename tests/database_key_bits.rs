@@ -0,0 +1,101 @@
+//! Test round-tripping `DatabaseKeyIndex` through `to_bits`/`from_bits`.
+
+use salsa::{Database, DatabaseKeyIndex, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    value: u32,
+}
+
+#[salsa::tracked]
+fn make_tracked(db: &dyn Database, input: MyInput) -> Option<MyTracked<'_>> {
+    let value = input.field(db);
+    (value != 0).then(|| MyTracked::new(db, value))
+}
+
+#[salsa::tracked]
+fn tracked_value<'db>(db: &'db dyn Database, tracked: MyTracked<'db>) -> u32 {
+    tracked.value(db)
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[test]
+fn round_trip() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let key = double::database_key_index(&db, input).unwrap();
+
+    let bits = key.to_bits(&db);
+    assert_eq!(DatabaseKeyIndex::from_bits(bits, &db), Some(key));
+}
+
+#[test]
+fn invalid_bits() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let bits = double::database_key_index(&db, input).unwrap().to_bits(&db);
+
+    // Unknown ingredient.
+    assert_eq!(
+        DatabaseKeyIndex::from_bits(bits | (0xFFFF << 48), &db),
+        None
+    );
+    // Id that was never allocated.
+    assert_eq!(DatabaseKeyIndex::from_bits(bits + 1, &db), None);
+    // Same key, different database.
+    let other = salsa::DatabaseImpl::new();
+    assert_eq!(DatabaseKeyIndex::from_bits(bits, &other), None);
+}
+
+#[test]
+fn reused_id_is_rejected() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let tracked = make_tracked(&db, input).unwrap();
+    assert_eq!(tracked_value(&db, tracked), 1);
+    let old_key = tracked_value::database_key_index(&db, tracked).unwrap();
+    let bits = old_key.to_bits(&db);
+    assert!(DatabaseKeyIndex::from_bits(bits, &db).is_some());
+
+    // Deletes the tracked struct...
+    input.set_field(&mut db).to(0);
+    assert!(make_tracked(&db, input).is_none());
+    assert_eq!(DatabaseKeyIndex::from_bits(bits, &db), None);
+
+    // ...and reuses its id for a new one.
+    input.set_field(&mut db).to(2);
+    let tracked = make_tracked(&db, input).unwrap();
+    let key = tracked_value::database_key_index(&db, tracked).unwrap();
+    assert_eq!(key, old_key);
+    assert_eq!(DatabaseKeyIndex::from_bits(bits, &db), None);
+    assert_eq!(
+        DatabaseKeyIndex::from_bits(key.to_bits(&db), &db),
+        Some(key)
+    );
+}
+
+#[test]
+fn foreign_id_is_rejected() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let tracked = make_tracked(&db, input).unwrap();
+    let bits = double::database_key_index(&db, input).unwrap().to_bits(&db);
+    let tracked_bits = tracked_value::database_key_index(&db, tracked)
+        .unwrap()
+        .to_bits(&db);
+
+    // The id and generation of `input` with the ingredient of `tracked_value`,
+    // which takes tracked structs.
+    let id_mask = (1 << 48) - 1;
+    let mixed = (tracked_bits & !id_mask) | (bits & id_mask);
+    assert_eq!(DatabaseKeyIndex::from_bits(mixed, &db), None);
+}
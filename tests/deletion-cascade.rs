@@ -81,10 +81,10 @@ fn basic() {
         [
             "intermediate_result(MyInput { [salsa id]: Id(0), field: 2 })",
             "salsa_event(WillDiscardStaleOutput { execute_key: create_tracked_structs(Id(0)), output_key: MyTracked(Id(402)) })",
-            "salsa_event(DidDiscard { key: MyTracked(Id(402)) })",
             "salsa_event(DidDiscard { key: contribution_from_struct(Id(402)) })",
-            "salsa_event(DidDiscard { key: MyTracked(Id(405)) })",
             "salsa_event(DidDiscard { key: copy_field(Id(405)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(405)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(402)) })",
             "final_result(MyInput { [salsa id]: Id(0), field: 2 })",
         ]"#]]);
 }
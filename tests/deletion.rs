@@ -57,19 +57,18 @@ fn basic() {
 
     // Creates only 2 tracked structs in this revision, should delete 1
     //
-    // Expect to see 3 DidDiscard events--
+    // Expect to see 2 DidDiscard events, in this order--
     //
-    // * the struct itself
-    // * the struct's field
     // * the `contribution_from_struct` result
+    // * the struct itself
     input.set_field(&mut db).to(2);
     assert_eq!(final_result(&db, input), 2);
     db.assert_logs(expect![[r#"
         [
             "intermediate_result(MyInput { [salsa id]: Id(0), field: 2 })",
             "salsa_event(WillDiscardStaleOutput { execute_key: create_tracked_structs(Id(0)), output_key: MyTracked(Id(402)) })",
-            "salsa_event(DidDiscard { key: MyTracked(Id(402)) })",
             "salsa_event(DidDiscard { key: contribution_from_struct(Id(402)) })",
+            "salsa_event(DidDiscard { key: MyTracked(Id(402)) })",
             "final_result(MyInput { [salsa id]: Id(0), field: 2 })",
         ]"#]]);
 }
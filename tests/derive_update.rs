@@ -0,0 +1,151 @@
+//! Test `#[derive(salsa::Update)]` on generic types
+//! and with `#[update(with = ...)]` field overrides,
+//! and that the `Update` impl of `Vec` reuses allocations.
+
+use salsa::Update;
+
+#[derive(Debug, PartialEq, Eq, salsa::Update)]
+enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+#[derive(Debug, PartialEq, Eq, salsa::Update)]
+struct Pair<T> {
+    first: T,
+    second: Vec<T>,
+}
+
+/// A foreign type that does not implement `Update` (and isn't `PartialEq`).
+#[derive(Debug)]
+struct Foreign(u32);
+
+/// Custom update logic for `Foreign`.
+///
+/// # Safety
+///
+/// Same contract as `Update::maybe_update`.
+unsafe fn update_foreign(old_pointer: *mut Foreign, new_value: Foreign) -> bool {
+    let old = unsafe { &mut *old_pointer };
+    if old.0 == new_value.0 {
+        false
+    } else {
+        *old = new_value;
+        true
+    }
+}
+
+#[derive(Debug, salsa::Update)]
+struct WithForeign {
+    #[update(with = update_foreign)]
+    foreign: Foreign,
+    plain: u32,
+}
+
+fn maybe_update<T: Update>(old: &mut T, new: T) -> bool {
+    unsafe { T::maybe_update(old, new) }
+}
+
+#[test]
+fn generic_enum() {
+    let mut value: Either<u32, String> = Either::Left(1);
+
+    assert!(!maybe_update(&mut value, Either::Left(1)));
+    assert!(maybe_update(&mut value, Either::Left(2)));
+    assert_eq!(value, Either::Left(2));
+
+    assert!(maybe_update(&mut value, Either::Right("hello".to_string())));
+    assert_eq!(value, Either::Right("hello".to_string()));
+    assert!(!maybe_update(
+        &mut value,
+        Either::Right("hello".to_string())
+    ));
+}
+
+#[test]
+fn generic_struct() {
+    let mut value = Pair {
+        first: 1,
+        second: vec![2, 3],
+    };
+
+    assert!(!maybe_update(
+        &mut value,
+        Pair {
+            first: 1,
+            second: vec![2, 3],
+        }
+    ));
+    assert!(maybe_update(
+        &mut value,
+        Pair {
+            first: 1,
+            second: vec![2, 4],
+        }
+    ));
+    assert_eq!(value.second, [2, 4]);
+}
+
+#[test]
+fn update_with() {
+    let mut value = WithForeign {
+        foreign: Foreign(1),
+        plain: 2,
+    };
+
+    assert!(!maybe_update(
+        &mut value,
+        WithForeign {
+            foreign: Foreign(1),
+            plain: 2,
+        }
+    ));
+    assert!(maybe_update(
+        &mut value,
+        WithForeign {
+            foreign: Foreign(3),
+            plain: 2,
+        }
+    ));
+    assert_eq!(value.foreign.0, 3);
+}
+
+#[test]
+fn string_takes_new_allocation() {
+    let mut value = "hello".to_string();
+    let pointer = value.as_ptr();
+    assert!(!maybe_update(&mut value, "hello".to_string()));
+    assert_eq!(value.as_ptr(), pointer);
+
+    // Copying into the old buffer would not save an allocation, as the new one is freed anyway.
+    let new_value = "goodbye".to_string();
+    let new_pointer = new_value.as_ptr();
+    assert!(maybe_update(&mut value, new_value));
+    assert_eq!(value.as_ptr(), new_pointer);
+}
+
+#[test]
+fn vec_reuses_allocations() {
+    let mut value = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let pointer = value.as_ptr();
+    let first_pointer = value[0].as_ptr();
+
+    assert!(maybe_update(
+        &mut value,
+        vec!["a".to_string(), "d".to_string()]
+    ));
+    assert_eq!(value, ["a", "d"]);
+    assert_eq!(value.as_ptr(), pointer);
+    assert_eq!(value[0].as_ptr(), first_pointer);
+
+    assert!(maybe_update(
+        &mut value,
+        vec!["a".to_string(), "d".to_string(), "e".to_string()]
+    ));
+    assert_eq!(value, ["a", "d", "e"]);
+    assert_eq!(value.as_ptr(), pointer);
+    assert!(!maybe_update(
+        &mut value,
+        vec!["a".to_string(), "d".to_string(), "e".to_string()]
+    ));
+}
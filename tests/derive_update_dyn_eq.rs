@@ -0,0 +1,165 @@
+//! Test `#[update(dyn_eq = ...)]`, which compares boxed trait objects so that
+//! tracked struct fields holding them can be backdated.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use std::any::Any;
+use std::fmt::Debug;
+
+use expect_test::expect;
+use salsa::{Setter, Update};
+use test_log::test;
+
+trait Plugin: Debug + Send + Sync {
+    fn name(&self) -> &str;
+    fn as_any(&self) -> &dyn Any;
+}
+
+#[derive(Debug, PartialEq)]
+struct Greeter {
+    greeting: String,
+}
+
+impl Plugin for Greeter {
+    fn name(&self) -> &str {
+        "greeter"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Counter(u32);
+
+impl Plugin for Counter {
+    fn name(&self) -> &str {
+        "counter"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Plugins are equal if they have the same type and compare equal as that type.
+fn plugin_eq(old: &dyn Plugin, new: &dyn Plugin) -> bool {
+    fn downcast_eq<T: PartialEq + 'static>(old: &dyn Plugin, new: &dyn Plugin) -> Option<bool> {
+        Some(old.as_any().downcast_ref::<T>()? == new.as_any().downcast_ref::<T>()?)
+    }
+    downcast_eq::<Greeter>(old, new)
+        .or_else(|| downcast_eq::<Counter>(old, new))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Update)]
+struct Plugins {
+    #[update(dyn_eq = plugin_eq)]
+    main: Box<dyn Plugin>,
+    #[update(dyn_eq = plugin_eq)]
+    extra: Option<Box<dyn Plugin>>,
+}
+
+fn maybe_update<T: Update>(old: &mut T, new: T) -> bool {
+    unsafe { T::maybe_update(old, new) }
+}
+
+#[test]
+fn compares_trait_objects() {
+    let greeter = |greeting: &str| {
+        Box::new(Greeter {
+            greeting: greeting.to_string(),
+        })
+    };
+    let mut value = Plugins {
+        main: greeter("hello"),
+        extra: None,
+    };
+
+    assert!(!maybe_update(
+        &mut value,
+        Plugins {
+            main: greeter("hello"),
+            extra: None,
+        }
+    ));
+    assert!(maybe_update(
+        &mut value,
+        Plugins {
+            main: Box::new(Counter(0)),
+            extra: None,
+        }
+    ));
+    assert_eq!(value.main.name(), "counter");
+    assert!(maybe_update(
+        &mut value,
+        Plugins {
+            main: Box::new(Counter(0)),
+            extra: Some(greeter("hi")),
+        }
+    ));
+    assert!(!maybe_update(
+        &mut value,
+        Plugins {
+            main: Box::new(Counter(0)),
+            extra: Some(greeter("hi")),
+        }
+    ));
+}
+
+#[salsa::input]
+struct MyInput {
+    count: u32,
+}
+
+#[salsa::tracked]
+struct Configured<'db> {
+    #[tracked]
+    #[return_ref]
+    plugins: Plugins,
+}
+
+/// Only uses the input to decide whether there is a counter plugin.
+#[salsa::tracked]
+fn configure(db: &dyn LogDatabase, input: MyInput) -> Configured<'_> {
+    let extra = (input.count(db) > 0).then(|| Box::new(Counter(1)) as Box<dyn Plugin>);
+    let main = Box::new(Greeter {
+        greeting: "hello".to_string(),
+    });
+    Configured::new(db, Plugins { main, extra })
+}
+
+#[salsa::tracked]
+fn plugin_names(db: &dyn LogDatabase, input: MyInput) -> Vec<String> {
+    db.push_log("plugin_names".to_string());
+    let plugins = configure(db, input).plugins(db);
+    std::iter::once(&plugins.main)
+        .chain(&plugins.extra)
+        .map(|plugin| plugin.name().to_string())
+        .collect()
+}
+
+#[test]
+fn backdates_tracked_field() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(plugin_names(&db, input), ["greeter", "counter"]);
+    db.assert_logs(expect![[r#"
+        [
+            "plugin_names",
+        ]"#]]);
+
+    // The plugins compare equal, so `plugin_names` is not re-executed.
+    input.set_count(&mut db).to(2);
+    assert_eq!(plugin_names(&db, input), ["greeter", "counter"]);
+    db.assert_logs(expect!["[]"]);
+
+    input.set_count(&mut db).to(0);
+    assert_eq!(plugin_names(&db, input), ["greeter"]);
+    db.assert_logs(expect![[r#"
+        [
+            "plugin_names",
+        ]"#]]);
+}
@@ -0,0 +1,52 @@
+//! Test finding the memoized values that changed with `salsa::diff::changed_outputs`.
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn is_even(db: &dyn Database, input: MyInput) -> bool {
+    input.field(db) % 2 == 0
+}
+
+#[test]
+fn reports_changed_values() {
+    let mut db = salsa::DatabaseImpl::new();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    for input in [a, b] {
+        double(&db, input);
+        is_even(&db, input);
+    }
+
+    let before = salsa::diff::current_revision(&db);
+    assert_eq!(salsa::diff::changed_outputs(&db, before), []);
+
+    // `is_even(a)` is backdated, as its value does not change.
+    a.set_field(&mut db).to(3);
+    for input in [a, b] {
+        double(&db, input);
+        is_even(&db, input);
+    }
+    assert_eq!(
+        salsa::diff::changed_outputs(&db, before),
+        [double::database_key_index(&db, a).unwrap()]
+    );
+
+    let middle = salsa::diff::current_revision(&db);
+    b.set_field(&mut db).to(5);
+    is_even(&db, b);
+    assert_eq!(
+        salsa::diff::changed_outputs(&db, middle),
+        [is_even::database_key_index(&db, b).unwrap()]
+    );
+    assert_eq!(salsa::diff::changed_outputs(&db, before).len(), 2);
+}
@@ -0,0 +1,53 @@
+//! Test formatting keys with `DatabaseKeyIndex::debug` inside `salsa_event`,
+//! without the database being attached.
+
+use std::sync::{Arc, Mutex};
+
+use expect_test::expect;
+use salsa::{Database, DatabaseKeyIndex, EventKind};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct Db {
+    storage: salsa::Storage<Self>,
+    executed: Arc<Mutex<Vec<(DatabaseKeyIndex, String)>>>,
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        if let EventKind::WillExecute { database_key } = event().kind {
+            let formatted = format!("{:?}", database_key.debug(self));
+            self.executed
+                .lock()
+                .unwrap()
+                .push((database_key, formatted));
+        }
+    }
+}
+
+#[test]
+fn debug_in_salsa_event() {
+    let db = Db::default();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(double(&db, input), 2);
+
+    let executed = db.executed.lock().unwrap().clone();
+    assert_eq!(executed.len(), 1);
+    let (key, formatted) = &executed[0];
+    expect!["double(Id(0))"].assert_eq(formatted);
+
+    // Outside of any query, the plain `Debug` impl has no database to resolve the key with.
+    expect!["DatabaseKeyIndex(IngredientIndex(2), Id(0))"].assert_eq(&format!("{key:?}"));
+    expect!["double(Id(0))"].assert_eq(&format!("{:?}", key.debug(&db)));
+}
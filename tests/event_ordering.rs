@@ -0,0 +1,85 @@
+//! Test the ordering of `DidDiscard` events with respect to the state of the database:
+//! a tracked struct is reported as discarded only once its memos are gone.
+
+mod common;
+use common::{HasLogger, LogDatabase, Logger};
+
+use expect_test::expect;
+use salsa::{Database, EventKind, Setter, Storage};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn create_tracked_structs(db: &dyn LogDatabase, input: MyInput) -> Vec<MyTracked<'_>> {
+    (0..input.field(db))
+        .map(|i| MyTracked::new(db, i))
+        .collect()
+}
+
+#[salsa::tracked]
+fn contribution_from_struct<'db>(db: &'db dyn LogDatabase, tracked: MyTracked<'db>) -> u32 {
+    tracked.field(db) * 2
+}
+
+#[salsa::tracked]
+fn final_result(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    create_tracked_structs(db, input)
+        .into_iter()
+        .map(|tracked| contribution_from_struct(db, tracked))
+        .sum()
+}
+
+/// Logs each `DidDiscard` event along with the number of memos
+/// of `contribution_from_struct` at that time.
+#[salsa::db]
+#[derive(Clone, Default)]
+struct Db {
+    storage: Storage<Self>,
+    logger: Logger,
+}
+
+impl HasLogger for Db {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        if let EventKind::DidDiscard { key } = event().kind {
+            let memos = self
+                .memory_report()
+                .ingredients
+                .iter()
+                .find(|usage| usage.debug_name == "contribution_from_struct")
+                .map_or(0, |usage| usage.memos);
+            self.push_log(format!("DidDiscard({key:?}), {memos} memos"));
+        }
+    }
+}
+
+#[test]
+fn memos_are_discarded_first() {
+    let mut db = Db::default();
+    let input = MyInput::new(&db, 3);
+    assert_eq!(final_result(&db, input), 6);
+    db.assert_logs(expect!["[]"]);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(final_result(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "DidDiscard(contribution_from_struct(Id(402))), 2 memos",
+            "DidDiscard(MyTracked(Id(402))), 2 memos",
+        ]"#]]);
+}
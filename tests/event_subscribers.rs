@@ -0,0 +1,139 @@
+//! Test that subscribers registered on `Storage` receive
+//! only the events they filtered for, along with the database emitting them.
+
+use std::sync::{Arc, Mutex};
+
+use salsa::{Database, DatabaseKeyIndex, EventFilter, EventKind, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct Db {
+    storage: salsa::Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[test]
+fn filtered_subscriber() {
+    let mut db = Db::default();
+    let executed: Arc<Mutex<Vec<DatabaseKeyIndex>>> = Default::default();
+    let id = db.storage.subscribe(EventFilter::WILL_EXECUTE, {
+        let executed = executed.clone();
+        move |event| match event.kind {
+            EventKind::WillExecute { database_key } => executed.lock().unwrap().push(database_key),
+            _ => panic!("unexpected event {event:?}"),
+        }
+    });
+
+    let input = MyInput::new(&db, 1);
+    assert_eq!(double(&db, input), 2);
+    assert_eq!(double(&db, input), 2);
+    let first = executed.lock().unwrap().clone();
+    assert_eq!(first.len(), 1);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(double(&db, input), 4);
+    assert_eq!(*executed.lock().unwrap(), [first[0], first[0]]);
+
+    assert!(db.storage.unsubscribe(id));
+    assert!(!db.storage.unsubscribe(id));
+
+    input.set_field(&mut db).to(3);
+    assert_eq!(double(&db, input), 6);
+    assert_eq!(executed.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn combined_filter() {
+    let db = Db::default();
+    let count = Arc::new(Mutex::new(0));
+    db.storage.subscribe(
+        EventFilter::WILL_EXECUTE | EventFilter::WILL_CHECK_CANCELLATION,
+        {
+            let count = count.clone();
+            move |event| {
+                assert!(matches!(
+                    event.kind,
+                    EventKind::WillExecute { .. } | EventKind::WillCheckCancellation
+                ));
+                *count.lock().unwrap() += 1;
+            }
+        },
+    );
+
+    let input = MyInput::new(&db, 1);
+    assert_eq!(double(&db, input), 2);
+    assert!(*count.lock().unwrap() >= 2);
+}
+
+#[test]
+fn subscriber_with_db() {
+    let db = Db::default();
+    let executed: Arc<Mutex<Vec<String>>> = Default::default();
+    db.storage.subscribe_with_db(EventFilter::WILL_EXECUTE, {
+        let executed = executed.clone();
+        move |db, event| {
+            if let EventKind::WillExecute { database_key } = event.kind {
+                let name = db.ingredient_debug_name(database_key.ingredient_index());
+                executed
+                    .lock()
+                    .unwrap()
+                    .push(format!("{name}: {:?}", database_key.debug(db)));
+            }
+        }
+    });
+
+    let input = MyInput::new(&db, 1);
+    assert_eq!(double(&db, input), 2);
+    assert_eq!(
+        *executed.lock().unwrap(),
+        ["double: double(Id(0))".to_string()]
+    );
+}
+
+#[test]
+fn concurrent_subscriptions() {
+    for _ in 0..100 {
+        let db = Db::default();
+        let executed = Arc::new(Mutex::new(0));
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let db = db.clone();
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        let id = db
+                            .storage
+                            .subscribe(EventFilter::WILL_CHECK_CANCELLATION, |_| {});
+                        db.storage.unsubscribe(id);
+                    }
+                });
+            }
+            let db = db.clone();
+            let executed = executed.clone();
+            scope.spawn(move || {
+                db.storage.subscribe(EventFilter::WILL_EXECUTE, move |_| {
+                    *executed.lock().unwrap() += 1
+                });
+            });
+        });
+
+        // The interest of the subscription is not lost to a concurrent unsubscription.
+        let input = MyInput::new(&db, 1);
+        assert_eq!(double(&db, input), 2);
+        assert_eq!(*executed.lock().unwrap(), 1);
+    }
+}
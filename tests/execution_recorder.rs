@@ -0,0 +1,69 @@
+//! Test that, with the `testing` feature, `ExecutionRecorder` records the queries
+//! executed and validated by a database.
+#![cfg(feature = "testing")]
+
+use expect_test::expect;
+use salsa::{testing::ExecutionRecorder, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::tracked]
+fn parse(db: &dyn salsa::Database, file: File) -> Vec<u32> {
+    file.text(db)
+        .split('+')
+        .map(|term| term.trim().parse().unwrap())
+        .collect()
+}
+
+#[salsa::tracked]
+fn compile(db: &dyn salsa::Database, file: File) -> u32 {
+    parse(db, file).iter().sum()
+}
+
+#[test]
+fn records_executions() {
+    let mut db = DatabaseImpl::new();
+    let recorder = ExecutionRecorder::attach(db.storage());
+    let file = File::new(&db, "1 + 2".to_string());
+
+    assert_eq!(compile(&db, file), 3);
+    recorder.assert_executed(["compile", "parse(Id(0))"]);
+
+    assert_eq!(compile(&db, file), 3);
+    recorder.assert_executed([]);
+
+    file.set_text(&mut db).to("1 +  2".to_string());
+    assert_eq!(compile(&db, file), 3);
+    recorder.assert_no_reexecution("compile");
+    recorder.assert_records(expect![[r#"
+        [
+            Executed {
+                key: "parse(Id(0))",
+            },
+            Validated {
+                key: "compile(Id(0))",
+                kind: Deep,
+            },
+        ]
+    "#]]);
+
+    let other = ExecutionRecorder::attach(db.storage());
+    recorder.detach();
+    file.set_text(&mut db).to("2".to_string());
+    assert_eq!(compile(&db, file), 2);
+    other.assert_executed(["parse", "compile"]);
+}
+
+#[test]
+#[should_panic(expected = "expected `parse` not to be executed")]
+fn no_reexecution_fails() {
+    let db = DatabaseImpl::new();
+    let recorder = ExecutionRecorder::attach(db.storage());
+    let file = File::new(&db, "1".to_string());
+
+    assert_eq!(compile(&db, file), 1);
+    recorder.assert_no_reexecution("parse");
+}
@@ -0,0 +1,60 @@
+//! Test that queries can depend on resources outside of salsa
+//! with `Database::report_external_dependency`.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use expect_test::expect;
+use salsa::{Database, Durability, Setter};
+use test_log::test;
+
+/// Stands in for the contents of a configuration file.
+static CONFIG: AtomicU64 = AtomicU64::new(0);
+
+fn config_fingerprint(key: &str) -> u64 {
+    assert_eq!(key, "config.toml");
+    CONFIG.load(Ordering::SeqCst)
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn read_config(db: &dyn LogDatabase, input: MyInput) -> u64 {
+    db.push_log(format!("read_config({})", input.field(db)));
+    db.report_external_dependency("config.toml", config_fingerprint);
+    CONFIG.load(Ordering::SeqCst) + u64::from(input.field(db))
+}
+
+#[test]
+fn reexecutes_only_when_fingerprint_changes() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(read_config(&db, input), 1);
+
+    // A new revision in which the resource did not change.
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(read_config(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "read_config(1)",
+        ]"#]]);
+
+    CONFIG.store(10, Ordering::SeqCst);
+    // Changes are only noticed in a new revision.
+    assert_eq!(read_config(&db, input), 1);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(read_config(&db, input), 11);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(read_config(&db, input), 12);
+    db.assert_logs(expect![[r#"
+        [
+            "read_config(1)",
+            "read_config(2)",
+        ]"#]]);
+}
@@ -0,0 +1,64 @@
+//! Test a database struct that is generic over a backend.
+//! Like any database, it must be `'static`, so the struct requires `B: 'static`.
+
+use salsa::{Database, Setter};
+
+trait Backend: Clone + Send {
+    fn read(&self, name: &str) -> String;
+}
+
+#[derive(Clone)]
+struct Uppercase;
+
+impl Backend for Uppercase {
+    fn read(&self, name: &str) -> String {
+        name.to_uppercase()
+    }
+}
+
+#[salsa::db]
+trait Db: Database {
+    fn read(&self, name: &str) -> String;
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct GenericDatabase<B: Backend + 'static> {
+    storage: salsa::Storage<Self>,
+    backend: B,
+}
+
+#[salsa::db]
+impl<B: Backend + 'static> Database for GenericDatabase<B> {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[salsa::db]
+impl<B: Backend + 'static> Db for GenericDatabase<B> {
+    fn read(&self, name: &str) -> String {
+        self.backend.read(name)
+    }
+}
+
+#[salsa::input]
+struct File {
+    name: String,
+}
+
+#[salsa::tracked]
+fn contents(db: &dyn Db, file: File) -> String {
+    db.read(&file.name(db))
+}
+
+#[test]
+fn execute() {
+    let mut db = GenericDatabase {
+        storage: Default::default(),
+        backend: Uppercase,
+    };
+    let file = File::new(&db, "hello".to_string());
+    assert_eq!(contents(&db, file), "HELLO");
+
+    file.set_name(&mut db).to("world".to_string());
+    assert_eq!(contents(&db, file), "WORLD");
+}
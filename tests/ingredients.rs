@@ -0,0 +1,107 @@
+//! Test that `Database::ingredients` describes the ingredients registered so far.
+
+use expect_test::expect;
+use salsa::{Accumulator, Database, DatabaseImpl, IngredientKind};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    #[tracked]
+    field: u32,
+}
+
+#[salsa::interned]
+struct MyInterned<'db> {
+    text: String,
+}
+
+#[salsa::accumulator]
+struct Log(#[allow(dead_code)] String);
+
+#[salsa::tracked]
+fn create_tracked(db: &dyn Database, input: MyInput) -> MyTracked<'_> {
+    Log("create_tracked".to_string()).accumulate(db);
+    MyTracked::new(db, input.field(db))
+}
+
+#[salsa::tracked]
+fn sum<'db>(db: &'db dyn Database, input: MyInput, interned: MyInterned<'db>) -> u32 {
+    create_tracked(db, input).field(db) + interned.text(db).len() as u32
+}
+
+fn describe(db: &dyn Database) -> Vec<(&'static str, IngredientKind, usize)> {
+    db.ingredients()
+        .into_iter()
+        .map(|info| (info.debug_name, info.kind, info.memos))
+        .collect()
+}
+
+#[test]
+fn execute() {
+    let db = DatabaseImpl::new();
+    assert!(db.ingredients().is_empty());
+
+    let input = MyInput::new(&db, 22);
+    let interned = MyInterned::new(&db, "hello".to_string());
+    assert_eq!(sum(&db, input, interned), 27);
+
+    for info in db.ingredients() {
+        assert_eq!(db.ingredient_debug_name(info.index), info.debug_name);
+    }
+
+    // Ingredients are listed in the order they were registered, when first used.
+    expect![[r#"
+        [
+            (
+                "MyInput",
+                Input,
+                0,
+            ),
+            (
+                "field",
+                InputField,
+                0,
+            ),
+            (
+                "MyInterned",
+                Interned,
+                0,
+            ),
+            (
+                "sum",
+                TrackedFn,
+                1,
+            ),
+            (
+                "Configuration",
+                Interned,
+                0,
+            ),
+            (
+                "create_tracked",
+                TrackedFn,
+                1,
+            ),
+            (
+                "Log",
+                Accumulator,
+                0,
+            ),
+            (
+                "MyTracked",
+                TrackedStruct,
+                0,
+            ),
+            (
+                "field",
+                TrackedField,
+                0,
+            ),
+        ]
+    "#]]
+    .assert_debug_eq(&describe(&db));
+}
@@ -0,0 +1,73 @@
+//! Test creating many inputs at once with `builder_many`.
+
+use std::sync::{Arc, Mutex};
+
+use salsa::{Database, Durability, EventFilter, EventKind, Setter};
+
+#[salsa::input]
+struct File {
+    path: String,
+
+    #[default]
+    contents: String,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn Database, file: File) -> usize {
+    file.contents(db).len()
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct Db {
+    storage: salsa::Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[test]
+fn creates_inputs_in_order() {
+    let mut db = Db::default();
+    let first = File::new(&db, "first".to_string());
+    let files = File::builder_many(
+        &db,
+        (0..2500).map(|i| {
+            File::builder(format!("{i}.rs"))
+                .contents(i.to_string())
+                .durability(Durability::HIGH)
+        }),
+    );
+
+    assert_eq!(files.len(), 2500);
+    for (i, file) in files.iter().enumerate() {
+        assert_eq!(file.path(&db), format!("{i}.rs"));
+        assert_ne!(*file, first);
+    }
+    assert_eq!(first.path(&db), "first");
+
+    assert_eq!(length(&db, files[1000]), 4);
+    files[1000].set_contents(&mut db).to("changed".to_string());
+    assert_eq!(length(&db, files[1000]), 7);
+    assert_eq!(File::new(&db, "last".to_string()).path(&db), "last");
+}
+
+#[test]
+fn emits_one_event() {
+    let db = Db::default();
+    let events: Arc<Mutex<Vec<usize>>> = Default::default();
+    db.storage.subscribe(EventFilter::DID_CREATE_INPUTS, {
+        let events = events.clone();
+        move |event| match event.kind {
+            EventKind::DidCreateInputs { count, .. } => events.lock().unwrap().push(count),
+            _ => panic!("unexpected event {event:?}"),
+        }
+    });
+
+    File::new(&db, "single".to_string());
+    File::builder_many(&db, (0..10).map(|i| File::builder(format!("{i}.rs"))));
+    File::builder_many(&db, []);
+    assert_eq!(*events.lock().unwrap(), [10, 0]);
+}
@@ -0,0 +1,103 @@
+//! Test that listeners registered with `Storage::on_change` receive
+//! one change set per revision.
+
+use std::sync::{Arc, Mutex};
+
+use salsa::{ChangeSet, Database, Setter};
+
+#[salsa::input]
+struct MyInput {
+    a: u32,
+    b: u32,
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct Db {
+    storage: salsa::Storage<Self>,
+    change_sets: Arc<Mutex<Vec<ChangeSet>>>,
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+impl Db {
+    fn new() -> Self {
+        let db = Self::default();
+        let change_sets = db.change_sets.clone();
+        db.storage
+            .on_change(move |change_set| change_sets.lock().unwrap().push(change_set.clone()));
+        db
+    }
+
+    fn take_change_sets(&self) -> Vec<ChangeSet> {
+        std::mem::take(&mut *self.change_sets.lock().unwrap())
+    }
+}
+
+#[test]
+fn single_sets() {
+    let mut db = Db::new();
+    let input = MyInput::new(&db, 1, 2);
+    assert!(db.take_change_sets().is_empty());
+
+    input.set_a(&mut db).to(10);
+    input.set_b(&mut db).to(20);
+    let change_sets = db.take_change_sets();
+    assert_eq!(change_sets.len(), 2);
+    assert_eq!(change_sets[0].changed().len(), 1);
+    assert_eq!(change_sets[1].changed().len(), 1);
+    assert_ne!(change_sets[0].changed(), change_sets[1].changed());
+    assert!(change_sets[0].revision() < change_sets[1].revision());
+}
+
+#[test]
+fn transaction_is_coalesced() {
+    let mut db = Db::new();
+    let input1 = MyInput::new(&db, 1, 2);
+    let input2 = MyInput::new(&db, 3, 4);
+
+    db.transaction(|db| {
+        input1.set_a(db).to(10);
+        input2.set_a(db).to(30);
+        input1.set_a(db).to(11);
+        assert!(db.take_change_sets().is_empty());
+    });
+
+    let change_sets = db.take_change_sets();
+    assert_eq!(change_sets.len(), 1);
+    assert_eq!(change_sets[0].changed().len(), 2);
+}
+
+#[test]
+fn write_scope_is_coalesced() {
+    let mut db = Db::new();
+    let input = MyInput::new(&db, 1, 2);
+
+    db.write_scope(|scope| {
+        input.set_a_in(scope).to(10);
+        input.set_b_in(scope).to(20);
+    });
+
+    let change_sets = db.take_change_sets();
+    assert_eq!(change_sets.len(), 1);
+    assert_eq!(change_sets[0].changed().len(), 2);
+}
+
+#[test]
+fn removed_listener() {
+    let mut db = Db::default();
+    let count = Arc::new(Mutex::new(0));
+    let id = db.storage.on_change({
+        let count = count.clone();
+        move |_| *count.lock().unwrap() += 1
+    });
+
+    let input = MyInput::new(&db, 1, 2);
+    input.set_a(&mut db).to(10);
+    assert!(db.storage.remove_change_listener(id));
+    input.set_a(&mut db).to(11);
+    assert_eq!(*count.lock().unwrap(), 1);
+}
@@ -0,0 +1,119 @@
+//! Test that `#[delta]` input fields only invalidate queries
+//! whose range was touched by an edit.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{Setter, TextEdit};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    #[delta]
+    contents: String,
+}
+
+#[salsa::tracked]
+fn header(db: &dyn LogDatabase, file: File) -> String {
+    db.push_log("header".to_string());
+    file.contents_range(db, 0..5).to_string()
+}
+
+#[salsa::tracked]
+fn body(db: &dyn LogDatabase, file: File) -> String {
+    db.push_log("body".to_string());
+    file.contents_range(db, 6..11).to_string()
+}
+
+#[salsa::tracked]
+fn len(db: &dyn LogDatabase, file: File) -> usize {
+    db.push_log("len".to_string());
+    file.contents(db).len()
+}
+
+#[test]
+fn edit_after_range() {
+    let mut db = LoggerDatabase::default();
+    let file = File::new(&db, "hello world".to_string());
+
+    assert_eq!(header(&db, file), "hello");
+    assert_eq!(body(&db, file), "world");
+    assert_eq!(len(&db, file), 11);
+    db.assert_logs(expect![[r#"
+        [
+            "header",
+            "body",
+            "len",
+        ]"#]]);
+
+    // Only `body` overlaps the edit; `len` reads the whole field.
+    file.set_contents_edit(&mut db, TextEdit::new(6..11, "there"));
+    assert_eq!(header(&db, file), "hello");
+    assert_eq!(body(&db, file), "there");
+    assert_eq!(len(&db, file), 11);
+    db.assert_logs(expect![[r#"
+        [
+            "body",
+            "len",
+        ]"#]]);
+
+    assert_eq!(file.contents(&db), "hello there");
+}
+
+#[test]
+fn shifting_edit_before_range() {
+    let mut db = LoggerDatabase::default();
+    let file = File::new(&db, "hello world".to_string());
+
+    assert_eq!(header(&db, file), "hello");
+    assert_eq!(body(&db, file), "world");
+    db.assert_logs(expect![[r#"
+        [
+            "header",
+            "body",
+        ]"#]]);
+
+    // Same-length edit before `body` does not move it.
+    file.set_contents_edit(&mut db, TextEdit::new(0..5, "HELLO"));
+    assert_eq!(header(&db, file), "HELLO");
+    assert_eq!(body(&db, file), "world");
+    db.assert_logs(expect![[r#"
+        [
+            "header",
+        ]"#]]);
+
+    // An insertion before `body` moves it and so invalidates it.
+    file.set_contents_edit(&mut db, TextEdit::new(0..0, ">"));
+    assert_eq!(header(&db, file), ">HELL");
+    assert_eq!(body(&db, file), " worl");
+    db.assert_logs(expect![[r#"
+        [
+            "header",
+            "body",
+        ]"#]]);
+}
+
+#[test]
+fn whole_value_set_invalidates_ranges() {
+    let mut db = LoggerDatabase::default();
+    let file = File::new(&db, "hello world".to_string());
+
+    file.set_contents_edit(&mut db, TextEdit::new(6..11, "there"));
+    assert_eq!(header(&db, file), "hello");
+    assert_eq!(body(&db, file), "there");
+    db.assert_logs(expect![[r#"
+        [
+            "header",
+            "body",
+        ]"#]]);
+
+    file.set_contents(&mut db).to("howdy world".to_string());
+    assert_eq!(header(&db, file), "howdy");
+    assert_eq!(body(&db, file), "world");
+    db.assert_logs(expect![[r#"
+        [
+            "header",
+            "body",
+        ]"#]]);
+}
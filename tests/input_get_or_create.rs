@@ -0,0 +1,75 @@
+//! Test creating inputs keyed by a `#[key]` field with `get_or_create`.
+
+use salsa::{Database, DatabaseImpl, Durability, Setter};
+
+#[salsa::input]
+struct File {
+    #[key]
+    path: String,
+
+    contents: String,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn Database, file: File) -> usize {
+    file.contents(db).len()
+}
+
+#[test]
+fn returns_existing_input() {
+    let mut db = DatabaseImpl::new();
+    assert_eq!(File::get_by_key(&db, &"a.rs".to_string()), None);
+
+    let a = File::get_or_create(&db, "a.rs".to_string(), "abc".to_string());
+    let b = File::get_or_create(&db, "b.rs".to_string(), "b".to_string());
+    assert_ne!(a, b);
+    assert_eq!(File::get_by_key(&db, &"a.rs".to_string()), Some(a));
+
+    // The fields of an existing input are left unchanged.
+    assert_eq!(
+        File::get_or_create(&db, "a.rs".to_string(), "ignored".to_string()),
+        a
+    );
+    assert_eq!(length(&db, a), 3);
+
+    a.set_contents(&mut db).to("abcd".to_string());
+    assert_eq!(
+        File::builder("a.rs".to_string(), String::new())
+            .durability(Durability::HIGH)
+            .get_or_create(&db),
+        a
+    );
+    assert_eq!(length(&db, a), 4);
+}
+
+#[test]
+fn new_is_not_keyed() {
+    let db = DatabaseImpl::new();
+    let a = File::new(&db, "a.rs".to_string(), String::new());
+    assert_eq!(File::get_by_key(&db, &"a.rs".to_string()), None);
+    assert_ne!(
+        File::get_or_create(&db, "a.rs".to_string(), String::new()),
+        a
+    );
+}
+
+#[salsa::input]
+struct Unrelated {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn main_length(db: &dyn Database) -> Option<usize> {
+    File::get_by_key(db, &"main.rs".to_string()).map(|file| length(db, file))
+}
+
+#[test]
+fn misses_are_tracked() {
+    let mut db = DatabaseImpl::new();
+    let unrelated = Unrelated::new(&db, 0);
+    assert_eq!(main_length(&db), None);
+
+    File::get_or_create(&db, "main.rs".to_string(), "fn main() {}".to_string());
+    unrelated.set_field(&mut db).to(1);
+    assert_eq!(main_length(&db), Some(12));
+}
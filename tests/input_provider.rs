@@ -0,0 +1,105 @@
+//! Test that inputs with a provider pull their fields
+//! from an external source when they may be stale.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use expect_test::expect;
+use salsa::{Database, Durability, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct Setting {
+    value: String,
+}
+
+#[salsa::tracked]
+fn setting_len(db: &dyn LogDatabase, setting: Setting) -> usize {
+    db.push_log("setting_len".to_string());
+    setting.value(db).len()
+}
+
+type Store = Arc<Mutex<HashMap<Setting, String>>>;
+
+fn register(db: &mut LoggerDatabase, durability: Durability) -> Store {
+    let store: Store = Default::default();
+    Setting::set_provider(db, {
+        let store = store.clone();
+        move |setting, (old,)| {
+            let value = store.lock().unwrap().get(&setting).cloned()?;
+            (value != *old).then_some(((value,), durability))
+        }
+    });
+    store
+}
+
+#[test]
+fn pulled_when_stale() {
+    let mut db = LoggerDatabase::default();
+    let store = register(&mut db, Durability::LOW);
+    let setting = Setting::new(&db, "a".to_string());
+    store.lock().unwrap().insert(setting, "a".to_string());
+
+    assert_eq!(setting_len(&db, setting), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "setting_len",
+        ]"#]]);
+
+    // The store changed, but salsa only finds out in a new revision.
+    store.lock().unwrap().insert(setting, "abc".to_string());
+    assert_eq!(setting_len(&db, setting), 1);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(setting_len(&db, setting), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "setting_len",
+        ]"#]]);
+
+    // Unchanged values do not invalidate readers.
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(setting_len(&db, setting), 3);
+    db.assert_logs(expect!["[]"]);
+
+    // Set values are kept until the next revision.
+    setting.set_value(&mut db).to("ab".to_string());
+    assert_eq!(setting_len(&db, setting), 2);
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(setting_len(&db, setting), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "setting_len",
+            "setting_len",
+        ]"#]]);
+}
+
+#[test]
+fn provider_decides_durability() {
+    let mut db = LoggerDatabase::default();
+    let store = register(&mut db, Durability::HIGH);
+    let setting = Setting::new(&db, "a".to_string());
+    store.lock().unwrap().insert(setting, "ab".to_string());
+
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(setting_len(&db, setting), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "setting_len",
+        ]"#]]);
+
+    // Now high durability: low-durability writes do not consult the provider.
+    store.lock().unwrap().insert(setting, "abc".to_string());
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(setting_len(&db, setting), 2);
+    db.synthetic_write(Durability::HIGH);
+    assert_eq!(setting_len(&db, setting), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "setting_len",
+        ]"#]]);
+}
@@ -0,0 +1,69 @@
+//! Test interned structs with fields borrowed from the database,
+//! whose data is copied into an arena when a new value is interned.
+
+use salsa::{plumbing::AsId, Database, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct SourceFile {
+    #[return_ref]
+    text: String,
+}
+
+#[salsa::interned]
+struct Word<'db> {
+    text: &'db str,
+    counts: &'db [u32],
+}
+
+#[salsa::tracked]
+fn first_word<'db>(db: &'db dyn Database, file: SourceFile) -> Word<'db> {
+    let text = file.text(db).split(' ').next().unwrap();
+    Word::new(db, text, &[text.len() as u32][..])
+}
+
+#[test]
+fn lookup_with_any_borrow() {
+    let db = DatabaseImpl::new();
+    let owned = String::from("hello");
+    let word = Word::new(&db, owned.as_str(), &[1, 2][..]);
+    drop(owned);
+
+    assert_eq!(word.text(&db), "hello");
+    assert_eq!(word.counts(&db), &[1, 2]);
+    assert_eq!(Word::new(&db, "hello", &[1, 2][..]), word);
+    assert_ne!(Word::new(&db, "hello", &[][..]), word);
+    assert_eq!(Word::new(&db, "", &[][..]).text(&db), "");
+}
+
+#[test]
+fn outlives_the_input() {
+    let mut db = DatabaseImpl::new();
+    let file = SourceFile::new(&db, "hello world".to_string());
+    let word = first_word(&db, file).as_id();
+
+    // The text the word was interned from is dropped, but the word remains.
+    file.set_text(&mut db).to("goodbye world".to_string());
+    assert_eq!(first_word(&db, file).text(&db), "goodbye");
+    assert_eq!(first_word(&db, file).counts(&db), &[7]);
+
+    let other = SourceFile::new(&db, "hello there".to_string());
+    assert_eq!(first_word(&db, other).as_id(), word);
+    assert_eq!(first_word(&db, other).text(&db), "hello");
+}
+
+#[test]
+fn debug() {
+    let db = DatabaseImpl::new();
+    let word = Word::new(&db, "hello", &[5][..]);
+    db.attach(|_| {
+        expect_test::expect![[r#"
+            Word {
+                text: "hello",
+                counts: [
+                    5,
+                ],
+            }
+        "#]]
+        .assert_debug_eq(&word);
+    });
+}
@@ -0,0 +1,46 @@
+//! Test the `data` accessor generated for `#[salsa::interned(data = ...)]`.
+
+use salsa::{Database, DatabaseImpl};
+
+#[salsa::interned(data = SignatureData)]
+struct Signature<'db> {
+    name: String,
+    params: Vec<String>,
+    inner: Option<Signature<'db>>,
+}
+
+#[salsa::interned(no_lifetime, data = PointData)]
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+#[salsa::tracked]
+fn arity(db: &dyn Database, point: Point) -> u32 {
+    let sig = Signature::new(db, "f", vec!["a".to_string(); point.x(db) as usize], None);
+    let SignatureData { params, inner, .. } = sig.data(db);
+    assert!(inner.is_none());
+    params.len() as u32
+}
+
+#[test]
+fn data() {
+    let db = DatabaseImpl::new();
+    let inner = Signature::new(&db, "g", vec![], None);
+    let sig = Signature::new(&db, "f", vec!["a".to_string()], Some(inner));
+
+    let SignatureData {
+        name,
+        params,
+        inner: data_inner,
+    } = sig.data(&db);
+    assert_eq!(name, "f");
+    assert_eq!(params, &["a"]);
+    assert_eq!(*data_inner, Some(inner));
+    assert_eq!(data_inner.unwrap().data(&db).name, "g");
+
+    let point = Point::new(&db, 3, 4);
+    let PointData { x, y } = point.data(&db);
+    assert_eq!((*x, *y), (3, 4));
+    assert_eq!(arity(&db, point), 3);
+}
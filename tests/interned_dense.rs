@@ -0,0 +1,55 @@
+//! Test `#[salsa::interned(dense)]`, whose values are numbered
+//! contiguously from 0 per type.
+
+use salsa::Database;
+
+#[salsa::interned(dense)]
+struct Symbol<'db> {
+    name: String,
+}
+
+#[salsa::interned(dense)]
+struct Path<'db> {
+    segments: Vec<String>,
+}
+
+#[salsa::tracked]
+fn symbol_count(db: &dyn Database) -> u32 {
+    Symbol::count(db)
+}
+
+#[test]
+fn indices_are_dense_per_type() {
+    let db = salsa::DatabaseImpl::new();
+    assert_eq!(Symbol::count(&db), 0);
+
+    let a = Symbol::new(&db, "a".to_string());
+    let p = Path::new(&db, vec!["a".to_string()]);
+    let b = Symbol::new(&db, "b".to_string());
+    let a2 = Symbol::new(&db, "a".to_string());
+
+    assert_eq!(a.index(&db), 0);
+    assert_eq!(b.index(&db), 1);
+    assert_eq!(a2.index(&db), 0);
+    assert_eq!(p.index(&db), 0);
+    assert_eq!(Symbol::count(&db), 2);
+    assert_eq!(Path::count(&db), 1);
+
+    // Side tables can be indexed by the dense index.
+    let mut lengths = vec![0; Symbol::count(&db) as usize];
+    for symbol in [a, b] {
+        lengths[symbol.index(&db) as usize] = symbol.name(&db).len();
+    }
+    assert_eq!(lengths, [1, 1]);
+}
+
+#[test]
+fn count_in_query_is_untracked() {
+    let mut db = salsa::DatabaseImpl::new();
+    Symbol::new(&db, "a".to_string());
+    assert_eq!(symbol_count(&db), 1);
+
+    Symbol::new(&db, "b".to_string());
+    db.synthetic_write(salsa::Durability::LOW);
+    assert_eq!(symbol_count(&db), 2);
+}
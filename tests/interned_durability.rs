@@ -0,0 +1,93 @@
+//! Test the durability of interned values, which queries interning
+//! an existing value report as a dependency.
+
+use salsa::{Backtrace, Database, DatabaseImpl, Durability};
+
+#[salsa::input]
+struct MyInput {
+    text: String,
+}
+
+#[salsa::interned]
+struct Name<'db> {
+    text: String,
+}
+
+/// Interns the text of `input` and returns the durability of the query
+/// as of that point.
+#[salsa::tracked]
+fn intern_durability(db: &dyn Database, input: MyInput) -> Durability {
+    Name::new(db, input.text(db));
+    Backtrace::capture().unwrap().frames()[0].durability()
+}
+
+#[salsa::tracked]
+fn intern_text(db: &dyn Database, input: MyInput) {
+    Name::new(db, input.text(db));
+}
+
+fn high_input(db: &dyn Database, text: &str) -> MyInput {
+    MyInput::builder(text.to_string())
+        .durability(Durability::HIGH)
+        .new(db)
+}
+
+#[test]
+fn outside_queries_default_to_high() {
+    let db = DatabaseImpl::new();
+    Name::new(&db, "a".to_string());
+    assert_eq!(
+        intern_durability(&db, high_input(&db, "a")),
+        Durability::HIGH
+    );
+}
+
+#[test]
+fn default_durability() {
+    let db = DatabaseImpl::new();
+    db.set_default_intern_durability(Durability::LOW);
+    Name::new(&db, "a".to_string());
+    assert_eq!(
+        intern_durability(&db, high_input(&db, "a")),
+        Durability::LOW
+    );
+
+    // Values interned by the query itself are not affected.
+    assert_eq!(
+        intern_durability(&db, high_input(&db, "b")),
+        Durability::HIGH
+    );
+}
+
+#[test]
+fn explicit_durability() {
+    let db = DatabaseImpl::new();
+    db.set_default_intern_durability(Durability::LOW);
+    let a = Name::new_with_durability(&db, "a".to_string(), Durability::MEDIUM);
+    assert_eq!(a, Name::new(&db, "a".to_string()));
+    assert_eq!(
+        intern_durability(&db, high_input(&db, "a")),
+        Durability::MEDIUM
+    );
+}
+
+#[test]
+fn inferred_from_query_inputs() {
+    let db = DatabaseImpl::new();
+    intern_text(&db, MyInput::new(&db, "a".to_string()));
+    assert_eq!(
+        intern_durability(&db, high_input(&db, "a")),
+        Durability::LOW
+    );
+}
+
+#[test]
+fn decided_when_first_interned() {
+    let db = DatabaseImpl::new();
+    Name::new_with_durability(&db, "a".to_string(), Durability::MEDIUM);
+    Name::new_with_durability(&db, "a".to_string(), Durability::LOW);
+    assert_eq!(
+        intern_durability(&db, high_input(&db, "a")),
+        Durability::MEDIUM
+    );
+}
@@ -0,0 +1,72 @@
+//! Test looking up interned values without interning them.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{plumbing::AsId, Database, Durability};
+use test_log::test;
+
+#[salsa::input]
+struct Source {
+    name: String,
+}
+
+#[salsa::interned]
+struct Symbol<'db> {
+    name: String,
+}
+
+#[salsa::tracked]
+fn is_known<'db>(db: &'db dyn LogDatabase, source: Source) -> Option<Symbol<'db>> {
+    db.push_log(format!("is_known({})", source.name(db)));
+    Symbol::get(db, source.name(db))
+}
+
+#[test]
+fn get_does_not_intern() {
+    let db = LoggerDatabase::default();
+    assert_eq!(Symbol::get(&db, "a"), None);
+    assert_eq!(Symbol::get(&db, "a"), None);
+
+    let a = Symbol::new(&db, "a");
+    assert_eq!(Symbol::get(&db, "a"), Some(a));
+    assert_eq!(Symbol::get(&db, "b"), None);
+}
+
+#[test]
+fn hit_is_tracked() {
+    let mut db = LoggerDatabase::default();
+    let symbol = Symbol::new(&db, "a").as_id();
+    let source = Source::new(&db, "a".to_string());
+    assert_eq!(is_known(&db, source).map(|s| s.as_id()), Some(symbol));
+    db.assert_logs(expect![[r#"
+        [
+            "is_known(a)",
+        ]"#]]);
+
+    // A hit only depends on the value, so the query is reused.
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(is_known(&db, source).map(|s| s.as_id()), Some(symbol));
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn miss_is_untracked() {
+    let mut db = LoggerDatabase::default();
+    let source = Source::new(&db, "a".to_string());
+    assert_eq!(is_known(&db, source), None);
+    db.assert_logs(expect![[r#"
+        [
+            "is_known(a)",
+        ]"#]]);
+
+    // The value may be interned later, so a miss is checked again in every revision.
+    let symbol = Symbol::new(&db, "a").as_id();
+    db.synthetic_write(Durability::LOW);
+    assert_eq!(is_known(&db, source).map(|s| s.as_id()), Some(symbol));
+    db.assert_logs(expect![[r#"
+        [
+            "is_known(a)",
+        ]"#]]);
+}
@@ -0,0 +1,74 @@
+//! Test forcing the queries that read an interned struct to be re-validated
+//! with `Database::invalidate_ingredient`.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{Database, Durability};
+
+#[salsa::input]
+struct MyInput {
+    text: String,
+}
+
+#[salsa::interned]
+struct Name<'db> {
+    text: String,
+}
+
+#[salsa::interned]
+struct Other<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn name_len(db: &dyn LogDatabase, input: MyInput) -> usize {
+    db.push_log("name_len".to_string());
+    Name::new(db, input.text(db)).text(db).len()
+}
+
+#[salsa::tracked]
+fn text_len(db: &dyn LogDatabase, input: MyInput) -> usize {
+    db.push_log("text_len".to_string());
+    input.text(db).len()
+}
+
+#[test]
+fn invalidates_readers_only() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::builder("hello".to_string())
+        .text_durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(name_len(&db, input), 5);
+    assert_eq!(text_len(&db, input), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "name_len",
+            "text_len",
+        ]"#]]);
+
+    db.invalidate_ingredient::<Name<'_>>();
+    assert_eq!(name_len(&db, input), 5);
+    assert_eq!(text_len(&db, input), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "name_len",
+        ]"#]]);
+
+    // Structs without values, or that no query read, do not affect anything.
+    db.invalidate_ingredient::<Other<'_>>();
+    Other::new(&db, "other".to_string());
+    db.invalidate_ingredient::<Other<'_>>();
+    assert_eq!(name_len(&db, input), 5);
+    assert_eq!(text_len(&db, input), 5);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+#[should_panic(expected = "`MyInput` cannot be invalidated")]
+fn inputs_cannot_be_invalidated() {
+    let mut db = LoggerDatabase::default();
+    MyInput::new(&db, "hello".to_string());
+    db.invalidate_ingredient::<MyInput>();
+}
@@ -0,0 +1,59 @@
+//! Test detecting handles to deleted tracked structs with `salsa::is_stale`.
+
+use salsa::plumbing::{AsId, FromId};
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::interned]
+struct MyInterned<'db> {
+    field: u32,
+}
+
+/// Creates a tracked struct only if the input is even.
+#[salsa::tracked]
+fn create_if_even(db: &dyn Database, input: MyInput) -> Option<MyTracked<'_>> {
+    let field = input.field(db);
+    (field % 2 == 0).then(|| MyTracked::new(db, field))
+}
+
+#[test]
+fn deleted_tracked_struct_is_stale() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 2);
+    let tracked = create_if_even(&db, input).unwrap();
+    assert!(!salsa::is_stale(&db, tracked));
+    let id = tracked.as_id();
+
+    // The struct survives as long as the query that created it still creates it.
+    input.set_field(&mut db).to(4);
+    let tracked = create_if_even(&db, input).unwrap();
+    assert_eq!(tracked.as_id(), id);
+    assert!(!salsa::is_stale(&db, tracked));
+
+    input.set_field(&mut db).to(5);
+    assert!(create_if_even(&db, input).is_none());
+    assert!(salsa::is_stale(&db, MyTracked::from_id(id)));
+}
+
+#[test]
+fn inputs_and_interned_are_never_stale() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let interned = MyInterned::new(&db, 1);
+    assert!(!salsa::is_stale(&db, input));
+    assert!(!salsa::is_stale(&db, interned));
+    let id = interned.as_id();
+
+    input.set_field(&mut db).to(2);
+    assert!(!salsa::is_stale(&db, input));
+    assert!(!salsa::is_stale(&db, MyInterned::from_id(id)));
+}
@@ -48,6 +48,11 @@ fn get_hot_potato2(db: &dyn LogDatabase, input: MyInput) -> u32 {
     get_hot_potato(db, input).0
 }
 
+#[salsa::tracked]
+fn get_unbounded_potato(db: &dyn LogDatabase, input: MyInput) -> Arc<HotPotato> {
+    Arc::new(HotPotato::new(input.field(db)))
+}
+
 #[salsa::tracked(lru = 32)]
 fn get_volatile(db: &dyn LogDatabase, _input: MyInput) -> usize {
     static COUNTER: AtomicUsize = AtomicUsize::new(0);
@@ -95,7 +100,7 @@ fn lru_doesnt_break_volatile_queries() {
 
 #[test]
 fn lru_can_be_changed_at_runtime() {
-    let db = common::LoggerDatabase::default();
+    let mut db = common::LoggerDatabase::default();
     assert_eq!(load_n_potatoes(), 0);
 
     let inputs: Vec<(u32, MyInput)> = (0..128).map(|i| (i, MyInput::new(&db, i))).collect();
@@ -109,7 +114,7 @@ fn lru_can_be_changed_at_runtime() {
     MyInput::new(&db, 0);
     assert_eq!(load_n_potatoes(), 32);
 
-    get_hot_potato::set_lru_capacity(&db, 64);
+    get_hot_potato::set_lru_capacity(&mut db, 64);
     assert_eq!(load_n_potatoes(), 32);
     for &(i, input) in inputs.iter() {
         let p = get_hot_potato(&db, input);
@@ -121,7 +126,7 @@ fn lru_can_be_changed_at_runtime() {
     assert_eq!(load_n_potatoes(), 64);
 
     // Special case: setting capacity to zero disables LRU
-    get_hot_potato::set_lru_capacity(&db, 0);
+    get_hot_potato::set_lru_capacity(&mut db, 0);
     assert_eq!(load_n_potatoes(), 64);
     for &(i, input) in inputs.iter() {
         let p = get_hot_potato(&db, input);
@@ -136,6 +141,38 @@ fn lru_can_be_changed_at_runtime() {
     assert_eq!(load_n_potatoes(), 0);
 }
 
+#[test]
+fn shrinking_lru_evicts_immediately() {
+    let mut db = common::LoggerDatabase::default();
+    let inputs: Vec<MyInput> = (0..32).map(|i| MyInput::new(&db, i)).collect();
+    for &input in &inputs {
+        get_hot_potato(&db, input);
+    }
+    assert_eq!(load_n_potatoes(), 32);
+
+    get_hot_potato::set_lru_capacity(&mut db, 8);
+    assert_eq!(load_n_potatoes(), 8);
+
+    // The most recently used values are kept.
+    db.assert_logs_len(32);
+    assert_eq!(get_hot_potato(&db, inputs[31]).0, 31);
+    db.assert_logs_len(0);
+    assert_eq!(get_hot_potato(&db, inputs[0]).0, 0);
+    db.assert_logs_len(1);
+}
+
+#[test]
+fn lru_can_be_enabled_at_runtime() {
+    let mut db = common::LoggerDatabase::default();
+    let inputs: Vec<MyInput> = (0..32).map(|i| MyInput::new(&db, i)).collect();
+
+    get_unbounded_potato::set_lru_capacity(&mut db, 4);
+    for &input in &inputs {
+        get_unbounded_potato(&db, input);
+    }
+    assert_eq!(load_n_potatoes(), 4);
+}
+
 #[test]
 fn lru_keeps_dependency_info() {
     let mut db = common::LoggerDatabase::default();
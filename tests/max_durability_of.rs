@@ -0,0 +1,93 @@
+//! Test explaining the durability of a memoized value with `Database::max_durability_of`.
+
+use salsa::{Database, Durability, DurabilityExplanation, Setter};
+
+#[salsa::input]
+struct MyInput {
+    config: u32,
+    source: u32,
+}
+
+#[salsa::tracked]
+fn read_config(db: &dyn Database, input: MyInput) -> u32 {
+    input.config(db)
+}
+
+#[salsa::tracked]
+fn read_source(db: &dyn Database, input: MyInput) -> u32 {
+    input.source(db)
+}
+
+#[salsa::tracked]
+fn total(db: &dyn Database, input: MyInput) -> u32 {
+    read_config(db, input) + read_source(db, input)
+}
+
+#[salsa::tracked]
+fn untracked(db: &dyn Database, input: MyInput) -> u32 {
+    db.report_untracked_read();
+    input.config(db)
+}
+
+fn path(db: &dyn Database, explanation: &DurabilityExplanation) -> Vec<String> {
+    explanation
+        .path
+        .iter()
+        .map(|key| format!("{:?}", key.debug(db)))
+        .collect()
+}
+
+#[test]
+fn explains_lowest_durability() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::builder(1, 2)
+        .config_durability(Durability::HIGH)
+        .source_durability(Durability::MEDIUM)
+        .new(&db);
+    assert_eq!(total(&db, input), 3);
+
+    let key = total::database_key_index(&db, input).unwrap();
+    let explanation = db.max_durability_of(key).unwrap();
+    assert_eq!(explanation.durability, Durability::MEDIUM);
+    assert_eq!(
+        path(&db, &explanation),
+        ["read_source(Id(0))", "source(Id(0))"]
+    );
+
+    // Once `source` is made high-durability, the whole query is.
+    input
+        .set_source(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(3);
+    assert_eq!(total(&db, input), 4);
+    let explanation = db.max_durability_of(key).unwrap();
+    assert_eq!(explanation.durability, Durability::HIGH);
+    assert_eq!(
+        path(&db, &explanation),
+        ["read_config(Id(0))", "config(Id(0))"]
+    );
+}
+
+#[test]
+fn untracked_reads_have_no_path() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::builder(1, 2)
+        .config_durability(Durability::HIGH)
+        .new(&db);
+    assert_eq!(untracked(&db, input), 1);
+
+    let explanation = db
+        .max_durability_of(untracked::database_key_index(&db, input).unwrap())
+        .unwrap();
+    assert_eq!(explanation.durability, Durability::LOW);
+    assert!(explanation.path.is_empty());
+}
+
+#[test]
+fn not_executed() {
+    let db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 1, 2);
+    assert!(db
+        .max_durability_of(total::database_key_index(&db, input).unwrap())
+        .is_none());
+}
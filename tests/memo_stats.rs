@@ -0,0 +1,155 @@
+//! Test collecting memo statistics and using them as a layout hint for a new database.
+
+use expect_test::expect;
+use salsa::{Database, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn rare(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) + 1
+}
+
+#[salsa::tracked]
+fn hot(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn unused(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db)
+}
+
+/// Calls `rare` for the first input and `hot` for all of them,
+/// so that `rare` is used first but has fewer memos.
+fn run(db: &mut DatabaseImpl) -> Vec<u32> {
+    let inputs: Vec<MyInput> = (0..3).map(|field| MyInput::new(db, field)).collect();
+    let mut results = vec![rare(db, inputs[0])];
+    results.extend(inputs.iter().map(|&input| hot(db, input)));
+
+    inputs[1].set_field(db).to(10);
+    results.extend(inputs.iter().map(|&input| hot(db, input)));
+    results
+}
+
+#[test]
+fn collect() {
+    let mut db = DatabaseImpl::new();
+    run(&mut db);
+    let stats = db.collect_memo_stats();
+    expect![[r#"
+        MemoStats {
+            structs: [
+                StructMemoStats {
+                    name: "MyInput",
+                    functions: [
+                        FunctionMemoStats {
+                            name: "hot",
+                            memos: 3,
+                        },
+                        FunctionMemoStats {
+                            name: "rare",
+                            memos: 1,
+                        },
+                    ],
+                },
+            ],
+        }
+    "#]]
+    .assert_debug_eq(&stats);
+}
+
+#[test]
+fn layout_hint() {
+    let mut db = DatabaseImpl::new();
+    let expected = run(&mut db);
+    let stats = db.collect_memo_stats();
+
+    // The hint changes where memos are stored, not the results.
+    let mut db = DatabaseImpl::builder()
+        .memo_layout_hint(stats.clone())
+        .build();
+    assert_eq!(run(&mut db), expected);
+    assert_eq!(db.collect_memo_stats(), stats);
+
+    // Functions missing from the hint are stored after the hinted ones.
+    assert_eq!(unused(&db, MyInput::new(&db, 7)), 7);
+    assert_eq!(
+        db.collect_memo_stats().structs[0]
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.memos))
+            .collect::<Vec<_>>(),
+        [("hot", 3), ("rare", 1), ("unused", 1)],
+    );
+}
+
+#[test]
+fn stale_hint() {
+    let mut db = DatabaseImpl::new();
+    let expected = run(&mut db);
+    let mut stats = db.collect_memo_stats();
+
+    // Entries for items that no longer exist are ignored.
+    stats.structs[0].functions[0].name = "renamed".to_string();
+    stats.structs[0].name.push_str("Renamed");
+    stats.structs.push(stats.structs[0].clone());
+    stats.structs[1].name = "MyInput".to_string();
+
+    let mut db = DatabaseImpl::builder().memo_layout_hint(stats).build();
+    assert_eq!(run(&mut db), expected);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let mut db = DatabaseImpl::new();
+    run(&mut db);
+    let stats = db.collect_memo_stats();
+
+    let json = serde_json::to_string(&stats).unwrap();
+    expect![[r#"{"structs":[{"name":"MyInput","functions":[{"name":"hot","memos":3},{"name":"rare","memos":1}]}]}"#]]
+        .assert_eq(&json);
+    assert_eq!(
+        serde_json::from_str::<salsa::memo_stats::MemoStats>(&json).unwrap(),
+        stats
+    );
+
+    // Fields added by later versions are ignored, and missing ones take their defaults.
+    let json = r#"{"structs":[{"name":"MyInput","functions":[{"name":"hot"}],"extra":1}]}"#;
+    let stats: salsa::memo_stats::MemoStats = serde_json::from_str(json).unwrap();
+    assert_eq!(stats.structs[0].functions[0].memos, 0);
+}
+
+#[salsa::input]
+struct Count {
+    n: u32,
+}
+
+#[salsa::tracked]
+struct Item<'db> {
+    index: u32,
+}
+
+#[salsa::tracked]
+fn create_items(db: &dyn Database, count: Count) -> u32 {
+    (0..count.n(db))
+        .map(|index| Item::new(db, index).index(db))
+        .sum()
+}
+
+/// Collecting the statistics must not lock tracked structs,
+/// or the query that created them could not delete them afterwards.
+#[test]
+fn does_not_lock_tracked_structs() {
+    let mut db = DatabaseImpl::new();
+    let count = Count::new(&db, 3);
+    assert_eq!(create_items(&db, count), 3);
+
+    count.set_n(&mut db).to(1);
+    db.collect_memo_stats();
+    assert_eq!(create_items(&db, count), 0);
+}
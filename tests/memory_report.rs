@@ -0,0 +1,99 @@
+//! Test that `Database::memory_report` counts the values of salsa structs
+//! and the memos of tracked functions.
+
+use salsa::{Database, DatabaseImpl, IngredientMemoryUsage, MemoryReport, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::interned]
+struct MyInterned<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn text_len<'db>(db: &'db dyn Database, interned: MyInterned<'db>) -> usize {
+    interned.text(db).len()
+}
+
+fn usage<'r>(report: &'r MemoryReport, name: &str) -> &'r IngredientMemoryUsage {
+    report
+        .ingredients
+        .iter()
+        .find(|usage| usage.debug_name == name)
+        .unwrap()
+}
+
+#[test]
+fn execute() {
+    let db = DatabaseImpl::new();
+    let inputs = (0..3).map(|i| MyInput::new(&db, i)).collect::<Vec<_>>();
+    let interned = MyInterned::new(&db, "hello".to_string());
+
+    for &input in &inputs[..2] {
+        double(&db, input);
+    }
+    text_len(&db, interned);
+
+    let report = db.memory_report();
+
+    let inputs = usage(&report, "MyInput");
+    assert_eq!(inputs.slots, 3);
+    assert!(inputs.slot_bytes > 0);
+    assert_eq!(inputs.memos, 0);
+
+    let interned = usage(&report, "MyInterned");
+    assert_eq!(interned.slots, 1);
+
+    let double = usage(&report, "double");
+    assert_eq!((double.slots, double.memos), (0, 2));
+    assert!(double.memo_bytes > 0);
+
+    assert_eq!(usage(&report, "text_len").memos, 1);
+
+    assert_eq!(
+        report.total_bytes(),
+        report
+            .ingredients
+            .iter()
+            .map(|usage| usage.slot_bytes + usage.memo_bytes)
+            .sum::<usize>()
+    );
+}
+
+#[salsa::input]
+struct Count {
+    n: u32,
+}
+
+#[salsa::tracked]
+struct Item<'db> {
+    index: u32,
+}
+
+#[salsa::tracked]
+fn create_items(db: &dyn Database, count: Count) -> u32 {
+    (0..count.n(db))
+        .map(|index| Item::new(db, index).index(db))
+        .sum()
+}
+
+/// Walking the memos of tracked structs must not lock them,
+/// or the query that created them could not delete them afterwards.
+#[test]
+fn does_not_lock_tracked_structs() {
+    let mut db = DatabaseImpl::new();
+    let count = Count::new(&db, 3);
+    assert_eq!(create_items(&db, count), 3);
+
+    count.set_n(&mut db).to(1);
+    db.memory_report();
+    assert_eq!(create_items(&db, count), 0);
+}
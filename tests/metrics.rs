@@ -0,0 +1,58 @@
+//! Test that, with the `metrics` feature, cache statistics are gathered
+//! and formatted for Prometheus.
+#![cfg(feature = "metrics")]
+
+use expect_test::expect;
+use salsa::{
+    metrics::{encode_text, gather_metrics, MetricFamily},
+    Database, DatabaseImpl, Setter,
+};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(lru = 1)]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+fn family<'a>(families: &'a [MetricFamily], name: &str) -> &'a MetricFamily {
+    families.iter().find(|family| family.name == name).unwrap()
+}
+
+#[test]
+fn counts_hits_misses_and_evictions() {
+    let mut db = DatabaseImpl::new();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    double(&db, a);
+    double(&db, a);
+    double(&db, b);
+
+    a.set_field(&mut db).to(3);
+    double(&db, b);
+
+    let families = gather_metrics(&db);
+    let value = |name| family(&families, name).samples[0].value;
+    assert_eq!(value("salsa_cache_hits_total"), 2.0);
+    assert_eq!(value("salsa_cache_misses_total"), 2.0);
+    assert_eq!(value("salsa_cache_evictions_total"), 1.0);
+    assert_eq!(value("salsa_memos"), 2.0);
+}
+
+#[test]
+fn encodes_text_format() {
+    let db = DatabaseImpl::new();
+    double(&db, MyInput::new(&db, 1));
+
+    let families = gather_metrics(&db);
+    let hits = family(&families, "salsa_cache_hits_total").clone();
+    expect![[r##"
+        # HELP salsa_cache_hits_total The number of calls that returned a memoized value.
+        # TYPE salsa_cache_hits_total counter
+        salsa_cache_hits_total{ingredient="double",index="2"} 0
+    "##]]
+    .assert_eq(&encode_text(&[hits]));
+}
@@ -1,9 +1,21 @@
 mod setup;
 
 mod parallel_cancellation;
+mod parallel_cancellation_blocked;
+mod parallel_cooperative_cancellation;
+mod parallel_cycle_accumulate;
 mod parallel_cycle_all_recover;
 mod parallel_cycle_mid_recover;
 mod parallel_cycle_none_recover;
 mod parallel_cycle_one_recover;
+mod parallel_deadlock_watchdog;
+mod parallel_deterministic;
+mod parallel_entries;
+mod parallel_event_ordering;
+mod parallel_get_or_create;
 mod parallel_map;
+mod parallel_pinned_revision;
+mod parallel_thread_stats;
+mod parallel_timeout;
+mod parallel_write_scope;
 mod signal;
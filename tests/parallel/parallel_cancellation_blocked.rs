@@ -0,0 +1,65 @@
+//! Test that a thread blocked on a query executing in another thread
+//! is cancelled as soon as a write is pending, without waiting for the query.
+
+use salsa::{Cancelled, Setter};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn slow(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(3);
+    input.field(db)
+}
+
+// Thread A                   Thread B                   Thread W
+// --------                   --------                   --------
+// slow: signal stage 1       wait for stage 1
+// wait for stage 3 (blocks)  slow: blocks on A,
+//                            signals stage 2            set input, triggers
+//                                                       cancellation
+//                            cancelled
+// (unblocked by main thread after B was cancelled)
+#[test]
+fn execute() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+    let signal = db.signal.clone();
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || slow(&db, input)
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        db.signal_on_will_block.store(2);
+        move || {
+            db.wait_for(1);
+            slow(&db, input)
+        }
+    });
+
+    signal.wait_for(2);
+    let thread_w = std::thread::spawn(move || {
+        input.set_field(&mut db).to(2);
+        slow(&db, input)
+    });
+
+    // B is cancelled while A is still executing `slow`.
+    let cancelled = thread_b
+        .join()
+        .unwrap_err()
+        .downcast::<Cancelled>()
+        .unwrap();
+    assert!(matches!(*cancelled, Cancelled::PendingWrite { .. }));
+
+    signal.signal(3);
+    assert_eq!(thread_a.join().unwrap(), 1);
+    assert_eq!(thread_w.join().unwrap(), 2);
+}
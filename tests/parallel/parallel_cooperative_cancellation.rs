@@ -0,0 +1,64 @@
+//! Test that in `CancellationMode::Cooperative` a pending write
+//! does not unwind running queries, and `try_call` reports it instead.
+
+use salsa::CancellationMode;
+use salsa::Cancelled;
+use salsa::Setter;
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+/// Returns whether a write was pending once it got going, and the input's value.
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> (bool, i32) {
+    db.signal(1);
+    db.wait_for(2);
+    (db.check_cancelled().is_err(), field(db, input))
+}
+
+#[salsa::tracked]
+fn field(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    input.field(db)
+}
+
+// Thread A                   Thread B
+// --------                   --------
+// a1
+// |                          wait for stage 1
+// signal stage 1             set input, triggers cancellation
+// wait for stage 2 (blocks)  triggering cancellation sends stage 2
+// |                          (blocks until thread A drops its handle)
+// (unblocked)
+// field runs to completion
+// try_call returns Err
+
+#[test]
+fn execute() {
+    let mut db = Knobs::with_cancellation_mode(CancellationMode::Cooperative);
+
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            let first = a1::try_call(&db, input);
+            let second = a1::try_call(&db, input);
+            (first, second)
+        }
+    });
+
+    db.wait_for(1);
+    db.signal_on_did_cancel.store(2);
+    input.set_field(&mut db).to(2);
+
+    let (first, second) = thread_a.join().unwrap();
+    assert!(matches!(first, Ok((true, 1))));
+    assert!(matches!(second, Err(Cancelled::PendingWrite { .. })));
+
+    assert_eq!(a1(&db, input), (false, 2));
+}
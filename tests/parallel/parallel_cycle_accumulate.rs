@@ -0,0 +1,94 @@
+//! Test that values accumulated by the participants of a cycle spread
+//! across two threads are collected exactly once after recovery.
+//! See `../accumulate_cycles.rs` for the single-threaded cases.
+
+use salsa::Accumulator;
+
+use crate::setup::Knobs;
+use crate::setup::KnobsDatabase;
+
+#[salsa::accumulator]
+pub(crate) struct Log(String);
+
+#[salsa::input]
+pub(crate) struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(recovery_fn = recover_a1)]
+pub(crate) fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    Log("a1 before".to_string()).accumulate(db);
+
+    // Wait to create the cycle until both threads have entered
+    db.signal(1);
+    db.wait_for(2);
+
+    a2(db, input)
+}
+
+fn recover_a1(db: &dyn KnobsDatabase, _cycle: &salsa::Cycle, key: MyInput) -> i32 {
+    Log("a1 recovered".to_string()).accumulate(db);
+    key.field(db) * 10 + 1
+}
+
+#[salsa::tracked]
+pub(crate) fn a2(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    Log("a2 before".to_string()).accumulate(db);
+    b1(db, input)
+}
+
+#[salsa::tracked(recovery_fn = recover_b1)]
+pub(crate) fn b1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    Log("b1 before".to_string()).accumulate(db);
+
+    // Wait to create the cycle until both threads have entered
+    db.wait_for(1);
+    db.signal(2);
+
+    // Wait for thread A to block on this thread
+    db.wait_for(3);
+    b2(db, input)
+}
+
+fn recover_b1(db: &dyn KnobsDatabase, _cycle: &salsa::Cycle, key: MyInput) -> i32 {
+    Log("b1 recovered".to_string()).accumulate(db);
+    key.field(db) * 20 + 1
+}
+
+#[salsa::tracked]
+pub(crate) fn b2(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    Log("b2 before".to_string()).accumulate(db);
+    a1(db, input)
+}
+
+fn logs(accumulated: Vec<Log>) -> Vec<String> {
+    let mut logs: Vec<_> = accumulated.into_iter().map(|log| log.0).collect();
+    logs.sort();
+    logs
+}
+
+// The threads interleave as in `parallel_cycle_all_recover`; `a2` and `b2` do not recover
+// and are unwound, so only the values accumulated by the recovery functions remain.
+#[test]
+fn execute() {
+    let db = Knobs::default();
+
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        db.knobs().signal_on_will_block.store(3);
+        move || a1(&db, input)
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        move || b1(&db, input)
+    });
+
+    assert_eq!(thread_a.join().unwrap(), 11);
+    assert_eq!(thread_b.join().unwrap(), 21);
+
+    assert_eq!(logs(a1::accumulated::<Log>(&db, input)), ["a1 recovered"]);
+    assert_eq!(logs(b1::accumulated::<Log>(&db, input)), ["b1 recovered"]);
+}
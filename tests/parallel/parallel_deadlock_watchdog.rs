@@ -0,0 +1,111 @@
+//! Test that waits on another thread exceeding the `deadlock_timeout` are reported,
+//! and abandoned with `panic_on_deadlock`.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use salsa::plumbing::HasStorage;
+use salsa::{Cancelled, DeadlockReport, EventFilter, EventKind};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn a1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn b1(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    a1(db, input) + 1
+}
+
+fn storage(panic_on_deadlock: bool) -> (salsa::Storage<Knobs>, Arc<Mutex<Vec<DeadlockReport>>>) {
+    let reports: Arc<Mutex<Vec<DeadlockReport>>> = Default::default();
+    let mut builder = salsa::Storage::builder().deadlock_timeout(Duration::from_millis(50));
+    if panic_on_deadlock {
+        builder = builder.panic_on_deadlock();
+    }
+    let storage = builder.build();
+    storage.subscribe(EventFilter::DID_DETECT_DEADLOCK, {
+        let reports = reports.clone();
+        move |event| match &event.kind {
+            EventKind::DidDetectDeadlock { report } => reports.lock().unwrap().push(report.clone()),
+            _ => panic!("unexpected event {event:?}"),
+        }
+    });
+    (storage, reports)
+}
+
+// Thread A                   Thread B
+// --------                   --------
+// a1
+// |                          wait for stage 1
+// signal stage 1             b1
+// wait for stage 2 (blocks)  a1 (blocks on A)
+// |                          times out, unwinds
+// |                          signal stage 2
+// (unblocked)
+#[test]
+fn panics_on_deadlock() {
+    let (storage, reports) = storage(true);
+    let db = Knobs::with_storage(storage);
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || (std::thread::current().id(), a1(&db, input))
+    });
+
+    db.wait_for(1);
+    let result = Cancelled::catch(AssertUnwindSafe(|| b1(&db, input)));
+    assert!(matches!(result, Err(Cancelled::DeadlockDetected { .. })));
+    db.signal(2);
+
+    let (thread_a_id, value) = thread_a.join().unwrap();
+    assert_eq!(value, 1);
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert!(report.waited() >= Duration::from_millis(50));
+    assert_eq!(report.running_thread(), thread_a_id);
+    let [wait] = report.waits() else {
+        panic!("expected one blocked thread, got {report}");
+    };
+    assert_eq!(wait.thread_id(), std::thread::current().id());
+    assert!(format!("{:?}", wait.blocked_on().debug(&db)).starts_with("a1("));
+    let frames = wait.backtrace().frames();
+    assert_eq!(frames.len(), 1);
+    assert!(format!("{:?}", frames[0].database_key_index().debug(&db)).starts_with("b1("));
+}
+
+// Like `panics_on_deadlock`, but thread B keeps waiting after the report,
+// which signals stage 2.
+#[test]
+fn reports_deadlock() {
+    let (storage, reports) = storage(false);
+    let db = Knobs::with_storage(storage);
+    let input = MyInput::new(&db, 1);
+    db.storage().subscribe(EventFilter::DID_DETECT_DEADLOCK, {
+        let signal = db.signal.clone();
+        move |_| signal.signal(2)
+    });
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || a1(&db, input)
+    });
+
+    db.wait_for(1);
+    assert_eq!(b1(&db, input), 2);
+    assert_eq!(thread_a.join().unwrap(), 1);
+    assert_eq!(reports.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,48 @@
+// test that `par_map` runs in order on the calling thread in deterministic mode.
+
+use std::sync::Mutex;
+use std::thread::ThreadId;
+
+use salsa::Database;
+
+#[salsa::input]
+struct ParallelInput {
+    field: Vec<u32>,
+}
+
+static CALLS: Mutex<Vec<(ThreadId, u32)>> = Mutex::new(Vec::new());
+
+#[salsa::tracked]
+fn tracked_fn(db: &dyn salsa::Database, input: ParallelInput) -> Vec<u32> {
+    salsa::par_map(db, input.field(db), |_db, field| {
+        CALLS
+            .lock()
+            .unwrap()
+            .push((std::thread::current().id(), field));
+        field + 1
+    })
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn par_map_is_sequential() {
+    let db = salsa::DatabaseImpl::new();
+    db.set_deterministic(true);
+
+    let counts = (1..=100).collect::<Vec<u32>>();
+    let input = ParallelInput::new(&db, counts.clone());
+
+    let result = tracked_fn(&db, input);
+    assert_eq!(result, (2..=101).collect::<Vec<u32>>());
+
+    let calls = std::mem::take(&mut *CALLS.lock().unwrap());
+    let this_thread = std::thread::current().id();
+    assert!(calls.iter().all(|&(thread, _)| thread == this_thread));
+    assert_eq!(
+        calls
+            .into_iter()
+            .map(|(_, field)| field)
+            .collect::<Vec<_>>(),
+        counts
+    );
+}
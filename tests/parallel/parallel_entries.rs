@@ -0,0 +1,101 @@
+//! Test iterating over all the values of a salsa struct in parallel.
+
+use rayon::iter::ParallelIterator;
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::input]
+struct Workspace {
+    items: u32,
+}
+
+#[salsa::tracked]
+struct Item<'db> {
+    index: u32,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn Database, workspace: Workspace) -> Vec<Item<'_>> {
+    (0..workspace.items(db))
+        .map(|index| Item::new(db, index))
+        .collect()
+}
+
+#[salsa::tracked]
+fn words(db: &dyn Database, file: File) -> usize {
+    file.text(db).split_whitespace().count()
+}
+
+/// Counts the words of every file, whether or not it is reachable from `workspace`.
+#[salsa::tracked]
+fn total_words(db: &dyn Database, workspace: Workspace) -> usize {
+    _ = workspace;
+    let counts: Vec<usize> = salsa::par_map(
+        db,
+        salsa::entries_par_chunks::<File>(db, 4),
+        |db, files: Vec<File>| files.iter().map(|&file| words(db, file)).sum(),
+    );
+    counts.into_iter().sum()
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn all_inputs() {
+    let mut db = salsa::DatabaseImpl::new();
+    let workspace = Workspace::new(&db, 0);
+    let files: Vec<File> = (0..10).map(|i| File::new(&db, "word ".repeat(i))).collect();
+    assert_eq!(total_words(&db, workspace), 45);
+
+    let chunks: Vec<Vec<File>> = salsa::entries_par_chunks::<File>(&db, 4).collect();
+    assert!(chunks
+        .iter()
+        .all(|chunk| !chunk.is_empty() && chunk.len() <= 4));
+    let mut entries: Vec<File> = chunks.into_iter().flatten().collect();
+    entries.sort_by_key(salsa::plumbing::AsId::as_id);
+    assert_eq!(entries, files);
+
+    // Enumerating the files is an untracked read, so new files are seen in the next revision.
+    File::new(&db, "one two".to_string());
+    files[0].set_text(&mut db).to("three".to_string());
+    assert_eq!(total_words(&db, workspace), 48);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn deleted_tracked_structs_are_skipped() {
+    let mut db = salsa::DatabaseImpl::new();
+    let workspace = Workspace::new(&db, 5);
+    assert_eq!(items(&db, workspace).len(), 5);
+
+    let count = |db: &salsa::DatabaseImpl| -> usize {
+        salsa::entries_par_chunks::<Item<'_>>(db, 2)
+            .map(|chunk| chunk.len())
+            .sum()
+    };
+    assert_eq!(count(&db), 5);
+
+    workspace.set_items(&mut db).to(2);
+    assert_eq!(items(&db, workspace).len(), 2);
+    assert_eq!(count(&db), 2);
+}
+
+/// Enumerating tracked structs must not lock them,
+/// or the query that created them could not delete them afterwards.
+#[test]
+#[cfg_attr(miri, ignore)]
+fn does_not_lock_tracked_structs() {
+    let mut db = salsa::DatabaseImpl::new();
+    let workspace = Workspace::new(&db, 3);
+    assert_eq!(items(&db, workspace).len(), 3);
+
+    workspace.set_items(&mut db).to(1);
+    let count: usize = salsa::entries_par_chunks::<Item<'_>>(&db, 2)
+        .map(|chunk| chunk.len())
+        .sum();
+    assert_eq!(count, 3);
+    assert_eq!(items(&db, workspace).len(), 1);
+}
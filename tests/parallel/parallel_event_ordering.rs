@@ -0,0 +1,87 @@
+//! Test that the memo of a query only becomes visible to other threads
+//! after the `WillExecute` handlers for it have returned.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use salsa::{Database, EventKind, Setter, Storage};
+
+use crate::signal::Signal;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+/// Pauses in each `WillExecute` handler until the main thread has looked at the database:
+/// the handler for the `n`th execution signals stage `2n - 1` and waits for stage `2n`.
+#[salsa::db]
+#[derive(Clone, Default)]
+struct Db {
+    storage: Storage<Self>,
+    signal: Arc<Signal>,
+    executions: Arc<AtomicUsize>,
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        if let EventKind::WillExecute { .. } = event().kind {
+            let n = self.executions.fetch_add(1, Ordering::SeqCst) + 1;
+            self.signal.signal(2 * n - 1);
+            self.signal.wait_for(2 * n);
+        }
+    }
+}
+
+/// The number of memos of `double`.
+fn memos(db: &Db) -> usize {
+    db.memory_report()
+        .ingredients
+        .iter()
+        .find(|usage| usage.debug_name == "double")
+        .map_or(0, |usage| usage.memos)
+}
+
+// Thread A                   Main thread
+// --------                   -----------
+// double
+// WillExecute handler:
+//   signal stage 1           wait for stage 1
+//   wait for stage 2         no memo yet, signal stage 2
+// execute, store memo
+//                            join A, memo is visible
+#[test]
+fn memo_visible_after_will_execute() {
+    let mut db = Db::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || double(&db, input)
+    });
+    db.signal.wait_for(1);
+    assert_eq!(memos(&db), 0);
+    db.signal.signal(2);
+    assert_eq!(thread_a.join().unwrap(), 2);
+    assert_eq!(memos(&db), 1);
+
+    // On re-execution, the old memo stays in place until the handler has returned.
+    let since = salsa::diff::current_revision(&db);
+    input.set_field(&mut db).to(2);
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || double(&db, input)
+    });
+    db.signal.wait_for(3);
+    assert_eq!(memos(&db), 1);
+    assert!(salsa::diff::changed_outputs(&db, since).is_empty());
+    db.signal.signal(4);
+    assert_eq!(thread_a.join().unwrap(), 4);
+    assert_eq!(salsa::diff::changed_outputs(&db, since).len(), 1);
+}
@@ -0,0 +1,32 @@
+//! Test that inputs created concurrently with `get_or_create` are not duplicated.
+
+#[salsa::input]
+struct File {
+    #[key]
+    path: u32,
+
+    contents: String,
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn execute() {
+    let db = salsa::DatabaseImpl::new();
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let db = db.clone();
+            std::thread::spawn(move || {
+                (0..100)
+                    .map(|path| File::get_or_create(&db, path, String::new()))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    let files: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+    for path in 0..100 {
+        let file = File::get_by_key(&db, &(path as u32)).unwrap();
+        assert!(files.iter().all(|files| files[path] == file));
+    }
+}
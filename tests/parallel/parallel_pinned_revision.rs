@@ -0,0 +1,101 @@
+//! Test that queries executed through a pinned handle observe one revision,
+//! rather than being cancelled by a pending write.
+
+use salsa::{Cancelled, Database, Setter};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn first(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn second(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    input.field(db) * 10
+}
+
+#[salsa::tracked]
+fn slow(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(3);
+    db.unwind_if_revision_cancelled();
+    input.field(db)
+}
+
+// Thread A                      Main thread
+// --------                      -----------
+// pin, first                    wait for stage 1
+// signal stage 1                set input, triggers cancellation
+// wait for stage 2 (blocks)     triggering cancellation sends stage 2
+// second, in the old revision   (blocks until A drops its handle)
+#[test]
+fn reads_observe_one_revision() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            let _pinned = db.pin_revision();
+            let first = first(&db, input);
+            db.signal(1);
+            db.wait_for(2);
+            (first, second(&db, input))
+        }
+    });
+
+    db.wait_for(1);
+    db.signal_on_did_cancel.store(2);
+    input.set_field(&mut db).to(2);
+
+    assert_eq!(thread_a.join().unwrap(), (1, 10));
+    assert_eq!(second(&db, input), 20);
+}
+
+// Thread A                      Thread B (pinned)             Main thread
+// --------                      -----------------             -----------
+// slow: signal stage 1          wait for stage 1
+// wait for stage 3 (blocks)     slow: blocks on A,
+//                               signals stage 2               wait for stage 2
+//                                                             set input, triggers
+//                                                             cancellation, stage 3
+// cancelled                     slow, executed in the old revision
+#[test]
+fn blocked_on_cancelled_query() {
+    let mut db = Knobs::default();
+    let input = MyInput::new(&db, 1);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || slow(&db, input)
+    });
+
+    let thread_b = std::thread::spawn({
+        let db = db.clone();
+        db.signal_on_will_block.store(2);
+        move || {
+            db.wait_for(1);
+            let _pinned = db.pin_revision();
+            slow(&db, input)
+        }
+    });
+
+    db.wait_for(2);
+    db.signal_on_did_cancel.store(3);
+    input.set_field(&mut db).to(2);
+
+    let cancelled = thread_a
+        .join()
+        .unwrap_err()
+        .downcast::<Cancelled>()
+        .unwrap();
+    assert!(matches!(*cancelled, Cancelled::PendingWrite { .. }));
+    assert_eq!(thread_b.join().unwrap(), 1);
+    assert_eq!(slow(&db, input), 2);
+}
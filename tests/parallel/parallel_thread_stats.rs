@@ -0,0 +1,53 @@
+//! Test that the time a handle spends blocked on other threads is recorded.
+
+use salsa::Database;
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked]
+fn query(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    input.field(db)
+}
+
+// Thread A                   Thread B
+// --------                   --------
+// query
+// |                          wait for stage 1
+// signal stage 1             query (blocks), signals stage 2
+// wait for stage 2
+// (unblocked)
+// completes                  (unblocked)
+
+#[test]
+fn execute() {
+    let db = Knobs::default();
+    let input = MyInput::new(&db, 22);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || (query(&db, input), db.thread_stats())
+    });
+
+    db.signal_on_will_block.store(2);
+    db.wait_for(1);
+    assert_eq!(query(&db, input), 22);
+
+    let (result_a, stats_a) = thread_a.join().unwrap();
+    assert_eq!(result_a, 22);
+    assert_eq!(stats_a.blocked_count, 0);
+
+    let stats = db.thread_stats();
+    assert_eq!(stats.blocked_count, 1);
+    assert!(stats.blocked >= std::time::Duration::from_millis(10));
+
+    assert_eq!(db.reset_thread_stats(), stats);
+    assert_eq!(db.thread_stats(), salsa::ThreadStats::default());
+}
@@ -0,0 +1,62 @@
+//! Test that a thread blocked on a query that another thread abandons because
+//! of a timeout executes the query itself, rather than being cancelled.
+
+use std::time::{Duration, Instant};
+
+use crate::setup::{Knobs, KnobsDatabase};
+
+#[salsa::input]
+struct MyInput {
+    field: i32,
+}
+
+#[salsa::tracked(timeout = "10ms", timeout_result = analyze_timed_out)]
+fn analyze(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    slow(db, input)
+}
+
+fn analyze_timed_out(_db: &dyn KnobsDatabase, _input: MyInput) -> i32 {
+    0
+}
+
+#[salsa::tracked]
+fn slow(db: &dyn KnobsDatabase, input: MyInput) -> i32 {
+    db.signal(1);
+    db.wait_for(2);
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_millis(100) {
+        step(db, input);
+    }
+    input.field(db)
+}
+
+#[salsa::tracked]
+fn step(_db: &dyn KnobsDatabase, _input: MyInput) {}
+
+// Thread A                   Thread B
+// --------                   --------
+// analyze
+// slow
+// |                          wait for stage 1
+// signal stage 1             slow (blocks), signals stage 2
+// wait for stage 2
+// (unblocked)
+// times out, unwinds slow    (unblocked)
+// analyze_timed_out          executes slow
+
+#[test]
+fn execute() {
+    let db = Knobs::default();
+    let input = MyInput::new(&db, 22);
+
+    let thread_a = std::thread::spawn({
+        let db = db.clone();
+        move || analyze(&db, input)
+    });
+
+    db.signal_on_will_block.store(2);
+    db.wait_for(1);
+    assert_eq!(slow(&db, input), 22);
+
+    assert_eq!(thread_a.join().unwrap(), 0);
+}
@@ -0,0 +1,67 @@
+// test for setting inputs from several threads within a `WriteScope`.
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct Left {
+    field: u32,
+}
+
+#[salsa::input]
+struct Right {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn salsa::Database, left: Left, right: Right) -> u32 {
+    left.field(db) + right.field(db)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn execute() {
+    let mut db = salsa::DatabaseImpl::new();
+    let lefts: Vec<_> = (0..10).map(|i| Left::new(&db, i)).collect();
+    let rights: Vec<_> = (0..10).map(|i| Right::new(&db, i)).collect();
+    assert_eq!(sum(&db, lefts[3], rights[4]), 7);
+
+    db.write_scope(|scope| {
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                for &left in &lefts {
+                    left.set_field_in(scope).to(100);
+                }
+            });
+            s.spawn(|| {
+                for &right in &rights {
+                    right.set_field_in(scope).to(1000);
+                }
+            });
+        });
+    });
+
+    assert_eq!(sum(&db, lefts[3], rights[4]), 1100);
+}
+
+/// Sets of different inputs of the same struct take the same lock, so they are serialized.
+#[test]
+#[cfg_attr(miri, ignore)]
+fn same_struct() {
+    let mut db = salsa::DatabaseImpl::new();
+    let lefts: Vec<_> = (0..10).map(|i| Left::new(&db, i)).collect();
+
+    db.write_scope(|scope| {
+        std::thread::scope(|s| {
+            for (i, chunk) in lefts.chunks(5).enumerate() {
+                s.spawn(move || {
+                    for &left in chunk {
+                        left.set_field_in(scope).to(100 * i as u32);
+                    }
+                });
+            }
+        });
+    });
+
+    let fields: Vec<_> = lefts.iter().map(|left| left.field(&db)).collect();
+    assert_eq!(fields, [0, 0, 0, 0, 0, 100, 100, 100, 100, 100]);
+}
@@ -35,6 +35,19 @@ pub(crate) struct Knobs {
     pub(crate) signal_on_did_cancel: AtomicCell<usize>,
 }
 
+impl Knobs {
+    pub(crate) fn with_cancellation_mode(mode: salsa::CancellationMode) -> Self {
+        Self::with_storage(salsa::Storage::with_cancellation_mode(mode))
+    }
+
+    pub(crate) fn with_storage(storage: salsa::Storage<Self>) -> Self {
+        Self {
+            storage,
+            ..Default::default()
+        }
+    }
+}
+
 impl Clone for Knobs {
     #[track_caller]
     fn clone(&self) -> Self {
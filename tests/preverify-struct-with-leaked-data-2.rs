@@ -79,6 +79,7 @@ fn test_leaked_inputs_ignored() {
     let result_in_rev_2 = function(&db, input);
     db.assert_logs(expect![[r#"
         [
+            "Event { thread_id: ThreadId(2), kind: WillChangeInputs { cause: SetField { field: DatabaseKeyIndex(IngredientIndex(2), Id(0)) } } }",
             "Event { thread_id: ThreadId(2), kind: DidSetCancellationFlag }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
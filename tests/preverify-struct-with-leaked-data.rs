@@ -76,10 +76,11 @@ fn test_leaked_inputs_ignored() {
     let result_in_rev_2 = function(&db, input);
     db.assert_logs(expect![[r#"
         [
+            "Event { thread_id: ThreadId(2), kind: WillChangeInputs { cause: SetField { field: DatabaseKeyIndex(IngredientIndex(2), Id(0)) } } }",
             "Event { thread_id: ThreadId(2), kind: DidSetCancellationFlag }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
-            "Event { thread_id: ThreadId(2), kind: DidValidateMemoizedValue { database_key: counter_field(Id(400)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidValidateMemoizedValue { database_key: counter_field(Id(400)), kind: Deep, edges_traversed: 1 } }",
             "Event { thread_id: ThreadId(2), kind: WillExecute { database_key: function(Id(0)) } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
         ]"#]]);
@@ -0,0 +1,50 @@
+//! Test that the `query_timing` feature records how long queries took to execute.
+#![cfg(feature = "query_timing")]
+
+use std::time::Duration;
+
+use salsa::plumbing::AsId;
+use salsa::{Database, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct Input {
+    delay_ms: u64,
+}
+
+#[salsa::tracked]
+fn slow(db: &dyn Database, input: Input) -> u64 {
+    let delay_ms = input.delay_ms(db);
+    std::thread::sleep(Duration::from_millis(delay_ms));
+    delay_ms
+}
+
+#[salsa::tracked]
+fn fast(db: &dyn Database, input: Input) -> u64 {
+    input.delay_ms(db) + 1
+}
+
+#[test]
+fn reports_most_expensive_queries() {
+    let mut db = DatabaseImpl::new();
+    let a = Input::new(&db, 20);
+    let b = Input::new(&db, 5);
+    slow(&db, a);
+    slow(&db, b);
+    fast(&db, a);
+
+    let top = db.top_expensive_queries(2);
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].0.key_index(), a.as_id());
+    assert_eq!(top[1].0.key_index(), b.as_id());
+    assert!(top[0].1.last >= Duration::from_millis(20));
+    assert_eq!(top[0].1.executions, 1);
+
+    a.set_delay_ms(&mut db).to(10);
+    slow(&db, a);
+    let top = db.top_expensive_queries(1);
+    let time = top[0].1;
+    assert_eq!(time.executions, 2);
+    assert!(time.last >= Duration::from_millis(10));
+    assert!(time.total >= Duration::from_millis(30));
+    assert!(time.total > time.last);
+}
@@ -0,0 +1,100 @@
+//! Test that `returns(arc)` functions and fields store their
+//! values in an `Arc` and return clones of it, backdating by the values they hold.
+
+use std::sync::Arc;
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct MyInput {
+    #[returns(arc)]
+    names: Vec<String>,
+}
+
+#[salsa::tracked]
+struct Summary<'db> {
+    #[returns(arc)]
+    text: String,
+}
+
+#[salsa::tracked(returns(arc))]
+fn sorted_names(db: &dyn Database, input: MyInput) -> Vec<String> {
+    let mut names = input.names(db).to_vec();
+    names.sort();
+    names
+}
+
+#[salsa::tracked]
+fn summary(db: &dyn Database, input: MyInput) -> Summary<'_> {
+    Summary::new(db, Arc::new(sorted_names(db, input).join(", ")))
+}
+
+#[salsa::tracked]
+impl MyInput {
+    #[salsa::tracked(returns(arc))]
+    fn count(self, db: &dyn Database) -> usize {
+        self.names(db).len()
+    }
+}
+
+#[test]
+fn function_returns_shared_value() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, Arc::new(vec!["b".to_string(), "a".to_string()]));
+
+    let first: Arc<Vec<String>> = sorted_names(&db, input);
+    let second = sorted_names(&db, input);
+    assert_eq!(*first, ["a", "b"]);
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(*input.count(&db), 2);
+
+    input.set_names(&mut db).to(Arc::new(vec!["c".to_string()]));
+    let third = sorted_names(&db, input);
+    assert_eq!(*third, ["c"]);
+    assert_eq!(*first, ["a", "b"]);
+}
+
+#[test]
+fn fields_return_shared_value() {
+    let db = salsa::DatabaseImpl::new();
+    let names = Arc::new(vec!["b".to_string(), "a".to_string()]);
+    let input = MyInput::new(&db, names.clone());
+    assert!(Arc::ptr_eq(&input.names(&db), &names));
+
+    let summary = summary(&db, input);
+    let text: Arc<String> = summary.text(&db);
+    assert_eq!(*text, "a, b");
+    assert!(Arc::ptr_eq(&text, &summary.text(&db)));
+}
+
+#[salsa::tracked]
+fn name_count(db: &dyn Database, input: MyInput) -> usize {
+    sorted_names(db, input).len()
+}
+
+#[test]
+fn backdates_by_inner_value() {
+    let executed = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut db = salsa::DatabaseImpl::builder()
+        .event_handler_with_db({
+            let executed = executed.clone();
+            move |db, event| {
+                if let salsa::EventKind::WillExecute { database_key } = event.kind {
+                    let name = db.ingredient_debug_name(database_key.ingredient_index());
+                    executed.lock().unwrap().push(name.into_owned());
+                }
+            }
+        })
+        .build();
+    let input = MyInput::new(&db, Arc::new(vec!["b".to_string(), "a".to_string()]));
+    assert_eq!(name_count(&db, input), 2);
+    assert_eq!(*executed.lock().unwrap(), ["name_count", "sorted_names"]);
+
+    // A new `Arc` with the same sorted names does not re-execute `name_count`.
+    executed.lock().unwrap().clear();
+    input
+        .set_names(&mut db)
+        .to(Arc::new(vec!["a".to_string(), "b".to_string()]));
+    assert_eq!(name_count(&db, input), 2);
+    assert_eq!(*executed.lock().unwrap(), ["sorted_names"]);
+}
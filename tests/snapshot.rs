@@ -0,0 +1,87 @@
+//! Test validating values exported from the database with `salsa::Snapshot`.
+
+use salsa::{Database, Durability, Setter, Snapshot};
+
+#[salsa::input]
+struct MyInput {
+    config: u32,
+    source: u32,
+}
+
+#[salsa::tracked]
+fn exported_config(db: &dyn Database, input: MyInput) -> Snapshot<u32> {
+    let config = input.config(db);
+    Snapshot::new(db, config)
+}
+
+#[test]
+fn outside_of_queries() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::builder(1, 2)
+        .config_durability(Durability::HIGH)
+        .new(&db);
+
+    let snapshot = Snapshot::new(&db, input.source(&db));
+    assert!(snapshot.is_current(&db));
+    assert_eq!(snapshot.durability(), Durability::LOW);
+
+    // Any change makes snapshots taken outside of queries stale.
+    input.set_source(&mut db).to(3);
+    assert!(!snapshot.is_current(&db));
+    assert_eq!(*snapshot.value(), 2);
+}
+
+#[test]
+fn inside_a_query() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::builder(1, 2)
+        .config_durability(Durability::HIGH)
+        .new(&db);
+
+    let snapshot = exported_config(&db, input);
+    assert_eq!(snapshot.durability(), Durability::HIGH);
+    let revision = snapshot.revision();
+
+    // Low-durability changes cannot affect the exported value.
+    input.set_source(&mut db).to(3);
+    assert!(snapshot.is_current(&db));
+
+    input
+        .set_config(&mut db)
+        .with_durability(Durability::HIGH)
+        .to(4);
+    assert!(!snapshot.is_current(&db));
+
+    let snapshot = exported_config(&db, input);
+    assert!(snapshot.is_current(&db));
+    assert!(snapshot.revision() > revision);
+    assert_eq!(snapshot.into_value(), 4);
+}
+
+#[test]
+fn with_durability() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::builder(1, 2)
+        .config_durability(Durability::MEDIUM)
+        .new(&db);
+
+    let snapshot = Snapshot::with_durability(&db, input.config(&db), Durability::MEDIUM);
+    input.set_source(&mut db).to(3);
+    assert!(snapshot.is_current(&db));
+
+    input
+        .set_config(&mut db)
+        .with_durability(Durability::MEDIUM)
+        .to(4);
+    assert!(!snapshot.is_current(&db));
+}
+
+#[test]
+fn other_database() {
+    let db = salsa::DatabaseImpl::new();
+    let other = salsa::DatabaseImpl::new();
+
+    let snapshot = Snapshot::new(&db, ());
+    assert!(snapshot.is_current(&db));
+    assert!(!snapshot.is_current(&other));
+}
@@ -0,0 +1,66 @@
+//! Test `specify_unchecked`, which seeds the values of a tracked function
+//! keyed by an interned struct.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct Registry {
+    crates: Vec<(String, u32)>,
+}
+
+#[salsa::interned]
+struct CrateName<'db> {
+    name: String,
+}
+
+#[salsa::tracked(specify_unchecked)]
+fn crate_version<'db>(db: &'db dyn LogDatabase, name: CrateName<'db>) -> u32 {
+    db.push_log(format!("crate_version({})", name.name(db)));
+    0
+}
+
+#[salsa::tracked]
+fn load_registry(db: &dyn LogDatabase, registry: Registry) {
+    db.push_log("load_registry".to_string());
+    for (name, version) in registry.crates(db) {
+        let name = CrateName::new(db, name);
+        // `lookup` always loads the registry before reading any version.
+        crate_version::specify_unchecked(db, name, version);
+    }
+}
+
+#[salsa::tracked]
+fn lookup(db: &dyn LogDatabase, registry: Registry, name: String) -> u32 {
+    load_registry(db, registry);
+    crate_version(db, CrateName::new(db, name))
+}
+
+#[test]
+fn seeded_values() {
+    let mut db = LoggerDatabase::default();
+    let registry = Registry::new(&db, vec![("a".to_string(), 1), ("b".to_string(), 2)]);
+
+    assert_eq!(lookup(&db, registry, "a".to_string()), 1);
+    assert_eq!(lookup(&db, registry, "b".to_string()), 2);
+    assert_eq!(lookup(&db, registry, "c".to_string()), 0);
+    db.assert_logs(expect![[r#"
+        [
+            "load_registry",
+            "crate_version(c)",
+        ]"#]]);
+
+    // Re-seeded: `b` changes, `a` is no longer seeded and so is computed.
+    registry.set_crates(&mut db).to(vec![("b".to_string(), 3)]);
+    assert_eq!(lookup(&db, registry, "b".to_string()), 3);
+    assert_eq!(lookup(&db, registry, "a".to_string()), 0);
+    db.assert_logs(expect![[r#"
+        [
+            "load_registry",
+            "crate_version(a)",
+        ]"#]]);
+}
@@ -0,0 +1,48 @@
+//! Test that references returned by `return_ref` tracked functions
+//! declared with `strict_revisions` panic when used after their revision.
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(return_ref, strict_revisions)]
+fn numbers(db: &dyn Database, input: MyInput) -> Vec<u32> {
+    (0..input.field(db)).collect()
+}
+
+#[salsa::tracked]
+impl MyInput {
+    #[salsa::tracked(return_ref, strict_revisions)]
+    fn doubled(self, db: &dyn Database) -> Vec<u32> {
+        numbers(db, self).iter().map(|n| n * 2).collect()
+    }
+}
+
+#[test]
+fn use_within_revision() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 3);
+    let result: salsa::RevisionRef<'_, Vec<u32>> = numbers(&db, input);
+    assert_eq!(*result, [0, 1, 2]);
+    assert_eq!(*input.doubled(&db), [0, 2, 4]);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(salsa::RevisionRef::get(numbers(&db, input)), &[0, 1]);
+}
+
+#[test]
+#[should_panic(expected = "reference returned in R1 was used in R2")]
+fn use_after_revision() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 3);
+    let result = numbers(&db, input);
+
+    // Simulate a reference that was leaked past the end of its revision.
+    // SAFETY: the memo is not freed as `numbers` is not executed again.
+    let leaked: salsa::RevisionRef<'static, Vec<u32>> = unsafe { std::mem::transmute(result) };
+    input.set_field(&mut db).to(2);
+    let _ = leaked.len();
+}
@@ -0,0 +1,74 @@
+//! Test that, with the `strict_tracked_structs` feature, reading a field through
+//! a stale tracked struct handle panics.
+#![cfg(feature = "strict_tracked_structs")]
+
+use salsa::plumbing::{AsId, FromId};
+use salsa::{Database, Durability, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+/// Creates a tracked struct only if the input is even.
+#[salsa::tracked]
+fn create_if_even(db: &dyn Database, input: MyInput) -> Option<MyTracked<'_>> {
+    let field = input.field(db);
+    (field % 2 == 0).then(|| MyTracked::new(db, field))
+}
+
+#[test]
+fn use_after_revalidation() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 2);
+    let id = create_if_even(&db, input).unwrap().as_id();
+
+    input.set_field(&mut db).to(4);
+    assert_eq!(create_if_even(&db, input).unwrap().field(&db), 4);
+    assert_eq!(MyTracked::from_id(id).field(&db), 4);
+}
+
+#[test]
+fn use_with_unchanged_durability() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::builder(2).durability(Durability::HIGH).new(&db);
+    let id = create_if_even(&db, input).unwrap().as_id();
+
+    // No input the creating query read has changed, so the struct is still valid.
+    let other = MyInput::new(&db, 0);
+    other.set_field(&mut db).to(1);
+    assert_eq!(MyTracked::from_id(id).field(&db), 2);
+}
+
+#[test]
+#[should_panic(
+    expected = "the query that created it has not been validated in the current revision R2"
+)]
+fn use_before_revalidation() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 2);
+    let id = create_if_even(&db, input).unwrap().as_id();
+
+    input.set_field(&mut db).to(4);
+    let _ = MyTracked::from_id(id).field(&db);
+}
+
+#[test]
+#[should_panic(
+    expected = "stale handle to tracked struct `MyTracked` Id(400) (generation 0) \
+    created in revision R1 by create_if_even(Id(0)): it has been deleted"
+)]
+fn use_after_deletion() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, 2);
+    let id = create_if_even(&db, input).unwrap().as_id();
+
+    input.set_field(&mut db).to(5);
+    assert!(create_if_even(&db, input).is_none());
+    let _ = MyTracked::from_id(id).field(&db);
+}
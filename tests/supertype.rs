@@ -0,0 +1,124 @@
+//! Test `#[derive(salsa::Supertype)]` enums mixing input, interned and tracked structs
+//! as the argument of a tracked function.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::interned]
+struct Name<'db> {
+    text: String,
+}
+
+#[salsa::tracked]
+struct Item<'db> {
+    #[id]
+    name: Name<'db>,
+
+    file: File,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, salsa::Supertype, salsa::Update)]
+enum Symbol<'db> {
+    File(File),
+    Name(Name<'db>),
+    Item(Item<'db>),
+}
+
+#[salsa::tracked]
+fn describe<'db>(db: &'db dyn LogDatabase, symbol: Symbol<'db>) -> String {
+    let description = match symbol {
+        Symbol::File(file) => format!("file `{}`", file.text(db)),
+        Symbol::Name(name) => format!("name `{}`", name.text(db)),
+        Symbol::Item(item) => format!(
+            "item `{}` in {}",
+            item.name(db).text(db),
+            describe(db, item.file(db).into())
+        ),
+    };
+    db.push_log(format!("describe() = {description}"));
+    description
+}
+
+#[salsa::tracked]
+fn item<'db>(db: &'db dyn LogDatabase, file: File) -> Item<'db> {
+    Item::new(db, Name::new(db, "main".to_string()), file)
+}
+
+#[salsa::tracked]
+fn describe_item(db: &dyn LogDatabase, file: File) -> String {
+    describe(db, item(db, file).into())
+}
+
+#[test]
+fn conversions() {
+    let db = LoggerDatabase::default();
+    let file = File::new(&db, "fn main() {}".to_string());
+    let name = Name::new(&db, "main".to_string());
+
+    let symbol = Symbol::from(file);
+    assert_eq!(symbol.as_file(), Some(file));
+    assert_eq!(symbol.as_name(), None);
+    assert_eq!(File::try_from(symbol), Ok(file));
+    assert_eq!(Name::try_from(symbol), Err(symbol));
+    assert_eq!(Symbol::from(name).as_name(), Some(name));
+}
+
+#[test]
+fn mixed_variants() {
+    let mut db = LoggerDatabase::default();
+    let file = File::new(&db, "fn main() {}".to_string());
+    assert_eq!(describe(&db, file.into()), "file `fn main() {}`");
+
+    let name = Name::new(&db, "main".to_string());
+    assert_eq!(describe(&db, name.into()), "name `main`");
+    assert_eq!(
+        describe_item(&db, file),
+        "item `main` in file `fn main() {}`"
+    );
+    db.assert_logs(expect![[r#"
+        [
+            "describe() = file `fn main() {}`",
+            "describe() = name `main`",
+            "describe() = item `main` in file `fn main() {}`",
+        ]"#]]);
+
+    assert_eq!(describe(&db, file.into()), "file `fn main() {}`");
+    assert_eq!(describe(&db, name.into()), "name `main`");
+    db.assert_logs(expect!["[]"]);
+
+    file.set_text(&mut db).to("fn lib() {}".to_string());
+    let name = Name::new(&db, "main".to_string());
+    assert_eq!(describe(&db, name.into()), "name `main`");
+    assert_eq!(
+        describe_item(&db, file),
+        "item `main` in file `fn lib() {}`"
+    );
+    db.assert_logs(expect![[r#"
+        [
+            "describe() = file `fn lib() {}`",
+            "describe() = item `main` in file `fn lib() {}`",
+        ]"#]]);
+}
+
+#[test]
+fn databases_register_variants_in_any_order() {
+    let db = LoggerDatabase::default();
+    let file = File::new(&db, "fn main() {}".to_string());
+    assert_eq!(describe(&db, file.into()), "file `fn main() {}`");
+
+    // The ingredients of the variants are registered in another order than in `db`.
+    let other = LoggerDatabase::default();
+    let name = Name::new(&other, "main".to_string());
+    let file = File::new(&other, "fn lib() {}".to_string());
+    assert_eq!(describe(&other, name.into()), "name `main`");
+    assert_eq!(describe(&other, file.into()), "file `fn lib() {}`");
+}
@@ -38,6 +38,6 @@ fn execute() {
 
     db.assert_logs(expect![[r#"
         [
-            "salsa_event(DidValidateMemoizedValue { database_key: tracked_fn(Id(0)) })",
+            "salsa_event(DidValidateMemoizedValue { database_key: tracked_fn(Id(0)), kind: Deep, edges_traversed: 1 })",
         ]"#]]);
 }
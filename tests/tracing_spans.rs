@@ -0,0 +1,130 @@
+//! Test the spans and structured events emitted with the `tracing` feature.
+#![cfg(feature = "tracing")]
+
+use std::fmt::{Debug, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use expect_test::expect;
+use salsa::{Database, DatabaseImpl, Setter};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Records spans and the events that carry fields other than a message.
+#[derive(Default)]
+struct Recorder {
+    log: Arc<Mutex<Vec<String>>>,
+    next_id: AtomicU64,
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    fields: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            write!(self.message, "{value:?}").unwrap();
+        } else {
+            write!(self.fields, " {}={value:?}", field.name()).unwrap();
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields = Fields::default();
+        span.record(&mut fields);
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("span {}:{}", span.metadata().name(), fields.fields));
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        if !fields.fields.is_empty() {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("event {}:{}", fields.message, fields.fields));
+        }
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+fn record(op: impl FnOnce()) -> Vec<String> {
+    let recorder = Recorder::default();
+    let log = recorder.log.clone();
+    tracing::subscriber::with_default(recorder, op);
+    let log = log.lock().unwrap().clone();
+    log
+}
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn is_even(db: &dyn Database, input: MyInput) -> bool {
+    input.field(db) % 2 == 0
+}
+
+#[salsa::tracked(recovery_fn = recover)]
+fn cycle(db: &dyn Database, input: MyInput) -> u32 {
+    cycle(db, input) + 1
+}
+
+fn recover(_db: &dyn Database, _cycle: &salsa::Cycle, _input: MyInput) -> u32 {
+    0
+}
+
+#[test]
+fn execution_spans_and_backdating() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 2);
+    let log = record(|| {
+        is_even(&db, input);
+        input.set_field(&mut db).to(4);
+        is_even(&db, input);
+    });
+    expect![[r#"
+        [
+            "span execute: query=\"is_even\" id=0 revision=1",
+            "span execute: query=\"is_even\" id=0 revision=2",
+            "event backdated: changed_at=1",
+        ]
+    "#]]
+    .assert_debug_eq(&log);
+}
+
+#[test]
+fn cycle_recovery_event() {
+    let db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 0);
+    let log = record(|| assert_eq!(cycle(&db, input), 0));
+    expect![[r#"
+        [
+            "span execute: query=\"cycle\" id=0 revision=1",
+            "event recovering from cycle: participants=1 trigger=cycle(Id(0))",
+        ]
+    "#]]
+    .assert_debug_eq(&log);
+}
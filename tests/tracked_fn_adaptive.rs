@@ -0,0 +1,118 @@
+//! Test that `adaptive` tracked functions only memoize values
+//! that took long enough to compute.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+    other: u32,
+}
+
+#[salsa::tracked(adaptive = "1s")]
+fn cheap(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("cheap({:?})", input.field(db)));
+    input.field(db) * 2
+}
+
+#[salsa::tracked(adaptive = "0ns")]
+fn memoized(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("memoized({:?})", input.field(db)));
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn parent(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log("parent".to_string());
+    cheap(db, input) + 1
+}
+
+#[test]
+fn cheap_values_are_not_memoized() {
+    let db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 0);
+
+    assert_eq!(cheap(&db, input), 2);
+    assert_eq!(cheap(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "cheap(1)",
+            "cheap(1)",
+        ]"#]]);
+}
+
+#[test]
+fn dependencies_are_kept() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 0);
+
+    assert_eq!(parent(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "parent",
+            "cheap(1)",
+        ]"#]]);
+
+    // `parent` can be verified without executing `cheap` again.
+    input.set_other(&mut db).to(1);
+    assert_eq!(parent(&db, input), 3);
+    db.assert_logs(expect!["[]"]);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(parent(&db, input), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "parent",
+            "cheap(2)",
+        ]"#]]);
+}
+
+#[test]
+fn zero_threshold_memoizes() {
+    let db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 0);
+
+    assert_eq!(memoized(&db, input), 2);
+    assert_eq!(memoized(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "memoized(1)",
+        ]"#]]);
+}
+
+#[test]
+fn executions_in_a_revision_share_dependencies() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 0);
+
+    // The first execution only leaves behind the memo holding its value...
+    assert_eq!(cheap(&db, input), 2);
+    let first = bytes_reclaimed(&mut db);
+    assert!(first > 0);
+
+    // ...and so do the next ones, which keep the memo without a value stored by the first.
+    assert_eq!(cheap(&db, input), 2);
+    assert_eq!(cheap(&db, input), 2);
+    assert_eq!(bytes_reclaimed(&mut db), 2 * first);
+    db.assert_logs(expect![[r#"
+        [
+            "cheap(1)",
+            "cheap(1)",
+            "cheap(1)",
+        ]"#]]);
+}
+
+fn bytes_reclaimed(db: &mut LoggerDatabase) -> usize {
+    let report = salsa::Database::compact(db);
+    report
+        .ingredients
+        .iter()
+        .find(|compaction| compaction.debug_name == "cheap")
+        .unwrap()
+        .bytes_reclaimed
+}
@@ -0,0 +1,73 @@
+//! Test backdating values that are equivalent according to a `compare_with` function.
+
+mod common;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::Setter as _;
+
+#[salsa::input]
+struct Source {
+    text: String,
+}
+
+/// Deliberately does not implement `Eq`.
+#[derive(Clone, Debug)]
+struct Item {
+    name: String,
+    offset: usize,
+}
+
+fn same_name(old: &Item, new: &Item) -> bool {
+    old.name == new.name
+}
+
+#[salsa::tracked(compare_with = same_name)]
+fn parse(db: &dyn LogDatabase, source: Source) -> Item {
+    let text = source.text(db);
+    db.push_log(format!("parse({text:?})"));
+    let name = text.trim();
+    Item {
+        name: name.to_string(),
+        offset: text.find(name).unwrap_or(0),
+    }
+}
+
+#[salsa::tracked(compare_with = same_name, returns(arc))]
+fn parse_shared(db: &dyn LogDatabase, source: Source) -> Item {
+    Item {
+        name: source.text(db).trim().to_string(),
+        offset: 0,
+    }
+}
+
+#[salsa::tracked]
+fn name_len(db: &dyn LogDatabase, source: Source) -> usize {
+    db.push_log("name_len".to_string());
+    parse(db, source).name.len() + parse_shared(db, source).name.len()
+}
+
+#[test]
+fn backdates_equivalent_values() {
+    let mut db = common::LoggerDatabase::default();
+    let source = Source::new(&db, "item".to_string());
+    assert_eq!(name_len(&db, source), 8);
+
+    // Only the offset changes, so `name_len` is not re-executed,
+    // while `parse` returns the new value.
+    source.set_text(&mut db).to("  item".to_string());
+    assert_eq!(name_len(&db, source), 8);
+    assert_eq!(parse(&db, source).offset, 2);
+
+    source.set_text(&mut db).to("items".to_string());
+    assert_eq!(name_len(&db, source), 10);
+
+    db.assert_logs(expect![[r#"
+        [
+            "name_len",
+            "parse(\"item\")",
+            "parse(\"  item\")",
+            "parse(\"items\")",
+            "name_len",
+        ]"#]]);
+}
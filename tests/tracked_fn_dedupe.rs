@@ -0,0 +1,75 @@
+//! Test that `#[salsa::tracked(dedupe)]` functions share
+//! equal output values across keys.
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(return_ref, dedupe)]
+fn diagnostics(db: &dyn Database, input: MyInput) -> Vec<String> {
+    (0..input.field(db)).map(|i| format!("error {i}")).collect()
+}
+
+#[salsa::tracked(dedupe)]
+fn diagnostic_count(db: &dyn Database, input: MyInput) -> usize {
+    diagnostics(db, input).len()
+}
+
+#[test]
+fn equal_values_are_shared() {
+    let db = salsa::DatabaseImpl::new();
+    let a = MyInput::new(&db, 2);
+    let b = MyInput::new(&db, 2);
+    let c = MyInput::new(&db, 3);
+
+    let da = diagnostics(&db, a);
+    let db_ = diagnostics(&db, b);
+    let dc = diagnostics(&db, c);
+    assert_eq!(da, db_);
+    assert!(std::ptr::eq(da, db_));
+    assert!(!std::ptr::eq(da, dc));
+
+    assert_eq!(diagnostic_count(&db, a), 2);
+    assert_eq!(diagnostic_count(&db, c), 3);
+}
+
+#[test]
+fn changed_values_are_not_shared() {
+    let mut db = salsa::DatabaseImpl::new();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 1);
+
+    assert!(std::ptr::eq(diagnostics(&db, a), diagnostics(&db, b)));
+
+    a.set_field(&mut db).to(2);
+    assert_eq!(diagnostics(&db, a), &["error 0", "error 1"]);
+    assert_eq!(diagnostics(&db, b), &["error 0"]);
+    assert!(!std::ptr::eq(diagnostics(&db, a), diagnostics(&db, b)));
+
+    // Once `b` catches up it shares `a`'s new value.
+    b.set_field(&mut db).to(2);
+    assert!(std::ptr::eq(diagnostics(&db, a), diagnostics(&db, b)));
+}
+
+#[salsa::tracked(dedupe, lru = 1)]
+fn labels(db: &dyn Database, input: MyInput) -> Vec<String> {
+    (0..input.field(db)).map(|i| format!("label {i}")).collect()
+}
+
+#[test]
+fn evicted_values_are_forgotten() {
+    let db = salsa::DatabaseImpl::new();
+    let a = MyInput::new(&db, 2);
+    let b = MyInput::new(&db, 2);
+    let c = MyInput::new(&db, 2);
+
+    // Each call evicts the value of the previous key, so there is nothing to share,
+    // but the values are still computed correctly.
+    assert_eq!(labels(&db, a), ["label 0", "label 1"]);
+    assert_eq!(labels(&db, b), ["label 0", "label 1"]);
+    assert_eq!(labels(&db, c), ["label 0", "label 1"]);
+    assert_eq!(labels(&db, a), ["label 0", "label 1"]);
+}
@@ -0,0 +1,51 @@
+//! Test `my_query::dependencies`, which reports the queries read
+//! by the last recorded execution without validating it.
+#![cfg(feature = "dependency_inspection")]
+
+use salsa::{Database, Setter};
+
+#[salsa::input]
+struct MyInput {
+    flag: bool,
+    a: u32,
+    b: u32,
+}
+
+#[salsa::tracked]
+fn a(db: &dyn Database, input: MyInput) -> u32 {
+    input.a(db)
+}
+
+#[salsa::tracked]
+fn b(db: &dyn Database, input: MyInput) -> u32 {
+    input.b(db)
+}
+
+#[salsa::tracked]
+fn pick(db: &dyn Database, input: MyInput) -> u32 {
+    if input.flag(db) {
+        a(db, input)
+    } else {
+        b(db, input)
+    }
+}
+
+#[test]
+fn last_execution_dependencies() {
+    let mut db = salsa::DatabaseImpl::new();
+    let input = MyInput::new(&db, true, 1, 2);
+    assert!(pick::dependencies(&db, input).is_empty());
+
+    assert_eq!(pick(&db, input), 1);
+    let deps = pick::dependencies(&db, input);
+    assert_eq!(deps.len(), 2);
+    assert_eq!(deps[1], a::database_key_index(&db, input).unwrap());
+
+    // Not validated: still reports the execution from the previous revision.
+    input.set_flag(&mut db).to(false);
+    assert_eq!(pick::dependencies(&db, input), deps);
+
+    assert_eq!(pick(&db, input), 2);
+    let deps = pick::dependencies(&db, input);
+    assert_eq!(deps[1], b::database_key_index(&db, input).unwrap());
+}
@@ -0,0 +1,93 @@
+//! Test that `#[salsa::tracked(fingerprint)]` functions backdate
+//! by comparing fingerprints instead of the values.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+static EQ_CALLS: AtomicUsize = AtomicUsize::new(0);
+static HASH_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+struct Words(Vec<String>);
+
+impl std::hash::Hash for Words {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        HASH_CALLS.fetch_add(1, Ordering::Relaxed);
+        self.0.hash(state)
+    }
+}
+
+impl PartialEq for Words {
+    fn eq(&self, other: &Self) -> bool {
+        EQ_CALLS.fetch_add(1, Ordering::Relaxed);
+        self.0 == other.0
+    }
+}
+
+impl Eq for Words {}
+
+#[salsa::input]
+struct MyInput {
+    text: String,
+}
+
+#[salsa::tracked(return_ref, fingerprint)]
+fn words(db: &dyn LogDatabase, input: MyInput) -> Words {
+    db.push_log("words".to_string());
+    Words(
+        input
+            .text(db)
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+#[salsa::tracked]
+fn word_count(db: &dyn LogDatabase, input: MyInput) -> usize {
+    db.push_log("word_count".to_string());
+    words(db, input).0.len()
+}
+
+#[test]
+fn execute() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, "hello  world".to_string());
+
+    assert_eq!(word_count(&db, input), 2);
+    let first: *const Words = words(&db, input);
+    db.assert_logs(expect![[r#"
+        [
+            "word_count",
+            "words",
+        ]"#]]);
+
+    // Same words: backdated by fingerprint, sharing the old value, without `Eq`.
+    input.set_text(&mut db).to("hello world".to_string());
+    assert_eq!(word_count(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "words",
+        ]"#]]);
+    assert!(std::ptr::eq(first, words(&db, input)));
+
+    // Different words: the fingerprints differ.
+    input.set_text(&mut db).to("hello there world".to_string());
+    assert_eq!(word_count(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "words",
+            "word_count",
+        ]"#]]);
+    assert!(!std::ptr::eq(first, words(&db, input)));
+
+    assert_eq!(EQ_CALLS.load(Ordering::Relaxed), 0);
+    // Each new value is hashed once, by the three executions of `words`.
+    assert_eq!(HASH_CALLS.load(Ordering::Relaxed), 3);
+}
@@ -0,0 +1,89 @@
+//! Test tracked functions with `#[salsa::not_key]` arguments,
+//! which are not part of the memoized value's key.
+
+mod common;
+
+use std::cell::Cell;
+
+use common::LogDatabase;
+use expect_test::expect;
+use salsa::Setter as _;
+
+#[salsa::input]
+struct Input {
+    number: u32,
+}
+
+/// Scratch space shared by the executions of `sum`, which does not affect their results.
+#[derive(Default)]
+struct Scratch {
+    executions: Cell<u32>,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn LogDatabase, input: Input, #[salsa::not_key] scratch: &Scratch) -> u32 {
+    scratch.executions.set(scratch.executions.get() + 1);
+    db.push_log(format!("double({})", input.number(db)));
+    input.number(db) * 2
+}
+
+#[salsa::tracked]
+fn sum(
+    db: &dyn LogDatabase,
+    a: Input,
+    b: Input,
+    #[salsa::not_key] scratch: &Scratch,
+    #[salsa::not_key] label: &str,
+) -> u32 {
+    db.push_log(format!("sum({label})"));
+    double(db, a, scratch) + double(db, b, scratch)
+}
+
+#[salsa::tracked]
+fn caller(db: &dyn LogDatabase, input: Input) -> u32 {
+    db.push_log("caller".to_string());
+    double(db, input, &Scratch::default()) / 2
+}
+
+#[test]
+fn memoized_regardless_of_context() {
+    let db = common::LoggerDatabase::default();
+    let input = Input::new(&db, 1);
+    let scratch = Scratch::default();
+
+    assert_eq!(double(&db, input, &scratch), 2);
+    assert_eq!(double(&db, input, &Scratch::default()), 2);
+    assert_eq!(scratch.executions.get(), 1);
+
+    let other = Input::new(&db, 2);
+    assert_eq!(sum(&db, input, other, &scratch, "first"), 6);
+    assert_eq!(sum(&db, input, other, &scratch, "second"), 6);
+    assert_eq!(scratch.executions.get(), 2);
+
+    db.assert_logs(expect![[r#"
+        [
+            "double(1)",
+            "sum(first)",
+            "double(2)",
+        ]"#]]);
+}
+
+#[test]
+fn not_executed_to_verify_readers() {
+    let mut db = common::LoggerDatabase::default();
+    let input = Input::new(&db, 1);
+    assert_eq!(caller(&db, input), 1);
+
+    // Verifying `caller` cannot execute `double`, which needs a scratch space,
+    // so `caller` re-executes, giving it one.
+    input.set_number(&mut db).to(3);
+    assert_eq!(caller(&db, input), 3);
+
+    db.assert_logs(expect![[r#"
+        [
+            "caller",
+            "double(1)",
+            "caller",
+            "double(3)",
+        ]"#]]);
+}
@@ -39,17 +39,18 @@ fn execute() {
     assert_eq!(tracked_fn(&db, input_low), 44);
     assert_eq!(tracked_fn(&db, input_high), 4400);
 
-    // FIXME: There's currently no good way to verify whether an input was validated using shallow or deep comparison.
-    // All we can do for now is verify that the values were validated.
+    // `input_low` had to be validated by walking its dependencies,
+    // but `input_high` was validated from its durability alone.
     // Note: It maybe confusing why it validates `input_high` when the write has `Durability::LOW`.
     // This is because all values must be validated whenever a write occurs. It doesn't mean that it
     // executed the query.
     db.assert_logs(expect![[r#"
         [
+            "Event { thread_id: ThreadId(2), kind: WillChangeInputs { cause: SyntheticWrite { durability: Durability(0) } } }",
             "Event { thread_id: ThreadId(2), kind: DidSetCancellationFlag }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
-            "Event { thread_id: ThreadId(2), kind: DidValidateMemoizedValue { database_key: tracked_fn(Id(0)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidValidateMemoizedValue { database_key: tracked_fn(Id(0)), kind: Deep, edges_traversed: 1 } }",
             "Event { thread_id: ThreadId(2), kind: WillCheckCancellation }",
-            "Event { thread_id: ThreadId(2), kind: DidValidateMemoizedValue { database_key: tracked_fn(Id(1)) } }",
+            "Event { thread_id: ThreadId(2), kind: DidValidateMemoizedValue { database_key: tracked_fn(Id(1)), kind: Shallow, edges_traversed: 0 } }",
         ]"#]]);
 }
@@ -0,0 +1,121 @@
+//! Test that `pin_subtree` keeps the values of a query and its dependencies
+//! from being evicted by the LRU or dropped by `Database::trim_memory`.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{Database, MemoryPressure};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked(lru = 1)]
+fn leaf(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("leaf({:?})", input.field(db)));
+    input.field(db)
+}
+
+#[salsa::tracked(lru = 1)]
+fn middle(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("middle({:?})", input.field(db)));
+    leaf(db, input) + 1
+}
+
+#[salsa::tracked(weak)]
+fn entry(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("entry({:?})", input.field(db)));
+    middle(db, input) + 1
+}
+
+/// Evicts the values of `leaf` and `middle` for any input other than `other`.
+fn use_other(db: &dyn LogDatabase, other: MyInput) {
+    middle(db, other);
+    leaf(db, other);
+}
+
+#[test]
+fn pinned_values_are_not_evicted() {
+    let db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1);
+    let other = MyInput::new(&db, 2);
+    assert_eq!(entry(&db, input), 3);
+    entry::pin_subtree(&db, input, 2);
+
+    use_other(&db, other);
+    assert_eq!(middle(&db, input), 2);
+    assert_eq!(leaf(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "entry(1)",
+            "middle(1)",
+            "leaf(1)",
+            "middle(2)",
+            "leaf(2)",
+        ]"#]]);
+}
+
+#[test]
+fn depth_limits_pinning() {
+    let db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1);
+    let other = MyInput::new(&db, 2);
+    assert_eq!(entry(&db, input), 3);
+    entry::pin_subtree(&db, input, 1);
+    db.assert_logs_len(3);
+
+    use_other(&db, other);
+    assert_eq!(middle(&db, input), 2);
+    assert_eq!(leaf(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "middle(2)",
+            "leaf(2)",
+            "leaf(1)",
+        ]"#]]);
+}
+
+#[test]
+fn pinned_values_are_not_trimmed() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1);
+    let other = MyInput::new(&db, 2);
+    assert_eq!(entry(&db, input), 3);
+    assert_eq!(entry(&db, other), 4);
+    entry::pin_subtree(&db, input, 0);
+    db.assert_logs_len(6);
+
+    db.trim_memory(MemoryPressure::Low);
+    assert_eq!(entry(&db, input), 3);
+    assert_eq!(entry(&db, other), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "entry(2)",
+        ]"#]]);
+}
+
+#[test]
+fn unpinned_values_are_evicted() {
+    let db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1);
+    let other = MyInput::new(&db, 2);
+    assert_eq!(entry(&db, input), 3);
+    entry::pin_subtree(&db, input, 2);
+    middle::pin_subtree(&db, input, 0);
+    entry::unpin_subtree(&db, input);
+    db.assert_logs_len(3);
+
+    // `middle` is still pinned by its own subtree.
+    use_other(&db, other);
+    assert_eq!(middle(&db, input), 2);
+    assert_eq!(leaf(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "middle(2)",
+            "leaf(2)",
+            "leaf(1)",
+        ]"#]]);
+}
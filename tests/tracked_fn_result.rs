@@ -0,0 +1,92 @@
+//! Test `#[salsa::tracked(result)]` functions, whose errors are shared
+//! across keys and, with `result(retry)`, recomputed in every new revision.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use std::sync::Arc;
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ParseError(String);
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::input]
+struct Counter {
+    value: u32,
+}
+
+#[salsa::tracked(result)]
+fn parse(db: &dyn LogDatabase, file: File) -> Result<u32, ParseError> {
+    db.push_log(format!("parse({:?})", file.text(db)));
+    file.text(db)
+        .parse()
+        .map_err(|_| ParseError("not a number".to_string()))
+}
+
+#[salsa::tracked(result(retry))]
+fn parse_retry(db: &dyn LogDatabase, file: File) -> Result<u32, ParseError> {
+    db.push_log(format!("parse_retry({:?})", file.text(db)));
+    file.text(db)
+        .parse()
+        .map_err(|_| ParseError("not a number".to_string()))
+}
+
+#[test]
+fn errors_are_shared() {
+    let db = LoggerDatabase::default();
+    let a = File::new(&db, "a".to_string());
+    let b = File::new(&db, "b".to_string());
+    let one = File::new(&db, "1".to_string());
+
+    assert_eq!(parse(&db, one), Ok(1));
+    assert!(!parse::is_err(&db, one));
+
+    let (Err(error_a), Err(error_b)) = (parse(&db, a), parse(&db, b)) else {
+        panic!("expected errors");
+    };
+    assert_eq!(*error_a, ParseError("not a number".to_string()));
+    assert!(Arc::ptr_eq(&error_a, &error_b));
+    assert!(parse::is_err(&db, a));
+}
+
+#[test]
+fn errors_are_memoized() {
+    let mut db = LoggerDatabase::default();
+    let file = File::new(&db, "a".to_string());
+    let counter = Counter::new(&db, 0);
+
+    assert!(parse::is_err(&db, file));
+    assert!(parse_retry::is_err(&db, file));
+    db.assert_logs(expect![[r#"
+        [
+            "parse(\"a\")",
+            "parse_retry(\"a\")",
+        ]"#]]);
+
+    // Only the function declared with `retry` re-executes in the next revision.
+    counter.set_value(&mut db).to(1);
+    assert!(parse::is_err(&db, file));
+    assert!(parse_retry::is_err(&db, file));
+    db.assert_logs(expect![[r#"
+        [
+            "parse_retry(\"a\")",
+        ]"#]]);
+
+    // Successful values are memoized as usual.
+    file.set_text(&mut db).to("2".to_string());
+    assert_eq!(parse_retry(&db, file), Ok(2));
+    counter.set_value(&mut db).to(2);
+    assert_eq!(parse_retry(&db, file), Ok(2));
+    db.assert_logs(expect![[r#"
+        [
+            "parse_retry(\"2\")",
+        ]"#]]);
+}
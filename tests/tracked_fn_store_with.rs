@@ -0,0 +1,99 @@
+//! Test that a `tracked` fn with `store_with` memoizes the encoded value,
+//! decodes it on each call, and backdates by comparing encoded values.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{Codec, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    text: String,
+}
+
+/// Stores lines as a single string, joined by newlines.
+struct JoinLines;
+
+impl Codec<Vec<String>> for JoinLines {
+    type Stored = Box<str>;
+
+    fn encode(value: &Vec<String>) -> Box<str> {
+        value.join("\n").into()
+    }
+
+    fn decode(stored: &Box<str>) -> Vec<String> {
+        stored.lines().map(str::to_string).collect()
+    }
+}
+
+#[salsa::tracked(store_with = JoinLines)]
+fn lines(db: &dyn LogDatabase, input: MyInput) -> Vec<String> {
+    db.push_log(format!("lines({:?})", input.text(db)));
+    input
+        .text(db)
+        .split(';')
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+#[salsa::tracked]
+fn line_count(db: &dyn LogDatabase, input: MyInput) -> usize {
+    db.push_log("line_count".to_string());
+    lines(db, input).len()
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::tracked(specify, store_with = JoinLines)]
+fn specified_lines<'db>(db: &'db dyn LogDatabase, tracked: MyTracked<'db>) -> Vec<String> {
+    vec![format!("default {}", tracked.field(db))]
+}
+
+#[salsa::tracked]
+fn create_and_specify(db: &dyn LogDatabase, input: MyInput) -> Vec<String> {
+    let tracked = MyTracked::new(db, 0);
+    specified_lines::specify(db, tracked, vec![input.text(db), "specified".to_string()]);
+    specified_lines(db, tracked)
+}
+
+#[test]
+fn execute() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, "a; b".to_string());
+
+    assert_eq!(lines(&db, input), ["a", "b"]);
+    assert_eq!(line_count(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "lines(\"a; b\")",
+            "line_count",
+        ]"#]]);
+
+    // The encoded value is unchanged, so `line_count` is not re-executed.
+    input.set_text(&mut db).to("a;b".to_string());
+    assert_eq!(line_count(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "lines(\"a;b\")",
+        ]"#]]);
+
+    input.set_text(&mut db).to("a;b;c".to_string());
+    assert_eq!(line_count(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "lines(\"a;b;c\")",
+            "line_count",
+        ]"#]]);
+}
+
+#[test]
+fn specify() {
+    let db = LoggerDatabase::default();
+    let input = MyInput::new(&db, "input".to_string());
+    assert_eq!(create_and_specify(&db, input), ["input", "specified"]);
+}
@@ -0,0 +1,87 @@
+//! Test that functions declared with `timeout` are unwound once they run
+//! past it, and memoize their `timeout_result` until the next revision.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    spin: bool,
+}
+
+#[salsa::input]
+struct Unrelated {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn total(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log("total".to_string());
+    analyze(db, input) + 1
+}
+
+#[salsa::tracked(timeout = "10ms", timeout_result = analyze_timed_out)]
+fn analyze(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log("analyze".to_string());
+    while input.spin(db) {
+        step(db, input);
+    }
+    22
+}
+
+fn analyze_timed_out(db: &dyn LogDatabase, _input: MyInput) -> u32 {
+    db.push_log("analyze_timed_out".to_string());
+    0
+}
+
+#[salsa::tracked]
+fn step(db: &dyn LogDatabase, _input: MyInput) {
+    db.push_log("step".to_string());
+}
+
+#[test]
+fn execute() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, true);
+    let unrelated = Unrelated::new(&db, 0);
+
+    assert_eq!(total(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "total",
+            "analyze",
+            "step",
+            "analyze_timed_out",
+        ]"#]]);
+
+    // The fallback is memoized within the revision.
+    assert_eq!(total(&db, input), 1);
+    db.assert_logs(expect!["[]"]);
+
+    // It is re-executed in the next revision, even though its input did not change.
+    // Its value is the same, so `total` is not.
+    unrelated.set_field(&mut db).to(1);
+    assert_eq!(total(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "analyze",
+            "analyze_timed_out",
+        ]"#]]);
+
+    // Executions that finish in time are memoized as usual.
+    input.set_spin(&mut db).to(false);
+    assert_eq!(total(&db, input), 23);
+    db.assert_logs(expect![[r#"
+        [
+            "analyze",
+            "total",
+        ]"#]]);
+
+    unrelated.set_field(&mut db).to(2);
+    assert_eq!(total(&db, input), 23);
+    db.assert_logs(expect!["[]"]);
+}
@@ -0,0 +1,87 @@
+//! Test tracked functions returning `()`, which only produce accumulated values:
+//! their memos are never dropped to free memory, and they keep their dependencies
+//! and accumulated values.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{Accumulator, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct File {
+    text: String,
+}
+
+#[salsa::accumulator]
+struct Diagnostic(String);
+
+#[salsa::tracked(lru = 1)]
+fn check(db: &dyn LogDatabase, file: File) {
+    db.push_log(format!("check({})", file.text(db)));
+    if file.text(db).contains("error") {
+        Diagnostic(file.text(db)).accumulate(db);
+    }
+}
+
+// Spelled out to test that an explicit `()` is recognized as well.
+#[allow(clippy::unused_unit)]
+#[salsa::tracked(adaptive = "1s")]
+fn check_cheaply(db: &dyn LogDatabase, file: File) -> () {
+    db.push_log(format!("check_cheaply({})", file.text(db)));
+}
+
+fn diagnostics(db: &dyn LogDatabase, file: File) -> Vec<String> {
+    check::accumulated::<Diagnostic>(db, file)
+        .into_iter()
+        .map(|diagnostic| diagnostic.0)
+        .collect()
+}
+
+#[test]
+fn accumulates() {
+    let mut db = LoggerDatabase::default();
+    let file = File::new(&db, "fine".to_string());
+    check(&db, file);
+    assert!(diagnostics(&db, file).is_empty());
+    db.assert_logs(expect![[r#"
+        [
+            "check(fine)",
+        ]"#]]);
+
+    file.set_text(&mut db).to("an error".to_string());
+    check(&db, file);
+    assert_eq!(diagnostics(&db, file), ["an error"]);
+    db.assert_logs(expect![[r#"
+        [
+            "check(an error)",
+        ]"#]]);
+
+    check(&db, file);
+    assert_eq!(diagnostics(&db, file), ["an error"]);
+    db.assert_logs(expect!["[]"]);
+}
+
+#[test]
+fn never_evicted() {
+    let db = LoggerDatabase::default();
+    let files: Vec<_> = (0..3)
+        .map(|i| File::new(&db, format!("file {i}")))
+        .collect();
+    for _ in 0..2 {
+        for &file in &files {
+            check(&db, file);
+            check_cheaply(&db, file);
+        }
+    }
+    db.assert_logs(expect![[r#"
+        [
+            "check(file 0)",
+            "check_cheaply(file 0)",
+            "check(file 1)",
+            "check_cheaply(file 1)",
+            "check(file 2)",
+            "check_cheaply(file 2)",
+        ]"#]]);
+}
@@ -0,0 +1,130 @@
+//! Test that the values of `weak` tracked functions are dropped by
+//! `Database::trim_memory` and recomputed when next needed.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{Database, MemoryPressure, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+    other: u32,
+}
+
+#[salsa::tracked(weak)]
+fn weak(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("weak({:?})", input.field(db)));
+    input.field(db) * 2
+}
+
+#[salsa::tracked]
+fn strong(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("strong({:?})", input.field(db)));
+    input.field(db) * 3
+}
+
+#[salsa::tracked(lru = 8)]
+fn lru(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log(format!("lru({:?})", input.field(db)));
+    input.field(db) * 4
+}
+
+#[salsa::tracked]
+fn parent(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log("parent".to_string());
+    weak(db, input) + 1
+}
+
+#[test]
+fn low_pressure_drops_weak_values() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 0);
+    assert_eq!(weak(&db, input), 2);
+    assert_eq!(strong(&db, input), 3);
+    assert_eq!(lru(&db, input), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "weak(1)",
+            "strong(1)",
+            "lru(1)",
+        ]"#]]);
+
+    db.trim_memory(MemoryPressure::Low);
+    assert_eq!(weak(&db, input), 2);
+    assert_eq!(strong(&db, input), 3);
+    assert_eq!(lru(&db, input), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "weak(1)",
+        ]"#]]);
+}
+
+#[test]
+fn high_pressure_drops_lru_values() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 0);
+    assert_eq!(weak(&db, input), 2);
+    assert_eq!(strong(&db, input), 3);
+    assert_eq!(lru(&db, input), 4);
+    db.assert_logs_len(3);
+
+    db.trim_memory(MemoryPressure::High);
+    assert_eq!(weak(&db, input), 2);
+    assert_eq!(strong(&db, input), 3);
+    assert_eq!(lru(&db, input), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "weak(1)",
+            "lru(1)",
+        ]"#]]);
+}
+
+#[test]
+fn dependents_are_not_invalidated() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 0);
+    assert_eq!(parent(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "parent",
+            "weak(1)",
+        ]"#]]);
+
+    // Dropping the value does not start a new revision, so `parent` is still valid.
+    db.trim_memory(MemoryPressure::Low);
+    assert_eq!(parent(&db, input), 3);
+    db.assert_logs(expect!["[]"]);
+
+    // The dependencies of `weak` are kept, so `parent` is validated without executing it.
+    input.set_other(&mut db).to(1);
+    assert_eq!(parent(&db, input), 3);
+    db.assert_logs(expect!["[]"]);
+
+    input.set_field(&mut db).to(2);
+    assert_eq!(parent(&db, input), 5);
+    db.assert_logs(expect![[r#"
+        [
+            "parent",
+            "weak(2)",
+        ]"#]]);
+}
+
+#[test]
+fn trim_with_other_handles() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 0);
+    assert_eq!(weak(&db, input), 2);
+
+    let other = db.clone();
+    db.trim_memory(MemoryPressure::Low);
+    assert_eq!(weak(&other, input), 2);
+    drop(other);
+    db.assert_logs(expect![[r#"
+        [
+            "weak(1)",
+            "weak(1)",
+        ]"#]]);
+}
@@ -0,0 +1,78 @@
+//! Test tracked methods in impls of a generic trait, one impl per type argument,
+//! used from generic code through the trait.
+//! Each impl has its own memoized function, so the results for different type
+//! arguments are cached separately.
+
+use salsa::{Database, DatabaseImpl, Setter};
+
+trait Language {
+    const COMMENT: &'static str;
+}
+
+struct Rust;
+
+impl Language for Rust {
+    const COMMENT: &'static str = "//";
+}
+
+struct Python;
+
+impl Language for Python {
+    const COMMENT: &'static str = "#";
+}
+
+trait CountComments<L: Language> {
+    fn count_comments(self, db: &dyn Database) -> usize;
+}
+
+#[salsa::input]
+struct File {
+    contents: String,
+}
+
+fn count<L: Language>(db: &dyn Database, file: File) -> usize {
+    file.contents(db)
+        .lines()
+        .filter(|line| line.trim_start().starts_with(L::COMMENT))
+        .count()
+}
+
+#[salsa::tracked]
+impl CountComments<Rust> for File {
+    #[salsa::tracked]
+    fn count_comments(self, db: &dyn Database) -> usize {
+        count::<Rust>(db, self)
+    }
+}
+
+#[salsa::tracked]
+impl CountComments<Python> for File {
+    #[salsa::tracked]
+    fn count_comments(self, db: &dyn Database) -> usize {
+        count::<Python>(db, self)
+    }
+}
+
+/// Generic code calls the tracked methods through the trait.
+fn total_comments<L: Language>(db: &dyn Database, files: &[File]) -> usize
+where
+    File: CountComments<L>,
+{
+    files
+        .iter()
+        .map(|&file| CountComments::<L>::count_comments(file, db))
+        .sum()
+}
+
+#[test]
+fn execute() {
+    let mut db = DatabaseImpl::new();
+    let a = File::new(&db, "// a\n# b\n// c\n".to_string());
+    let b = File::new(&db, "# d\n".to_string());
+    assert_eq!(total_comments::<Rust>(&db, &[a, b]), 2);
+    assert_eq!(total_comments::<Python>(&db, &[a, b]), 2);
+
+    b.set_contents(&mut db).to("// e\n".to_string());
+    assert_eq!(total_comments::<Rust>(&db, &[a, b]), 3);
+    assert_eq!(total_comments::<Python>(&db, &[a, b]), 1);
+}
@@ -0,0 +1,180 @@
+//! Test `salsa::adopt`, which transfers ownership of a tracked struct
+//! from the query that created it to the query that adopts it.
+
+use expect_test::expect;
+use salsa::{Database, Setter};
+use test_log::test;
+
+mod common;
+use common::{HasLogger, LogDatabase, Logger};
+
+#[salsa::input]
+struct MyInput {
+    create: bool,
+    adopt: bool,
+}
+
+#[salsa::tracked]
+struct MyTracked<'db> {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn helper(db: &dyn Database, input: MyInput) -> Option<MyTracked<'_>> {
+    if input.create(db) {
+        Some(MyTracked::new(db, 22))
+    } else {
+        None
+    }
+}
+
+#[salsa::tracked]
+fn caller(db: &dyn Database, input: MyInput) -> Option<u32> {
+    let tracked = helper(db, input)?;
+    if input.adopt(db) {
+        salsa::adopt(db, tracked);
+    }
+    Some(tracked.field(db))
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct Db {
+    storage: salsa::Storage<Self>,
+    logger: Logger,
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
+        let (what, key) = match event().kind {
+            salsa::EventKind::WillExecute { database_key } => ("execute", database_key),
+            salsa::EventKind::DidDiscard { key } => ("discard", key),
+            _ => return,
+        };
+        let name = self.ingredient_debug_name(key.ingredient_index());
+        self.push_log(format!("{what}({name})"));
+    }
+}
+
+impl HasLogger for Db {
+    fn logger(&self) -> &Logger {
+        &self.logger
+    }
+}
+
+#[test]
+fn adopted_struct_outlives_creator() {
+    let mut db = Db::default();
+    let input = MyInput::new(&db, true, true);
+
+    assert_eq!(caller(&db, input), Some(22));
+    db.assert_logs(expect![[r#"
+        [
+            "execute(caller)",
+            "execute(helper)",
+        ]"#]]);
+
+    // The helper no longer creates the struct, but the caller adopted it:
+    // the struct is only discarded once the caller has re-executed without it.
+    input.set_create(&mut db).to(false);
+    assert_eq!(caller(&db, input), None);
+    db.assert_logs(expect![[r#"
+        [
+            "execute(helper)",
+            "execute(caller)",
+            "discard(MyTracked)",
+        ]"#]]);
+}
+
+#[test]
+fn giving_up_adoption_returns_ownership() {
+    let mut db = Db::default();
+    let input = MyInput::new(&db, true, true);
+
+    assert_eq!(caller(&db, input), Some(22));
+    db.assert_logs_len(2);
+
+    // The caller stops adopting the struct, but the helper still creates it,
+    // so it must be kept alive.
+    input.set_adopt(&mut db).to(false);
+    assert_eq!(caller(&db, input), Some(22));
+    db.assert_logs(expect![[r#"
+        [
+            "execute(caller)",
+        ]"#]]);
+
+    // Now the helper (the owner again) stops creating it.
+    input.set_create(&mut db).to(false);
+    assert_eq!(caller(&db, input), None);
+    db.assert_logs(expect![[r#"
+        [
+            "execute(helper)",
+            "discard(MyTracked)",
+            "execute(caller)",
+        ]"#]]);
+}
+
+#[salsa::input]
+struct Shared {
+    create: bool,
+    first: bool,
+    second: bool,
+}
+
+#[salsa::tracked]
+fn shared_helper(db: &dyn Database, input: Shared) -> Option<MyTracked<'_>> {
+    if input.create(db) {
+        Some(MyTracked::new(db, 22))
+    } else {
+        None
+    }
+}
+
+#[salsa::tracked]
+fn first_adopter(db: &dyn Database, input: Shared) -> Option<u32> {
+    let tracked = shared_helper(db, input)?;
+    if input.first(db) {
+        salsa::adopt(db, tracked);
+    }
+    Some(tracked.field(db))
+}
+
+#[salsa::tracked]
+fn second_adopter(db: &dyn Database, input: Shared) -> Option<u32> {
+    let tracked = shared_helper(db, input)?;
+    if input.second(db) {
+        salsa::adopt(db, tracked);
+    }
+    Some(tracked.field(db))
+}
+
+#[test]
+fn several_adopters() {
+    let mut db = Db::default();
+    let input = Shared::new(&db, true, true, true);
+
+    assert_eq!(first_adopter(&db, input), Some(22));
+    assert_eq!(second_adopter(&db, input), Some(22));
+    db.assert_logs_len(3);
+
+    // One adopter gives the struct up, but the other one still owns it.
+    input.set_second(&mut db).to(false);
+    assert_eq!(second_adopter(&db, input), Some(22));
+    assert_eq!(first_adopter(&db, input), Some(22));
+    db.assert_logs(expect![[r#"
+        [
+            "execute(second_adopter)",
+        ]"#]]);
+
+    // The helper no longer creates the struct: it is only discarded
+    // once the remaining adopter has re-executed without it.
+    input.set_create(&mut db).to(false);
+    assert_eq!(first_adopter(&db, input), None);
+    db.assert_logs(expect![[r#"
+        [
+            "execute(shared_helper)",
+            "execute(first_adopter)",
+            "discard(MyTracked)",
+        ]"#]]);
+}
@@ -0,0 +1,88 @@
+//! Test that reading an `#[elements]` field of a tracked struct one element
+//! at a time only invalidates readers of the elements that changed.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    items: Vec<u32>,
+}
+
+#[salsa::tracked]
+struct Items<'db> {
+    #[elements]
+    items: Vec<u32>,
+}
+
+#[salsa::tracked]
+fn items(db: &dyn LogDatabase, input: MyInput) -> Items<'_> {
+    Items::new(db, input.items(db))
+}
+
+#[salsa::tracked]
+fn first(db: &dyn LogDatabase, input: MyInput) -> Option<u32> {
+    db.push_log("first".to_string());
+    items(db, input).items_element(db, 0).copied()
+}
+
+#[salsa::tracked]
+fn third(db: &dyn LogDatabase, input: MyInput) -> Option<u32> {
+    db.push_log("third".to_string());
+    items(db, input).items_element(db, 2).copied()
+}
+
+#[salsa::tracked]
+fn total(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log("total".to_string());
+    items(db, input).items(db).iter().sum()
+}
+
+#[test]
+fn execute() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, vec![1, 2, 3]);
+
+    assert_eq!(first(&db, input), Some(1));
+    assert_eq!(third(&db, input), Some(3));
+    assert_eq!(total(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "first",
+            "third",
+            "total",
+        ]"#]]);
+
+    // Only the third element changed.
+    input.set_items(&mut db).to(vec![1, 2, 4]);
+    assert_eq!(first(&db, input), Some(1));
+    assert_eq!(third(&db, input), Some(4));
+    assert_eq!(total(&db, input), 7);
+    db.assert_logs(expect![[r#"
+        [
+            "third",
+            "total",
+        ]"#]]);
+
+    // Removing the third element changes it too.
+    input.set_items(&mut db).to(vec![1, 2]);
+    assert_eq!(first(&db, input), Some(1));
+    assert_eq!(third(&db, input), None);
+    db.assert_logs(expect![[r#"
+        [
+            "third",
+        ]"#]]);
+
+    // As does adding it back.
+    input.set_items(&mut db).to(vec![1, 2, 5, 6]);
+    assert_eq!(first(&db, input), Some(1));
+    assert_eq!(third(&db, input), Some(5));
+    db.assert_logs(expect![[r#"
+        [
+            "third",
+        ]"#]]);
+}
@@ -0,0 +1,123 @@
+//! Test that tracked structs declared with `identity_key` keep their identity
+//! when the order in which they are created changes.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    items: Vec<(String, u32)>,
+}
+
+#[salsa::tracked(identity_key)]
+struct Keyed<'db> {
+    value: u32,
+}
+
+#[salsa::tracked]
+struct Ordered<'db> {
+    value: u32,
+}
+
+#[salsa::tracked]
+fn create_keyed(db: &dyn LogDatabase, input: MyInput) -> Vec<Keyed<'_>> {
+    input
+        .items(db)
+        .into_iter()
+        .map(|(name, value)| Keyed::new(db, name, value))
+        .collect()
+}
+
+#[salsa::tracked]
+fn create_ordered(db: &dyn LogDatabase, input: MyInput) -> Vec<Ordered<'_>> {
+    input
+        .items(db)
+        .into_iter()
+        .map(|(_, value)| Ordered::new(db, value))
+        .collect()
+}
+
+#[salsa::tracked]
+fn keyed_double<'db>(db: &'db dyn LogDatabase, keyed: Keyed<'db>) -> u32 {
+    db.push_log(format!("keyed_double({})", keyed.value(db)));
+    keyed.value(db) * 2
+}
+
+#[salsa::tracked]
+fn ordered_double<'db>(db: &'db dyn LogDatabase, ordered: Ordered<'db>) -> u32 {
+    db.push_log(format!("ordered_double({})", ordered.value(db)));
+    ordered.value(db) * 2
+}
+
+#[salsa::tracked]
+fn keyed_sum(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    create_keyed(db, input)
+        .into_iter()
+        .map(|keyed| keyed_double(db, keyed))
+        .sum()
+}
+
+#[salsa::tracked]
+fn ordered_sum(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    create_ordered(db, input)
+        .into_iter()
+        .map(|ordered| ordered_double(db, ordered))
+        .sum()
+}
+
+fn items(items: &[(&str, u32)]) -> Vec<(String, u32)> {
+    items
+        .iter()
+        .map(|&(name, value)| (name.to_string(), value))
+        .collect()
+}
+
+#[test]
+fn reordered_creations() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, items(&[("a", 1), ("b", 2)]));
+    assert_eq!(keyed_sum(&db, input), 6);
+    assert_eq!(ordered_sum(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "keyed_double(1)",
+            "keyed_double(2)",
+            "ordered_double(1)",
+            "ordered_double(2)",
+        ]"#]]);
+
+    // The keyed structs are reused as they were; the ordered structs swap their values.
+    input.set_items(&mut db).to(items(&[("b", 2), ("a", 1)]));
+    assert_eq!(keyed_sum(&db, input), 6);
+    assert_eq!(ordered_sum(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "ordered_double(2)",
+            "ordered_double(1)",
+        ]"#]]);
+}
+
+#[test]
+fn equal_keys() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, items(&[("a", 1), ("a", 2)]));
+    assert_eq!(keyed_sum(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "keyed_double(1)",
+            "keyed_double(2)",
+        ]"#]]);
+
+    // Structs with equal keys are told apart by the order in which they are created.
+    input.set_items(&mut db).to(items(&[("a", 2), ("a", 1)]));
+    assert_eq!(keyed_sum(&db, input), 6);
+    db.assert_logs(expect![[r#"
+        [
+            "keyed_double(2)",
+            "keyed_double(1)",
+        ]"#]]);
+}
@@ -0,0 +1,94 @@
+//! Test that the fields of a `lazy_update` tracked struct are compared
+//! with their old values when they are first read.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::Setter;
+use test_log::test;
+
+#[salsa::input]
+struct MyInput {
+    a: u32,
+    b: u32,
+}
+
+#[salsa::tracked(lazy_update)]
+struct MyTracked<'db> {
+    a: u32,
+    b: u32,
+}
+
+#[salsa::tracked]
+fn create<'db>(db: &'db dyn LogDatabase, input: MyInput) -> MyTracked<'db> {
+    MyTracked::new(db, input.a(db), input.b(db))
+}
+
+#[salsa::tracked]
+fn read_a(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log("read_a".to_string());
+    create(db, input).a(db)
+}
+
+#[salsa::tracked]
+fn read_b(db: &dyn LogDatabase, input: MyInput) -> u32 {
+    db.push_log("read_b".to_string());
+    create(db, input).b(db)
+}
+
+#[test]
+fn unchanged_fields_are_backdated() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 2);
+    assert_eq!(read_a(&db, input), 1);
+    assert_eq!(read_b(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "read_a",
+            "read_b",
+        ]"#]]);
+
+    input.set_b(&mut db).to(3);
+    assert_eq!(read_a(&db, input), 1);
+    assert_eq!(read_b(&db, input), 3);
+    db.assert_logs(expect![[r#"
+        [
+            "read_b",
+        ]"#]]);
+
+    input.set_a(&mut db).to(4);
+    assert_eq!(read_b(&db, input), 3);
+    assert_eq!(read_a(&db, input), 4);
+    db.assert_logs(expect![[r#"
+        [
+            "read_a",
+        ]"#]]);
+}
+
+#[test]
+fn fields_not_read_before_recreation_are_changed() {
+    let mut db = LoggerDatabase::default();
+    let input = MyInput::new(&db, 1, 2);
+    assert_eq!(read_a(&db, input), 1);
+    assert_eq!(read_b(&db, input), 2);
+    db.assert_logs(expect![[r#"
+        [
+            "read_a",
+            "read_b",
+        ]"#]]);
+
+    // `a` is not read before the struct is re-created again,
+    // so it is conservatively treated as changed.
+    input.set_b(&mut db).to(3);
+    assert_eq!(read_b(&db, input), 3);
+    input.set_b(&mut db).to(4);
+    assert_eq!(read_b(&db, input), 4);
+    assert_eq!(read_a(&db, input), 1);
+    db.assert_logs(expect![[r#"
+        [
+            "read_b",
+            "read_b",
+            "read_a",
+        ]"#]]);
+}
@@ -0,0 +1,120 @@
+//! Test `singleton` tracked structs:
+//!
+//! At most one exists at a time; it keeps its identity when re-created
+//! and is found with `get`, which tracks whether it exists.
+
+mod common;
+use common::{LogDatabase, LoggerDatabase};
+
+use expect_test::expect;
+use salsa::{plumbing::AsId, Setter};
+use test_log::test;
+
+#[salsa::input]
+struct Graph {
+    crates: u32,
+    enabled: bool,
+}
+
+#[salsa::tracked(singleton)]
+struct Summary<'db> {
+    #[id]
+    crates: u32,
+}
+
+#[salsa::tracked]
+fn summarize(db: &dyn LogDatabase, graph: Graph) -> Option<Summary<'_>> {
+    db.push_log("summarize".to_string());
+    graph
+        .enabled(db)
+        .then(|| Summary::new(db, graph.crates(db)))
+}
+
+#[salsa::tracked]
+fn crate_count(db: &dyn LogDatabase) -> Option<u32> {
+    db.push_log("crate_count".to_string());
+    Summary::try_get(db).map(|summary| summary.crates(db))
+}
+
+#[salsa::tracked]
+fn summarize_twice(db: &dyn LogDatabase, graph: Graph) {
+    Summary::new(db, graph.crates(db));
+    Summary::new(db, graph.crates(db) + 1);
+}
+
+#[test]
+fn keeps_identity() {
+    let mut db = LoggerDatabase::default();
+    let graph = Graph::new(&db, 3, true);
+    let summary = summarize(&db, graph).unwrap().as_id();
+    assert_eq!(Summary::get(&db).as_id(), summary);
+
+    graph.set_crates(&mut db).to(4);
+    assert_eq!(summarize(&db, graph).unwrap().as_id(), summary);
+    assert_eq!(Summary::get(&db).crates(&db), 4);
+}
+
+#[test]
+fn readers_track_existence() {
+    let mut db = LoggerDatabase::default();
+    let graph = Graph::new(&db, 3, false);
+    assert_eq!(summarize(&db, graph), None);
+    assert_eq!(crate_count(&db), None);
+    db.assert_logs(expect![[r#"
+        [
+            "summarize",
+            "crate_count",
+        ]"#]]);
+
+    graph.set_enabled(&mut db).to(true);
+    assert!(summarize(&db, graph).is_some());
+    assert_eq!(crate_count(&db), Some(3));
+    db.assert_logs(expect![[r#"
+        [
+            "summarize",
+            "crate_count",
+        ]"#]]);
+
+    graph.set_enabled(&mut db).to(false);
+    assert_eq!(summarize(&db, graph), None);
+    assert_eq!(crate_count(&db), None);
+    db.assert_logs(expect![[r#"
+        [
+            "summarize",
+            "crate_count",
+        ]"#]]);
+}
+
+#[test]
+#[should_panic(expected = "singleton struct may not be duplicated")]
+fn twice() {
+    let db = LoggerDatabase::default();
+    let graph = Graph::new(&db, 3, true);
+    summarize_twice(&db, graph);
+}
+
+#[test]
+fn readers_validate_the_creator_first() {
+    let mut db = LoggerDatabase::default();
+    let graph = Graph::new(&db, 3, true);
+    assert!(summarize(&db, graph).is_some());
+    assert_eq!(crate_count(&db), Some(3));
+    db.assert_logs_len(2);
+
+    // `crate_count` is fetched before `summarize` executes again and deletes the singleton.
+    graph.set_enabled(&mut db).to(false);
+    assert_eq!(crate_count(&db), None);
+    db.assert_logs(expect![[r#"
+        [
+            "summarize",
+            "crate_count",
+        ]"#]]);
+
+    graph.set_enabled(&mut db).to(true);
+    assert_eq!(crate_count(&db), Some(3));
+    db.assert_logs(expect![[r#"
+        [
+            "summarize",
+            "crate_count",
+        ]"#]]);
+}
@@ -0,0 +1,69 @@
+//! Test that `Database::transaction` applies several writes in one revision.
+
+mod common;
+use common::{EventLoggerDatabase, LogDatabase};
+
+use salsa::{plumbing::current_revision, Cancelled, Database, Setter};
+
+#[salsa::input]
+struct File {
+    contents: String,
+}
+
+#[salsa::tracked]
+fn length(db: &dyn Database, file: File) -> usize {
+    file.contents(db).len()
+}
+
+#[test]
+fn single_revision() {
+    let mut db = EventLoggerDatabase::default();
+    let files: Vec<_> = (0..3).map(|_| File::new(&db, String::new())).collect();
+    assert_eq!(length(&db, files[1]), 0);
+    assert_eq!(format!("{:?}", current_revision(&db)), "R1");
+    db.assert_logs_len(2);
+
+    db.transaction(|db| {
+        for (i, file) in files.iter().enumerate() {
+            file.set_contents(db).to("x".repeat(i));
+        }
+    });
+    assert_eq!(format!("{:?}", current_revision(&db)), "R2");
+    // One `WillChangeInputs` event for the transaction and for each write,
+    // but only one `DidSetCancellationFlag` event.
+    db.assert_logs_len(5);
+
+    assert_eq!(length(&db, files[1]), 1);
+    assert_eq!(length(&db, files[2]), 2);
+}
+
+#[test]
+fn queries_are_cancelled() {
+    let mut db = EventLoggerDatabase::default();
+    let file = File::new(&db, String::new());
+
+    db.transaction(|db| {
+        file.set_contents(db).to("abc".to_string());
+        let result = Cancelled::catch(std::panic::AssertUnwindSafe(|| length(db, file)));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "cancelled because of pending write"
+        );
+    });
+
+    assert_eq!(length(&db, file), 3);
+}
+
+#[test]
+fn queries_are_cancelled_in_cooperative_mode() {
+    let mut db = salsa::DatabaseImpl::with_cancellation_mode(salsa::CancellationMode::Cooperative);
+    let file = File::new(&db, String::new());
+
+    db.transaction(|db| {
+        file.set_contents(db).to("abc".to_string());
+        let result = Cancelled::catch(std::panic::AssertUnwindSafe(|| length(db, file)));
+        assert!(matches!(result, Err(Cancelled::PendingWrite { .. })));
+    });
+
+    assert_eq!(length(&db, file), 3);
+}
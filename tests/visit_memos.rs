@@ -0,0 +1,128 @@
+//! Test that `visit_memos` enumerates the memoized values of a tracked function.
+
+use salsa::plumbing::{current_revision, AsId, FromId};
+use salsa::{Database, DatabaseImpl, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::tracked]
+fn double(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 2
+}
+
+#[salsa::tracked(lru = 1)]
+fn triple(db: &dyn Database, input: MyInput) -> u32 {
+    input.field(db) * 3
+}
+
+#[salsa::tracked]
+fn sum(db: &dyn Database, input: MyInput) -> u32 {
+    double(db, input) + triple(db, input)
+}
+
+fn visit_double(db: &dyn Database) -> Vec<(MyInput, Option<u32>)> {
+    let mut memos = vec![];
+    double::visit_memos(db, |memo| {
+        // The visitor may read from the database.
+        let input = MyInput::from_id(memo.key());
+        memos.push((input, memo.value().copied()));
+    });
+    memos.sort_by_key(|&(input, _)| input.field(db));
+    memos
+}
+
+#[test]
+fn visit_values() {
+    let mut db = DatabaseImpl::new();
+    let a = MyInput::new(&db, 1);
+    let b = MyInput::new(&db, 2);
+    let _c = MyInput::new(&db, 3);
+    assert!(visit_double(&db).is_empty());
+
+    double(&db, a);
+    double(&db, b);
+    assert_eq!(visit_double(&db), vec![(a, Some(2)), (b, Some(4))]);
+
+    // Memos from earlier revisions are visited as they were last computed.
+    a.set_field(&mut db).to(10);
+    assert_eq!(visit_double(&db), vec![(b, Some(4)), (a, Some(2))]);
+}
+
+#[test]
+fn visit_dependencies() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    assert_eq!(sum(&db, input), 5);
+
+    let mut memos = vec![];
+    sum::visit_memos(&db, |memo| {
+        memos.push((
+            memo.key(),
+            *memo.value().unwrap(),
+            memo.dependencies().collect::<Vec<_>>(),
+            memo.changed_at(),
+            memo.verified_at(),
+        ));
+        assert!(!memo.has_untracked_reads());
+        assert_eq!(memo.assigned_by(), None);
+    });
+    let revision = current_revision(&db);
+    assert_eq!(
+        memos,
+        vec![(
+            input.as_id(),
+            5,
+            vec![
+                double::database_key_index(&db, input).unwrap(),
+                triple::database_key_index(&db, input).unwrap(),
+            ],
+            revision,
+            revision,
+        )]
+    );
+
+    // Evicted values are visited without a value.
+    let other = MyInput::new(&db, 2);
+    input.set_field(&mut db).to(1);
+    triple(&db, other);
+    let mut values = vec![];
+    triple::visit_memos(&db, |memo| values.push(memo.value().copied()));
+    values.sort();
+    assert_eq!(values, vec![None, Some(6)]);
+}
+
+#[salsa::tracked]
+struct Tracked<'db> {
+    value: u32,
+}
+
+#[salsa::tracked]
+fn create_tracked<'db>(db: &'db dyn Database, input: MyInput) -> Tracked<'db> {
+    Tracked::new(db, input.field(db))
+}
+
+#[salsa::tracked]
+fn tracked_value<'db>(db: &'db dyn Database, tracked: Tracked<'db>) -> u32 {
+    tracked.value(db)
+}
+
+#[test]
+fn visiting_does_not_lock_tracked_structs() {
+    let mut db = DatabaseImpl::new();
+    let input = MyInput::new(&db, 1);
+    let tracked = create_tracked(&db, input);
+    assert_eq!(tracked_value(&db, tracked), 1);
+
+    // Visiting in a new revision must not mark the struct as read in it,
+    // which would keep the query that created it from updating it.
+    input.set_field(&mut db).to(2);
+    let mut values = vec![];
+    tracked_value::visit_memos(&db, |memo| values.push(memo.value().copied()));
+    assert_eq!(values, vec![Some(1)]);
+
+    let tracked = create_tracked(&db, input);
+    assert_eq!(tracked_value(&db, tracked), 2);
+}
@@ -0,0 +1,101 @@
+//! Test that `InputWatcher` applies the changes to watched files in a single revision.
+#![cfg(feature = "watch")]
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use salsa::watch::InputWatcher;
+use salsa::Setter;
+
+#[salsa::input]
+struct File {
+    path: PathBuf,
+    #[return_ref]
+    contents: String,
+}
+
+#[salsa::db]
+trait Db: salsa::Database {
+    fn file(&self, path: PathBuf) -> File;
+}
+
+#[salsa::db]
+#[derive(Clone)]
+struct Database {
+    storage: salsa::Storage<Self>,
+    watcher: Arc<InputWatcher<File>>,
+}
+
+#[salsa::db]
+impl salsa::Database for Database {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+#[salsa::db]
+impl Db for Database {
+    fn file(&self, path: PathBuf) -> File {
+        self.watcher
+            .input(&path, |path, contents| File::new(self, path, contents))
+            .unwrap()
+    }
+}
+
+/// Adds up the number in `root` and the numbers in the files named on its following lines.
+#[salsa::tracked]
+fn sum(db: &dyn Db, root: File) -> u32 {
+    let mut lines = root.contents(db).lines();
+    let value: u32 = lines.next().unwrap().parse().unwrap();
+    let dir = root.path(db).parent().unwrap().to_path_buf();
+    value
+        + lines
+            .map(|name| db.file(dir.join(name)).contents(db).parse::<u32>().unwrap())
+            .sum::<u32>()
+}
+
+#[test]
+fn applies_changes_in_one_revision() {
+    let dir = std::env::temp_dir().join(format!("salsa-watch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("root"), "1\na\nb").unwrap();
+    std::fs::write(dir.join("a"), "2").unwrap();
+    std::fs::write(dir.join("b"), "3").unwrap();
+
+    let mut db = Database {
+        storage: Default::default(),
+        watcher: Arc::new(InputWatcher::new(Duration::from_millis(50)).unwrap()),
+    };
+    let root = db.file(dir.join("root"));
+    assert_eq!(sum(&db, root), 6);
+
+    let revisions = Arc::new(AtomicUsize::new(0));
+    db.storage.on_change({
+        let revisions = revisions.clone();
+        move |_| {
+            revisions.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+    std::fs::write(dir.join("a"), "20").unwrap();
+    std::fs::write(dir.join("b"), "30").unwrap();
+
+    // The debouncer may report the two changes separately;
+    // each call applies the changes it received in one revision.
+    let watcher = db.watcher.clone();
+    let mut changed = 0;
+    let mut calls = 0;
+    while changed < 2 {
+        changed += watcher
+            .recv_changes(&mut db, |db, file, contents| {
+                file.set_contents(db).to(contents.unwrap());
+            })
+            .unwrap()
+            .len();
+        calls += 1;
+    }
+    assert_eq!(changed, 2);
+    assert_eq!(revisions.load(Ordering::SeqCst), calls);
+    assert_eq!(sum(&db, root), 51);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,105 @@
+//! Test that every write to the database is reported with a `WillChangeInputs` event
+//! naming its cause, before the new revision starts.
+
+use std::sync::{Arc, Mutex};
+
+use expect_test::expect;
+use salsa::{ChangeCause, Channel, Database, Durability, EventFilter, EventKind, Setter};
+
+#[salsa::input]
+struct MyInput {
+    field: u32,
+}
+
+#[salsa::interned]
+struct Name<'db> {
+    text: String,
+}
+
+#[salsa::db]
+#[derive(Clone, Default)]
+struct Db {
+    storage: salsa::Storage<Self>,
+}
+
+#[salsa::db]
+impl Database for Db {
+    fn salsa_event(&self, _event: &dyn Fn() -> salsa::Event) {}
+}
+
+/// Records each cause, along with the revision the database was in when it was reported.
+fn record_causes(db: &Db) -> Arc<Mutex<Vec<String>>> {
+    let causes: Arc<Mutex<Vec<String>>> = Default::default();
+    db.storage
+        .subscribe_with_db(EventFilter::WILL_CHANGE_INPUTS, {
+            let causes = causes.clone();
+            move |db, event| {
+                let EventKind::WillChangeInputs { cause } = event.kind else {
+                    panic!("unexpected event {event:?}");
+                };
+                let cause = match cause {
+                    ChangeCause::SetField { field } => format!("set {:?}", field.debug(db)),
+                    ChangeCause::SetChannel { input, channel } => {
+                        format!("set channel of {:?} to {channel:?}", input.debug(db))
+                    }
+                    cause => format!("{cause:?}"),
+                };
+                let revision = salsa::plumbing::current_revision(db);
+                causes
+                    .lock()
+                    .unwrap()
+                    .push(format!("{cause} in {revision:?}"));
+            }
+        });
+    causes
+}
+
+#[test]
+fn setters() {
+    let mut db = Db::default();
+    let causes = record_causes(&db);
+
+    let input = MyInput::new(&db, 1);
+    input.set_field(&mut db).to(2);
+    db.set_channel(input, Channel::new(1));
+    db.set_config(true);
+
+    expect![[r#"
+        [
+            "set field(Id(0)) in R1",
+            "set channel of MyInput(Id(0)) to Channel(1) in R2",
+            "set value(Id(400)) in R3",
+        ]
+    "#]]
+    .assert_debug_eq(&causes.lock().unwrap());
+}
+
+#[test]
+fn other_writes() {
+    let mut db = Db::default();
+    let causes = record_causes(&db);
+
+    let input = MyInput::new(&db, 1);
+    Name::new(&db, "name".to_string());
+    db.synthetic_write(Durability::MEDIUM);
+    db.invalidate_ingredient::<Name<'_>>();
+    db.transaction(|db| {
+        input.set_field(db).to(2);
+        input.set_field(db).to(3);
+    });
+    db.write_scope(|scope| {
+        input.set_field_in(scope).to(4);
+    });
+
+    expect![[r#"
+        [
+            "SyntheticWrite { durability: Durability(1) } in R1",
+            "InvalidateIngredient { ingredient_index: IngredientIndex(2) } in R2",
+            "Transaction in R3",
+            "set field(Id(0)) in R4",
+            "set field(Id(0)) in R4",
+            "WriteScope in R4",
+        ]
+    "#]]
+    .assert_debug_eq(&causes.lock().unwrap());
+}